@@ -0,0 +1,31 @@
+/// Commands of `tauri_commands`, as registered in its `generate_handler!`
+#[cfg(feature = "tauri")]
+const COMMANDS: &[&str] = &[
+    "cache_widget_description",
+    "remap_event_ids",
+    "get_description_issues",
+    "save_preset_and_learn",
+    "delete_preset",
+    "snapshot_preset",
+    "record_widget_value",
+    "flush_widget_values",
+    "get_widget_value_suggestions",
+    "get_widget_value_suggestions_batch",
+    "get_widget_insight",
+    "recall_preset",
+    "apply_preset",
+    "get_preset",
+    "export_preset_kyma",
+    "export_widgets_kyma",
+    "import_kyma_presets",
+    "get_intelligence_stats",
+    "list_widgets",
+    "list_presets",
+    "flush",
+    "clear_all_data",
+];
+
+fn main() {
+    #[cfg(feature = "tauri")]
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -0,0 +1,75 @@
+use serde::{Serialize, Serializer};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Every error returned by the crate
+#[derive(Debug, Error)]
+pub enum WidgetIntelligenceError {
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sled::Error),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+    #[error("Deserialization error: {0}")]
+    DeserializationError(String),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Integrity error: {0}")]
+    IntegrityError(String),
+    /// A write was attempted on a database opened read-only
+    #[error("Database is opened read-only")]
+    ReadOnly,
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+    /// Input that is well-formed but not acceptable, e.g. a widget whose minimum is above
+    /// its maximum
+    #[error("Validation error: {0}")]
+    ValidationError(String),
+    /// Input that could not be read at all, e.g. malformed JSON
+    #[error("Parse error: {0}")]
+    ParseError(String),
+    /// A lock was poisoned by a thread that panicked while holding it
+    #[error("Failed to lock {0}")]
+    LockError(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    /// A connection to Kyma could not be made or was lost
+    #[error("Connection error: {0}")]
+    ConnectionError(String),
+    /// The database is locked by another process, or another handle in this one
+    #[error("Database {} is in use by {}", .path.display(), describe_owner(.pid))]
+    DatabaseBusy {
+        path: PathBuf,
+        /// Process holding the lock, if it recorded itself
+        pid: Option<u32>,
+    },
+}
+
+fn describe_owner(pid: &Option<u32>) -> String {
+    match pid {
+        Some(pid) => format!("process {pid}"),
+        None => "another process".to_string(),
+    }
+}
+
+/// The error's former name, from when only the persistence layer had a typed error
+pub type SledPersistenceError = WidgetIntelligenceError;
+
+impl From<bincode::error::EncodeError> for WidgetIntelligenceError {
+    fn from(err: bincode::error::EncodeError) -> Self {
+        WidgetIntelligenceError::SerializationError(err.to_string())
+    }
+}
+
+impl From<bincode::error::DecodeError> for WidgetIntelligenceError {
+    fn from(err: bincode::error::DecodeError) -> Self {
+        WidgetIntelligenceError::DeserializationError(err.to_string())
+    }
+}
+
+/// Serialized as its message, so it can be returned from Tauri commands as is
+impl Serialize for WidgetIntelligenceError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
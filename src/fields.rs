@@ -0,0 +1,163 @@
+//! Field lookup shared by the extractor and `From<FilteredWidgetDescription>`
+//!
+//! Descriptions from different Kyma versions and tools name some fields differently,
+//! e.g. `min` for `minimum` or `eventID` for `concreteEventID`, and sometimes encode
+//! numbers and flags as strings. `FieldAliases` looks a field up under its canonical
+//! name and then under each of its aliases, coercing the value the same way wherever a
+//! description is read.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Alternative names of description fields, by canonical name
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldAliases {
+    aliases: HashMap<String, Vec<String>>,
+}
+
+impl Default for FieldAliases {
+    /// The spellings seen in Kyma descriptions and exports
+    fn default() -> Self {
+        Self::none()
+            .with_alias("concreteEventID", "eventID")
+            .with_alias("concreteEventID", "id")
+            .with_alias("minimum", "min")
+            .with_alias("maximum", "max")
+            .with_alias("defaultValue", "default")
+            .with_alias("grid", "step")
+            .with_alias("displayType", "widgetType")
+            .with_alias("displayType", "controlType")
+            .with_alias("current_value", "currentValue")
+    }
+}
+
+impl FieldAliases {
+    /// No aliases; every field is only found under its canonical name
+    pub fn none() -> Self {
+        Self {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Adds `alias` after the existing aliases of `field`
+    pub fn with_alias(mut self, field: &str, alias: &str) -> Self {
+        let aliases = self.aliases.entry(field.to_string()).or_default();
+        if !aliases.iter().any(|a| a == alias) {
+            aliases.push(alias.to_string());
+        }
+        self
+    }
+
+    /// Replaces the aliases of `field`, tried in the given order
+    pub fn set_aliases<I, S>(&mut self, field: &str, aliases: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let aliases: Vec<String> = aliases.into_iter().map(Into::into).collect();
+        if aliases.is_empty() {
+            self.aliases.remove(field);
+        } else {
+            self.aliases.insert(field.to_string(), aliases);
+        }
+    }
+
+    pub fn aliases(&self, field: &str) -> &[String] {
+        self.aliases.get(field).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// `field` followed by its aliases
+    pub fn keys<'a>(&'a self, field: &'a str) -> impl Iterator<Item = &'a str> {
+        std::iter::once(field).chain(self.aliases(field).iter().map(String::as_str))
+    }
+
+    /// Whether `data` has a non-null value under `field` or one of its aliases
+    pub fn contains(&self, data: &HashMap<String, Value>, field: &str) -> bool {
+        self.raw(data, field).is_some()
+    }
+
+    /// The first non-null value under `field` or one of its aliases, uncoerced
+    pub fn raw<'a>(&self, data: &'a HashMap<String, Value>, field: &str) -> Option<&'a Value> {
+        self.keys(field)
+            .find_map(|key| data.get(key).filter(|value| !value.is_null()))
+    }
+
+    /// The first value under `field` or one of its aliases that coerces to a number
+    pub fn f64(&self, data: &HashMap<String, Value>, field: &str) -> Option<f64> {
+        self.find(data, field, coerce_f64)
+    }
+
+    /// The first value under `field` or one of its aliases that coerces to an integer
+    pub fn i64(&self, data: &HashMap<String, Value>, field: &str) -> Option<i64> {
+        self.find(data, field, coerce_i64)
+    }
+
+    /// The first value under `field` or one of its aliases that coerces to a flag
+    pub fn bool(&self, data: &HashMap<String, Value>, field: &str) -> Option<bool> {
+        self.find(data, field, coerce_bool)
+    }
+
+    /// The first non-blank string under `field` or one of its aliases
+    pub fn string(&self, data: &HashMap<String, Value>, field: &str) -> Option<String> {
+        self.find(data, field, coerce_string)
+    }
+
+    fn find<T>(
+        &self,
+        data: &HashMap<String, Value>,
+        field: &str,
+        coerce: impl Fn(&Value) -> Option<T>,
+    ) -> Option<T> {
+        self.keys(field)
+            .find_map(|key| data.get(key).and_then(&coerce))
+    }
+}
+
+/// A finite number, or a string holding one
+pub fn coerce_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .filter(|v| v.is_finite())
+}
+
+/// A whole number, or a string holding one; `3.0` counts, `3.5` does not
+pub fn coerce_i64(value: &Value) -> Option<i64> {
+    if let Some(n) = value.as_i64() {
+        return Some(n);
+    }
+    if let Value::String(s) = value {
+        if let Ok(n) = s.trim().parse::<i64>() {
+            return Some(n);
+        }
+    }
+
+    let float = coerce_f64(value)?;
+    let whole = float.fract() == 0.0 && float >= i64::MIN as f64 && float < i64::MAX as f64;
+    whole.then_some(float as i64)
+}
+
+/// A boolean, a number (non-zero is true) or one of "true"/"false", "yes"/"no",
+/// "on"/"off" and "1"/"0" in any case
+pub fn coerce_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) => n.as_f64().map(|num| num != 0.0),
+        Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Some(true),
+            "false" | "0" | "no" | "off" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A string with more than whitespace in it, as written
+pub fn coerce_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,235 @@
+//! WebSocket client for Kyma's JSON interface
+//!
+//! `connect_kyma` subscribes to sound change notifications and, whenever a sound is
+//! loaded, asks for its widget descriptions and caches them in the extractor, so event
+//! IDs resolve without the host app fetching descriptions itself. What happened is
+//! reported to the host app as `KymaConnectEvent`s.
+//!
+//! Message names are configurable in `KymaConnectConfig`, as they differ between Kyma
+//! versions.
+
+use crate::error::WidgetIntelligenceError;
+use crate::kyma_extractor::{CacheDiff, CacheReport, CacheSnapshot, KymaWidgetExtractor};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Settings of `connect_kyma`
+#[derive(Debug, Clone)]
+pub struct KymaConnectConfig {
+    /// e.g. `ws://beslime-742.local:8080/kyma`
+    pub url: String,
+    /// Sent once connected to receive notifications
+    pub subscribe_request: Value,
+    /// `event` names of the notifications sent when a sound is loaded
+    pub sound_loaded_events: Vec<String>,
+    /// Sent after a sound is loaded to ask for its widget descriptions
+    pub describe_request: Value,
+}
+
+impl KymaConnectConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            subscribe_request: json!({ "request": "/interest" }),
+            sound_loaded_events: vec!["/soundLoaded".to_string()],
+            describe_request: json!({ "request": "/widgets" }),
+        }
+    }
+}
+
+/// What the client reports to the host app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum KymaConnectEvent {
+    Connected,
+    /// A sound was loaded; the cache was cleared and its descriptions requested
+    SoundLoaded,
+    WidgetsCached(CacheReport),
+    /// How the newly loaded sound's widgets differ from the previous sound's, reported
+    /// after its descriptions are cached
+    WidgetsChanged(CacheDiff),
+    /// A message that could not be handled; the connection stays open
+    Error(String),
+    Disconnected,
+}
+
+/// Handles the messages of one connection, without the socket
+pub struct KymaConnectSession {
+    config: KymaConnectConfig,
+    extractor: Arc<KymaWidgetExtractor>,
+    /// The cache from before the last sound was loaded, until its descriptions arrive
+    previous_sound: Mutex<Option<CacheSnapshot>>,
+}
+
+impl KymaConnectSession {
+    pub fn new(config: KymaConnectConfig, extractor: Arc<KymaWidgetExtractor>) -> Self {
+        Self {
+            config,
+            extractor,
+            previous_sound: Mutex::new(None),
+        }
+    }
+
+    /// Returns the events to report and the request to send back, if any
+    pub fn handle_message(&self, text: &str) -> (Vec<KymaConnectEvent>, Option<String>) {
+        let message: Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                return (
+                    vec![KymaConnectEvent::Error(format!(
+                        "Failed to parse message: {e}"
+                    ))],
+                    None,
+                )
+            }
+        };
+
+        let event = message.get("event").and_then(Value::as_str);
+        if event.is_some_and(|event| self.config.sound_loaded_events.iter().any(|e| e == event)) {
+            let snapshot = self.extractor.cache_snapshot();
+            *self.previous_sound() = (!snapshot.is_empty()).then_some(snapshot);
+            self.extractor.clear_cache();
+            return (
+                vec![KymaConnectEvent::SoundLoaded],
+                Some(self.config.describe_request.to_string()),
+            );
+        }
+
+        let holds_descriptions =
+            message.is_array() || message.get("widgets").is_some() || message.get("vcs").is_some();
+        if !holds_descriptions {
+            log::trace!("Ignoring Kyma message: {text}");
+            return (Vec::new(), None);
+        }
+
+        match self.extractor.cache_widget_descriptions_from_json(text) {
+            Ok(report) => {
+                let mut events = vec![KymaConnectEvent::WidgetsCached(report)];
+                if let Some(previous) = self.previous_sound().take() {
+                    events.push(KymaConnectEvent::WidgetsChanged(
+                        self.extractor.diff_against(&previous),
+                    ));
+                }
+                (events, None)
+            }
+            Err(e) => (vec![KymaConnectEvent::Error(e.to_string())], None),
+        }
+    }
+
+    fn previous_sound(&self) -> std::sync::MutexGuard<'_, Option<CacheSnapshot>> {
+        self.previous_sound
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn subscribe_request(&self) -> String {
+        self.config.subscribe_request.to_string()
+    }
+}
+
+/// A running connection; it closes when `stop` is called or the handle is dropped
+pub struct KymaConnectHandle {
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl KymaConnectHandle {
+    /// Closes the connection
+    pub async fn stop(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(task) = self.task.take() {
+            if task.await.is_err() {
+                log::warn!("Kyma connection task panicked");
+            }
+        }
+    }
+}
+
+impl Drop for KymaConnectHandle {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+/// Connects to Kyma and keeps `extractor` filled with the loaded sound's widgets
+///
+/// Fails if the connection cannot be made; afterwards problems are reported as events
+/// and the last event is always `Disconnected`.
+pub async fn connect_kyma(
+    config: KymaConnectConfig,
+    extractor: Arc<KymaWidgetExtractor>,
+) -> Result<(KymaConnectHandle, mpsc::UnboundedReceiver<KymaConnectEvent>), WidgetIntelligenceError>
+{
+    let (socket, _) = tokio_tungstenite::connect_async(config.url.as_str())
+        .await
+        .map_err(|e| {
+            WidgetIntelligenceError::ConnectionError(format!(
+                "Failed to connect to {}: {e}",
+                config.url
+            ))
+        })?;
+    log::info!("Connected to Kyma at {}", config.url);
+
+    let (mut write, mut read) = socket.split();
+    let session = KymaConnectSession::new(config, extractor);
+    let (events, receiver) = mpsc::unbounded_channel();
+    let (stop_tx, mut stop_rx) = oneshot::channel();
+
+    let task = tokio::spawn(async move {
+        let _ = events.send(KymaConnectEvent::Connected);
+        if let Err(e) = write.send(Message::Text(session.subscribe_request())).await {
+            let _ = events.send(KymaConnectEvent::Error(format!("Failed to subscribe: {e}")));
+        }
+
+        loop {
+            let message = tokio::select! {
+                _ = &mut stop_rx => {
+                    let _ = write.send(Message::Close(None)).await;
+                    break;
+                }
+                message = read.next() => message,
+            };
+
+            let text = match message {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    let _ = events.send(KymaConnectEvent::Error(e.to_string()));
+                    break;
+                }
+            };
+
+            let (session_events, reply) = session.handle_message(&text);
+            for event in session_events {
+                let _ = events.send(event);
+            }
+            if let Some(reply) = reply {
+                if let Err(e) = write.send(Message::Text(reply)).await {
+                    let _ = events.send(KymaConnectEvent::Error(format!(
+                        "Failed to request widget descriptions: {e}"
+                    )));
+                }
+            }
+        }
+
+        log::info!("Disconnected from Kyma");
+        let _ = events.send(KymaConnectEvent::Disconnected);
+    });
+
+    Ok((
+        KymaConnectHandle {
+            stop: Some(stop_tx),
+            task: Some(task),
+        },
+        receiver,
+    ))
+}
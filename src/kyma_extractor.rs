@@ -1,39 +1,540 @@
-use crate::similarity_engine::Widget;
+use crate::error::WidgetIntelligenceError;
+use crate::fields::FieldAliases;
+use crate::labels::LabelSanitizer;
+use crate::similarity_engine::{Preset, Taper, ValueKind, Widget, WidgetValue};
+use crate::units::Units;
+use crate::widget_source::WidgetSource;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+/// Widgets with a finer grid than this many positions are treated as continuous
+pub const MAX_GRID_STEPS: u32 = 1024;
+
+/// Lowest ID `composite_event_id` hands out
+pub const COMPOSITE_EVENT_ID_BASE: i64 = 1 << 62;
+
+/// Widest range `validate_description` accepts without flagging it as suspicious
+pub const MAX_PLAUSIBLE_SPAN: f64 = 1e9;
+
+/// Confidence `snapshot` gives values outside their widget's cached range
+pub const OUT_OF_RANGE_CONFIDENCE: f64 = 0.0;
+
+/// Confidence `snapshot` gives values of event IDs without a cached description
+pub const UNCHECKED_VALUE_CONFIDENCE: f64 = 0.5;
+
+/// Most widgets `widgets_near` returns
+pub const MAX_NEARBY_WIDGETS: usize = 8;
+
+/// Fields `validate_description` expects to hold numbers
+const NUMERIC_FIELDS: [&str; 4] = ["minimum", "maximum", "defaultValue", "grid"];
+
+/// Display types of widgets that show what the sound does rather than control it
+const METER_DISPLAY_TYPES: &[&str] = &[
+    "meter",
+    "vu",
+    "vumeter",
+    "vu meter",
+    "level",
+    "oscilloscope",
+    "scope",
+    "spectrum",
+];
+
+const TEXT_DISPLAY_TYPES: &[&str] = &["text", "label", "caption", "textdisplay", "message"];
+
+/// Fields of Pacarana status messages, which carry no event ID
+const STATUS_FIELDS: &[&str] = &["status", "pacarana", "dspLoad", "cpuLoad", "dspStatus"];
+
+/// Caches Kyma widget descriptions by event ID and turns them into widgets and presets
+///
+/// The cache sits behind a read-write lock, so one extractor can be shared between
+/// threads, e.g. in an `Arc`, and lookups during live learning never wait for each other.
 pub struct KymaWidgetExtractor {
-    widget_descriptions: HashMap<i64, HashMap<String, Value>>,
+    cache: RwLock<DescriptionCache>,
+    /// Source of `DescriptionCache::last_used` ticks
+    clock: AtomicU64,
+    fields: FieldAliases,
+    labels: LabelSanitizer,
+}
+
+#[derive(Default)]
+struct DescriptionCache {
+    widget_descriptions: HashMap<i64, Arc<HashMap<String, Value>>>,
+    /// Current event ID of each `!EventName`, see `event_id_for_name`
+    event_names: HashMap<String, i64>,
+    /// Tick of the last cache or lookup of each description, for eviction; atomic so
+    /// lookups can update it under the read lock
+    last_used: HashMap<i64, AtomicU64>,
+    /// Most descriptions to keep, `None` for no bound
+    max_cache_size: Option<usize>,
+    /// Event IDs never evicted, e.g. those of the active sound
+    pinned: HashSet<i64>,
+}
+
+impl DescriptionCache {
+    fn remove(&mut self, event_id: i64) {
+        self.widget_descriptions.remove(&event_id);
+        self.last_used.remove(&event_id);
+        self.event_names.retain(|_, id| *id != event_id);
+    }
+
+    /// Evicts unpinned descriptions, least recently used first, until the cache fits
+    /// `max_cache_size`
+    fn evict(&mut self) -> Vec<i64> {
+        let Some(max_cache_size) = self.max_cache_size else {
+            return Vec::new();
+        };
+        let excess = self
+            .widget_descriptions
+            .len()
+            .saturating_sub(max_cache_size);
+        if excess == 0 {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<(u64, i64)> = self
+            .last_used
+            .iter()
+            .filter(|(event_id, _)| !self.pinned.contains(event_id))
+            .map(|(&event_id, last_used)| (last_used.load(Ordering::Relaxed), event_id))
+            .collect();
+        candidates.sort_unstable();
+
+        let evicted: Vec<i64> = candidates
+            .into_iter()
+            .take(excess)
+            .map(|(_, event_id)| event_id)
+            .collect();
+        for &event_id in &evicted {
+            self.remove(event_id);
+        }
+        if !evicted.is_empty() {
+            log::debug!("Evicted {} widget descriptions", evicted.len());
+        }
+        evicted
+    }
 }
 
 impl KymaWidgetExtractor {
     pub fn new() -> Self {
         Self {
-            widget_descriptions: HashMap::new(),
+            cache: RwLock::new(DescriptionCache::default()),
+            clock: AtomicU64::new(0),
+            fields: FieldAliases::default(),
+            labels: LabelSanitizer::default(),
+        }
+    }
+
+    /// Reads descriptions with `fields` instead of the default aliases
+    pub fn with_field_aliases(mut self, fields: FieldAliases) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn field_aliases(&self) -> &FieldAliases {
+        &self.fields
+    }
+
+    /// Cleans labels with `labels`; give the engine the same one through
+    /// `EngineConfig::label_sanitizer` so both sides compare identical labels
+    pub fn with_label_sanitizer(mut self, labels: LabelSanitizer) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn label_sanitizer(&self) -> &LabelSanitizer {
+        &self.labels
+    }
+
+    /// An extractor keeping at most `max_cache_size` descriptions, see `set_max_cache_size`
+    pub fn with_max_cache_size(max_cache_size: usize) -> Self {
+        let extractor = Self::new();
+        extractor.write().max_cache_size = Some(max_cache_size);
+        extractor
+    }
+
+    pub fn max_cache_size(&self) -> Option<usize> {
+        self.read().max_cache_size
+    }
+
+    /// Bounds the cache, evicting the least recently cached or looked up descriptions
+    /// beyond `max_cache_size` right away and whenever one is cached. Pinned descriptions
+    /// are never evicted, so the cache can exceed the bound if more than that are pinned.
+    /// Returns the evicted event IDs.
+    pub fn set_max_cache_size(&self, max_cache_size: Option<usize>) -> Vec<i64> {
+        let mut cache = self.write();
+        cache.max_cache_size = max_cache_size;
+        cache.evict()
+    }
+
+    /// Protects an event ID from eviction; it need not be cached yet
+    pub fn pin(&self, event_id: i64) {
+        self.write().pinned.insert(event_id);
+    }
+
+    pub fn unpin(&self, event_id: i64) {
+        self.write().pinned.remove(&event_id);
+    }
+
+    /// Replaces the pinned event IDs, e.g. with those of a sound just loaded, evicting
+    /// previously pinned descriptions if the cache is over its bound
+    pub fn set_pinned(&self, event_ids: impl IntoIterator<Item = i64>) -> Vec<i64> {
+        let mut cache = self.write();
+        cache.pinned = event_ids.into_iter().collect();
+        cache.evict()
+    }
+
+    pub fn is_pinned(&self, event_id: i64) -> bool {
+        self.read().pinned.contains(&event_id)
+    }
+
+    // A panic while holding the lock cannot leave the maps half-updated in a way that
+    // matters more than losing the cache, so a poisoned lock is used as is
+    fn read(&self) -> RwLockReadGuard<'_, DescriptionCache> {
+        self.cache
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, DescriptionCache> {
+        self.cache
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The cached description of `event_id`, marking it as used
+    fn description(&self, event_id: i64) -> Option<Arc<HashMap<String, Value>>> {
+        let cache = self.read();
+        let data = cache.widget_descriptions.get(&event_id)?.clone();
+        if let Some(last_used) = cache.last_used.get(&event_id) {
+            last_used.store(self.tick(), Ordering::Relaxed);
+        }
+        Some(data)
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Caches a description by its `concreteEventID`
+    ///
+    /// Event IDs change when a sound is recompiled, while `!EventName`s do not. A
+    /// description whose name was cached under another ID replaces that entry. The
+    /// sub-controls of an aggregate widget are cached as widgets of their own.
+    pub fn cache_widget_description(&self, kyma_data: HashMap<String, Value>) {
+        let mut entries = Vec::new();
+        self.collect_entries(kyma_data, &mut entries);
+        if entries.is_empty() {
+            return;
+        }
+
+        let mut cache = self.write();
+        for (id, name, data) in entries {
+            log::trace!("Caching widget description for event ID: {id}");
+            if let Some(name) = name {
+                if let Some(previous) = cache.event_names.insert(name, id) {
+                    if previous != id {
+                        log::debug!("Event ID {previous} was recompiled as {id}");
+                        cache.remove(previous);
+                    }
+                }
+            }
+            cache.widget_descriptions.insert(id, Arc::new(data));
+            cache.last_used.insert(id, AtomicU64::new(self.tick()));
+            cache.evict();
+        }
+    }
+
+    /// Event IDs that caching `kyma_data` would drop, as the event names it carries are
+    /// cached under those IDs now, e.g. from before the sound was recompiled
+    pub fn replaced_event_ids(&self, kyma_data: &HashMap<String, Value>) -> Vec<i64> {
+        let mut entries = Vec::new();
+        self.collect_entries(kyma_data.clone(), &mut entries);
+
+        let cache = self.read();
+        entries
+            .into_iter()
+            .filter_map(|(id, name, _)| {
+                let previous = *cache.event_names.get(&name?)?;
+                (previous != id).then_some(previous)
+            })
+            .collect()
+    }
+
+    /// The entries to cache for a description, sub-controls before their aggregate, each
+    /// with its event ID and event name
+    fn collect_entries(
+        &self,
+        kyma_data: HashMap<String, Value>,
+        entries: &mut Vec<(i64, Option<String>, HashMap<String, Value>)>,
+    ) {
+        let Some(id) = self.extract_event_id(&kyma_data) else {
+            return;
+        };
+        if let Some(reason) = self.classify_description(&kyma_data) {
+            log::debug!("Not caching widget {id}: {reason}");
+            return;
+        }
+        if self.is_aggregate(&kyma_data) {
+            for child in self.sub_control_entries(id, &kyma_data) {
+                self.collect_entries(child, entries);
+            }
+        }
+        let name = self.extract_event_name(&kyma_data);
+        entries.push((id, name, kyma_data));
+    }
+
+    /// The sub-controls of an aggregate, linked to it through `aggregateEventID`.
+    /// Unlabelled ones are labelled after the aggregate and their position in it.
+    fn sub_control_entries(
+        &self,
+        aggregate_id: i64,
+        data: &HashMap<String, Value>,
+    ) -> Vec<HashMap<String, Value>> {
+        let aggregate_label = self.extract_label(data);
+
+        let mut children = Vec::new();
+        for (index, child) in sub_control_descriptions(data).iter().enumerate() {
+            let Value::Object(child) = child else {
+                continue;
+            };
+            let mut child: HashMap<String, Value> = child.clone().into_iter().collect();
+            if self.extract_event_id(&child).is_none() {
+                log::debug!(
+                    "Skipping sub-control {index} of aggregate {aggregate_id} without an event ID"
+                );
+                continue;
+            }
+
+            child.insert("aggregateEventID".to_string(), Value::from(aggregate_id));
+            let labelled = ["label", "name", "title", "address"]
+                .into_iter()
+                .any(|field| self.extract_string_field(&child, field).is_some());
+            if let (false, Some(label)) = (labelled, &aggregate_label) {
+                child.insert(
+                    "label".to_string(),
+                    Value::from(format!("{label} {}", index + 1)),
+                );
+            }
+            children.push(child);
+        }
+        children
+    }
+
+    fn is_aggregate(&self, data: &HashMap<String, Value>) -> bool {
+        self.extract_bool_field(data, "isAggregate") == Some(true)
+            && !sub_control_descriptions(data).is_empty()
+    }
+
+    /// Metadata of the sub-controls of an aggregate widget, in order; empty for other
+    /// widgets
+    pub fn sub_controls(&self, event_id: i64) -> Vec<WidgetMetadata> {
+        self.extract_widget_metadata(event_id)
+            .map(|metadata| {
+                metadata
+                    .sub_control_ids
+                    .iter()
+                    .filter_map(|&child| self.extract_widget_metadata(child))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The event ID currently cached for an `!EventName`, given with or without the `!`
+    pub fn event_id_for_name(&self, name: &str) -> Option<i64> {
+        self.read()
+            .event_names
+            .get(name.strip_prefix('!').unwrap_or(name))
+            .copied()
+    }
+
+    /// Caches every widget of a Kyma widget description response
+    ///
+    /// Accepts an array of descriptions, an envelope holding one under `widgets` or `vcs`,
+    /// or a single description. Entries that fail validation are reported and skipped;
+    /// only JSON that does not parse, or holds no descriptions at all, is an error.
+    pub fn cache_widget_descriptions_from_json(
+        &self,
+        json_str: &str,
+    ) -> Result<CacheReport, WidgetIntelligenceError> {
+        let json: Value = serde_json::from_str(json_str).map_err(|e| {
+            WidgetIntelligenceError::ParseError(format!("Failed to parse JSON: {e}"))
+        })?;
+        let mut report = CacheReport::default();
+        if !self.cache_response(json, &mut report) {
+            return Err(no_descriptions());
+        }
+
+        log::debug!(
+            "Cached {} widget descriptions, {} rejected, {} skipped",
+            report.cached.len(),
+            report.errors.len(),
+            report.skipped.len()
+        );
+        Ok(report)
+    }
+
+    /// Like `cache_widget_descriptions_from_json`, but caches each description of an array
+    /// as soon as it is parsed, for layouts of thousands of widgets
+    ///
+    /// Only one description of an array is held in memory at a time rather than the whole
+    /// response. An object is read whole first, as a `concreteEventID` anywhere in it makes
+    /// it a description whose `widgets` are sub-controls rather than an envelope. If an
+    /// array breaks off midway, the descriptions before the break stay cached.
+    pub fn cache_widget_descriptions_from_reader<R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<CacheReport, WidgetIntelligenceError> {
+        let parse_error = |e: serde_json::Error| {
+            WidgetIntelligenceError::ParseError(format!("Failed to parse JSON: {e}"))
+        };
+
+        let mut report = CacheReport::default();
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        let found = DescriptionStream {
+            extractor: self,
+            report: &mut report,
+        }
+        .deserialize(&mut deserializer)
+        .map_err(parse_error)?;
+        deserializer.end().map_err(parse_error)?;
+
+        if !found {
+            return Err(no_descriptions());
+        }
+
+        log::debug!(
+            "Streamed {} widget descriptions, {} rejected, {} skipped",
+            report.cached.len(),
+            report.errors.len(),
+            report.skipped.len()
+        );
+        Ok(report)
+    }
+
+    /// Caches the descriptions of a parsed response, returning `false` if it holds none
+    fn cache_response(&self, json: Value, report: &mut CacheReport) -> bool {
+        match widget_entries(json, &self.fields) {
+            Ok(entries) => {
+                for entry in entries {
+                    let index = report.entry_count();
+                    self.cache_entry(index, entry, report);
+                }
+                true
+            }
+            Err(Value::Object(object)) => {
+                // A status message or the like rather than a malformed response
+                let data: HashMap<String, Value> = object.into_iter().collect();
+                match self.classify_description(&data) {
+                    Some(reason) => {
+                        let index = report.entry_count();
+                        report.skipped.push(SkippedEntry { index, reason });
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Validates and caches one entry of a description response
+    fn cache_entry(&self, index: usize, entry: Value, report: &mut CacheReport) {
+        let Value::Object(object) = entry else {
+            report.errors.push(CacheEntryError {
+                index,
+                message: "Widget description must be an object".to_string(),
+            });
+            return;
+        };
+
+        match self.try_cache_widget_description(object.into_iter().collect()) {
+            CacheOutcome::Cached(validation) => {
+                report.cached.extend(validation.event_id);
+                if !validation.is_clean() {
+                    report.warnings.push(validation);
+                }
+            }
+            CacheOutcome::Skipped(reason) => report.skipped.push(SkippedEntry { index, reason }),
+            CacheOutcome::Rejected(validation) => report.errors.push(CacheEntryError {
+                index,
+                message: validation.error_message(),
+            }),
         }
     }
 
-    pub fn cache_widget_description(&mut self, kyma_data: HashMap<String, Value>) {
-        if let Some(Value::Number(event_id)) = kyma_data.get("concreteEventID") {
-            if let Some(id) = event_id.as_i64() {
-                log::trace!("Caching widget description for event ID: {id}");
-                self.widget_descriptions.insert(id, kyma_data);
+    /// Caches a description unless it is not a controllable widget or fails validation,
+    /// reporting which
+    pub fn try_cache_widget_description(&self, data: HashMap<String, Value>) -> CacheOutcome {
+        if let Some(reason) = self.classify_description(&data) {
+            return CacheOutcome::Skipped(reason);
+        }
+
+        let validation = self.validate_description(&data);
+        if !validation.is_valid() {
+            return CacheOutcome::Rejected(validation);
+        }
+        self.cache_widget_description(data);
+        CacheOutcome::Cached(validation)
+    }
+
+    /// Why a message is not a widget that can be learned, if it is not: a Pacarana status
+    /// message, a meter or another display driven by the sound, or text. `None` for
+    /// anything that may be a control, including descriptions that fail validation.
+    pub fn classify_description(&self, data: &HashMap<String, Value>) -> Option<SkipReason> {
+        let display_type = self
+            .extract_string_field(data, "displayType")
+            .map(|display_type| display_type.trim().to_lowercase());
+        if let Some(display_type) = display_type.as_deref() {
+            if METER_DISPLAY_TYPES.contains(&display_type) {
+                return Some(SkipReason::Meter);
+            }
+            if TEXT_DISPLAY_TYPES.contains(&display_type) {
+                return Some(SkipReason::Text);
             }
         }
+
+        if self.fields.contains(data, "concreteEventID") {
+            return None;
+        }
+        if STATUS_FIELDS.iter().any(|field| data.contains_key(*field)) {
+            return Some(SkipReason::Status);
+        }
+        let has_range =
+            self.fields.contains(data, "minimum") || self.fields.contains(data, "maximum");
+        if !has_range && self.extract_string_field(data, "text").is_some() {
+            return Some(SkipReason::Text);
+        }
+        None
     }
 
+    /// A widget to learn `current_value` from; `None` for event IDs without a cached
+    /// description and for aggregates, whose values are learned per sub-control
     pub fn create_training_widget(&self, event_id: i64, current_value: f64) -> Option<Widget> {
-        let kyma_data = self.widget_descriptions.get(&event_id)?;
+        let kyma_data = self.description(event_id)?;
+        if self.is_aggregate(&kyma_data) {
+            log::trace!("Not training on aggregate widget {event_id}");
+            return None;
+        }
 
         let widget = Widget {
-            label: self.extract_label(kyma_data),
-            minimum: self.extract_float_field(kyma_data, "minimum"),
-            maximum: self.extract_float_field(kyma_data, "maximum"),
+            label: self.extract_label(&kyma_data),
+            minimum: self.extract_float_field(&kyma_data, "minimum"),
+            maximum: self.extract_float_field(&kyma_data, "maximum"),
             current_value: Some(current_value),
-            is_generated: self.extract_bool_field(kyma_data, "isGenerated"),
-            display_type: self.extract_display_type(kyma_data),
-            event_id: Some(event_id as u64),
+            is_generated: self.extract_bool_field(&kyma_data, "isGenerated"),
+            display_type: self.extract_display_type(&kyma_data),
+            event_id: u64::try_from(event_id).ok(),
             values: vec![current_value],
+            value_kind: self.extract_value_kind(&kyma_data),
+            taper: self.extract_taper(&kyma_data),
         };
 
         log::trace!(
@@ -44,20 +545,79 @@ impl KymaWidgetExtractor {
         Some(widget)
     }
 
-    pub fn get_cached_description(&self, event_id: i64) -> Option<&HashMap<String, Value>> {
-        self.widget_descriptions.get(&event_id)
+    pub fn get_cached_description(&self, event_id: i64) -> Option<Arc<HashMap<String, Value>>> {
+        self.description(event_id)
     }
 
     pub fn get_cached_event_ids(&self) -> Vec<i64> {
-        self.widget_descriptions.keys().copied().collect()
+        self.read().widget_descriptions.keys().copied().collect()
+    }
+
+    /// The cached descriptions as they are now, e.g. before a new sound is loaded, to
+    /// compare with `diff_against` later. Descriptions are shared, not copied.
+    pub fn cache_snapshot(&self) -> CacheSnapshot {
+        CacheSnapshot {
+            descriptions: self.read().widget_descriptions.clone(),
+        }
+    }
+
+    /// What changed in the cache since `previous` was taken: the widgets added and
+    /// removed, and those whose description differs, each sorted by event ID
+    ///
+    /// Removed widgets are those whose learning the user may want to migrate or archive,
+    /// see `PersistentWidgetSuggestionEngine::remap_event_ids`.
+    pub fn diff_against(&self, previous: &CacheSnapshot) -> CacheDiff {
+        let current = self.cache_snapshot();
+        let entry = |event_id: i64, data: &HashMap<String, Value>| CacheDiffEntry {
+            event_id,
+            label: self.extract_label(data),
+        };
+
+        let mut diff = CacheDiff::default();
+        for (&event_id, data) in &current.descriptions {
+            match previous.descriptions.get(&event_id) {
+                None => diff.added.push(entry(event_id, data)),
+                Some(old) if Arc::ptr_eq(old, data) || old == data => {}
+                Some(old) => {
+                    let fields: BTreeSet<&String> = old
+                        .keys()
+                        .chain(data.keys())
+                        .filter(|key| old.get(*key) != data.get(*key))
+                        .collect();
+                    diff.changed.push(DescriptionChange {
+                        event_id,
+                        label: self.extract_label(data),
+                        fields: fields.into_iter().cloned().collect(),
+                    });
+                }
+            }
+        }
+        for (&event_id, data) in &previous.descriptions {
+            if !current.descriptions.contains_key(&event_id) {
+                diff.removed.push(entry(event_id, data));
+            }
+        }
+
+        diff.added.sort_by_key(|entry| entry.event_id);
+        diff.removed.sort_by_key(|entry| entry.event_id);
+        diff.changed.sort_by_key(|change| change.event_id);
+        diff
     }
 
-    pub fn clear_cache(&mut self) {
-        self.widget_descriptions.clear();
+    /// Empties the cache; pinned event IDs stay pinned
+    pub fn clear_cache(&self) {
+        let mut cache = self.write();
+        cache.widget_descriptions.clear();
+        cache.event_names.clear();
+        cache.last_used.clear();
     }
 
     pub fn cache_size(&self) -> usize {
-        self.widget_descriptions.len()
+        self.read().widget_descriptions.len()
+    }
+
+    fn is_cached(&self, event_id: i64) -> bool {
+        self.read().widget_descriptions.contains_key(&event_id)
     }
 
     pub fn extract_all_widgets_with_values(&self, values: &HashMap<i64, f64>) -> Vec<Widget> {
@@ -72,93 +632,206 @@ impl KymaWidgetExtractor {
         widgets
     }
 
-    fn extract_label(&self, data: &HashMap<String, Value>) -> Option<String> {
-        if let Some(Value::String(label)) = data.get("label") {
-            if !label.is_empty() {
-                return Some(label.clone());
-            }
+    /// The event ID under any of its keys, or the composite one if there is none
+    fn extract_event_id(&self, data: &HashMap<String, Value>) -> Option<i64> {
+        match self.fields.raw(data, "concreteEventID") {
+            Some(_) => self.fields.i64(data, "concreteEventID"),
+            None => composite_identity(&self.fields, data),
         }
+    }
 
-        if let Some(Value::String(name)) = data.get("name") {
-            if !name.is_empty() {
-                return Some(name.clone());
-            }
+    fn extract_label(&self, data: &HashMap<String, Value>) -> Option<String> {
+        let sanitized = |label: String| self.labels.sanitize(&label);
+
+        if let Some(label) = self.extract_string_field(data, "label").and_then(sanitized) {
+            return Some(label);
         }
 
-        if let Some(Value::String(title)) = data.get("title") {
-            if !title.is_empty() {
-                return Some(title.clone());
-            }
+        // Preferred over a `name` that is an address like "/vcs/!Amp"
+        if let Some(event_name) = self.extract_event_name(data).and_then(sanitized) {
+            return Some(event_name);
         }
 
-        if let Some(Value::Number(event_id)) = data.get("concreteEventID") {
-            return Some(format!("Widget {event_id}"));
+        ["name", "title"]
+            .into_iter()
+            .find_map(|field| self.extract_string_field(data, field).and_then(sanitized))
+            .or_else(|| {
+                self.extract_event_id(data)
+                    .map(|event_id| format!("Widget {event_id}"))
+            })
+    }
+
+    fn extract_value_kind(&self, data: &HashMap<String, Value>) -> Option<ValueKind> {
+        if self.extract_bool_field(data, "isBoolean") == Some(true) {
+            return Some(ValueKind::Boolean);
         }
 
-        None
+        grid_steps(
+            self.extract_float_field(data, "minimum"),
+            self.extract_float_field(data, "maximum"),
+            self.extract_grid(data),
+        )
+        .map(|steps| ValueKind::Stepped { steps })
     }
 
-    fn extract_display_type(&self, data: &HashMap<String, Value>) -> Option<String> {
-        if let Some(Value::String(display_type)) = data.get("displayType") {
-            return Some(display_type.clone());
+    /// The `!EventName` from an `address` or `name` field, without the `!`
+    fn extract_event_name(&self, data: &HashMap<String, Value>) -> Option<String> {
+        ["address", "name"]
+            .into_iter()
+            .filter_map(|field| self.extract_string_field(data, field))
+            .find_map(|value| parse_event_name(&value))
+    }
+
+    fn extract_taper(&self, data: &HashMap<String, Value>) -> Option<Taper> {
+        let taper = self.extract_string_field(data, "taper")?;
+        match taper.to_lowercase().as_str() {
+            "linear" | "lin" => Some(Taper::Linear),
+            "log" | "logarithmic" => Some(Taper::Logarithmic),
+            "exp" | "exponential" => Some(Taper::Exponential),
+            _ => {
+                log::debug!("Unknown taper: {taper}");
+                None
+            }
         }
+    }
+
+    /// The value increment, from `grid` or an alias such as `step`
+    fn extract_grid(&self, data: &HashMap<String, Value>) -> Option<f64> {
+        self.extract_float_field(data, "grid")
+            .filter(|grid| *grid > 0.0)
+    }
+
+    /// Position on the VCS from a `layout`, `frame` or `bounds` object or the
+    /// description itself: `x`/`left` and `y`/`top` with an optional `width`/`height` or
+    /// `right`/`bottom`, or else a grid `column` and `row`
+    fn extract_layout(&self, data: &HashMap<String, Value>) -> Option<WidgetLayout> {
+        let nested = ["layout", "frame", "bounds"]
+            .into_iter()
+            .find_map(|field| data.get(field)?.as_object());
+        let field = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                let value = match nested {
+                    Some(object) => object.get(*name),
+                    None => data.get(*name),
+                }?;
+                match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => s.parse::<f64>().ok(),
+                    _ => None,
+                }
+                .filter(|v| v.is_finite())
+            })
+        };
 
-        if let Some(Value::String(widget_type)) = data.get("widgetType") {
-            return Some(widget_type.clone());
+        if let (Some(x), Some(y)) = (field(&["x", "left"]), field(&["y", "top"])) {
+            let width = field(&["width"]).or_else(|| Some(field(&["right"])? - x));
+            let height = field(&["height"]).or_else(|| Some(field(&["bottom"])? - y));
+            return Some(WidgetLayout {
+                x,
+                y,
+                width: width.unwrap_or(0.0).max(0.0),
+                height: height.unwrap_or(0.0).max(0.0),
+            });
         }
 
-        if let Some(Value::String(control_type)) = data.get("controlType") {
-            return Some(control_type.clone());
+        let (column, row) = (field(&["column"])?, field(&["row"])?);
+        Some(WidgetLayout {
+            x: column,
+            y: row,
+            width: 1.0,
+            height: 1.0,
+        })
+    }
+
+    /// The widgets closest to `event_id` on the VCS, nearest first, at most
+    /// `MAX_NEARBY_WIDGETS` of them
+    ///
+    /// Distances are between the widgets' centres, in the units of the descriptions.
+    /// Widgets without a layout are never near anything.
+    pub fn widgets_near(&self, event_id: i64) -> Vec<NearbyWidget> {
+        let cache = self.read();
+        let Some(layout) = cache
+            .widget_descriptions
+            .get(&event_id)
+            .and_then(|data| self.extract_layout(data))
+        else {
+            return Vec::new();
+        };
+
+        let mut nearby: Vec<NearbyWidget> = cache
+            .widget_descriptions
+            .iter()
+            .filter(|(&other, _)| other != event_id)
+            .filter_map(|(&other, data)| {
+                Some(NearbyWidget {
+                    event_id: other,
+                    label: self.extract_label(data),
+                    distance: layout.distance_to(&self.extract_layout(data)?),
+                })
+            })
+            .collect();
+        nearby.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then_with(|| a.event_id.cmp(&b.event_id))
+        });
+        nearby.truncate(MAX_NEARBY_WIDGETS);
+        nearby
+    }
+
+    fn extract_display_type(&self, data: &HashMap<String, Value>) -> Option<String> {
+        if let Some(display_type) = self.extract_string_field(data, "displayType") {
+            return Some(display_type);
         }
 
-        None
+        infer_display_type(
+            self.extract_bool_field(data, "isBoolean"),
+            self.extract_bool_field(data, "isFullRange"),
+            self.extract_bool_field(data, "isEventSource"),
+        )
+        .map(str::to_string)
     }
 
     fn extract_float_field(&self, data: &HashMap<String, Value>, field_name: &str) -> Option<f64> {
-        if let Some(value) = data.get(field_name) {
-            match value {
-                Value::Number(n) => n.as_f64(),
-                Value::String(s) => s.parse::<f64>().ok(),
-                _ => None,
-            }
-        } else {
-            None
-        }
+        self.fields.f64(data, field_name)
     }
 
     fn extract_bool_field(&self, data: &HashMap<String, Value>, field_name: &str) -> Option<bool> {
-        if let Some(value) = data.get(field_name) {
-            match value {
-                Value::Bool(b) => Some(*b),
-                Value::String(s) => match s.to_lowercase().as_str() {
-                    "true" | "1" | "yes" | "on" => Some(true),
-                    "false" | "0" | "no" | "off" => Some(false),
-                    _ => None,
-                },
-                Value::Number(n) => n.as_i64().map(|num| num != 0),
-                _ => None,
-            }
-        } else {
-            None
-        }
+        self.fields.bool(data, field_name)
     }
 
     pub fn extract_widget_metadata(&self, event_id: i64) -> Option<WidgetMetadata> {
-        let kyma_data = self.widget_descriptions.get(&event_id)?;
+        let kyma_data = self.description(event_id)?;
 
         Some(WidgetMetadata {
             event_id,
-            label: self.extract_label(kyma_data),
-            display_type: self.extract_display_type(kyma_data),
-            minimum: self.extract_float_field(kyma_data, "minimum"),
-            maximum: self.extract_float_field(kyma_data, "maximum"),
-            default_value: self
-                .extract_float_field(kyma_data, "defaultValue")
-                .or_else(|| self.extract_float_field(kyma_data, "default")),
-            is_generated: self.extract_bool_field(kyma_data, "isGenerated"),
-            units: self.extract_string_field(kyma_data, "units"),
-            category: self.extract_string_field(kyma_data, "category"),
-            description: self.extract_string_field(kyma_data, "description"),
+            label: self.extract_label(&kyma_data),
+            event_name: self.extract_event_name(&kyma_data),
+            aggregate_event_id: self.fields.i64(&kyma_data, "aggregateEventID"),
+            sub_control_ids: if self.is_aggregate(&kyma_data) {
+                sub_control_descriptions(&kyma_data)
+                    .iter()
+                    .filter_map(|child| {
+                        let child: HashMap<String, Value> =
+                            child.as_object()?.clone().into_iter().collect();
+                        self.extract_event_id(&child)
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            display_type: self.extract_display_type(&kyma_data),
+            minimum: self.extract_float_field(&kyma_data, "minimum"),
+            maximum: self.extract_float_field(&kyma_data, "maximum"),
+            default_value: self.extract_float_field(&kyma_data, "defaultValue"),
+            is_generated: self.extract_bool_field(&kyma_data, "isGenerated"),
+            value_kind: self.extract_value_kind(&kyma_data),
+            taper: self.extract_taper(&kyma_data),
+            grid: self.extract_grid(&kyma_data),
+            layout: self.extract_layout(&kyma_data),
+            units: self.extract_string_field(&kyma_data, "units"),
+            category: self.extract_string_field(&kyma_data, "category"),
+            description: self.extract_string_field(&kyma_data, "description"),
         })
     }
 
@@ -167,53 +840,717 @@ impl KymaWidgetExtractor {
         data: &HashMap<String, Value>,
         field_name: &str,
     ) -> Option<String> {
-        if let Some(Value::String(s)) = data.get(field_name) {
-            if !s.is_empty() {
-                Some(s.clone())
-            } else {
-                None
-            }
+        self.fields.string(data, field_name)
+    }
+
+    /// Builds a preset from live values, labelling each from its cached description
+    ///
+    /// Preset values are normalized, so each value is normalized to its widget's cached
+    /// range; values of widgets without a range are kept as they are. Event IDs without
+    /// a cached description are left out.
+    pub fn snapshot_preset(&self, name: &str, values: &HashMap<i64, f64>) -> Preset {
+        let mut event_ids: Vec<i64> = values.keys().copied().collect();
+        event_ids.sort_unstable();
+
+        let widget_values = event_ids
+            .into_iter()
+            .filter_map(|event_id| {
+                let metadata = self.extract_widget_metadata(event_id)?;
+                let value = values[&event_id];
+                Some(WidgetValue {
+                    widget_id: event_id.to_string(),
+                    value: metadata.normalize_value(value).unwrap_or(value),
+                    label: metadata.label,
+                    confidence: 1.0,
+                })
+            })
+            .collect();
+
+        new_preset(name, widget_values)
+    }
+
+    /// Builds a complete preset from live values, ready for `store_preset`
+    ///
+    /// Values are normalized like in `snapshot_preset`. Unlike there, every value is kept
+    /// and its confidence says how far it can be trusted: 1.0 within the cached range,
+    /// `UNCHECKED_VALUE_CONFIDENCE` without a cached description to check against, and
+    /// `OUT_OF_RANGE_CONFIDENCE` outside the range. Those are normalized without snapping
+    /// them back into the range, which flags them for `store_preset_validated` to leave out.
+    pub fn snapshot(&self, values: &HashMap<i64, f64>, name: &str) -> Preset {
+        let mut event_ids: Vec<i64> = values.keys().copied().collect();
+        event_ids.sort_unstable();
+
+        let widget_values = event_ids
+            .into_iter()
+            .map(|event_id| {
+                let live = values[&event_id];
+                let metadata = self.extract_widget_metadata(event_id);
+                let (value, confidence) = match &metadata {
+                    Some(metadata) if live.is_finite() && metadata.is_valid_value(live) => {
+                        (metadata.normalize_value(live).unwrap_or(live), 1.0)
+                    }
+                    Some(metadata) => (
+                        metadata.range_position(live).unwrap_or(live),
+                        OUT_OF_RANGE_CONFIDENCE,
+                    ),
+                    None => (live, UNCHECKED_VALUE_CONFIDENCE),
+                };
+                WidgetValue {
+                    widget_id: event_id.to_string(),
+                    label: metadata.and_then(|metadata| metadata.label),
+                    value,
+                    confidence,
+                }
+            })
+            .collect();
+
+        new_preset(name, widget_values)
+    }
+
+    /// Reads the presets of an exported Kyma preset file, see `import_presets`
+    ///
+    /// Snapshots without a name are named after the file.
+    pub fn import_preset_file(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<ImportedPreset>, WidgetIntelligenceError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let file_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Imported preset".to_string());
+        self.import_snapshots(&contents, &file_name)
+    }
+
+    /// Converts the snapshots of an exported Kyma preset file into presets and training
+    /// widgets
+    ///
+    /// Both the JSON and the XML snapshot format are read. JSON holds one snapshot, an
+    /// array of them or an object holding one under `presets` or `snapshots`; each has a
+    /// `name` and its values as an object keyed by event ID or `!EventName`, or as an
+    /// array of `{"eventID", "name", "value"}` entries. XML holds `preset` or `snapshot`
+    /// elements with `value` or `widget` children carrying `eventID` and `name`
+    /// attributes and the value as text or a `value` attribute.
+    ///
+    /// Values resolve by event name first, as event IDs change when a sound is
+    /// recompiled, then by event ID. Values of widgets without a cached description are
+    /// listed in `unresolved`.
+    pub fn import_presets(
+        &self,
+        contents: &str,
+    ) -> Result<Vec<ImportedPreset>, WidgetIntelligenceError> {
+        self.import_snapshots(contents, "Imported preset")
+    }
+
+    fn import_snapshots(
+        &self,
+        contents: &str,
+        default_name: &str,
+    ) -> Result<Vec<ImportedPreset>, WidgetIntelligenceError> {
+        let snapshots = if contents.trim_start().starts_with('<') {
+            parse_xml_snapshots(contents)?
         } else {
-            None
+            parse_json_snapshots(contents)?
+        };
+
+        let count = snapshots.len();
+        let imported: Vec<ImportedPreset> = snapshots
+            .into_iter()
+            .enumerate()
+            .map(|(index, snapshot)| {
+                let name = snapshot.name.clone().unwrap_or_else(|| {
+                    if count == 1 {
+                        default_name.to_string()
+                    } else {
+                        format!("{default_name} {}", index + 1)
+                    }
+                });
+                self.import_snapshot(&name, snapshot)
+            })
+            .collect();
+
+        log::debug!("Imported {} Kyma presets", imported.len());
+        Ok(imported)
+    }
+
+    fn import_snapshot(&self, name: &str, snapshot: PresetSnapshot) -> ImportedPreset {
+        let mut values = HashMap::new();
+        let mut unresolved = Vec::new();
+        for entry in snapshot.values {
+            let resolved = entry
+                .name
+                .as_deref()
+                .and_then(|name| self.event_id_for_name(name))
+                .or(entry.event_id.filter(|&event_id| self.is_cached(event_id)));
+            match resolved {
+                Some(event_id) => {
+                    values.insert(event_id, entry.value);
+                }
+                None => unresolved.push(entry.key()),
+            }
+        }
+
+        let mut preset = self.snapshot_preset(name, &values);
+        preset.description = snapshot.description;
+
+        let training_widgets = preset
+            .widget_values
+            .iter()
+            .filter_map(|widget_value| {
+                let event_id = widget_value.widget_id.parse::<i64>().ok()?;
+                self.create_training_widget(event_id, widget_value.value)
+            })
+            .collect();
+
+        ImportedPreset {
+            preset,
+            training_widgets,
+            unresolved,
         }
     }
 
-    pub fn parse_kyma_json_string(json_str: &str) -> Result<HashMap<String, Value>, String> {
-        serde_json::from_str(json_str).map_err(|e| format!("Failed to parse JSON: {e}"))
+    /// Checks each normalized preset value against the cached range of its event ID
+    pub fn validate_preset(&self, preset: &Preset) -> PresetValidationReport {
+        let mut report = PresetValidationReport {
+            preset: preset.name.clone(),
+            ..Default::default()
+        };
+
+        for widget_value in &preset.widget_values {
+            let Some(metadata) = widget_value
+                .widget_id
+                .parse::<i64>()
+                .ok()
+                .and_then(|event_id| self.extract_widget_metadata(event_id))
+            else {
+                report.unchecked.push(widget_value.widget_id.clone());
+                continue;
+            };
+
+            let value = widget_value.value;
+            if !value.is_finite() || !metadata.is_valid_normalized(value) {
+                report.out_of_range.push(OutOfRangeValue {
+                    event_id: metadata.event_id,
+                    label: metadata.label,
+                    value,
+                    minimum: metadata.minimum,
+                    maximum: metadata.maximum,
+                });
+            }
+        }
+
+        report
     }
 
-    pub fn validate_kyma_data(data: &HashMap<String, Value>) -> Result<(), String> {
-        if !data.contains_key("concreteEventID") {
-            return Err("Missing required field: concreteEventID".to_string());
+    /// Checks a widget description for data-quality problems
+    ///
+    /// Unlike `validate_kyma_data`, which only requires an event ID, the report lists
+    /// every problem found: missing or inverted ranges, numeric fields that do not parse,
+    /// implausibly wide ranges and defaults outside the range. Problems that make the
+    /// description unusable count as errors, see `DescriptionIssue::is_error`.
+    pub fn validate_description(&self, data: &HashMap<String, Value>) -> DescriptionReport {
+        let mut issues = Vec::new();
+
+        let event_id = match self.fields.raw(data, "concreteEventID") {
+            None => {
+                let event_id = composite_identity(&self.fields, data);
+                issues.push(match event_id {
+                    Some(event_id) => DescriptionIssue::CompositeEventId { event_id },
+                    None => DescriptionIssue::MissingEventId,
+                });
+                event_id
+            }
+            Some(value) => {
+                let event_id = self.extract_event_id(data);
+                if event_id.is_none() {
+                    issues.push(DescriptionIssue::InvalidEventId {
+                        value: value.to_string(),
+                    });
+                }
+                event_id
+            }
+        };
+
+        for field in NUMERIC_FIELDS {
+            let Some(value) = self.fields.raw(data, field) else {
+                continue;
+            };
+            if self.extract_float_field(data, field).is_none() {
+                issues.push(DescriptionIssue::UnparseableNumber {
+                    field: field.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+
+        let minimum = self.extract_float_field(data, "minimum");
+        let maximum = self.extract_float_field(data, "maximum");
+        if !self.fields.contains(data, "minimum") {
+            issues.push(DescriptionIssue::MissingMinimum);
+        }
+        if !self.fields.contains(data, "maximum") {
+            issues.push(DescriptionIssue::MissingMaximum);
         }
 
-        if let Some(Value::Number(event_id)) = data.get("concreteEventID") {
-            if event_id.as_i64().is_none() {
-                return Err("concreteEventID must be a valid integer".to_string());
+        if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+            if minimum >= maximum {
+                issues.push(DescriptionIssue::InvertedRange { minimum, maximum });
+            } else if maximum - minimum > MAX_PLAUSIBLE_SPAN {
+                issues.push(DescriptionIssue::SuspiciousRange { minimum, maximum });
+            } else if let Some(default_value) = self.extract_float_field(data, "defaultValue") {
+                if default_value < minimum || default_value > maximum {
+                    issues.push(DescriptionIssue::DefaultOutOfRange {
+                        default_value,
+                        minimum,
+                        maximum,
+                    });
+                }
             }
-        } else {
-            return Err("concreteEventID must be a number".to_string());
         }
 
-        Ok(())
+        DescriptionReport {
+            event_id,
+            label: self.extract_label(data),
+            issues,
+        }
+    }
+
+    /// Reports of the cached descriptions with problems, by event ID
+    pub fn validate_cache(&self) -> Vec<DescriptionReport> {
+        let cache = self.read();
+        let mut event_ids: Vec<i64> = cache.widget_descriptions.keys().copied().collect();
+        event_ids.sort_unstable();
+        event_ids
+            .into_iter()
+            .map(|event_id| self.validate_description(&cache.widget_descriptions[&event_id]))
+            .filter(|report| !report.is_clean())
+            .collect()
+    }
+
+    pub fn parse_kyma_json_string(
+        json_str: &str,
+    ) -> Result<HashMap<String, Value>, WidgetIntelligenceError> {
+        serde_json::from_str(json_str)
+            .map_err(|e| WidgetIntelligenceError::ParseError(format!("Failed to parse JSON: {e}")))
+    }
+
+    /// Checks that a description has an event ID, under the default field aliases
+    pub fn validate_kyma_data(
+        data: &HashMap<String, Value>,
+    ) -> Result<(), WidgetIntelligenceError> {
+        validate_event_id(&FieldAliases::default(), data).map(drop)
+    }
+
+    /// The event ID of a description, read with this extractor's field aliases, or the
+    /// reason it has none
+    pub fn validated_event_id(
+        &self,
+        data: &HashMap<String, Value>,
+    ) -> Result<i64, WidgetIntelligenceError> {
+        validate_event_id(&self.fields, data)
+    }
+}
+
+/// Caches the descriptions of a response while it is deserialized; the value is whether
+/// the response held descriptions at all
+struct DescriptionStream<'a> {
+    extractor: &'a KymaWidgetExtractor,
+    report: &'a mut CacheReport,
+}
+
+impl<'de> DeserializeSeed<'de> for DescriptionStream<'_> {
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DescriptionStream<'_> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("an array of widget descriptions or an object holding one")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<bool, A::Error> {
+        while let Some(entry) = seq.next_element::<Value>()? {
+            let index = self.report.entry_count();
+            self.extractor.cache_entry(index, entry, self.report);
+        }
+        Ok(true)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<bool, A::Error> {
+        // Whether the object is an envelope or a description depends on keys that may
+        // follow its `widgets`, so it is only looked at once it has been read whole
+        let mut object = serde_json::Map::new();
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            object.insert(key, value);
+        }
+        Ok(self
+            .extractor
+            .cache_response(Value::Object(object), self.report))
+    }
+}
+
+/// The widget descriptions in a response: an array, an envelope holding one under
+/// `widgets` or `vcs` (possibly nested), or a single description. Otherwise the value
+/// found instead, e.g. a status message.
+fn widget_entries(json: Value, fields: &FieldAliases) -> Result<Vec<Value>, Value> {
+    match json {
+        Value::Array(entries) => Ok(entries),
+        Value::Object(mut object) => {
+            if fields
+                .keys("concreteEventID")
+                .any(|key| object.contains_key(key))
+            {
+                return Ok(vec![Value::Object(object)]);
+            }
+            match ["widgets", "vcs"]
+                .into_iter()
+                .find_map(|key| object.remove(key))
+            {
+                Some(json) => widget_entries(json, fields),
+                None => Err(Value::Object(object)),
+            }
+        }
+        json => Err(json),
+    }
+}
+
+fn no_descriptions() -> WidgetIntelligenceError {
+    WidgetIntelligenceError::ParseError(
+        "Expected an array of widget descriptions or an object holding one".to_string(),
+    )
+}
+
+/// A preset of `widget_values` saved now
+fn new_preset(name: &str, widget_values: Vec<WidgetValue>) -> Preset {
+    Preset {
+        name: name.to_string(),
+        description: None,
+        widget_values,
+        created_by: None,
+        usage_count: 1,
+        last_used: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        tags: Vec::new(),
+        favorite: false,
+    }
+}
+
+/// A snapshot as read from a preset file, before its values are resolved
+struct PresetSnapshot {
+    name: Option<String>,
+    description: Option<String>,
+    values: Vec<SnapshotValue>,
+}
+
+struct SnapshotValue {
+    event_id: Option<i64>,
+    /// Event name without the `!`
+    name: Option<String>,
+    value: f64,
+}
+
+impl SnapshotValue {
+    /// How the value was keyed in the file
+    fn key(&self) -> String {
+        match (&self.name, self.event_id) {
+            (Some(name), _) => format!("!{name}"),
+            (None, Some(event_id)) => event_id.to_string(),
+            (None, None) => "?".to_string(),
+        }
+    }
+
+    /// A value keyed by an event ID or an event name
+    fn keyed(key: &str, value: f64) -> Self {
+        let event_id = key.trim().parse().ok();
+        Self {
+            event_id,
+            name: event_id
+                .is_none()
+                .then(|| snapshot_event_name(key))
+                .flatten(),
+            value,
+        }
     }
 }
 
+/// An event name as written in a preset file, with or without the `!`
+fn snapshot_event_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    parse_event_name(name).or_else(|| (!name.is_empty()).then(|| name.to_string()))
+}
+
+fn parse_json_snapshots(contents: &str) -> Result<Vec<PresetSnapshot>, WidgetIntelligenceError> {
+    let json: Value = serde_json::from_str(contents)
+        .map_err(|e| WidgetIntelligenceError::ParseError(format!("Failed to parse JSON: {e}")))?;
+
+    let entries = match json {
+        Value::Array(entries) => entries,
+        Value::Object(mut object) => match ["presets", "snapshots"]
+            .into_iter()
+            .find_map(|key| object.remove(key))
+        {
+            Some(Value::Array(entries)) => entries,
+            Some(_) => {
+                return Err(WidgetIntelligenceError::ParseError(
+                    "Expected an array of preset snapshots".to_string(),
+                ))
+            }
+            None => vec![Value::Object(object)],
+        },
+        _ => {
+            return Err(WidgetIntelligenceError::ParseError(
+                "Expected a preset snapshot or an array of them".to_string(),
+            ))
+        }
+    };
+
+    Ok(entries.iter().filter_map(json_snapshot).collect())
+}
+
+fn json_snapshot(entry: &Value) -> Option<PresetSnapshot> {
+    let object = entry.as_object()?;
+    let text = |field: &str| {
+        object
+            .get(field)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    let values = match ["values", "widgets"]
+        .into_iter()
+        .find_map(|field| object.get(field))
+    {
+        Some(Value::Object(values)) => values
+            .iter()
+            .filter_map(|(key, value)| Some(SnapshotValue::keyed(key, value.as_f64()?)))
+            .collect(),
+        Some(Value::Array(values)) => values.iter().filter_map(json_snapshot_value).collect(),
+        _ => Vec::new(),
+    };
+
+    Some(PresetSnapshot {
+        name: text("name"),
+        description: text("description"),
+        values,
+    })
+}
+
+fn json_snapshot_value(entry: &Value) -> Option<SnapshotValue> {
+    let value = entry.get("value")?.as_f64()?;
+    let event_id = ["eventID", "concreteEventID"]
+        .into_iter()
+        .find_map(|field| entry.get(field)?.as_i64());
+    let name = entry
+        .get("name")
+        .and_then(Value::as_str)
+        .and_then(snapshot_event_name);
+    (event_id.is_some() || name.is_some()).then_some(SnapshotValue {
+        event_id,
+        name,
+        value,
+    })
+}
+
+fn parse_xml_snapshots(contents: &str) -> Result<Vec<PresetSnapshot>, WidgetIntelligenceError> {
+    let document = roxmltree::Document::parse(contents)
+        .map_err(|e| WidgetIntelligenceError::ParseError(format!("Failed to parse XML: {e}")))?;
+
+    Ok(document
+        .descendants()
+        .filter(|node| matches!(node.tag_name().name(), "preset" | "snapshot"))
+        .map(|node| PresetSnapshot {
+            name: node.attribute("name").map(str::to_string),
+            description: node.attribute("description").map(str::to_string),
+            values: node
+                .children()
+                .filter(|child| matches!(child.tag_name().name(), "value" | "widget"))
+                .filter_map(|child| {
+                    let value = child
+                        .attribute("value")
+                        .or_else(|| child.text())?
+                        .trim()
+                        .parse()
+                        .ok()?;
+                    let event_id = ["eventID", "concreteEventID"]
+                        .into_iter()
+                        .find_map(|attribute| child.attribute(attribute)?.trim().parse().ok());
+                    let name = child.attribute("name").and_then(snapshot_event_name);
+                    (event_id.is_some() || name.is_some()).then_some(SnapshotValue {
+                        event_id,
+                        name,
+                        value,
+                    })
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+fn validate_event_id(
+    fields: &FieldAliases,
+    data: &HashMap<String, Value>,
+) -> Result<i64, WidgetIntelligenceError> {
+    let invalid = |reason: &str| WidgetIntelligenceError::ValidationError(reason.to_string());
+    if !fields.contains(data, "concreteEventID") {
+        return composite_identity(fields, data)
+            .ok_or_else(|| invalid("Missing required field: concreteEventID"));
+    }
+    fields
+        .i64(data, "concreteEventID")
+        .ok_or_else(|| invalid("concreteEventID must be a valid integer"))
+}
+
+/// `composite_event_id` of a description without an event ID, from its label (or name
+/// or title), range and display type
+fn composite_identity(fields: &FieldAliases, data: &HashMap<String, Value>) -> Option<i64> {
+    let label = ["label", "name", "title"]
+        .into_iter()
+        .find_map(|field| fields.string(data, field))?;
+    Some(composite_event_id(
+        &label,
+        fields.f64(data, "minimum")?,
+        fields.f64(data, "maximum")?,
+        fields.string(data, "displayType").as_deref(),
+    ))
+}
+
+/// Stands in for the event ID of a widget whose source gives none, so it can still be
+/// cached and learned
+///
+/// The ID is a hash of the label, range and display type, so the same widget gets the
+/// same ID every time it is described, also across restarts. Composite IDs lie at
+/// `COMPOSITE_EVENT_ID_BASE` and above, far past Kyma's event IDs, and are positive so
+/// they convert to the `u64` event IDs of learned widgets and back without loss.
+pub fn composite_event_id(
+    label: &str,
+    minimum: f64,
+    maximum: f64,
+    display_type: Option<&str>,
+) -> i64 {
+    let mut hasher = Sha256::new();
+    hasher.update(label.trim().as_bytes());
+    hasher.update([0]);
+    hasher.update(minimum.to_le_bytes());
+    hasher.update(maximum.to_le_bytes());
+    if let Some(display_type) = display_type {
+        hasher.update(display_type.trim().to_lowercase().as_bytes());
+    }
+
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    COMPOSITE_EVENT_ID_BASE | (u64::from_le_bytes(bytes) >> 2) as i64
+}
+
+/// The sub-control descriptions of an aggregate widget
+fn sub_control_descriptions(data: &HashMap<String, Value>) -> &[Value] {
+    ["children", "widgets", "subWidgets"]
+        .into_iter()
+        .find_map(|field| data.get(field)?.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// The event name in an OSC-style address such as "/vcs/!Amp" or a bare "!Amp",
+/// without the `!`
+pub fn parse_event_name(address: &str) -> Option<String> {
+    address
+        .split('/')
+        .filter_map(|segment| segment.trim().strip_prefix('!'))
+        .find(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Best guess at the display type of a description without one, from its Kyma flags
+///
+/// A boolean event source is taken for a momentary "gate" and any other boolean for a
+/// "toggle". A widget that is flagged as not boolean, full range or an event source is
+/// taken for a "slider". `None` if none of the flags are given.
+pub fn infer_display_type(
+    is_boolean: Option<bool>,
+    is_full_range: Option<bool>,
+    is_event_source: Option<bool>,
+) -> Option<&'static str> {
+    match (is_boolean, is_full_range, is_event_source) {
+        (Some(true), _, Some(true)) => Some("gate"),
+        (Some(true), _, _) => Some("toggle"),
+        (None, None, None) => None,
+        _ => Some("slider"),
+    }
+}
+
+/// Number of grid positions in the range, both ends included, if the grid divides the
+/// range evenly into no more than `MAX_GRID_STEPS` positions
+fn grid_steps(minimum: Option<f64>, maximum: Option<f64>, grid: Option<f64>) -> Option<u32> {
+    let (min, max, grid) = (minimum?, maximum?, grid?);
+    if max <= min {
+        return None;
+    }
+
+    let intervals = (max - min) / grid;
+    let rounded = intervals.round();
+    if rounded < 1.0 || (intervals - rounded).abs() > 1e-6 * rounded.max(1.0) {
+        return None;
+    }
+    let steps = rounded + 1.0;
+    (steps <= f64::from(MAX_GRID_STEPS)).then_some(steps as u32)
+}
+
 impl Default for KymaWidgetExtractor {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[derive(Debug, Clone)]
+impl WidgetSource for KymaWidgetExtractor {
+    fn cache_description(&self, description: HashMap<String, Value>) {
+        self.cache_widget_description(description);
+    }
+
+    fn cached_event_ids(&self) -> Vec<i64> {
+        self.get_cached_event_ids()
+    }
+
+    fn widget_metadata(&self, event_id: i64) -> Option<WidgetMetadata> {
+        self.extract_widget_metadata(event_id)
+    }
+
+    fn create_training_widget(&self, event_id: i64, current_value: f64) -> Option<Widget> {
+        KymaWidgetExtractor::create_training_widget(self, event_id, current_value)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct WidgetMetadata {
     pub event_id: i64,
     pub label: Option<String>,
+    /// The `!EventName`, which unlike the event ID survives recompiling the sound
+    pub event_name: Option<String>,
+    /// The aggregate widget this is a sub-control of
+    pub aggregate_event_id: Option<i64>,
+    /// Event IDs of the sub-controls, for an aggregate widget
+    pub sub_control_ids: Vec<i64>,
     pub display_type: Option<String>,
     pub minimum: Option<f64>,
     pub maximum: Option<f64>,
     pub default_value: Option<f64>,
     pub is_generated: Option<bool>,
+    /// Boolean, or stepped when the grid divides the range evenly
+    pub value_kind: Option<ValueKind>,
+    /// Response curve of the fader
+    pub taper: Option<Taper>,
+    /// Value increment; values snap to `minimum` plus a multiple of it
+    pub grid: Option<f64>,
+    /// Position on the VCS
+    pub layout: Option<WidgetLayout>,
     pub units: Option<String>,
     pub category: Option<String>,
     pub description: Option<String>,
@@ -228,8 +1565,10 @@ impl WidgetMetadata {
             current_value: Some(current_value),
             is_generated: self.is_generated,
             display_type: self.display_type.clone(),
-            event_id: Some(self.event_id as u64),
+            event_id: u64::try_from(self.event_id).ok(),
             values: vec![current_value],
+            value_kind: self.value_kind,
+            taper: self.taper,
         }
     }
 
@@ -242,17 +1581,404 @@ impl WidgetMetadata {
         }
     }
 
+    /// Whether a normalized value lies within the range, 0.0 to 1.0. Values of widgets
+    /// without a range cannot be checked and count as valid.
+    pub fn is_valid_normalized(&self, normalized_value: f64) -> bool {
+        match (self.minimum, self.maximum) {
+            (Some(min), Some(max)) if max > min => (0.0..=1.0).contains(&normalized_value),
+            _ => true,
+        }
+    }
+
+    /// Linear position of `value` in the range, after snapping it to the grid
+    ///
+    /// Normalized values are linear like everywhere else in the crate; the taper only
+    /// applies to fader positions, see `fader_position`.
     pub fn normalize_value(&self, value: f64) -> Option<f64> {
+        self.range_position(self.snap_to_grid(value))
+    }
+
+    /// Linear position of `value` in the range as it is, below 0.0 or above 1.0 outside it
+    fn range_position(&self, value: f64) -> Option<f64> {
         match (self.minimum, self.maximum) {
             (Some(min), Some(max)) if max > min => Some((value - min) / (max - min)),
             _ => None,
         }
     }
 
+    /// The value at a linear position in the range, snapped to the grid
     pub fn denormalize_value(&self, normalized_value: f64) -> Option<f64> {
         match (self.minimum, self.maximum) {
-            (Some(min), Some(max)) if max > min => Some(min + normalized_value * (max - min)),
+            (Some(min), Some(max)) if max > min => {
+                Some(self.snap_to_grid(min + normalized_value * (max - min)))
+            }
             _ => None,
         }
     }
+
+    /// Rounds `value` to the nearest grid position within the range, so values past the
+    /// end land on the last grid position rather than on a `maximum` off the grid; values
+    /// of widgets without a grid are returned as they are
+    pub fn snap_to_grid(&self, value: f64) -> f64 {
+        let Some(grid) = self.grid else {
+            return value;
+        };
+        let origin = self.minimum.unwrap_or(0.0);
+        let position = ((value - origin) / grid).round();
+
+        match (self.minimum, self.maximum) {
+            (Some(min), Some(max)) if max >= min => {
+                // Tolerate float error in ranges the grid divides evenly
+                let last = ((max - min) / grid + 1e-9).floor();
+                origin + position.clamp(0.0, last) * grid
+            }
+            _ => origin + position * grid,
+        }
+    }
+
+    /// `default_value` as a normalized value, e.g. as the prior for a widget nothing was
+    /// learned for
+    pub fn normalized_default(&self) -> Option<f64> {
+        self.default_value
+            .and_then(|value| self.normalize_value(value))
+    }
+
+    /// `units` parsed, for conversion and display
+    pub fn parsed_units(&self) -> Option<Units> {
+        self.units.as_deref().and_then(Units::parse)
+    }
+
+    /// A normalized value such as a suggestion as the concrete value it stands for,
+    /// e.g. "≈ 630 Hz"; `None` without a range
+    pub fn format_normalized(&self, normalized_value: f64) -> Option<String> {
+        let value = self.denormalize_value(normalized_value)?;
+        Some(match self.parsed_units() {
+            Some(units) => units.format_approx(value),
+            None => format!("≈ {value:.2}"),
+        })
+    }
+
+    /// Where `value` sits on the fader, 0.0-1.0, following the taper
+    pub fn fader_position(&self, value: f64) -> Option<f64> {
+        let normalized = self.normalize_value(value)?;
+        Some(self.taper.unwrap_or(Taper::Linear).to_position(
+            normalized,
+            self.minimum,
+            self.maximum,
+        ))
+    }
+
+    /// The value at a fader position, 0.0-1.0, following the taper
+    pub fn value_at_fader_position(&self, position: f64) -> Option<f64> {
+        let normalized =
+            self.taper
+                .unwrap_or(Taper::Linear)
+                .from_position(position, self.minimum, self.maximum);
+        self.denormalize_value(normalized)
+    }
+}
+
+/// Where a widget sits on the VCS, in the units of its description
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WidgetLayout {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl WidgetLayout {
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn distance_to(&self, other: &WidgetLayout) -> f64 {
+        let ((x1, y1), (x2, y2)) = (self.center(), other.center());
+        (x1 - x2).hypot(y1 - y2)
+    }
+}
+
+/// A widget close to another on the VCS, from `KymaWidgetExtractor::widgets_near`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyWidget {
+    pub event_id: i64,
+    pub label: Option<String>,
+    pub distance: f64,
+}
+
+/// Result of `KymaWidgetExtractor::cache_widget_descriptions_from_json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheReport {
+    /// Event IDs of the descriptions cached, in response order
+    pub cached: Vec<i64>,
+    pub errors: Vec<CacheEntryError>,
+    /// Reports of cached descriptions with problems short of errors
+    #[serde(default)]
+    pub warnings: Vec<DescriptionReport>,
+    /// Messages that are not controllable widgets, e.g. meters or status messages
+    #[serde(default)]
+    pub skipped: Vec<SkippedEntry>,
+}
+
+impl CacheReport {
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Number of entries handled so far, the index of the next one
+    fn entry_count(&self) -> usize {
+        self.cached.len() + self.errors.len() + self.skipped.len()
+    }
+}
+
+/// The cached descriptions at one point in time, see `KymaWidgetExtractor::cache_snapshot`
+#[derive(Debug, Clone, Default)]
+pub struct CacheSnapshot {
+    descriptions: HashMap<i64, Arc<HashMap<String, Value>>>,
+}
+
+impl CacheSnapshot {
+    pub fn len(&self) -> usize {
+        self.descriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.descriptions.is_empty()
+    }
+
+    pub fn contains(&self, event_id: i64) -> bool {
+        self.descriptions.contains_key(&event_id)
+    }
+}
+
+/// Result of `KymaWidgetExtractor::diff_against`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheDiff {
+    pub added: Vec<CacheDiffEntry>,
+    pub removed: Vec<CacheDiffEntry>,
+    pub changed: Vec<DescriptionChange>,
+}
+
+impl CacheDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A widget added to or removed from the cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheDiffEntry {
+    pub event_id: i64,
+    pub label: Option<String>,
+}
+
+/// A widget cached under the same event ID with a different description
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DescriptionChange {
+    pub event_id: i64,
+    /// Label of the new description
+    pub label: Option<String>,
+    /// Fields added, removed or changed, sorted
+    pub fields: Vec<String>,
+}
+
+/// An entry of a response that was left out as it is not a controllable widget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedEntry {
+    /// Position of the entry in the response
+    pub index: usize,
+    pub reason: SkipReason,
+}
+
+/// Why a message was not cached as a widget, see `KymaWidgetExtractor::classify_description`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// Pacarana status, e.g. DSP load or connection state
+    Status,
+    /// A level meter, scope or other display driven by the sound
+    Meter,
+    /// Text without a value
+    Text,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SkipReason::Status => "Pacarana status message",
+            SkipReason::Meter => "meter, not a control",
+            SkipReason::Text => "text, not a control",
+        })
+    }
+}
+
+/// What became of a description, see `KymaWidgetExtractor::try_cache_widget_description`
+#[derive(Debug, Clone)]
+pub enum CacheOutcome {
+    /// Cached; the report lists any problems short of errors
+    Cached(DescriptionReport),
+    /// Not a controllable widget
+    Skipped(SkipReason),
+    /// Failed validation; the report lists why
+    Rejected(DescriptionReport),
+}
+
+/// A description in a response that was not cached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntryError {
+    /// Position of the entry in the response
+    pub index: usize,
+    pub message: String,
+}
+
+/// A data-quality problem of a widget description
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DescriptionIssue {
+    MissingEventId,
+    /// No event ID; the description is identified by `composite_event_id` instead
+    CompositeEventId {
+        event_id: i64,
+    },
+    InvalidEventId {
+        value: String,
+    },
+    MissingMinimum,
+    MissingMaximum,
+    /// A numeric field holding something that is not a number
+    UnparseableNumber {
+        field: String,
+        value: String,
+    },
+    /// `minimum` is not below `maximum`
+    InvertedRange {
+        minimum: f64,
+        maximum: f64,
+    },
+    /// A range wider than `MAX_PLAUSIBLE_SPAN`, usually a placeholder
+    SuspiciousRange {
+        minimum: f64,
+        maximum: f64,
+    },
+    DefaultOutOfRange {
+        default_value: f64,
+        minimum: f64,
+        maximum: f64,
+    },
+}
+
+impl DescriptionIssue {
+    /// Whether the description is unusable rather than merely doubtful
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            DescriptionIssue::MissingEventId
+                | DescriptionIssue::InvalidEventId { .. }
+                | DescriptionIssue::UnparseableNumber { .. }
+                | DescriptionIssue::InvertedRange { .. }
+        )
+    }
+}
+
+impl std::fmt::Display for DescriptionIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DescriptionIssue::MissingEventId => {
+                write!(f, "Missing required field: concreteEventID")
+            }
+            DescriptionIssue::InvalidEventId { value } => {
+                write!(f, "concreteEventID must be a valid integer, got {value}")
+            }
+            DescriptionIssue::CompositeEventId { event_id } => write!(
+                f,
+                "No concreteEventID, identified by label, range and display type as {event_id}"
+            ),
+            DescriptionIssue::MissingMinimum => write!(f, "Missing minimum"),
+            DescriptionIssue::MissingMaximum => write!(f, "Missing maximum"),
+            DescriptionIssue::UnparseableNumber { field, value } => {
+                write!(f, "{field} is not a number: {value}")
+            }
+            DescriptionIssue::InvertedRange { minimum, maximum } => {
+                write!(f, "minimum {minimum} is not below maximum {maximum}")
+            }
+            DescriptionIssue::SuspiciousRange { minimum, maximum } => {
+                write!(f, "Range {minimum} to {maximum} is implausibly wide")
+            }
+            DescriptionIssue::DefaultOutOfRange {
+                default_value,
+                minimum,
+                maximum,
+            } => write!(
+                f,
+                "defaultValue {default_value} is outside {minimum} to {maximum}"
+            ),
+        }
+    }
+}
+
+/// Result of `KymaWidgetExtractor::validate_description`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DescriptionReport {
+    pub event_id: Option<i64>,
+    pub label: Option<String>,
+    pub issues: Vec<DescriptionIssue>,
+}
+
+impl DescriptionReport {
+    /// No issue is an error; the description can be cached
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(DescriptionIssue::is_error)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The errors, joined for a log line or a `CacheEntryError`
+    pub fn error_message(&self) -> String {
+        self.issues
+            .iter()
+            .filter(|issue| issue.is_error())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// A snapshot of a Kyma preset file, from `KymaWidgetExtractor::import_presets`
+#[derive(Debug, Clone)]
+pub struct ImportedPreset {
+    pub preset: Preset,
+    /// One per value of `preset`, to learn the values from
+    pub training_widgets: Vec<Widget>,
+    /// Event IDs and `!EventName`s of values without a cached description
+    pub unresolved: Vec<String>,
+}
+
+/// A preset value outside the cached range of its widget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutOfRangeValue {
+    pub event_id: i64,
+    pub label: Option<String>,
+    /// The normalized value as the preset holds it
+    pub value: f64,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+}
+
+/// Result of `KymaWidgetExtractor::validate_preset`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetValidationReport {
+    pub preset: String,
+    pub out_of_range: Vec<OutOfRangeValue>,
+    /// Widget IDs without a cached description, which could not be checked
+    pub unchecked: Vec<String>,
+}
+
+impl PresetValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.out_of_range.is_empty()
+    }
+
+    pub fn is_out_of_range(&self, event_id: i64) -> bool {
+        self.out_of_range.iter().any(|v| v.event_id == event_id)
+    }
 }
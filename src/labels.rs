@@ -0,0 +1,142 @@
+//! Label cleanup shared by the extractor and the engine tokenizer
+//!
+//! Kyma labels arrive with address prefixes such as "vcs/", doubled separators and the
+//! odd very long generated name. `LabelSanitizer` is the one place they are cleaned up,
+//! so the labels the extractor hands out and the labels the engine tokenizes are the
+//! same strings.
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Configurable cleanup applied to every widget label
+///
+/// The steps run in order: trim, strip prefixes, collapse separators, cut to
+/// `max_length`.
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct LabelSanitizer {
+    /// Trims whitespace around the label and around what is left after each step
+    pub trim: bool,
+    /// Prefixes removed from the start of the label, repeatedly and in any order
+    pub strip_prefixes: Vec<String>,
+    /// Characters that separate words, besides whitespace
+    pub separators: Vec<char>,
+    /// Replaces each run of separators with one: a space if the run has whitespace in
+    /// it, otherwise its first character
+    pub collapse_separators: bool,
+    /// Most characters to keep, `None` for no limit
+    pub max_length: Option<usize>,
+}
+
+impl Default for LabelSanitizer {
+    fn default() -> Self {
+        Self {
+            trim: true,
+            strip_prefixes: ["/vcs/", "vcs/", "!"].map(str::to_string).to_vec(),
+            separators: vec!['_', '-'],
+            collapse_separators: true,
+            max_length: Some(64),
+        }
+    }
+}
+
+impl LabelSanitizer {
+    /// Leaves labels exactly as written
+    pub fn none() -> Self {
+        Self {
+            trim: false,
+            strip_prefixes: Vec::new(),
+            separators: Vec::new(),
+            collapse_separators: false,
+            max_length: None,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        if !prefix.is_empty() && !self.strip_prefixes.iter().any(|p| p == prefix) {
+            self.strip_prefixes.push(prefix.to_string());
+        }
+        self
+    }
+
+    pub fn with_max_length(mut self, max_length: Option<usize>) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// The cleaned label, `None` if nothing is left of it
+    pub fn sanitize(&self, label: &str) -> Option<String> {
+        let mut label = self.trimmed(label);
+
+        while let Some(rest) = self
+            .strip_prefixes
+            .iter()
+            .filter(|prefix| !prefix.is_empty())
+            .find_map(|prefix| label.strip_prefix(prefix.as_str()))
+        {
+            label = self.trimmed(rest);
+        }
+
+        let mut label = if self.collapse_separators {
+            self.collapsed(label)
+        } else {
+            label.to_string()
+        };
+
+        if let Some(max_length) = self.max_length {
+            if let Some((index, _)) = label.char_indices().nth(max_length) {
+                label.truncate(index);
+                label = self.trimmed(&label).to_string();
+            }
+        }
+
+        (!label.is_empty()).then_some(label)
+    }
+
+    /// Lowercase words of the sanitized label, as the engine compares them
+    pub fn tokens(&self, label: &str) -> Vec<String> {
+        self.sanitize(label)
+            .map(|label| {
+                label
+                    .to_lowercase()
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn trimmed<'a>(&self, label: &'a str) -> &'a str {
+        if self.trim {
+            label.trim()
+        } else {
+            label
+        }
+    }
+
+    fn collapsed(&self, label: &str) -> String {
+        let mut result = String::with_capacity(label.len());
+        let mut run: Option<char> = None;
+
+        for c in label.chars() {
+            if c.is_whitespace() || self.separators.contains(&c) {
+                run = Some(if c.is_whitespace() {
+                    ' '
+                } else {
+                    run.unwrap_or(c)
+                });
+            } else {
+                if let Some(separator) = run.take() {
+                    result.push(separator);
+                }
+                result.push(c);
+            }
+        }
+        if let Some(separator) = run {
+            if !self.trim {
+                result.push(separator);
+            }
+        }
+
+        result
+    }
+}
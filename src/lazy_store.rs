@@ -0,0 +1,194 @@
+use crate::error::WidgetIntelligenceError;
+use crate::persistence::PersistenceBackend;
+use crate::similarity_engine::{Widget, WidgetFeatures, WidgetRecord, WidgetSuggestionEngine};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Records hydrated per requested suggestion; the final ranking picks from these
+const CANDIDATES_PER_SUGGESTION: usize = 4;
+
+/// What the index keeps of a record: enough to find and rank it, without the value
+/// history and statistics that make records heavy
+#[derive(Debug, Clone)]
+pub struct RecordSummary {
+    pub id: u64,
+    pub label: Option<String>,
+    pub event_id: Option<u64>,
+    pub display_type: Option<String>,
+    pub frequency: u32,
+    pub last_seen: u64,
+    pub features: WidgetFeatures,
+}
+
+impl From<&WidgetRecord> for RecordSummary {
+    fn from(record: &WidgetRecord) -> Self {
+        let mut features = record.features.clone();
+        // Only the mean of the patterns takes part in similarity
+        if !features.value_patterns.is_empty() {
+            let mean =
+                features.value_patterns.iter().sum::<f64>() / features.value_patterns.len() as f64;
+            features.value_patterns = vec![mean];
+        }
+
+        Self {
+            id: record.id,
+            label: record.widget.label.clone(),
+            event_id: record.widget.event_id,
+            display_type: record.widget.display_type.clone(),
+            frequency: record.frequency,
+            last_seen: record.last_seen,
+            features,
+        }
+    }
+}
+
+/// The records of a persisted engine opened with `open_lazy`, see
+/// `PersistentWidgetSuggestionEngine::open_lazy`
+///
+/// Built by streaming the records once; full records are loaded from the backend when
+/// a query needs them.
+#[derive(Default)]
+pub(crate) struct RecordIndex {
+    summaries: BTreeMap<u64, RecordSummary>,
+    by_event_id: HashMap<u64, Vec<u64>>,
+}
+
+impl RecordIndex {
+    pub(crate) fn build<B: PersistenceBackend>(
+        persistence: &B,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        let mut summaries = BTreeMap::new();
+        let mut by_event_id: HashMap<u64, Vec<u64>> = HashMap::new();
+        persistence.for_each_widget(&mut |record| {
+            if let Some(event_id) = record.widget.event_id {
+                by_event_id.entry(event_id).or_default().push(record.id);
+            }
+            summaries.insert(record.id, RecordSummary::from(&record));
+        })?;
+
+        log::info!("Indexed {} widget records", summaries.len());
+        Ok(Self {
+            summaries,
+            by_event_id,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.summaries.len()
+    }
+
+    /// Summaries of every record, in id order
+    pub(crate) fn summaries(&self) -> impl Iterator<Item = &RecordSummary> {
+        self.summaries.values()
+    }
+
+    pub(crate) fn max_id(&self) -> Option<u64> {
+        self.summaries.keys().next_back().copied()
+    }
+
+    /// An engine with the settings and id counter of `engine` holding only the records
+    /// the index ranks highest for `partial_widget`, and the presets if `with_presets`
+    pub(crate) fn candidate_engine<B: PersistenceBackend>(
+        &self,
+        engine: &WidgetSuggestionEngine,
+        persistence: &B,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+        with_presets: bool,
+    ) -> WidgetSuggestionEngine {
+        let candidates = self.candidates(engine, partial_widget, max_suggestions);
+        let mut scratch = engine_with_records(engine, hydrate_all(persistence, candidates));
+        if !with_presets {
+            scratch.presets.clear();
+        }
+        scratch
+    }
+
+    /// Ids of the records worth hydrating for a query: those sharing its event ID or
+    /// label, then the most similar ones by indexed features
+    fn candidates(
+        &self,
+        engine: &WidgetSuggestionEngine,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+    ) -> Vec<u64> {
+        let mut ids: Vec<u64> = Vec::new();
+        let mut features = None;
+
+        if let Some(event_id) = partial_widget.event_id {
+            ids.extend(self.by_event_id.get(&event_id).into_iter().flatten());
+            if self.summaries.contains_key(&event_id) {
+                ids.push(event_id);
+            }
+            // The engine ranks the rest by similarity to the matched record
+            features = ids
+                .first()
+                .and_then(|id| self.summaries.get(id))
+                .map(|summary| summary.features.clone());
+        }
+        let features = features.unwrap_or_else(|| engine.partial_features(partial_widget));
+
+        if let Some(label) = &partial_widget.clone().sanitized().label {
+            ids.extend(
+                self.summaries
+                    .values()
+                    .filter(|summary| summary.label.as_ref() == Some(label))
+                    .map(|summary| summary.id),
+            );
+        }
+
+        // The engine's own cut-off for suggesting a record
+        let threshold = engine.config.similarity_thresholds.suggestion;
+        let mut scored: Vec<(f64, u64)> = self
+            .summaries
+            .values()
+            .map(|summary| {
+                (
+                    engine.feature_similarity(&features, &summary.features),
+                    summary.id,
+                )
+            })
+            .filter(|(similarity, _)| *similarity > threshold)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ids.extend(
+            scored
+                .into_iter()
+                .take(max_suggestions.max(1) * CANDIDATES_PER_SUGGESTION)
+                .map(|(_, id)| id),
+        );
+
+        let mut seen = HashSet::new();
+        ids.retain(|id| seen.insert(*id));
+        ids
+    }
+}
+
+/// A copy of `engine` without its records, holding `records` instead
+pub(crate) fn engine_with_records(
+    engine: &WidgetSuggestionEngine,
+    records: Vec<WidgetRecord>,
+) -> WidgetSuggestionEngine {
+    let mut copy = WidgetSuggestionEngine::with_config(engine.config.clone());
+    copy.calibration = engine.calibration.clone();
+    copy.presets = engine.presets.clone();
+    copy.display_types = engine.display_types.clone();
+    copy.next_id = engine.next_id;
+    copy.records = records;
+    copy
+}
+
+/// Loads the records with the given ids, skipping those that fail to load
+fn hydrate_all<B: PersistenceBackend>(
+    persistence: &B,
+    ids: impl IntoIterator<Item = u64>,
+) -> Vec<WidgetRecord> {
+    let mut records = Vec::new();
+    for id in ids {
+        match persistence.load_widget(id) {
+            Ok(Some(record)) => records.push(record),
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to load widget record {id}: {e}"),
+        }
+    }
+    records
+}
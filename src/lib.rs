@@ -14,7 +14,8 @@
 //! - **Similarity Engine**: Core algorithm for finding similar widgets based on multiple features
 //! - **Persistence**: Sled-based storage for long-term learning
 //! - **Kyma Integration**: Extract widget data from Kyma JSON format
-//! - **Tauri Commands**: Ready-to-use Tauri commands for frontend integration
+//! - **Tauri Commands**: Ready-to-use Tauri commands for frontend integration, mounted with
+//!   one line by the `tauri` feature's `tauri_commands::init_plugin`
 //!
 //! ## Example
 //!
@@ -30,6 +31,7 @@
 //!     current_value: Some(95.0),
 //!     is_generated: Some(false),
 //!     display_type: Some("slider".to_string()),
+//!     ..Default::default()
 //! };
 //!
 //! engine.store_widget(widget);
@@ -40,65 +42,120 @@
 //! }, 5);
 //! ```
 
+pub mod error;
+pub mod fields;
+#[cfg(feature = "kyma-connect")]
+pub mod kyma_connect;
 pub mod kyma_extractor;
+pub mod labels;
+pub mod lazy_store;
+pub mod metadata_store;
+#[cfg(feature = "osc")]
+pub mod osc;
 pub mod persistence;
+pub mod rate_limiter;
 pub mod similarity_engine;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_backend;
+#[cfg(feature = "tauri")]
+pub mod tauri_commands;
 pub mod tauri_examples;
+pub mod units;
+pub mod widget_source;
 
 // Re-export main types for convenience
+pub use error::{SledPersistenceError, WidgetIntelligenceError};
+
 pub use similarity_engine::{
-    FilteredWidgetDescription, Preset, Suggestion, ValueStats, Widget, WidgetFeatures,
-    WidgetRecord, WidgetSuggestionEngine, WidgetValue,
+    default_value_priors, CalibrationBucket, CapacityLimits, ConfidenceCalibration, EngineConfig,
+    EventIdRemap, EvictionReport, FeedbackEntry, FilteredWidgetDescription, OutlierFilter, Page,
+    Preset, PresetCluster, PresetInsight, PresetRecommendation, RecordUpdate, RelatedWidget,
+    RetentionPolicy, SimilarityBreakdown, SimilarityComponent, SimilarityThresholds,
+    SimilarityWeights, Suggestion, SuggestionReason, Taper, ValueKind, ValueStats, ValueStrategy,
+    Widget, WidgetFeatures, WidgetRecord, WidgetSort, WidgetSuggestionEngine, WidgetValue,
+    GENERATED_PRESET_AUTHOR, REMAP_MIN_SCORE,
 };
 
+pub use lazy_store::RecordSummary;
+
+pub use units::Units;
+
+pub use fields::FieldAliases;
+
+pub use labels::LabelSanitizer;
+
+pub use rate_limiter::RateLimiter;
+
+pub use widget_source::WidgetSource;
+
+pub use metadata_store::{MetadataStore, MetadataValue};
+
 pub use persistence::{
-    ExportData, PersistentWidgetSuggestionEngine, SledPersistenceError, SledPersistenceManager,
+    verify_backup, AutoFlushPolicy, BackupManifest, CorruptEntry, DatabaseMergeReport, ExportData,
+    ExportFormat, ImportReport, ImportStrategy, IntegrityReport, MaintenanceReport, MemoryBackend,
+    MigrationReport, MigrationStatus, PayloadCodec, PersistenceBackend,
+    PersistentWidgetSuggestionEngine, PresetConflictPolicy, PresetImportReport, PresetRevision,
+    ProfileStats, ReadOnlyBackend, RejectedValue, SledOptions, SledPersistenceManager, WriteBatch,
+    BACKUP_FORMAT_VERSION, DEFAULT_PROFILE, MAX_PRESET_REVISIONS,
 };
 
-pub use kyma_extractor::{KymaWidgetExtractor, WidgetMetadata};
+#[cfg(feature = "sqlite")]
+pub use sqlite_backend::SqliteBackend;
 
-pub use tauri_examples::{
-    IntelligenceStats, PresetData, StandaloneIntelligenceService, SuggestionResponse,
-    WidgetInsightResponse,
+#[cfg(feature = "kyma-connect")]
+pub use kyma_connect::{
+    connect_kyma, KymaConnectConfig, KymaConnectEvent, KymaConnectHandle, KymaConnectSession,
 };
 
-impl Default for Widget {
-    fn default() -> Self {
-        Self {
-            label: None,
-            minimum: None,
-            maximum: None,
-            current_value: None,
-            is_generated: None,
-            display_type: None,
-            event_id: None,
-            values: Vec::new(),
-        }
-    }
-}
+#[cfg(feature = "osc")]
+pub use osc::{
+    osc_values, spawn_osc_listener, OscListenerConfig, OscListenerHandle, OscRateLimiter,
+};
+
+pub use kyma_extractor::{
+    composite_event_id, infer_display_type, parse_event_name, CacheDiff, CacheDiffEntry,
+    CacheEntryError, CacheOutcome, CacheReport, CacheSnapshot, DescriptionChange, DescriptionIssue,
+    DescriptionReport, ImportedPreset, KymaWidgetExtractor, NearbyWidget, OutOfRangeValue,
+    PresetValidationReport, SkipReason, SkippedEntry, WidgetLayout, WidgetMetadata,
+    COMPOSITE_EVENT_ID_BASE, MAX_GRID_STEPS, MAX_NEARBY_WIDGETS, MAX_PLAUSIBLE_SPAN,
+    OUT_OF_RANGE_CONFIDENCE, UNCHECKED_VALUE_CONFIDENCE,
+};
+
+pub use tauri_examples::{
+    IntelligenceEvent, IntelligenceStats, PresetData, PresetSummaryResponse,
+    StandaloneIntelligenceService, SuggestionResponse, WidgetInsightResponse,
+    WidgetSummaryResponse, CLEAR_ALL_DATA_CONFIRMATION, EVENT_CHANNEL_CAPACITY,
+    VALUE_LEARNING_INTERVAL,
+};
 
 /// Initialize the widget intelligence system with a database path
 pub fn init_intelligence_system<P: AsRef<std::path::Path>>(
     db_path: P,
-) -> Result<PersistentWidgetSuggestionEngine, persistence::SledPersistenceError> {
+) -> Result<PersistentWidgetSuggestionEngine, WidgetIntelligenceError> {
     PersistentWidgetSuggestionEngine::new(db_path)
 }
 
 /// Initialize the standalone intelligence service
-pub fn init_standalone_service(db_path: &str) -> Result<StandaloneIntelligenceService, String> {
+pub fn init_standalone_service(
+    db_path: &str,
+) -> Result<StandaloneIntelligenceService, WidgetIntelligenceError> {
     StandaloneIntelligenceService::new(db_path)
 }
 
 /// Utility function to validate widget data
-pub fn validate_widget(widget: &Widget) -> Result<(), String> {
+pub fn validate_widget(widget: &Widget) -> Result<(), WidgetIntelligenceError> {
     if let (Some(min), Some(max)) = (widget.minimum, widget.maximum) {
         if min >= max {
-            return Err("Minimum value must be less than maximum value".to_string());
+            return Err(WidgetIntelligenceError::ValidationError(
+                "Minimum value must be less than maximum value".to_string(),
+            ));
         }
 
         if let Some(current) = widget.current_value {
             if current < min || current > max {
-                return Err("Current value must be within minimum and maximum bounds".to_string());
+                return Err(WidgetIntelligenceError::ValidationError(
+                    "Current value must be within minimum and maximum bounds".to_string(),
+                ));
             }
         }
     }
@@ -117,6 +174,8 @@ pub fn create_test_widget(label: &str, min: f64, max: f64, current: f64) -> Widg
         display_type: Some("slider".to_string()),
         event_id: None,
         values: vec![current],
+        value_kind: None,
+        taper: None,
     }
 }
 
@@ -0,0 +1,98 @@
+use crate::error::WidgetIntelligenceError;
+use crate::persistence::PersistenceBackend;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A value kept in the metadata tree. Values are stored as plain strings so existing
+/// entries such as `next_id` keep their format.
+pub trait MetadataValue: Sized {
+    fn to_metadata(&self) -> String;
+
+    fn from_metadata(value: &str) -> Result<Self, String>;
+}
+
+macro_rules! parsed_metadata_value {
+    ($($ty:ty),*) => {
+        $(
+            impl MetadataValue for $ty {
+                fn to_metadata(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_metadata(value: &str) -> Result<Self, String> {
+                    value.parse().map_err(|e| format!("{e}"))
+                }
+            }
+        )*
+    };
+}
+
+parsed_metadata_value!(u64, i64, f64, bool);
+
+impl MetadataValue for String {
+    fn to_metadata(&self) -> String {
+        self.clone()
+    }
+
+    fn from_metadata(value: &str) -> Result<Self, String> {
+        Ok(value.to_string())
+    }
+}
+
+/// Typed access to a backend's metadata
+///
+/// Getters return `Ok(None)` for a missing key and an error for a value that does not
+/// parse as the requested type, leaving it to the caller whether to fall back.
+pub struct MetadataStore<'a, B: PersistenceBackend + ?Sized> {
+    backend: &'a B,
+}
+
+impl<'a, B: PersistenceBackend + ?Sized> MetadataStore<'a, B> {
+    pub fn new(backend: &'a B) -> Self {
+        Self { backend }
+    }
+
+    pub fn get<T: MetadataValue>(&self, key: &str) -> Result<Option<T>, WidgetIntelligenceError> {
+        match self.backend.load_metadata(key)? {
+            Some(value) => T::from_metadata(&value)
+                .map(Some)
+                .map_err(|e| invalid_value(key, e)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set<T: MetadataValue>(
+        &self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), WidgetIntelligenceError> {
+        self.backend.store_metadata(key, &value.to_metadata())
+    }
+
+    /// Reads a value stored as JSON, e.g. a policy or the engine configuration
+    pub fn get_json<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, WidgetIntelligenceError> {
+        match self.backend.load_metadata(key)? {
+            Some(value) => serde_json::from_str(&value)
+                .map(Some)
+                .map_err(|e| invalid_value(key, e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_json<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let json = serde_json::to_string(value)
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))?;
+        self.backend.store_metadata(key, &json)
+    }
+}
+
+fn invalid_value(key: &str, reason: String) -> WidgetIntelligenceError {
+    WidgetIntelligenceError::DeserializationError(format!("Invalid metadata '{key}': {reason}"))
+}
@@ -0,0 +1,255 @@
+//! Learning from live Kyma OSC traffic
+//!
+//! Kyma broadcasts `/vcs` messages carrying `eventID, value` pairs while a sound is
+//! played. `spawn_osc_listener` receives them, resolves each event ID through the
+//! extractor's cached widget descriptions and stores the widgets, so learning carries
+//! on while the user performs rather than only when a preset is saved.
+
+use crate::error::WidgetIntelligenceError;
+use crate::persistence::{PersistenceBackend, PersistentWidgetSuggestionEngine};
+use crate::rate_limiter::RateLimiter;
+use crate::widget_source::WidgetSource;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Settings of `spawn_osc_listener`
+#[derive(Debug, Clone)]
+pub struct OscListenerConfig {
+    /// Address to receive Kyma's OSC messages on
+    pub bind_addr: SocketAddr,
+    /// OSC address of the value messages
+    pub address: String,
+    /// Shortest time between two stores of the same event ID. Values arriving sooner
+    /// are held back, and the last of them is stored once the interval has passed.
+    pub min_interval: Duration,
+}
+
+impl Default for OscListenerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 8000)),
+            address: "/vcs".to_string(),
+            min_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The `eventID, value` pairs of every message to `address` in `packet`, including
+/// those nested in bundles
+pub fn osc_values(packet: &OscPacket, address: &str) -> Vec<(i64, f64)> {
+    match packet {
+        OscPacket::Message(message) if message.addr == address => message_values(message),
+        OscPacket::Message(_) => Vec::new(),
+        OscPacket::Bundle(bundle) => bundle
+            .content
+            .iter()
+            .flat_map(|packet| osc_values(packet, address))
+            .collect(),
+    }
+}
+
+fn message_values(message: &OscMessage) -> Vec<(i64, f64)> {
+    message
+        .args
+        .chunks_exact(2)
+        .filter_map(|pair| {
+            let event_id = match pair[0] {
+                OscType::Int(id) => i64::from(id),
+                OscType::Long(id) => id,
+                _ => return None,
+            };
+            let value = match pair[1] {
+                OscType::Float(value) => f64::from(value),
+                OscType::Double(value) => value,
+                OscType::Int(value) => f64::from(value),
+                _ => return None,
+            };
+            value.is_finite().then_some((event_id, value))
+        })
+        .collect()
+}
+
+/// The rate limiter under its name from before the standalone service shared it
+pub type OscRateLimiter = RateLimiter;
+
+/// A running listener; it stops when `stop` is called or the handle is dropped
+pub struct OscListenerHandle {
+    local_addr: SocketAddr,
+    learned: Arc<AtomicUsize>,
+    stop: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl OscListenerHandle {
+    /// The address the listener is bound to, e.g. to find the port picked for port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Number of widget values stored so far
+    pub fn learned(&self) -> usize {
+        self.learned.load(Ordering::Relaxed)
+    }
+
+    /// Stops the listener after it has stored the values it holds back
+    pub async fn stop(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(task) = self.task.take() {
+            if task.await.is_err() {
+                log::warn!("OSC listener task panicked");
+            }
+        }
+    }
+}
+
+impl Drop for OscListenerHandle {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+/// Binds a UDP socket and learns from the value messages it receives
+///
+/// Event IDs without a cached description in `source`, e.g. a `KymaWidgetExtractor`,
+/// are ignored. Store errors are logged; the listener keeps running.
+pub async fn spawn_osc_listener<B, S>(
+    config: OscListenerConfig,
+    source: Arc<S>,
+    engine: Arc<Mutex<PersistentWidgetSuggestionEngine<B>>>,
+) -> Result<OscListenerHandle, WidgetIntelligenceError>
+where
+    B: PersistenceBackend + Send + 'static,
+    S: WidgetSource + ?Sized + 'static,
+{
+    let socket = UdpSocket::bind(config.bind_addr).await?;
+    let local_addr = socket.local_addr()?;
+    let learned = Arc::new(AtomicUsize::new(0));
+    let (stop_tx, mut stop_rx) = oneshot::channel();
+
+    log::info!("Listening for Kyma OSC messages on {local_addr}");
+
+    let task_learned = learned.clone();
+    let task = tokio::spawn(async move {
+        let mut limiter = RateLimiter::new(config.min_interval);
+        let mut ticks = tokio::time::interval(config.min_interval.max(Duration::from_millis(10)));
+        let mut buffer = vec![0u8; rosc::decoder::MTU];
+
+        loop {
+            let values = tokio::select! {
+                _ = &mut stop_rx => break,
+                _ = ticks.tick() => limiter.take_due(Instant::now()),
+                received = socket.recv_from(&mut buffer) => match received {
+                    Ok((size, _)) => match rosc::decoder::decode_udp(&buffer[..size]) {
+                        Ok((_, packet)) => {
+                            let now = Instant::now();
+                            osc_values(&packet, &config.address)
+                                .into_iter()
+                                .filter_map(|(event_id, value)| {
+                                    limiter.offer(event_id, value, now).map(|v| (event_id, v))
+                                })
+                                .collect()
+                        }
+                        Err(e) => {
+                            log::debug!("Ignoring undecodable OSC packet: {e:?}");
+                            continue;
+                        }
+                    },
+                    Err(e) => {
+                        log::warn!("OSC receive failed: {e}");
+                        continue;
+                    }
+                },
+            };
+
+            let count = learn_blocking(&source, &engine, values).await;
+            task_learned.fetch_add(count, Ordering::Relaxed);
+        }
+
+        // Values held back when stopping are stored rather than lost
+        let values = limiter.take_all(Instant::now());
+        let count = learn_blocking(&source, &engine, values).await;
+        task_learned.fetch_add(count, Ordering::Relaxed);
+    });
+
+    Ok(OscListenerHandle {
+        local_addr,
+        learned,
+        stop: Some(stop_tx),
+        task: Some(task),
+    })
+}
+
+/// Runs `learn` on the blocking thread pool, as it waits for the engine lock and writes
+/// to the database
+async fn learn_blocking<B, S>(
+    source: &Arc<S>,
+    engine: &Arc<Mutex<PersistentWidgetSuggestionEngine<B>>>,
+    values: Vec<(i64, f64)>,
+) -> usize
+where
+    B: PersistenceBackend + Send + 'static,
+    S: WidgetSource + ?Sized + 'static,
+{
+    if values.is_empty() {
+        return 0;
+    }
+
+    let (source, engine) = (source.clone(), engine.clone());
+    match tokio::task::spawn_blocking(move || learn(&*source, &engine, &values)).await {
+        Ok(count) => count,
+        Err(e) => {
+            log::warn!("OSC learning task failed: {e}");
+            0
+        }
+    }
+}
+
+/// Stores the widgets of `values`, returning how many were stored
+fn learn<B: PersistenceBackend, S: WidgetSource + ?Sized>(
+    source: &S,
+    engine: &Mutex<PersistentWidgetSuggestionEngine<B>>,
+    values: &[(i64, f64)],
+) -> usize {
+    if values.is_empty() {
+        return 0;
+    }
+
+    // Values are learned normalized to the cached range, like recorded and preset values
+    let widgets: Vec<_> = values
+        .iter()
+        .filter_map(|&(event_id, value)| {
+            let metadata = source.widget_metadata(event_id)?;
+            let normalized = metadata.normalize_value(value).unwrap_or(value);
+            source.create_training_widget(event_id, normalized)
+        })
+        .collect();
+    if widgets.is_empty() {
+        return 0;
+    }
+
+    let count = widgets.len();
+    let stored = match engine.lock() {
+        Ok(mut engine) => engine.store_widgets(widgets),
+        Err(_) => Err(WidgetIntelligenceError::LockError("engine".to_string())),
+    };
+    match stored {
+        Ok(()) => {
+            log::trace!("Learned {count} widget values from OSC");
+            count
+        }
+        Err(e) => {
+            log::warn!("Failed to store widgets from OSC: {e}");
+            0
+        }
+    }
+}
@@ -1,291 +1,4013 @@
-use crate::similarity_engine::{Preset, Suggestion, Widget, WidgetRecord, WidgetSuggestionEngine};
+pub use crate::error::SledPersistenceError;
+use crate::error::WidgetIntelligenceError;
+use crate::kyma_extractor::{KymaWidgetExtractor, PresetValidationReport};
+use crate::lazy_store::{engine_with_records, RecordIndex, RecordSummary};
+use crate::metadata_store::{MetadataStore, MetadataValue};
+use crate::similarity_engine::{
+    CapacityLimits, ConfidenceCalibration, EngineConfig, EventIdRemap, EvictionReport,
+    FeedbackEntry, Page, Preset, PresetCluster, PresetInsight, PresetRecommendation, RecordUpdate,
+    RelatedWidget, RetentionPolicy, Suggestion, ValueKind, ValueStats, Widget, WidgetFeatures,
+    WidgetRecord, WidgetSort, WidgetSuggestionEngine, WidgetValue, REMAP_MIN_SCORE,
+};
+use crate::widget_source::WidgetSource;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize}; // Keep temporarily for migration
-use sled::{Db, Tree};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use sled::transaction::{TransactionError, TransactionResult};
+use sled::{Db, Transactional, Tree};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
 
-#[derive(Debug)]
-pub enum SledPersistenceError {
-    DatabaseError(sled::Error),
-    SerializationError(String),
-    DeserializationError(String),
+/// Number of previous versions kept per preset
+pub const MAX_PRESET_REVISIONS: usize = 10;
+
+/// Layout version of the archives written by `backup_to`
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const BACKUP_MANIFEST_ENTRY: &str = "manifest.json";
+const BACKUP_DATA_ENTRY: &str = "data.bin";
+
+/// The profile stored in the trees without a name prefix, i.e. every database created
+/// before profiles existed
+pub const DEFAULT_PROFILE: &str = "default";
+
+const PROFILE_TREE_PREFIX: &str = "profile:";
+
+/// File in the database directory holding the id of the process that has it open
+const OWNER_PID_FILE: &str = "owner.pid";
+
+/// How long opening waits for a lock before reporting `DatabaseBusy`. sled releases the
+/// lock of a closed database from its background threads, so a database this process
+/// just closed can still be locked for a moment.
+const LOCK_WAIT: Duration = Duration::from_millis(500);
+
+/// Metadata key of the event ID remap log, stored as JSON
+const EVENT_ID_REMAPS_KEY: &str = "event_id_remaps";
+
+/// Name of `tree` within the tree set of `profile`
+fn profile_tree_name(profile: &str, tree: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        tree.to_string()
+    } else {
+        format!("{PROFILE_TREE_PREFIX}{profile}:{tree}")
+    }
+}
+
+/// Where `verify` moves entries that fail to decode
+const CORRUPT_TREE: &str = "corrupt";
+
+/// Trees written by releases that stored serde_json payloads
+const LEGACY_WIDGETS_TREE: &str = "widgets";
+const LEGACY_PRESETS_TREE: &str = "presets";
+
+/// What to do when an imported preset has the name of an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PresetConflictPolicy {
+    /// Keep the existing preset and drop the imported one
+    #[default]
+    Skip,
+    /// Replace the existing preset, keeping it in the revision history
+    Overwrite,
+    /// Keep whichever preset was used most recently, the existing one on a tie
+    KeepNewest,
+    /// Keep the most recently used preset, counting the uses of both
+    SumUsageCounts,
+    /// Store the imported preset under the first free name like "Name (2)"
+    KeepBothWithSuffix,
+}
+
+/// File format written by `PersistentWidgetSuggestionEngine::export_to_file`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// Pretty-printed JSON, readable and diffable under version control
+    #[default]
+    Json,
+    /// Compact bincode, the layout the database itself uses
+    Bincode,
 }
 
-impl From<sled::Error> for SledPersistenceError {
-    fn from(err: sled::Error) -> Self {
-        SledPersistenceError::DatabaseError(err)
+/// A preset as accepted by `import_presets_from_json`, matching the Tauri `PresetData` shape
+#[derive(Debug, Clone, Deserialize)]
+struct ImportedPreset {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    widget_values: HashMap<String, f64>,
+    #[serde(default)]
+    created_by: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    favorite: bool,
+    /// Unix seconds the preset was last used, for `PresetConflictPolicy::KeepNewest`;
+    /// the time of the import if missing
+    #[serde(default)]
+    last_used: Option<u64>,
+}
+
+/// A widget value left out of an imported preset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedValue {
+    pub preset: String,
+    pub widget_id: String,
+    pub reason: String,
+}
+
+/// Outcome of importing presets from JSON
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetImportReport {
+    /// Names the imported presets were stored under
+    pub imported: Vec<String>,
+    /// Presets not stored because of a name conflict or no valid values
+    pub skipped: Vec<String>,
+    pub rejected_values: Vec<RejectedValue>,
+}
+
+/// How `import_data` combines imported records and presets with the local ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ImportStrategy {
+    /// Drop everything learned locally and take the imported state as it is
+    ReplaceAll,
+    /// Add what has no local counterpart; where both exist the local one is kept
+    #[default]
+    MergePreferLocal,
+    /// Add what has no local counterpart; where both exist the imported one replaces it
+    MergePreferImported,
+}
+
+/// Outcome of `import_data`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Records with no local counterpart, added under new ids
+    pub added_widgets: usize,
+    /// Local records replaced by their imported counterpart, keeping their ids
+    pub replaced_widgets: usize,
+    /// Imported records dropped in favour of their local counterpart
+    pub skipped_widgets: usize,
+    pub presets: PresetImportReport,
+}
+
+/// Outcome of `merge_from`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseMergeReport {
+    /// Records folded into an existing similar record
+    pub merged_widgets: usize,
+    /// Records with no counterpart, added under new ids
+    pub added_widgets: usize,
+    pub presets: PresetImportReport,
+}
+
+/// A previous version of a preset, kept when it was overwritten
+#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct PresetRevision {
+    pub preset: Preset,
+    pub replaced_at: u64,
+}
+
+impl PresetRevision {
+    fn replaced_now(preset: Preset) -> Self {
+        Self {
+            preset,
+            replaced_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
     }
 }
 
-impl From<bincode::error::EncodeError> for SledPersistenceError {
-    fn from(err: bincode::error::EncodeError) -> Self {
-        SledPersistenceError::SerializationError(err.to_string())
+/// Appends a revision, dropping the oldest beyond `MAX_PRESET_REVISIONS`
+fn push_bounded_revision(revisions: &mut Vec<PresetRevision>, revision: PresetRevision) {
+    revisions.push(revision);
+    if revisions.len() > MAX_PRESET_REVISIONS {
+        revisions.drain(..revisions.len() - MAX_PRESET_REVISIONS);
     }
 }
 
-impl From<bincode::error::DecodeError> for SledPersistenceError {
-    fn from(err: bincode::error::DecodeError) -> Self {
-        SledPersistenceError::DeserializationError(err.to_string())
+/// Writes that belong together, applied all-or-nothing by `apply_batch`
+#[derive(Debug, Default)]
+pub struct WriteBatch<'a> {
+    pub widgets: Vec<&'a WidgetRecord>,
+    pub presets: Vec<&'a Preset>,
+    /// Complete revision histories keyed by preset name
+    pub preset_revisions: Vec<(String, Vec<PresetRevision>)>,
+    pub metadata: Vec<(String, String)>,
+}
+
+impl WriteBatch<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+            && self.presets.is_empty()
+            && self.preset_revisions.is_empty()
+            && self.metadata.is_empty()
     }
 }
 
-impl std::fmt::Display for SledPersistenceError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// How widget and preset payloads are stored in sled, recorded in the metadata tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadCodec {
+    Bincode,
+    /// Bincode compressed with zstd, available with the `zstd` feature
+    BincodeZstd,
+}
+
+impl PayloadCodec {
+    const METADATA_KEY: &'static str = "payload_codec";
+
+    pub fn as_str(self) -> &'static str {
         match self {
-            SledPersistenceError::DatabaseError(e) => write!(f, "Database error: {e}"),
-            SledPersistenceError::SerializationError(e) => write!(f, "Serialization error: {e}"),
-            SledPersistenceError::DeserializationError(e) => {
-                write!(f, "Deserialization error: {e}")
-            }
+            PayloadCodec::Bincode => "bincode",
+            PayloadCodec::BincodeZstd => "bincode+zstd",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "bincode" => Some(PayloadCodec::Bincode),
+            "bincode+zstd" => Some(PayloadCodec::BincodeZstd),
+            _ => None,
+        }
+    }
+
+    /// The codec new databases are created with
+    fn preferred() -> Self {
+        if cfg!(feature = "zstd") {
+            PayloadCodec::BincodeZstd
+        } else {
+            PayloadCodec::Bincode
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// When the sled manager flushes on its own, on top of explicit `flush()` calls and the
+/// flush when it is dropped. Both triggers can be combined; the default uses neither.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AutoFlushPolicy {
+    /// Flush once this many writes happened since the last flush
+    pub every_writes: Option<usize>,
+    /// Flush unflushed writes from a background thread at this interval
+    pub every: Option<Duration>,
+}
+
+impl AutoFlushPolicy {
+    pub fn every_writes(writes: usize) -> Self {
+        Self {
+            every_writes: Some(writes),
+            every: None,
+        }
+    }
+
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            every_writes: None,
+            every: Some(interval),
+        }
+    }
+}
+
+/// Tuning of the sled database itself, see `SledPersistenceManager::with_options`.
+/// The defaults are sled's own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SledOptions {
+    /// Upper bound of sled's page cache in bytes
+    pub cache_capacity: u64,
+    /// Compress pages with zstd; needs the `sled-compression` feature
+    pub use_compression: bool,
+    /// zstd level used when compression is on, 1 to 22
+    pub compression_factor: i32,
+    /// Interval of sled's own background flush, `None` to only flush as the auto-flush
+    /// policy and explicit `flush()` calls do
+    pub flush_every_ms: Option<u64>,
+    /// Remove the database once it is closed, e.g. for tests and scratch sessions
+    pub temporary: bool,
+}
+
+impl Default for SledOptions {
+    fn default() -> Self {
+        Self {
+            cache_capacity: 1024 * 1024 * 1024,
+            use_compression: false,
+            compression_factor: 5,
+            flush_every_ms: Some(500),
+            temporary: false,
+        }
+    }
+}
+
+impl SledOptions {
+    /// A 16 MiB page cache for embedded and other memory-constrained hosts
+    pub fn low_memory() -> Self {
+        Self {
+            cache_capacity: 16 * 1024 * 1024,
+            ..Self::default()
+        }
+    }
+
+    fn config(&self) -> sled::Config {
+        sled::Config::new()
+            .cache_capacity(self.cache_capacity)
+            .use_compression(self.use_compression)
+            .compression_factor(self.compression_factor)
+            .flush_every_ms(self.flush_every_ms)
+            .temporary(self.temporary)
+    }
+}
+
+type WidgetHook = Arc<dyn Fn(&WidgetRecord) + Send + Sync>;
+type PresetHook = Arc<dyn Fn(&Preset) + Send + Sync>;
+type FlushHook = Arc<dyn Fn(usize) + Send + Sync>;
+/// Decodes one entry of a tree `verify` walks, describing why it is unreadable
+type EntryCheck = fn(&SledPersistenceManager, &[u8], &[u8]) -> Result<(), String>;
+
+/// Callbacks registered on a sled manager, run after the write they observe succeeded
+#[derive(Default)]
+struct PersistenceHooks {
+    widget_stored: Vec<WidgetHook>,
+    preset_stored: Vec<PresetHook>,
+    flushed: Vec<FlushHook>,
+}
+
+/// Shared with the background flush thread; callbacks are cloned out before they run
+/// so a callback can register further ones
+#[derive(Clone, Default)]
+struct SharedHooks(Arc<Mutex<PersistenceHooks>>);
+
+impl SharedHooks {
+    fn lock(&self) -> MutexGuard<'_, PersistenceHooks> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn widget_stored(&self, record: &WidgetRecord) {
+        let hooks = self.lock().widget_stored.clone();
+        for hook in hooks {
+            hook(record);
+        }
+    }
+
+    fn preset_stored(&self, preset: &Preset) {
+        let hooks = self.lock().preset_stored.clone();
+        for hook in hooks {
+            hook(preset);
         }
     }
+
+    fn flushed(&self, writes: usize) {
+        let hooks = self.lock().flushed.clone();
+        for hook in hooks {
+            hook(writes);
+        }
+    }
+}
+
+/// The background thread of an interval flush policy
+struct Flusher {
+    stop: mpsc::Sender<()>,
+    handle: JoinHandle<()>,
 }
 
-impl std::error::Error for SledPersistenceError {}
+impl Flusher {
+    fn spawn(
+        db: Db,
+        pending_writes: Arc<AtomicUsize>,
+        hooks: SharedHooks,
+        interval: Duration,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = std::thread::spawn(move || {
+            // Runs until the manager sends a stop or goes away
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let writes = pending_writes.swap(0, Ordering::AcqRel);
+                if writes > 0 {
+                    match db.flush() {
+                        Ok(_) => hooks.flushed(writes),
+                        Err(e) => log::warn!("Background flush failed: {e}"),
+                    }
+                }
+            }
+        });
+
+        Self { stop, handle }
+    }
+
+    fn stop(self) {
+        let _ = self.stop.send(());
+        if self.handle.join().is_err() {
+            log::warn!("Background flush thread panicked");
+        }
+    }
+}
 
 pub struct SledPersistenceManager {
     db: Db,
+    profile: String,
+    codec: PayloadCodec,
+    auto_flush: AutoFlushPolicy,
+    pending_writes: Arc<AtomicUsize>,
+    flusher: Option<Flusher>,
+    hooks: SharedHooks,
     widgets_tree: Tree,
+    /// Usage statistics and recent values of records, see `WidgetActivity`
+    widget_activity_tree: Tree,
     presets_tree: Tree,
     metadata_tree: Tree,
+    feedback_tree: Tree,
+    preset_history_tree: Tree,
+    /// Kyma widget descriptions by event ID, shared by all profiles
+    widget_descriptions_tree: Tree,
+    /// `OWNER_PID_FILE` written on open, removed again on drop
+    owner_file: Option<PathBuf>,
+    /// Opened by `open_snapshot`: the codec is not recorded, legacy entries are not
+    /// rewritten and nothing is flushed
+    read_only: bool,
 }
 
-impl SledPersistenceManager {
-    pub fn new<P: AsRef<std::path::Path>>(db_path: P) -> Result<Self, SledPersistenceError> {
-        let db = sled::open(db_path)?;
-        let widgets_tree = db.open_tree("widgets_v1")?; // New tree for bincode format
-        let presets_tree = db.open_tree("presets_v1")?; // New tree for bincode format
-        let metadata_tree = db.open_tree("metadata")?;
+/// Storage behind `PersistentWidgetSuggestionEngine`
+///
+/// Implementors provide the primitives; batching, bounded preset histories and disk
+/// statistics have defaults built on top of them.
+pub trait PersistenceBackend {
+    fn store_widget(&self, record: &WidgetRecord) -> Result<(), WidgetIntelligenceError>;
 
-        Ok(Self {
-            db,
-            widgets_tree,
-            presets_tree,
-            metadata_tree,
-        })
-    }
+    fn delete_widget(&self, id: u64) -> Result<(), WidgetIntelligenceError>;
+
+    /// Loads every stored record, migrating legacy layouts where the backend has them
+    fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError>;
 
-    pub fn store_widget(&self, record: &WidgetRecord) -> Result<(), SledPersistenceError> {
-        let key = record.id.to_be_bytes();
-        let value = bincode::encode_to_vec(record, bincode::config::standard())?;
+    /// Loads a single record; the default scans `load_all_widgets`
+    fn load_widget(&self, id: u64) -> Result<Option<WidgetRecord>, WidgetIntelligenceError> {
+        Ok(self
+            .load_all_widgets()?
+            .into_iter()
+            .find(|record| record.id == id))
+    }
 
-        self.widgets_tree.insert(key, value)?;
+    /// Visits every stored record in id order. Backends that can should decode them one
+    /// at a time instead of materializing the whole set like the default does.
+    fn for_each_widget(
+        &self,
+        visit: &mut dyn FnMut(WidgetRecord),
+    ) -> Result<(), WidgetIntelligenceError> {
+        for record in self.load_all_widgets()? {
+            visit(record);
+        }
         Ok(())
     }
 
-    pub fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, SledPersistenceError> {
-        let mut records = Vec::new();
+    fn store_preset(&self, preset: &Preset) -> Result<(), WidgetIntelligenceError>;
 
-        for result in self.widgets_tree.iter() {
-            let (_key, value) = result?;
-            match bincode::decode_from_slice(&value, bincode::config::standard()) {
-                Ok((record, _)) => records.push(record),
-                Err(e) => {
-                    log::warn!("Failed to decode widget record with bincode: {e}");
-                }
-            }
-        }
+    /// Deletes a preset together with its revision history
+    fn delete_preset(&self, name: &str) -> Result<(), WidgetIntelligenceError>;
 
-        Ok(records)
+    fn load_all_presets(&self) -> Result<Vec<Preset>, WidgetIntelligenceError>;
+
+    /// Replaces a preset's revision history; an empty list removes it
+    fn store_preset_revisions(
+        &self,
+        name: &str,
+        revisions: &[PresetRevision],
+    ) -> Result<(), WidgetIntelligenceError>;
+
+    /// Previous versions of a preset, oldest first
+    fn load_preset_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError>;
+
+    /// Appends a feedback entry to the log, keeping insertion order
+    fn store_feedback(&self, entry: &FeedbackEntry) -> Result<(), WidgetIntelligenceError>;
+
+    fn load_feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError>;
+
+    /// Raw string storage behind `metadata()`
+    fn store_metadata(&self, key: &str, value: &str) -> Result<(), WidgetIntelligenceError>;
+
+    fn load_metadata(&self, key: &str) -> Result<Option<String>, WidgetIntelligenceError>;
+
+    /// Keeps a Kyma widget description so the extractor cache survives a restart. The
+    /// default keeps nothing.
+    fn store_widget_description(
+        &self,
+        _event_id: i64,
+        _description: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), WidgetIntelligenceError> {
+        Ok(())
     }
 
-    pub fn store_preset(&self, preset: &Preset) -> Result<(), SledPersistenceError> {
-        let key = preset.name.as_bytes();
-        let value = bincode::encode_to_vec(preset, bincode::config::standard())?;
+    /// Every stored widget description, in event ID order
+    fn load_widget_descriptions(
+        &self,
+    ) -> Result<Vec<HashMap<String, serde_json::Value>>, WidgetIntelligenceError> {
+        Ok(Vec::new())
+    }
 
-        self.presets_tree.insert(key, value)?;
+    /// Removes the description stored for `event_id`, if any
+    fn delete_widget_description(&self, _event_id: i64) -> Result<(), WidgetIntelligenceError> {
         Ok(())
     }
 
-    pub fn load_all_presets(&self) -> Result<Vec<Preset>, SledPersistenceError> {
-        let mut presets = Vec::new();
+    fn clear_widget_descriptions(&self) -> Result<(), WidgetIntelligenceError> {
+        Ok(())
+    }
 
-        for result in self.presets_tree.iter() {
-            let (_key, value) = result?;
-            match bincode::decode_from_slice(&value, bincode::config::standard()) {
-                Ok((preset, _)) => presets.push(preset),
-                Err(e) => {
-                    log::warn!("Failed to decode preset with bincode: {e}");
-                }
-            }
+    /// Removes everything stored: widgets, presets and their history, feedback, metadata
+    /// and widget descriptions
+    ///
+    /// The default only reaches widgets, presets and descriptions through the other
+    /// methods; backends override it to clear feedback and metadata as well.
+    fn clear_all(&self) -> Result<(), WidgetIntelligenceError> {
+        for record in self.load_all_widgets()? {
+            self.delete_widget(record.id)?;
         }
+        for preset in self.load_all_presets()? {
+            self.delete_preset(&preset.name)?;
+            self.store_preset_revisions(&preset.name, &[])?;
+        }
+        self.clear_widget_descriptions()
+    }
 
-        Ok(presets)
+    /// Typed access to the metadata
+    fn metadata(&self) -> MetadataStore<'_, Self>
+    where
+        Self: Sized,
+    {
+        MetadataStore::new(self)
     }
 
-    pub fn store_metadata(&self, key: &str, value: &str) -> Result<(), SledPersistenceError> {
-        self.metadata_tree
-            .insert(key.as_bytes(), value.as_bytes())?;
-        Ok(())
+    fn flush(&self) -> Result<(), WidgetIntelligenceError>;
+
+    /// Whether every write is rejected with `WidgetIntelligenceError::ReadOnly`
+    fn is_read_only(&self) -> bool {
+        false
     }
 
-    pub fn load_metadata(&self, key: &str) -> Result<Option<String>, SledPersistenceError> {
-        if let Some(value) = self.metadata_tree.get(key.as_bytes())? {
-            let string_value = String::from_utf8_lossy(&value).to_string();
-            Ok(Some(string_value))
-        } else {
-            Ok(None)
+    fn store_widgets(&self, records: &[&WidgetRecord]) -> Result<(), WidgetIntelligenceError> {
+        for record in records {
+            self.store_widget(record)?;
         }
+        Ok(())
     }
 
-    pub fn flush(&self) -> Result<(), SledPersistenceError> {
-        self.db.flush()?;
+    /// Persists records that changed only in frequency, last seen time and value
+    /// statistics since they were last written, with the given number of values appended
+    /// to their history. The default rewrites them in full.
+    fn store_widget_activity(
+        &self,
+        updates: &[(&WidgetRecord, usize)],
+    ) -> Result<(), WidgetIntelligenceError> {
+        let records: Vec<&WidgetRecord> = updates.iter().map(|(record, _)| *record).collect();
+        self.store_widgets(&records)
+    }
+
+    /// Applies every write in `batch` or none of them. The default writes them one by
+    /// one, which is only all-or-nothing for backends whose writes cannot fail.
+    fn apply_batch(&self, batch: &WriteBatch<'_>) -> Result<(), WidgetIntelligenceError> {
+        self.store_widgets(&batch.widgets)?;
+        for preset in &batch.presets {
+            self.store_preset(preset)?;
+        }
+        for (name, revisions) in &batch.preset_revisions {
+            self.store_preset_revisions(name, revisions)?;
+        }
+        for (key, value) in &batch.metadata {
+            self.store_metadata(key, value)?;
+        }
         Ok(())
     }
 
-    pub fn compact(&self) -> Result<(), SledPersistenceError> {
-        // Note: sled doesn't have a direct compact method, this clears the database
-        // In a real implementation, you might want to implement a proper compaction
-        log::warn!("Compact operation not implemented for sled database");
+    /// Appends a revision to a preset's history, dropping the oldest beyond the bound
+    fn push_preset_revision(
+        &self,
+        revision: PresetRevision,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let key = revision.preset.name.clone();
+        let mut revisions = self.load_preset_revisions(&key)?;
+        push_bounded_revision(&mut revisions, revision);
+
+        self.store_preset_revisions(&key, &revisions)
+    }
+
+    /// Moves a preset's revision history to a new name
+    fn rename_preset_revisions(
+        &self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let mut revisions = self.load_preset_revisions(old_name)?;
+        if revisions.is_empty() {
+            return Ok(());
+        }
+
+        for revision in &mut revisions {
+            revision.preset.name = new_name.to_string();
+        }
+        self.store_preset_revisions(new_name, &revisions)?;
+        self.store_preset_revisions(old_name, &[])
+    }
+
+    fn compact(&self) -> Result<(), WidgetIntelligenceError> {
         Ok(())
     }
 
-    pub fn size_on_disk(&self) -> Result<u64, SledPersistenceError> {
-        Ok(self.db.size_on_disk()?)
+    fn size_on_disk(&self) -> Result<u64, WidgetIntelligenceError> {
+        Ok(0)
     }
 }
 
-#[derive(Debug)]
-pub struct MigrationStatus {
-    pub legacy_widgets: usize,
-    pub legacy_presets: usize,
-    pub new_widgets: usize,
-    pub new_presets: usize,
-    pub migration_needed: bool,
-}
+impl SledPersistenceManager {
+    pub fn new<P: AsRef<std::path::Path>>(db_path: P) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_profile(db_path, DEFAULT_PROFILE)
+    }
 
-pub struct PersistentWidgetSuggestionEngine {
-    pub engine: WidgetSuggestionEngine,
-    pub persistence: SledPersistenceManager,
-}
+    /// Opens the database at `db_path` on the trees of `profile`, creating it if needed
+    pub fn with_profile<P: AsRef<std::path::Path>>(
+        db_path: P,
+        profile: &str,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        let db_path = db_path.as_ref();
+        let db = open_locked(sled::Config::new().path(db_path), db_path)?;
+        Self::open_profile(db, profile, false)?.owned_at(db_path)
+    }
+
+    /// Opens a copy of the database at `db_path` read-only, for a second process while
+    /// another one has it open. The copy holds what the owner had flushed when it was
+    /// taken and is deleted again on drop. The database itself is neither locked nor
+    /// written, and legacy entries are read without being migrated.
+    pub fn open_snapshot<P: AsRef<Path>>(
+        db_path: P,
+    ) -> Result<ReadOnlyBackend<Self>, WidgetIntelligenceError> {
+        let db_path = db_path.as_ref();
+        if !db_path.is_dir() {
+            return Err(WidgetIntelligenceError::NotFound(format!(
+                "database {}",
+                db_path.display()
+            )));
+        }
 
-impl PersistentWidgetSuggestionEngine {
-    pub fn new<P: AsRef<std::path::Path>>(db_path: P) -> Result<Self, SledPersistenceError> {
-        let persistence = SledPersistenceManager::new(db_path)?;
-        let mut engine = WidgetSuggestionEngine::new();
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let copy = std::env::temp_dir().join(format!(
+            "widget-intelligence-snapshot-{}-{nanos}",
+            std::process::id()
+        ));
+        copy_dir(db_path, &copy)?;
 
-        match persistence.load_all_widgets() {
-            Ok(widgets) => {
-                engine.records = widgets;
-                log::info!(
-                    "Loaded {} widget records from database",
-                    engine.records.len()
-                );
-            }
+        // Temporary databases are not locked and are removed when closed
+        let db = match sled::Config::new().path(&copy).temporary(true).open() {
+            Ok(db) => db,
             Err(e) => {
-                log::warn!("Failed to load widgets from database: {e}");
+                let _ = std::fs::remove_dir_all(&copy);
+                return Err(e.into());
             }
+        };
+        Ok(ReadOnlyBackend::new(Self::open_profile(
+            db,
+            DEFAULT_PROFILE,
+            true,
+        )?))
+    }
+
+    /// Opens the database at `db_path` with sled configured by `options`
+    pub fn with_options<P: AsRef<std::path::Path>>(
+        db_path: P,
+        options: &SledOptions,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        if options.use_compression && !cfg!(feature = "sled-compression") {
+            return Err(WidgetIntelligenceError::DatabaseError(
+                sled::Error::Unsupported(
+                    "Page compression needs the sled-compression feature".to_string(),
+                ),
+            ));
         }
 
-        match persistence.load_all_presets() {
-            Ok(presets) => {
-                engine.presets = presets;
-                log::info!("Loaded {} presets from database", engine.presets.len());
-            }
-            Err(e) => {
-                log::warn!("Failed to load presets from database: {e}");
-            }
+        let db_path = db_path.as_ref();
+        let db = open_locked(options.config().path(db_path), db_path)?;
+        Self::open_profile(db, DEFAULT_PROFILE, false)?.owned_at(db_path)
+    }
+
+    /// Records this process as the owner of the database at `db_path`, so a second one
+    /// can tell who holds the lock
+    fn owned_at(mut self, db_path: &Path) -> Result<Self, WidgetIntelligenceError> {
+        let owner_file = db_path.join(OWNER_PID_FILE);
+        std::fs::write(&owner_file, std::process::id().to_string())?;
+        self.owner_file = Some(owner_file);
+        Ok(self)
+    }
+
+    fn open_profile(
+        db: Db,
+        profile: &str,
+        read_only: bool,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        if profile.trim().is_empty() {
+            return Err(WidgetIntelligenceError::ValidationError(
+                "Profile names must not be empty".to_string(),
+            ));
         }
 
-        if let Some(next_id) = persistence.load_metadata("next_id").ok().flatten() {
-            if let Ok(id) = next_id.parse::<u64>() {
-                engine.next_id = id;
+        let tree = |name: &str| db.open_tree(profile_tree_name(profile, name));
+        let widgets_tree = tree("widgets_v1")?; // New tree for bincode format
+        let widget_activity_tree = tree("widget_activity_v1")?;
+        let presets_tree = tree("presets_v1")?; // New tree for bincode format
+        let metadata_tree = tree("metadata")?;
+        let feedback_tree = tree("feedback_v1")?;
+        let preset_history_tree = tree("preset_history_v1")?;
+        let widget_descriptions_tree = db.open_tree("widget_descriptions_v1")?;
+
+        // Existing databases keep the codec they were written with; data from before
+        // the codec was recorded is plain bincode
+        let codec = match metadata_tree.get(PayloadCodec::METADATA_KEY)? {
+            Some(value) => {
+                let value = String::from_utf8_lossy(&value);
+                PayloadCodec::parse(&value).ok_or_else(|| {
+                    WidgetIntelligenceError::DeserializationError(format!(
+                        "Unknown payload codec: {value}"
+                    ))
+                })?
             }
+            None => {
+                let codec = if widgets_tree.is_empty() && presets_tree.is_empty() {
+                    PayloadCodec::preferred()
+                } else {
+                    PayloadCodec::Bincode
+                };
+                if !read_only {
+                    metadata_tree.insert(PayloadCodec::METADATA_KEY, codec.as_str())?;
+                }
+                codec
+            }
+        };
+        if codec == PayloadCodec::BincodeZstd && !cfg!(feature = "zstd") {
+            return Err(WidgetIntelligenceError::DeserializationError(
+                "Database is zstd-compressed; enable the zstd feature to open it".to_string(),
+            ));
         }
 
         Ok(Self {
-            engine,
-            persistence,
+            db,
+            profile: profile.to_string(),
+            codec,
+            auto_flush: AutoFlushPolicy::default(),
+            pending_writes: Arc::new(AtomicUsize::new(0)),
+            flusher: None,
+            hooks: SharedHooks::default(),
+            widgets_tree,
+            widget_activity_tree,
+            presets_tree,
+            metadata_tree,
+            feedback_tree,
+            preset_history_tree,
+            widget_descriptions_tree,
+            owner_file: None,
+            read_only,
         })
     }
 
-    pub fn store_widget(&mut self, widget: Widget) -> Result<(), SledPersistenceError> {
-        let initial_count = self.engine.records.len();
-        self.engine.store_widget(widget);
-
-        if self.engine.records.len() > initial_count {
-            if let Some(record) = self.engine.records.last() {
-                self.persistence.store_widget(record)?;
-                self.persistence
-                    .store_metadata("next_id", &self.engine.next_id.to_string())?;
-            }
-        } else if let Some(record) = self.engine.records.iter().find(|r| r.frequency > 1) {
-            self.persistence.store_widget(record)?;
-        }
+    pub fn payload_codec(&self) -> PayloadCodec {
+        self.codec
+    }
 
-        Ok(())
+    /// The profile whose trees are in use
+    pub fn profile(&self) -> &str {
+        &self.profile
     }
 
-    pub fn store_preset(&mut self, preset: Preset) -> Result<(), SledPersistenceError> {
-        self.engine.store_preset(preset.clone());
-        self.persistence.store_preset(&preset)?;
+    /// Flushes and moves over to the trees of `profile`, creating them if needed
+    pub fn switch_profile(&mut self, profile: &str) -> Result<(), WidgetIntelligenceError> {
+        self.flush()?;
+        let other = Self::open_profile(self.db.clone(), profile, self.read_only)?;
+
+        self.profile = other.profile.clone();
+        self.codec = other.codec;
+        self.widgets_tree = other.widgets_tree.clone();
+        self.widget_activity_tree = other.widget_activity_tree.clone();
+        self.presets_tree = other.presets_tree.clone();
+        self.metadata_tree = other.metadata_tree.clone();
+        self.feedback_tree = other.feedback_tree.clone();
+        self.preset_history_tree = other.preset_history_tree.clone();
         Ok(())
     }
 
-    pub fn get_suggestions(
-        &self,
-        partial_widget: &Widget,
-        max_suggestions: usize,
-    ) -> Vec<Suggestion> {
-        self.engine.get_suggestions(partial_widget, max_suggestions)
+    /// Every profile in the database, the default one first
+    pub fn list_profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = self
+            .db
+            .tree_names()
+            .iter()
+            .filter_map(|name| {
+                let name = std::str::from_utf8(name).ok()?;
+                let profile = name
+                    .strip_prefix(PROFILE_TREE_PREFIX)?
+                    .strip_suffix(":metadata")?;
+                Some(profile.to_string())
+            })
+            .collect();
+        profiles.sort();
+        profiles.insert(0, DEFAULT_PROFILE.to_string());
+        profiles
     }
 
-    pub fn get_suggestions_by_event_id(
+    /// Record counts of `profile`, or `None` if it does not exist
+    pub fn profile_stats(
         &self,
-        event_id: u64,
-        max_suggestions: usize,
-    ) -> Vec<Suggestion> {
-        self.engine.get_suggestions_by_event_id(event_id, max_suggestions)
+        profile: &str,
+    ) -> Result<Option<ProfileStats>, WidgetIntelligenceError> {
+        if !self.list_profiles().iter().any(|name| name == profile) {
+            return Ok(None);
+        }
+
+        let count = |tree: &str| -> Result<usize, WidgetIntelligenceError> {
+            Ok(self.db.open_tree(profile_tree_name(profile, tree))?.len())
+        };
+        Ok(Some(ProfileStats {
+            profile: profile.to_string(),
+            widgets: count("widgets_v1")?,
+            presets: count("presets_v1")?,
+            feedback_entries: count("feedback_v1")?,
+        }))
     }
 
-    pub fn get_preset_insights(&self, widget: &Widget) -> Option<String> {
-        self.engine.get_preset_insights(widget)
+    pub fn auto_flush(&self) -> AutoFlushPolicy {
+        self.auto_flush
     }
 
-    pub fn get_stats(&self) -> HashMap<String, usize> {
-        self.engine.get_stats()
+    /// Replaces the auto-flush policy, restarting the background thread if it has one
+    pub fn set_auto_flush(&mut self, policy: AutoFlushPolicy) {
+        if let Some(flusher) = self.flusher.take() {
+            flusher.stop();
+        }
+
+        self.flusher = policy
+            .every
+            .filter(|interval| !interval.is_zero())
+            .map(|interval| {
+                Flusher::spawn(
+                    self.db.clone(),
+                    self.pending_writes.clone(),
+                    self.hooks.clone(),
+                    interval,
+                )
+            });
+        self.auto_flush = policy;
     }
 
-    pub fn flush(&self) -> Result<(), SledPersistenceError> {
-        self.persistence.flush()
+    /// Calls `hook` with every widget record written, including those of batches
+    pub fn on_widget_stored(&self, hook: impl Fn(&WidgetRecord) + Send + Sync + 'static) {
+        self.hooks.lock().widget_stored.push(Arc::new(hook));
     }
 
-    pub fn compact(&self) -> Result<(), SledPersistenceError> {
-        self.persistence.compact()
+    /// Calls `hook` with every preset written, including those of batches
+    pub fn on_preset_stored(&self, hook: impl Fn(&Preset) + Send + Sync + 'static) {
+        self.hooks.lock().preset_stored.push(Arc::new(hook));
     }
 
-    pub fn size_on_disk(&self) -> Result<u64, SledPersistenceError> {
-        self.persistence.size_on_disk()
+    /// Calls `hook` after each flush with the number of writes it made durable. Flushes
+    /// of the background thread are reported from that thread.
+    pub fn on_flush(&self, hook: impl Fn(usize) + Send + Sync + 'static) {
+        self.hooks.lock().flushed.push(Arc::new(hook));
     }
 
-    pub fn export_data(&self) -> Result<ExportData, SledPersistenceError> {
-        Ok(ExportData {
-            widgets: self.engine.records.clone(),
-            presets: self.engine.presets.clone(),
-            display_types: self.engine.display_types.clone(),
-            next_id: self.engine.next_id,
-        })
+    /// Writes made since the last flush
+    pub fn pending_writes(&self) -> usize {
+        self.pending_writes.load(Ordering::Acquire)
     }
 
-    pub fn import_data(&mut self, data: ExportData) -> Result<(), SledPersistenceError> {
-        for record in &data.widgets {
-            self.persistence.store_widget(record)?;
+    /// Counts a write and flushes if the policy's write threshold is reached
+    fn note_write(&self) -> Result<(), WidgetIntelligenceError> {
+        let pending = self.pending_writes.fetch_add(1, Ordering::AcqRel) + 1;
+        if let Some(threshold) = self.auto_flush.every_writes {
+            if pending >= threshold.max(1) {
+                self.flush()?;
+            }
         }
+        Ok(())
+    }
 
-        for preset in &data.presets {
-            self.persistence.store_preset(preset)?;
+    /// Encodes a widget or preset for storage with the database's codec
+    fn pack<T: Encode>(&self, value: &T) -> Result<Vec<u8>, WidgetIntelligenceError> {
+        let bytes = bincode::encode_to_vec(value, bincode::config::standard())?;
+        match self.codec {
+            PayloadCodec::Bincode => Ok(bytes),
+            #[cfg(feature = "zstd")]
+            PayloadCodec::BincodeZstd => Ok(zstd::encode_all(bytes.as_slice(), ZSTD_LEVEL)?),
+            #[cfg(not(feature = "zstd"))]
+            PayloadCodec::BincodeZstd => Err(WidgetIntelligenceError::SerializationError(
+                "zstd support is not enabled".to_string(),
+            )),
         }
+    }
 
-        self.engine.records = data.widgets;
-        self.engine.presets = data.presets;
-        self.engine.display_types = data.display_types;
-        self.engine.next_id = data.next_id;
+    /// Counts what is left in the legacy JSON trees next to the current ones
+    pub fn migration_status(&self) -> Result<MigrationStatus, WidgetIntelligenceError> {
+        let legacy_widgets = self
+            .legacy_tree(LEGACY_WIDGETS_TREE)?
+            .map_or(0, |t| t.len());
+        let legacy_presets = self
+            .legacy_tree(LEGACY_PRESETS_TREE)?
+            .map_or(0, |t| t.len());
 
+        Ok(MigrationStatus {
+            legacy_widgets,
+            legacy_presets,
+            new_widgets: self.widgets_tree.len(),
+            new_presets: self.presets_tree.len(),
+            migration_needed: legacy_widgets + legacy_presets > 0,
+        })
+    }
+
+    /// Converts the legacy JSON trees into the current bincode trees. Entries already
+    /// present in the current trees win. A legacy tree is dropped once every entry in it
+    /// has been converted, so failures can be retried after fixing the data.
+    pub fn migrate_legacy(&self) -> Result<MigrationReport, WidgetIntelligenceError> {
+        let mut report = MigrationReport::default();
+
+        if let Some(tree) = self.legacy_tree(LEGACY_WIDGETS_TREE)? {
+            let mut next_id = self.metadata().get::<u64>("next_id")?.unwrap_or(0);
+
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+                match serde_json::from_slice::<WidgetRecord>(&value) {
+                    Ok(record) => {
+                        if self.widgets_tree.contains_key(record.id.to_be_bytes())? {
+                            report.skipped_widgets += 1;
+                        } else {
+                            self.store_widget(&record)?;
+                            report.migrated_widgets += 1;
+                        }
+                        next_id = next_id.max(record.id + 1);
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to convert legacy widget record {}: {e}",
+                            String::from_utf8_lossy(&key)
+                        );
+                        report.failed_widgets += 1;
+                    }
+                }
+            }
+
+            self.metadata().set("next_id", &next_id)?;
+            if report.failed_widgets == 0 {
+                self.db.drop_tree(LEGACY_WIDGETS_TREE)?;
+            }
+        }
+
+        if let Some(tree) = self.legacy_tree(LEGACY_PRESETS_TREE)? {
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+                match serde_json::from_slice::<Preset>(&value) {
+                    Ok(preset) => {
+                        if self.presets_tree.contains_key(preset.name.as_bytes())? {
+                            report.skipped_presets += 1;
+                        } else {
+                            self.store_preset(&preset)?;
+                            report.migrated_presets += 1;
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to convert legacy preset {}: {e}",
+                            String::from_utf8_lossy(&key)
+                        );
+                        report.failed_presets += 1;
+                    }
+                }
+            }
+
+            if report.failed_presets == 0 {
+                self.db.drop_tree(LEGACY_PRESETS_TREE)?;
+            }
+        }
+
+        self.db.flush()?;
+        log::info!(
+            "Migrated {} widget records and {} presets from the legacy JSON trees",
+            report.migrated_widgets,
+            report.migrated_presets
+        );
+        Ok(report)
+    }
+
+    /// Decodes every entry of every tree, reporting the ones that fail. With `quarantine`
+    /// they are moved to the `corrupt` tree, keyed by their tree name, a zero byte and
+    /// their original key, so they stop being skipped on every load but stay recoverable.
+    pub fn verify(&self, quarantine: bool) -> Result<IntegrityReport, WidgetIntelligenceError> {
+        let mut report = IntegrityReport::default();
+
+        let trees: [(&Tree, EntryCheck); 6] = [
+            (&self.widgets_tree, Self::check_widget),
+            (&self.widget_activity_tree, |_, key, value| {
+                if key.len() != 8 {
+                    return Err("key is not a record id".to_string());
+                }
+                decode_widget_activity(value)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }),
+            (&self.presets_tree, Self::check_preset),
+            (&self.preset_history_tree, |_, _, value| {
+                decode_preset_revisions(value)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }),
+            (&self.feedback_tree, |_, _, value| {
+                bincode::decode_from_slice::<FeedbackEntry, _>(value, bincode::config::standard())
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }),
+            (&self.metadata_tree, |_, _, value| {
+                std::str::from_utf8(value)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            }),
+        ];
+
+        for (tree, check) in trees {
+            let tree_name = String::from_utf8_lossy(&tree.name()).into_owned();
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+                report.checked += 1;
+                if let Err(reason) = check(self, &key, &value) {
+                    log::warn!("Corrupt entry in {tree_name}: {reason}");
+                    report.corrupt.push(CorruptEntry {
+                        tree: tree_name.clone(),
+                        key: key.to_vec(),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        if quarantine && !report.corrupt.is_empty() {
+            let corrupt_tree = self.db.open_tree(CORRUPT_TREE)?;
+            for entry in &report.corrupt {
+                let tree = self.db.open_tree(&entry.tree)?;
+                if let Some(value) = tree.remove(&entry.key)? {
+                    let mut key = entry.tree.as_bytes().to_vec();
+                    key.push(0);
+                    key.extend_from_slice(&entry.key);
+                    corrupt_tree.insert(key, value)?;
+                    report.quarantined += 1;
+                }
+            }
+            self.db.flush()?;
+        }
+
+        Ok(report)
+    }
+
+    fn check_widget(&self, key: &[u8], value: &[u8]) -> Result<(), String> {
+        let (record, _) = self
+            .unpack(value)
+            .and_then(|bytes| decode_widget_record(&bytes))
+            .map_err(|e| e.to_string())?;
+        if key != record.id.to_be_bytes() {
+            return Err(format!("key does not match record id {}", record.id));
+        }
+        Ok(())
+    }
+
+    fn check_preset(&self, key: &[u8], value: &[u8]) -> Result<(), String> {
+        let (preset, _) = self
+            .unpack(value)
+            .and_then(|bytes| decode_preset(&bytes))
+            .map_err(|e| e.to_string())?;
+        if key != preset.name.as_bytes() {
+            return Err(format!("key does not match preset name '{}'", preset.name));
+        }
+        Ok(())
+    }
+
+    /// Writes full records in one transaction, dropping the activity they supersede
+    fn write_records(&self, records: &[&WidgetRecord]) -> Result<(), WidgetIntelligenceError> {
+        let encoded = records
+            .iter()
+            .map(|record| Ok((record.id.to_be_bytes(), self.pack(*record)?)))
+            .collect::<Result<Vec<_>, WidgetIntelligenceError>>()?;
+
+        let result: TransactionResult<(), WidgetIntelligenceError> =
+            (&self.widgets_tree, &self.widget_activity_tree).transaction(
+                |(widgets_tree, activity_tree)| {
+                    for (key, value) in &encoded {
+                        widgets_tree.insert(&key[..], value.as_slice())?;
+                        activity_tree.remove(&key[..])?;
+                    }
+                    Ok(())
+                },
+            );
+        result.map_err(|e| match e {
+            TransactionError::Abort(e) => e,
+            TransactionError::Storage(e) => e.into(),
+        })?;
+
+        for record in records {
+            self.hooks.widget_stored(record);
+        }
+        Ok(())
+    }
+
+    /// Applies the stored activity of a record on top of its full version
+    fn with_activity(
+        &self,
+        mut record: WidgetRecord,
+    ) -> Result<WidgetRecord, WidgetIntelligenceError> {
+        let Some(value) = self.widget_activity_tree.get(record.id.to_be_bytes())? else {
+            return Ok(record);
+        };
+
+        match decode_widget_activity(&value) {
+            Ok(activity) if activity.base_values as usize == record.widget.values.len() => {
+                activity.apply_to(&mut record);
+            }
+            Ok(_) => log::warn!(
+                "Ignoring activity of widget record {} stored for another version of it",
+                record.id
+            ),
+            Err(e) => log::warn!(
+                "Failed to decode activity of widget record {}: {e}",
+                record.id
+            ),
+        }
+        Ok(record)
+    }
+
+    /// Opens a legacy tree without creating it when it was never written
+    fn legacy_tree(&self, name: &str) -> Result<Option<Tree>, WidgetIntelligenceError> {
+        if self
+            .db
+            .tree_names()
+            .iter()
+            .any(|tree| tree.as_ref() == name.as_bytes())
+        {
+            Ok(Some(self.db.open_tree(name)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Undoes the codec of a stored widget or preset, leaving the bincode bytes
+    fn unpack<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, [u8]>, WidgetIntelligenceError> {
+        match self.codec {
+            PayloadCodec::Bincode => Ok(Cow::Borrowed(bytes)),
+            #[cfg(feature = "zstd")]
+            PayloadCodec::BincodeZstd => Ok(Cow::Owned(zstd::decode_all(bytes)?)),
+            #[cfg(not(feature = "zstd"))]
+            PayloadCodec::BincodeZstd => Err(WidgetIntelligenceError::DeserializationError(
+                "zstd support is not enabled".to_string(),
+            )),
+        }
+    }
+}
+
+impl PersistenceBackend for SledPersistenceManager {
+    fn store_widget(&self, record: &WidgetRecord) -> Result<(), WidgetIntelligenceError> {
+        self.write_records(&[record])?;
+        self.note_write()
+    }
+
+    /// Writes several records in a single sled transaction
+    fn store_widgets(&self, records: &[&WidgetRecord]) -> Result<(), WidgetIntelligenceError> {
+        self.write_records(records)?;
+        self.note_write()
+    }
+
+    /// Writes only the activity entries of the records. Once a record has collected
+    /// `MAX_ACTIVITY_VALUES` values outside its full version it is rewritten in full.
+    fn store_widget_activity(
+        &self,
+        updates: &[(&WidgetRecord, usize)],
+    ) -> Result<(), WidgetIntelligenceError> {
+        let mut batch = sled::Batch::default();
+        let mut updated = Vec::new();
+        let mut rewrites = Vec::new();
+
+        for &(record, new_values) in updates {
+            let key = record.id.to_be_bytes();
+            let base_values = match self.widget_activity_tree.get(key)? {
+                Some(value) => decode_widget_activity(&value)
+                    .ok()
+                    .map(|activity| activity.base_values as usize),
+                None => record.widget.values.len().checked_sub(new_values),
+            };
+
+            let recent_values = base_values.and_then(|base| record.widget.values.get(base..));
+            match (base_values, recent_values) {
+                (Some(base), Some(recent)) if recent.len() <= MAX_ACTIVITY_VALUES => {
+                    let activity = WidgetActivity {
+                        frequency: record.frequency,
+                        last_seen: record.last_seen,
+                        base_values: base as u32,
+                        recent_values: recent.to_vec(),
+                        value_stats: record.value_stats.clone(),
+                    };
+                    batch.insert(
+                        key.to_vec(),
+                        bincode::encode_to_vec(&activity, bincode::config::standard())?,
+                    );
+                    updated.push(record);
+                }
+                _ => rewrites.push(record),
+            }
+        }
+
+        self.widget_activity_tree.apply_batch(batch)?;
+        for record in updated {
+            self.hooks.widget_stored(record);
+        }
+        if !rewrites.is_empty() {
+            self.write_records(&rewrites)?;
+        }
+        self.note_write()
+    }
+
+    fn delete_widget(&self, id: u64) -> Result<(), WidgetIntelligenceError> {
+        self.widgets_tree.remove(id.to_be_bytes())?;
+        self.widget_activity_tree.remove(id.to_be_bytes())?;
+        self.note_write()
+    }
+
+    /// Commits the batch in one transaction across the trees it touches
+    fn apply_batch(&self, batch: &WriteBatch<'_>) -> Result<(), WidgetIntelligenceError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        // Encode up front so the transaction body cannot fail halfway for our own reasons
+        let widgets = batch
+            .widgets
+            .iter()
+            .map(|record| Ok((record.id.to_be_bytes().to_vec(), self.pack(*record)?)))
+            .collect::<Result<Vec<_>, WidgetIntelligenceError>>()?;
+        let presets = batch
+            .presets
+            .iter()
+            .map(|preset| Ok((preset.name.as_bytes().to_vec(), self.pack(*preset)?)))
+            .collect::<Result<Vec<_>, WidgetIntelligenceError>>()?;
+        let histories = batch
+            .preset_revisions
+            .iter()
+            .map(|(name, revisions)| {
+                let value = if revisions.is_empty() {
+                    None
+                } else {
+                    Some(bincode::encode_to_vec(
+                        revisions,
+                        bincode::config::standard(),
+                    )?)
+                };
+                Ok((name.as_bytes().to_vec(), value))
+            })
+            .collect::<Result<Vec<_>, WidgetIntelligenceError>>()?;
+
+        let result: TransactionResult<(), WidgetIntelligenceError> = (
+            &self.widgets_tree,
+            &self.widget_activity_tree,
+            &self.presets_tree,
+            &self.preset_history_tree,
+            &self.metadata_tree,
+        )
+            .transaction(
+                |(widgets_tree, activity_tree, presets_tree, history_tree, metadata_tree)| {
+                    for (key, value) in &widgets {
+                        widgets_tree.insert(key.as_slice(), value.as_slice())?;
+                        activity_tree.remove(key.as_slice())?;
+                    }
+                    for (key, value) in &presets {
+                        presets_tree.insert(key.as_slice(), value.as_slice())?;
+                    }
+                    for (key, value) in &histories {
+                        match value {
+                            Some(value) => history_tree.insert(key.as_slice(), value.as_slice())?,
+                            None => history_tree.remove(key.as_slice())?,
+                        };
+                    }
+                    for (key, value) in &batch.metadata {
+                        metadata_tree.insert(key.as_bytes(), value.as_bytes())?;
+                    }
+                    Ok(())
+                },
+            );
+
+        result.map_err(|e| match e {
+            TransactionError::Abort(e) => e,
+            TransactionError::Storage(e) => e.into(),
+        })?;
+        for record in &batch.widgets {
+            self.hooks.widget_stored(record);
+        }
+        for preset in &batch.presets {
+            self.hooks.preset_stored(preset);
+        }
+        self.note_write()
+    }
+
+    fn load_widget(&self, id: u64) -> Result<Option<WidgetRecord>, WidgetIntelligenceError> {
+        match self.widgets_tree.get(id.to_be_bytes())? {
+            Some(value) => Ok(Some(
+                self.with_activity(decode_widget_record(&self.unpack(&value)?)?.0)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn for_each_widget(
+        &self,
+        visit: &mut dyn FnMut(WidgetRecord),
+    ) -> Result<(), WidgetIntelligenceError> {
+        for result in self.widgets_tree.iter() {
+            let (_key, value) = result?;
+            match self
+                .unpack(&value)
+                .and_then(|bytes| decode_widget_record(&bytes))
+            {
+                Ok((record, _)) => visit(self.with_activity(record)?),
+                Err(e) => log::warn!("Failed to decode widget record with bincode: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError> {
+        let mut records = Vec::new();
+        let mut migrated = 0;
+
+        for result in self.widgets_tree.iter() {
+            let (_key, value) = result?;
+            match self
+                .unpack(&value)
+                .and_then(|bytes| decode_widget_record(&bytes))
+            {
+                Ok((record, is_legacy)) => {
+                    let record = self.with_activity(record)?;
+                    if is_legacy && !self.read_only {
+                        // Rewrite in the current layout so the fallback is only needed once
+                        self.store_widget(&record)?;
+                        migrated += 1;
+                    }
+                    records.push(record);
+                }
+                Err(e) => {
+                    log::warn!("Failed to decode widget record with bincode: {e}");
+                }
+            }
+        }
+
+        if migrated > 0 {
+            log::info!("Migrated {migrated} widget records from the legacy layout");
+        }
+
+        Ok(records)
+    }
+
+    fn store_preset(&self, preset: &Preset) -> Result<(), WidgetIntelligenceError> {
+        let key = preset.name.as_bytes();
+        let value = self.pack(preset)?;
+
+        self.presets_tree.insert(key, value)?;
+        self.hooks.preset_stored(preset);
+        self.note_write()
+    }
+
+    fn delete_preset(&self, name: &str) -> Result<(), WidgetIntelligenceError> {
+        self.presets_tree.remove(name.as_bytes())?;
+        self.preset_history_tree.remove(name.as_bytes())?;
+        self.note_write()
+    }
+
+    fn store_preset_revisions(
+        &self,
+        name: &str,
+        revisions: &[PresetRevision],
+    ) -> Result<(), WidgetIntelligenceError> {
+        if revisions.is_empty() {
+            self.preset_history_tree.remove(name.as_bytes())?;
+        } else {
+            let value = bincode::encode_to_vec(revisions, bincode::config::standard())?;
+            self.preset_history_tree.insert(name.as_bytes(), value)?;
+        }
+        self.note_write()
+    }
+
+    fn load_preset_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+        match self.preset_history_tree.get(name.as_bytes())? {
+            Some(value) => decode_preset_revisions(&value),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn load_all_presets(&self) -> Result<Vec<Preset>, WidgetIntelligenceError> {
+        let mut presets = Vec::new();
+        let mut migrated = 0;
+
+        for result in self.presets_tree.iter() {
+            let (_key, value) = result?;
+            match self.unpack(&value).and_then(|bytes| decode_preset(&bytes)) {
+                Ok((preset, is_legacy)) => {
+                    if is_legacy && !self.read_only {
+                        self.store_preset(&preset)?;
+                        migrated += 1;
+                    }
+                    presets.push(preset);
+                }
+                Err(e) => {
+                    log::warn!("Failed to decode preset with bincode: {e}");
+                }
+            }
+        }
+
+        if migrated > 0 {
+            log::info!("Migrated {migrated} presets from the legacy layout");
+        }
+
+        Ok(presets)
+    }
+
+    fn store_feedback(&self, entry: &FeedbackEntry) -> Result<(), WidgetIntelligenceError> {
+        let key = self.db.generate_id()?.to_be_bytes();
+        let value = bincode::encode_to_vec(entry, bincode::config::standard())?;
+
+        self.feedback_tree.insert(key, value)?;
+        self.note_write()
+    }
+
+    fn load_feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError> {
+        let mut entries = Vec::new();
+
+        for result in self.feedback_tree.iter() {
+            let (_key, value) = result?;
+            match bincode::decode_from_slice(&value, bincode::config::standard()) {
+                Ok((entry, _)) => entries.push(entry),
+                Err(e) => {
+                    log::warn!("Failed to decode feedback entry with bincode: {e}");
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn store_metadata(&self, key: &str, value: &str) -> Result<(), WidgetIntelligenceError> {
+        self.metadata_tree
+            .insert(key.as_bytes(), value.as_bytes())?;
+        self.note_write()
+    }
+
+    fn load_metadata(&self, key: &str) -> Result<Option<String>, WidgetIntelligenceError> {
+        if let Some(value) = self.metadata_tree.get(key.as_bytes())? {
+            let string_value = String::from_utf8_lossy(&value).to_string();
+            Ok(Some(string_value))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn store_widget_description(
+        &self,
+        event_id: i64,
+        description: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), WidgetIntelligenceError> {
+        // JSON rather than bincode, as descriptions hold arbitrary JSON values
+        let value = serde_json::to_vec(description)
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))?;
+        self.widget_descriptions_tree
+            .insert(event_id.to_be_bytes(), value)?;
+        self.note_write()
+    }
+
+    fn load_widget_descriptions(
+        &self,
+    ) -> Result<Vec<HashMap<String, serde_json::Value>>, WidgetIntelligenceError> {
+        let mut descriptions = Vec::new();
+
+        for result in self.widget_descriptions_tree.iter() {
+            let (_key, value) = result?;
+            match serde_json::from_slice(&value) {
+                Ok(description) => descriptions.push(description),
+                Err(e) => log::warn!("Failed to decode widget description: {e}"),
+            }
+        }
+
+        Ok(descriptions)
+    }
+
+    fn delete_widget_description(&self, event_id: i64) -> Result<(), WidgetIntelligenceError> {
+        self.widget_descriptions_tree
+            .remove(event_id.to_be_bytes())?;
+        self.note_write()
+    }
+
+    fn clear_widget_descriptions(&self) -> Result<(), WidgetIntelligenceError> {
+        self.widget_descriptions_tree.clear()?;
+        self.note_write()
+    }
+
+    fn clear_all(&self) -> Result<(), WidgetIntelligenceError> {
+        for tree in [
+            &self.widgets_tree,
+            &self.widget_activity_tree,
+            &self.presets_tree,
+            &self.feedback_tree,
+            &self.preset_history_tree,
+            &self.widget_descriptions_tree,
+        ] {
+            tree.clear()?;
+        }
+
+        // The codec says how this database is written rather than what it learned
+        let codec = self.metadata_tree.get(PayloadCodec::METADATA_KEY)?;
+        self.metadata_tree.clear()?;
+        if let Some(codec) = codec {
+            self.metadata_tree
+                .insert(PayloadCodec::METADATA_KEY, codec)?;
+        }
+        self.note_write()
+    }
+
+    fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        let writes = self.pending_writes.swap(0, Ordering::AcqRel);
+        self.db.flush()?;
+        self.hooks.flushed(writes);
+        Ok(())
+    }
+
+    fn compact(&self) -> Result<(), WidgetIntelligenceError> {
+        // Note: sled doesn't have a direct compact method, this clears the database
+        // In a real implementation, you might want to implement a proper compaction
+        log::warn!("Compact operation not implemented for sled database");
+        Ok(())
+    }
+
+    fn size_on_disk(&self) -> Result<u64, WidgetIntelligenceError> {
+        Ok(self.db.size_on_disk()?)
+    }
+}
+
+impl Drop for SledPersistenceManager {
+    fn drop(&mut self) {
+        if let Some(flusher) = self.flusher.take() {
+            flusher.stop();
+        }
+        if !self.read_only {
+            if let Err(e) = self.flush() {
+                log::warn!("Failed to flush database on drop: {e}");
+            }
+        }
+        if let Some(owner_file) = self.owner_file.take() {
+            let _ = std::fs::remove_file(owner_file);
+        }
+    }
+}
+
+#[derive(Default)]
+struct MemoryState {
+    widgets: BTreeMap<u64, WidgetRecord>,
+    presets: BTreeMap<String, Preset>,
+    preset_history: HashMap<String, Vec<PresetRevision>>,
+    feedback: Vec<FeedbackEntry>,
+    metadata: HashMap<String, String>,
+    widget_descriptions: BTreeMap<i64, HashMap<String, serde_json::Value>>,
+}
+
+/// A backend that keeps everything in memory and never touches the disk
+///
+/// Useful in unit tests and sandboxed sessions where sled's file locks get in the way.
+/// Its contents are lost when it is dropped.
+#[derive(Default)]
+pub struct MemoryBackend {
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        // The state holds plain values, so a panic elsewhere cannot leave it half-written
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PersistenceBackend for MemoryBackend {
+    fn store_widget(&self, record: &WidgetRecord) -> Result<(), WidgetIntelligenceError> {
+        self.state().widgets.insert(record.id, record.clone());
+        Ok(())
+    }
+
+    fn delete_widget(&self, id: u64) -> Result<(), WidgetIntelligenceError> {
+        self.state().widgets.remove(&id);
+        Ok(())
+    }
+
+    fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError> {
+        Ok(self.state().widgets.values().cloned().collect())
+    }
+
+    fn load_widget(&self, id: u64) -> Result<Option<WidgetRecord>, WidgetIntelligenceError> {
+        Ok(self.state().widgets.get(&id).cloned())
+    }
+
+    fn store_preset(&self, preset: &Preset) -> Result<(), WidgetIntelligenceError> {
+        self.state()
+            .presets
+            .insert(preset.name.clone(), preset.clone());
+        Ok(())
+    }
+
+    fn delete_preset(&self, name: &str) -> Result<(), WidgetIntelligenceError> {
+        let mut state = self.state();
+        state.presets.remove(name);
+        state.preset_history.remove(name);
+        Ok(())
+    }
+
+    fn load_all_presets(&self) -> Result<Vec<Preset>, WidgetIntelligenceError> {
+        Ok(self.state().presets.values().cloned().collect())
+    }
+
+    fn store_preset_revisions(
+        &self,
+        name: &str,
+        revisions: &[PresetRevision],
+    ) -> Result<(), WidgetIntelligenceError> {
+        let mut state = self.state();
+        if revisions.is_empty() {
+            state.preset_history.remove(name);
+        } else {
+            state
+                .preset_history
+                .insert(name.to_string(), revisions.to_vec());
+        }
+        Ok(())
+    }
+
+    fn load_preset_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+        Ok(self
+            .state()
+            .preset_history
+            .get(name)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn store_feedback(&self, entry: &FeedbackEntry) -> Result<(), WidgetIntelligenceError> {
+        self.state().feedback.push(entry.clone());
+        Ok(())
+    }
+
+    fn load_feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError> {
+        Ok(self.state().feedback.clone())
+    }
+
+    fn store_metadata(&self, key: &str, value: &str) -> Result<(), WidgetIntelligenceError> {
+        self.state()
+            .metadata
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn load_metadata(&self, key: &str) -> Result<Option<String>, WidgetIntelligenceError> {
+        Ok(self.state().metadata.get(key).cloned())
+    }
+
+    fn store_widget_description(
+        &self,
+        event_id: i64,
+        description: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), WidgetIntelligenceError> {
+        self.state()
+            .widget_descriptions
+            .insert(event_id, description.clone());
+        Ok(())
+    }
+
+    fn load_widget_descriptions(
+        &self,
+    ) -> Result<Vec<HashMap<String, serde_json::Value>>, WidgetIntelligenceError> {
+        Ok(self.state().widget_descriptions.values().cloned().collect())
+    }
+
+    fn delete_widget_description(&self, event_id: i64) -> Result<(), WidgetIntelligenceError> {
+        self.state().widget_descriptions.remove(&event_id);
+        Ok(())
+    }
+
+    fn clear_widget_descriptions(&self) -> Result<(), WidgetIntelligenceError> {
+        self.state().widget_descriptions.clear();
+        Ok(())
+    }
+
+    fn clear_all(&self) -> Result<(), WidgetIntelligenceError> {
+        *self.state() = MemoryState::default();
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        Ok(())
+    }
+}
+
+/// Wraps a backend so that reads pass through and every write is rejected
+pub struct ReadOnlyBackend<B: PersistenceBackend = SledPersistenceManager> {
+    inner: B,
+}
+
+impl<B: PersistenceBackend> ReadOnlyBackend<B> {
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+}
+
+impl<B: PersistenceBackend> PersistenceBackend for ReadOnlyBackend<B> {
+    fn store_widget(&self, _record: &WidgetRecord) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn store_widgets(&self, _records: &[&WidgetRecord]) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn apply_batch(&self, _batch: &WriteBatch<'_>) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn delete_widget(&self, _id: u64) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError> {
+        self.inner.load_all_widgets()
+    }
+
+    fn load_widget(&self, id: u64) -> Result<Option<WidgetRecord>, WidgetIntelligenceError> {
+        self.inner.load_widget(id)
+    }
+
+    fn for_each_widget(
+        &self,
+        visit: &mut dyn FnMut(WidgetRecord),
+    ) -> Result<(), WidgetIntelligenceError> {
+        self.inner.for_each_widget(visit)
+    }
+
+    fn store_preset(&self, _preset: &Preset) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn delete_preset(&self, _name: &str) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn load_all_presets(&self) -> Result<Vec<Preset>, WidgetIntelligenceError> {
+        self.inner.load_all_presets()
+    }
+
+    fn store_preset_revisions(
+        &self,
+        _name: &str,
+        _revisions: &[PresetRevision],
+    ) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn load_preset_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+        self.inner.load_preset_revisions(name)
+    }
+
+    fn store_feedback(&self, _entry: &FeedbackEntry) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn load_feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError> {
+        self.inner.load_feedback_log()
+    }
+
+    fn store_metadata(&self, _key: &str, _value: &str) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn load_metadata(&self, key: &str) -> Result<Option<String>, WidgetIntelligenceError> {
+        self.inner.load_metadata(key)
+    }
+
+    fn store_widget_description(
+        &self,
+        _event_id: i64,
+        _description: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn load_widget_descriptions(
+        &self,
+    ) -> Result<Vec<HashMap<String, serde_json::Value>>, WidgetIntelligenceError> {
+        self.inner.load_widget_descriptions()
+    }
+
+    fn delete_widget_description(&self, _event_id: i64) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn clear_widget_descriptions(&self) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    fn clear_all(&self) -> Result<(), WidgetIntelligenceError> {
+        Err(WidgetIntelligenceError::ReadOnly)
+    }
+
+    /// Nothing was written, so there is nothing to flush
+    fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        Ok(())
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn size_on_disk(&self) -> Result<u64, WidgetIntelligenceError> {
+        self.inner.size_on_disk()
+    }
+}
+
+// Widget layouts written by earlier versions. bincode is not self-describing, so each
+// layout change keeps its predecessor here for `decode_widget_record` to fall back on.
+
+/// Widget layout stored before `event_id` and `values` were added
+#[derive(Decode)]
+struct WidgetLayoutV0 {
+    label: Option<String>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    is_generated: Option<bool>,
+    display_type: Option<String>,
+    current_value: Option<f64>,
+}
+
+/// Widget layout stored before `value_kind` was added
+#[derive(Decode)]
+struct WidgetLayoutV1 {
+    label: Option<String>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    is_generated: Option<bool>,
+    display_type: Option<String>,
+    current_value: Option<f64>,
+    event_id: Option<u64>,
+    values: Vec<f64>,
+}
+
+/// Widget layout stored before `taper` was added
+#[derive(Decode)]
+struct WidgetLayoutV2 {
+    label: Option<String>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    is_generated: Option<bool>,
+    display_type: Option<String>,
+    current_value: Option<f64>,
+    event_id: Option<u64>,
+    values: Vec<f64>,
+    value_kind: Option<ValueKind>,
+}
+
+impl From<WidgetLayoutV0> for Widget {
+    fn from(widget: WidgetLayoutV0) -> Self {
+        Widget {
+            label: widget.label,
+            minimum: widget.minimum,
+            maximum: widget.maximum,
+            is_generated: widget.is_generated,
+            display_type: widget.display_type,
+            current_value: widget.current_value,
+            event_id: None,
+            values: widget.current_value.into_iter().collect(),
+            value_kind: None,
+            taper: None,
+        }
+    }
+}
+
+impl From<WidgetLayoutV1> for Widget {
+    fn from(widget: WidgetLayoutV1) -> Self {
+        Widget {
+            label: widget.label,
+            minimum: widget.minimum,
+            maximum: widget.maximum,
+            is_generated: widget.is_generated,
+            display_type: widget.display_type,
+            current_value: widget.current_value,
+            event_id: widget.event_id,
+            values: widget.values,
+            value_kind: None,
+            taper: None,
+        }
+    }
+}
+
+impl From<WidgetLayoutV2> for Widget {
+    fn from(widget: WidgetLayoutV2) -> Self {
+        Widget {
+            label: widget.label,
+            minimum: widget.minimum,
+            maximum: widget.maximum,
+            is_generated: widget.is_generated,
+            display_type: widget.display_type,
+            current_value: widget.current_value,
+            event_id: widget.event_id,
+            values: widget.values,
+            value_kind: widget.value_kind,
+            taper: None,
+        }
+    }
+}
+
+/// A widget record as stored with an older widget layout `W`
+#[derive(Decode)]
+struct LegacyWidgetRecord<W> {
+    id: u64,
+    widget: W,
+    features: WidgetFeatures,
+    frequency: u32,
+    last_seen: u64,
+    value_stats: Option<ValueStats>,
+}
+
+impl<W: Into<Widget>> From<LegacyWidgetRecord<W>> for WidgetRecord {
+    fn from(legacy: LegacyWidgetRecord<W>) -> Self {
+        WidgetRecord {
+            id: legacy.id,
+            widget: legacy.widget.into(),
+            features: legacy.features,
+            frequency: legacy.frequency,
+            last_seen: legacy.last_seen,
+            value_stats: legacy.value_stats,
+        }
+    }
+}
+
+/// Values a record may collect in its activity entry before it is rewritten in full
+const MAX_ACTIVITY_VALUES: usize = 64;
+
+/// The part of a widget record that changes with every observation
+///
+/// The sled manager stores it apart from the record so learning from a known control
+/// writes a few bytes instead of the record and its whole value history. The full
+/// record stays authoritative for everything else and replaces the entry when written.
+#[derive(Debug, Clone, Encode, Decode)]
+struct WidgetActivity {
+    frequency: u32,
+    last_seen: u64,
+    /// Length of the value history of the full record this entry extends
+    base_values: u32,
+    /// Values observed since the full record was written
+    recent_values: Vec<f64>,
+    value_stats: Option<ValueStats>,
+}
+
+impl WidgetActivity {
+    fn apply_to(self, record: &mut WidgetRecord) {
+        record.frequency = self.frequency;
+        record.last_seen = self.last_seen;
+        record
+            .features
+            .value_patterns
+            .extend_from_slice(&self.recent_values);
+        record.widget.values.extend(self.recent_values);
+        record.value_stats = self.value_stats;
+    }
+}
+
+fn decode_widget_activity(bytes: &[u8]) -> Result<WidgetActivity, WidgetIntelligenceError> {
+    let (activity, read) = bincode::decode_from_slice(bytes, bincode::config::standard())?;
+    if read != bytes.len() {
+        return Err(WidgetIntelligenceError::DeserializationError(
+            "Trailing bytes after widget activity".to_string(),
+        ));
+    }
+    Ok(activity)
+}
+
+/// Decodes a widget record, falling back to legacy layouts, newest first.
+/// Returns the record and whether it was stored in a legacy layout.
+fn decode_widget_record(bytes: &[u8]) -> Result<(WidgetRecord, bool), WidgetIntelligenceError> {
+    let config = bincode::config::standard();
+
+    // A record is only accepted if it consumes the whole value, otherwise a legacy
+    // record could be misread as a (garbled) current one
+    let current_error = match bincode::decode_from_slice::<WidgetRecord, _>(bytes, config) {
+        Ok((record, read)) if read == bytes.len() => return Ok((record, false)),
+        Ok(_) => WidgetIntelligenceError::DeserializationError(
+            "Trailing bytes after widget record".to_string(),
+        ),
+        Err(e) => e.into(),
+    };
+
+    if let Ok((legacy, read)) =
+        bincode::decode_from_slice::<LegacyWidgetRecord<WidgetLayoutV2>, _>(bytes, config)
+    {
+        if read == bytes.len() {
+            return Ok((legacy.into(), true));
+        }
+    }
+
+    if let Ok((legacy, read)) =
+        bincode::decode_from_slice::<LegacyWidgetRecord<WidgetLayoutV1>, _>(bytes, config)
+    {
+        if read == bytes.len() {
+            return Ok((legacy.into(), true));
+        }
+    }
+
+    if let Ok((legacy, read)) =
+        bincode::decode_from_slice::<LegacyWidgetRecord<WidgetLayoutV0>, _>(bytes, config)
+    {
+        if read == bytes.len() {
+            return Ok((legacy.into(), true));
+        }
+    }
+
+    Err(current_error)
+}
+
+/// Preset layout stored before `tags` was added
+#[derive(Decode)]
+struct PresetLayoutV0 {
+    name: String,
+    description: Option<String>,
+    widget_values: Vec<WidgetValue>,
+    created_by: Option<String>,
+    usage_count: u32,
+    last_used: u64,
+}
+
+impl From<PresetLayoutV0> for Preset {
+    fn from(preset: PresetLayoutV0) -> Self {
+        Preset {
+            name: preset.name,
+            description: preset.description,
+            widget_values: preset.widget_values,
+            created_by: preset.created_by,
+            usage_count: preset.usage_count,
+            last_used: preset.last_used,
+            tags: Vec::new(),
+            favorite: false,
+        }
+    }
+}
+
+/// Preset layout stored before `favorite` was added
+#[derive(Decode)]
+struct PresetLayoutV1 {
+    name: String,
+    description: Option<String>,
+    widget_values: Vec<WidgetValue>,
+    created_by: Option<String>,
+    usage_count: u32,
+    last_used: u64,
+    tags: Vec<String>,
+}
+
+impl From<PresetLayoutV1> for Preset {
+    fn from(preset: PresetLayoutV1) -> Self {
+        Preset {
+            name: preset.name,
+            description: preset.description,
+            widget_values: preset.widget_values,
+            created_by: preset.created_by,
+            usage_count: preset.usage_count,
+            last_used: preset.last_used,
+            tags: preset.tags,
+            favorite: false,
+        }
+    }
+}
+
+/// A preset revision as stored with a legacy preset layout
+#[derive(Decode)]
+struct LegacyPresetRevision<P> {
+    preset: P,
+    replaced_at: u64,
+}
+
+impl<P: Into<Preset>> From<LegacyPresetRevision<P>> for PresetRevision {
+    fn from(legacy: LegacyPresetRevision<P>) -> Self {
+        PresetRevision {
+            preset: legacy.preset.into(),
+            replaced_at: legacy.replaced_at,
+        }
+    }
+}
+
+/// Decodes a preset, falling back to the layouts without favorites and tags.
+/// Returns the preset and whether it was stored in the legacy layout.
+fn decode_preset(bytes: &[u8]) -> Result<(Preset, bool), WidgetIntelligenceError> {
+    let config = bincode::config::standard();
+
+    let current_error = match bincode::decode_from_slice::<Preset, _>(bytes, config) {
+        Ok((preset, read)) if read == bytes.len() => return Ok((preset, false)),
+        Ok(_) => {
+            WidgetIntelligenceError::DeserializationError("Trailing bytes after preset".to_string())
+        }
+        Err(e) => e.into(),
+    };
+
+    if let Ok((legacy, read)) = bincode::decode_from_slice::<PresetLayoutV1, _>(bytes, config) {
+        if read == bytes.len() {
+            return Ok((legacy.into(), true));
+        }
+    }
+
+    if let Ok((legacy, read)) = bincode::decode_from_slice::<PresetLayoutV0, _>(bytes, config) {
+        if read == bytes.len() {
+            return Ok((legacy.into(), true));
+        }
+    }
+
+    Err(current_error)
+}
+
+/// Decodes a preset's revision history, falling back to the layouts without favorites and tags
+fn decode_preset_revisions(bytes: &[u8]) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+    let config = bincode::config::standard();
+
+    let current_error = match bincode::decode_from_slice::<Vec<PresetRevision>, _>(bytes, config) {
+        Ok((revisions, read)) if read == bytes.len() => return Ok(revisions),
+        Ok(_) => WidgetIntelligenceError::DeserializationError(
+            "Trailing bytes after preset revisions".to_string(),
+        ),
+        Err(e) => e.into(),
+    };
+
+    if let Ok((legacy, read)) =
+        bincode::decode_from_slice::<Vec<LegacyPresetRevision<PresetLayoutV1>>, _>(bytes, config)
+    {
+        if read == bytes.len() {
+            return Ok(legacy.into_iter().map(PresetRevision::from).collect());
+        }
+    }
+
+    if let Ok((legacy, read)) =
+        bincode::decode_from_slice::<Vec<LegacyPresetRevision<PresetLayoutV0>>, _>(bytes, config)
+    {
+        if read == bytes.len() {
+            return Ok(legacy.into_iter().map(PresetRevision::from).collect());
+        }
+    }
+
+    Err(current_error)
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub legacy_widgets: usize,
+    pub legacy_presets: usize,
+    pub new_widgets: usize,
+    pub new_presets: usize,
+    pub migration_needed: bool,
+}
+
+/// How much a profile has learned
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileStats {
+    pub profile: String,
+    pub widgets: usize,
+    pub presets: usize,
+    pub feedback_entries: usize,
+}
+
+/// An entry `verify` could not decode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    pub tree: String,
+    pub key: Vec<u8>,
+    pub reason: String,
+}
+
+/// The outcome of `verify`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Entries looked at across all trees
+    pub checked: usize,
+    pub corrupt: Vec<CorruptEntry>,
+    /// Corrupt entries moved to the `corrupt` tree
+    pub quarantined: usize,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupt.is_empty()
+    }
+}
+
+/// What `migrate_legacy` did with the entries of the legacy trees
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub migrated_widgets: usize,
+    pub migrated_presets: usize,
+    /// Entries whose id or name already existed in the current trees
+    pub skipped_widgets: usize,
+    pub skipped_presets: usize,
+    /// Entries that could not be parsed; their legacy tree is kept
+    pub failed_widgets: usize,
+    pub failed_presets: usize,
+}
+
+pub struct PersistentWidgetSuggestionEngine<B: PersistenceBackend = SledPersistenceManager> {
+    /// Holds no records while the engine is lazy, see `open_lazy`
+    pub engine: WidgetSuggestionEngine,
+    pub persistence: B,
+    capacity: CapacityLimits,
+    retention: RetentionPolicy,
+    last_eviction: Option<EvictionReport>,
+    /// Summaries of the records while they are loaded on demand
+    index: Option<RecordIndex>,
+    /// Every record, loaded for the first read the index cannot serve
+    hydrated: OnceLock<WidgetSuggestionEngine>,
+}
+
+/// What `run_maintenance` removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaintenanceReport {
+    /// Records dropped by the retention policy
+    pub expired: Vec<u64>,
+    pub eviction: EvictionReport,
+}
+
+impl PersistentWidgetSuggestionEngine<SledPersistenceManager> {
+    pub fn new<P: AsRef<std::path::Path>>(db_path: P) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend(SledPersistenceManager::new(db_path)?)
+    }
+
+    /// Opens the database at `db_path` for suggestions and stats only; every write is
+    /// rejected with `WidgetIntelligenceError::ReadOnly`
+    ///
+    /// sled cannot open a database without locking it, so this reads a snapshot like
+    /// `open_snapshot`: the database is left untouched and may be open elsewhere.
+    pub fn open_read_only<P: AsRef<std::path::Path>>(
+        db_path: P,
+    ) -> Result<PersistentWidgetSuggestionEngine<ReadOnlyBackend>, WidgetIntelligenceError> {
+        Self::open_snapshot(db_path)
+    }
+
+    /// Opens the database at `db_path` with only an index of the records in memory, see
+    /// `with_backend_lazy`
+    pub fn open_lazy<P: AsRef<std::path::Path>>(
+        db_path: P,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend_lazy(SledPersistenceManager::new(db_path)?)
+    }
+
+    /// Opens a read-only copy of the database at `db_path`, which works while another
+    /// process has it open, see `SledPersistenceManager::open_snapshot`
+    pub fn open_snapshot<P: AsRef<std::path::Path>>(
+        db_path: P,
+    ) -> Result<PersistentWidgetSuggestionEngine<ReadOnlyBackend>, WidgetIntelligenceError> {
+        PersistentWidgetSuggestionEngine::with_backend(SledPersistenceManager::open_snapshot(
+            db_path,
+        )?)
+    }
+
+    pub fn migration_status(&self) -> Result<MigrationStatus, WidgetIntelligenceError> {
+        self.persistence.migration_status()
+    }
+
+    pub fn verify(&self, quarantine: bool) -> Result<IntegrityReport, WidgetIntelligenceError> {
+        self.persistence.verify(quarantine)
+    }
+
+    /// Combines the learning of the database at `other_path` into this one, e.g. from a
+    /// studio machine and a touring laptop
+    ///
+    /// Similar records are merged, adding up frequencies and value histories. Presets with
+    /// a name that exists here are resolved according to `policy`. The other database is
+    /// read from a snapshot, like `open_snapshot`, so it is neither locked nor written and
+    /// may be open in another process.
+    pub fn merge_from<P: AsRef<std::path::Path>>(
+        &mut self,
+        other_path: P,
+        policy: PresetConflictPolicy,
+    ) -> Result<DatabaseMergeReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let other = SledPersistenceManager::open_snapshot(other_path)?;
+        let widgets = other.load_all_widgets()?;
+        let presets = other.load_all_presets()?;
+        drop(other);
+
+        let initial_next_id = self.engine.next_id;
+        let mut report = DatabaseMergeReport::default();
+        let mut touched = HashSet::new();
+        for record in widgets {
+            let (id, merged) = self.engine.merge_record(record);
+            if merged {
+                report.merged_widgets += 1;
+            } else {
+                report.added_widgets += 1;
+            }
+            touched.insert(id);
+        }
+
+        let records: Vec<&WidgetRecord> = self
+            .engine
+            .records
+            .iter()
+            .filter(|r| touched.contains(&r.id))
+            .collect();
+        self.store_records(records, initial_next_id)?;
+
+        for preset in presets {
+            let original_name = preset.name.clone();
+            match self.merge_preset(preset, policy)? {
+                Some(name) => report.presets.imported.push(name),
+                None => report.presets.skipped.push(original_name),
+            }
+        }
+
+        self.enforce_capacity()?;
+        self.flush()?;
+        log::info!(
+            "Merged {} and added {} widget records from another database",
+            report.merged_widgets,
+            report.added_widgets
+        );
+        Ok(report)
+    }
+
+    /// Opens the database at `db_path` on the trees of `profile`
+    pub fn with_profile<P: AsRef<std::path::Path>>(
+        db_path: P,
+        profile: &str,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend(SledPersistenceManager::with_profile(db_path, profile)?)
+    }
+
+    pub fn profile(&self) -> &str {
+        self.persistence.profile()
+    }
+
+    /// Persists the current profile and loads `profile` in its place, e.g. when another
+    /// performer takes over or a different sound bank is opened
+    pub fn switch_profile(&mut self, profile: &str) -> Result<(), WidgetIntelligenceError> {
+        self.persistence.switch_profile(profile)?;
+        self.reload()
+    }
+
+    pub fn list_profiles(&self) -> Vec<String> {
+        self.persistence.list_profiles()
+    }
+
+    pub fn profile_stats(
+        &self,
+        profile: &str,
+    ) -> Result<Option<ProfileStats>, WidgetIntelligenceError> {
+        self.persistence.profile_stats(profile)
+    }
+
+    /// Opens the database at `db_path`, with `config` if it has none stored yet, see
+    /// `with_backend_and_config`
+    pub fn with_config<P: AsRef<std::path::Path>>(
+        db_path: P,
+        config: EngineConfig,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend_and_config(SledPersistenceManager::new(db_path)?, config)
+    }
+
+    /// Opens the database at `db_path` with sled configured by `options`
+    pub fn with_options<P: AsRef<std::path::Path>>(
+        db_path: P,
+        options: &SledOptions,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend(SledPersistenceManager::with_options(db_path, options)?)
+    }
+
+    /// Opens the database at `db_path` flushing according to `policy`
+    pub fn with_auto_flush<P: AsRef<std::path::Path>>(
+        db_path: P,
+        policy: AutoFlushPolicy,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        let mut persistence = SledPersistenceManager::new(db_path)?;
+        persistence.set_auto_flush(policy);
+        Self::with_backend(persistence)
+    }
+
+    pub fn set_auto_flush(&mut self, policy: AutoFlushPolicy) {
+        self.persistence.set_auto_flush(policy);
+    }
+
+    /// Moves training data from the legacy JSON trees into the current ones and
+    /// reloads the engine with it
+    pub fn migrate_legacy(&mut self) -> Result<MigrationReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let report = self.persistence.migrate_legacy()?;
+
+        if report.migrated_widgets + report.migrated_presets > 0 {
+            self.engine.records = self.persistence.load_all_widgets()?;
+            self.engine.presets = self.persistence.load_all_presets()?;
+            if let Some(max_id) = self.engine.records.iter().map(|r| r.id).max() {
+                self.engine.next_id = self.engine.next_id.max(max_id + 1);
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl PersistentWidgetSuggestionEngine<MemoryBackend> {
+    /// An engine whose learned state lives only as long as the engine itself
+    pub fn in_memory() -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend(MemoryBackend::new())
+    }
+}
+
+impl<B: PersistenceBackend> PersistentWidgetSuggestionEngine<B> {
+    /// Loads the learned state from `persistence` and keeps it in sync from then on
+    pub fn with_backend(persistence: B) -> Result<Self, WidgetIntelligenceError> {
+        Self::open_backend(persistence, None)
+    }
+
+    /// Like `with_backend`, with `config` for a database that has none stored yet. A
+    /// database keeps the configuration it was created with; a different `config` is
+    /// reported and ignored, see `set_config` to replace the stored one.
+    pub fn with_backend_and_config(
+        persistence: B,
+        config: EngineConfig,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        Self::open_backend(persistence, Some(config))
+    }
+
+    /// Like `with_backend`, keeping only summaries of the records in memory, for large
+    /// databases that are mostly queried
+    ///
+    /// Suggestions load the records the index ranks highest for the query from the
+    /// backend. Other reads load every record once; the first write does the same and
+    /// the engine carries on like one opened with `with_backend`. Until then `engine`
+    /// holds the presets and settings but no records.
+    pub fn with_backend_lazy(persistence: B) -> Result<Self, WidgetIntelligenceError> {
+        Self::open(persistence, None, true)
+    }
+
+    fn open_backend(
+        persistence: B,
+        config: Option<EngineConfig>,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        Self::open(persistence, config, false)
+    }
+
+    fn open(
+        persistence: B,
+        config: Option<EngineConfig>,
+        lazy: bool,
+    ) -> Result<Self, WidgetIntelligenceError> {
+        let mut system = Self {
+            engine: WidgetSuggestionEngine::with_config(config.clone().unwrap_or_default()),
+            persistence,
+            capacity: CapacityLimits::default(),
+            retention: RetentionPolicy::default(),
+            last_eviction: None,
+            // An empty index marks the engine lazy; reload builds it
+            index: lazy.then(RecordIndex::default),
+            hydrated: OnceLock::new(),
+        };
+        system.reload()?;
+
+        if let Some(config) = config {
+            if !same_config(&config, &system.engine.config) {
+                log::warn!(
+                    "The supplied engine configuration differs from the one stored in the \
+                     database, which is kept; use set_config to replace it"
+                );
+            }
+        }
+        Ok(system)
+    }
+
+    /// Writes `records` in one batch, together with the id counter if it moved since
+    /// `initial_next_id`, so a crash cannot persist a record without the counter
+    /// moving past its id
+    fn store_records(
+        &self,
+        records: Vec<&WidgetRecord>,
+        initial_next_id: u64,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let mut batch = WriteBatch {
+            widgets: records,
+            ..WriteBatch::default()
+        };
+        if self.engine.next_id != initial_next_id {
+            batch
+                .metadata
+                .push(("next_id".to_string(), self.engine.next_id.to_metadata()));
+        }
+        self.persistence.apply_batch(&batch)
+    }
+
+    /// Replaces the in-memory state with what the backend holds, or with the index of
+    /// it while the engine is lazy
+    fn reload(&mut self) -> Result<(), WidgetIntelligenceError> {
+        let persistence = &self.persistence;
+        let mut engine = WidgetSuggestionEngine::with_config(self.engine.config.clone());
+        self.hydrated = OnceLock::new();
+
+        if let Some(index) = &mut self.index {
+            *index = RecordIndex::build(persistence)?;
+        } else {
+            match persistence.load_all_widgets() {
+                Ok(widgets) => {
+                    engine.records = widgets;
+                    log::info!(
+                        "Loaded {} widget records from database",
+                        engine.records.len()
+                    );
+                }
+                Err(e) => {
+                    log::warn!("Failed to load widgets from database: {e}");
+                }
+            }
+        }
+
+        match persistence.load_all_presets() {
+            Ok(presets) => {
+                engine.presets = presets;
+                log::info!("Loaded {} presets from database", engine.presets.len());
+            }
+            Err(e) => {
+                log::warn!("Failed to load presets from database: {e}");
+            }
+        }
+
+        let metadata = persistence.metadata();
+        // Databases without a stored configuration take the current one
+        let mut store_config = false;
+        match metadata.get_json::<EngineConfig>("engine_config") {
+            Ok(Some(config)) => engine.config = config,
+            Ok(None) => store_config = true,
+            Err(e) => log::warn!("Failed to load engine configuration: {e}"),
+        }
+
+        match metadata.get::<u64>("next_id") {
+            Ok(Some(id)) => engine.next_id = id,
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to load next record id: {e}"),
+        }
+        // A counter behind the stored records, e.g. from a database written before ids
+        // were committed with their records, would hand their ids out again
+        let max_id = match &self.index {
+            Some(index) => index.max_id(),
+            None => engine.records.iter().map(|r| r.id).max(),
+        };
+        let after_records = max_id.map_or(1, |id| id + 1);
+        if engine.next_id < after_records {
+            log::warn!(
+                "Next record id {} is taken by a stored record, continuing at {after_records}",
+                engine.next_id
+            );
+            engine.next_id = after_records;
+        }
+
+        match metadata.get_json::<ConfidenceCalibration>("calibration") {
+            Ok(Some(calibration)) => engine.calibration = calibration,
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to load confidence calibration: {e}"),
+        }
+
+        let capacity = metadata
+            .get_json::<CapacityLimits>("capacity_limits")
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to load capacity limits: {e}");
+                None
+            })
+            .unwrap_or_default();
+
+        let retention = metadata
+            .get_json::<RetentionPolicy>("retention_policy")
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to load retention policy: {e}");
+                None
+            })
+            .unwrap_or_default();
+
+        self.engine = engine;
+        self.capacity = capacity;
+        self.retention = retention;
+        self.last_eviction = None;
+        if !self.persistence.is_read_only() {
+            if store_config {
+                self.persistence
+                    .metadata()
+                    .set_json("engine_config", &self.engine.config)?;
+            }
+            // Maintenance needs every record; a lazy engine runs it once they are loaded
+            if self.index.is_none() {
+                self.run_maintenance()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether only an index of the records is in memory, see `with_backend_lazy`
+    pub fn is_lazy(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Summaries of every record, in id order, from the index while the engine is lazy
+    pub fn record_summaries(&self) -> Vec<RecordSummary> {
+        match &self.index {
+            Some(index) => index.summaries().cloned().collect(),
+            None => self
+                .engine
+                .records
+                .iter()
+                .map(RecordSummary::from)
+                .collect(),
+        }
+    }
+
+    /// The engine with every record in it, loading them on first use while the engine
+    /// is lazy
+    fn loaded(&self) -> &WidgetSuggestionEngine {
+        if self.index.is_none() {
+            return &self.engine;
+        }
+        if let Some(engine) = self.hydrated.get() {
+            return engine;
+        }
+        match self.persistence.load_all_widgets() {
+            Ok(records) => {
+                let _ = self
+                    .hydrated
+                    .set(engine_with_records(&self.engine, records));
+                self.hydrated.get().unwrap_or(&self.engine)
+            }
+            Err(e) => {
+                log::warn!("Failed to load widgets from database: {e}");
+                &self.engine
+            }
+        }
+    }
+
+    /// Loads every record and stops using the index, e.g. before a write
+    fn ensure_loaded(&mut self) -> Result<(), WidgetIntelligenceError> {
+        if self.index.take().is_some() {
+            self.reload()?;
+        }
+        Ok(())
+    }
+
+    /// A lazy engine holding the records the index ranks highest for `partial_widget`,
+    /// or `None` once every record is loaded
+    fn candidate_engine(
+        &self,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+        with_presets: bool,
+    ) -> Option<WidgetSuggestionEngine> {
+        let index = self.index.as_ref()?;
+        Some(index.candidate_engine(
+            &self.engine,
+            &self.persistence,
+            partial_widget,
+            max_suggestions,
+            with_presets,
+        ))
+    }
+
+    /// The configuration suggestions and learning run with
+    pub fn config(&self) -> &EngineConfig {
+        &self.engine.config
+    }
+
+    /// Replaces the engine configuration and stores it, so the database reopens with it.
+    /// Changes made to `engine.config` directly last until the database is closed.
+    pub fn set_config(&mut self, config: EngineConfig) -> Result<(), WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        self.persistence
+            .metadata()
+            .set_json("engine_config", &config)?;
+        self.engine.config = config;
+        Ok(())
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.persistence.is_read_only()
+    }
+
+    /// Rejects a write up front, before the in-memory state is touched, and loads every
+    /// record of a lazy engine
+    fn ensure_writable(&mut self) -> Result<(), WidgetIntelligenceError> {
+        self.reject_read_only()?;
+        self.ensure_loaded()
+    }
+
+    fn reject_read_only(&self) -> Result<(), WidgetIntelligenceError> {
+        if self.persistence.is_read_only() {
+            return Err(WidgetIntelligenceError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        self.retention
+    }
+
+    /// Stores a new retention policy and drops the records that expired under it
+    pub fn set_retention_policy(
+        &mut self,
+        policy: RetentionPolicy,
+    ) -> Result<Vec<u64>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        self.persistence
+            .metadata()
+            .set_json("retention_policy", &policy)?;
+        self.retention = policy;
+        self.apply_retention()
+    }
+
+    /// Drops the records that expired under the retention policy from memory and the
+    /// database
+    pub fn apply_retention(&mut self) -> Result<Vec<u64>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let expired = self.engine.apply_retention(&self.retention);
+        for id in &expired {
+            self.persistence.delete_widget(*id)?;
+        }
+
+        if !expired.is_empty() {
+            log::info!("Dropped {} expired widget records", expired.len());
+        }
+        Ok(expired)
+    }
+
+    /// Applies the retention policy and capacity limits. Runs when the engine is opened;
+    /// long-running hosts can call it periodically.
+    pub fn run_maintenance(&mut self) -> Result<MaintenanceReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let expired = self.apply_retention()?;
+        let eviction = self.enforce_capacity()?;
+        if !expired.is_empty() || !eviction.is_empty() {
+            self.flush()?;
+        }
+
+        Ok(MaintenanceReport { expired, eviction })
+    }
+
+    pub fn capacity_limits(&self) -> CapacityLimits {
+        self.capacity
+    }
+
+    /// Stores new capacity limits and evicts records until they hold
+    pub fn set_capacity_limits(
+        &mut self,
+        limits: CapacityLimits,
+    ) -> Result<EvictionReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        self.persistence
+            .metadata()
+            .set_json("capacity_limits", &limits)?;
+        self.capacity = limits;
+        self.enforce_capacity()
+    }
+
+    /// Evicts records beyond the capacity limits from memory and the database. Learning
+    /// calls this itself; the report of the latest eviction is kept for
+    /// `take_eviction_report`.
+    pub fn enforce_capacity(&mut self) -> Result<EvictionReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let report = self.engine.enforce_capacity(&self.capacity);
+        if report.is_empty() {
+            return Ok(report);
+        }
+
+        for id in &report.evicted {
+            self.persistence.delete_widget(*id)?;
+        }
+        log::info!(
+            "Evicted {} widget records to stay within capacity ({} bytes left)",
+            report.evicted.len(),
+            report.bytes_after
+        );
+        self.last_eviction = Some(report.clone());
+        Ok(report)
+    }
+
+    /// The report of the latest eviction not taken yet
+    pub fn take_eviction_report(&mut self) -> Option<EvictionReport> {
+        self.last_eviction.take()
+    }
+
+    pub fn store_widget(&mut self, widget: Widget) -> Result<(), WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        self.store_widgets(vec![widget])
+    }
+
+    /// Learns from many widgets at once, persisting every touched record in one batch
+    pub fn store_widgets(&mut self, widgets: Vec<Widget>) -> Result<(), WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let initial_next_id = self.engine.next_id;
+        // Learning only ever fills in a missing label or event ID of a known record;
+        // records where neither happened changed in their activity alone
+        let before: HashMap<u64, (usize, bool, bool)> = self
+            .engine
+            .records
+            .iter()
+            .map(|r| {
+                let widget = &r.widget;
+                (
+                    r.id,
+                    (
+                        widget.values.len(),
+                        widget.label.is_some(),
+                        widget.event_id.is_some(),
+                    ),
+                )
+            })
+            .collect();
+        let touched = self.engine.store_widgets(widgets);
+
+        let mut records: Vec<&WidgetRecord> = Vec::new();
+        let mut activity: Vec<(&WidgetRecord, usize)> = Vec::new();
+        for record in self
+            .engine
+            .records
+            .iter()
+            .filter(|r| touched.contains(&r.id))
+        {
+            let widget = &record.widget;
+            match before.get(&record.id) {
+                Some(&(values, has_label, has_event_id))
+                    if has_label == widget.label.is_some()
+                        && has_event_id == widget.event_id.is_some() =>
+                {
+                    activity.push((record, widget.values.len() - values));
+                }
+                _ => records.push(record),
+            }
+        }
+        self.store_records(records, initial_next_id)?;
+        if !activity.is_empty() {
+            self.persistence.store_widget_activity(&activity)?;
+        }
+
+        self.enforce_capacity()?;
+        Ok(())
+    }
+
+    pub fn store_preset(&mut self, preset: Preset) -> Result<(), WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let name = preset.name.clone();
+        let previous = self.current_preset(&name);
+        self.engine.store_preset(preset);
+        self.archive_preset(previous)?;
+        self.persist_preset(&name)
+    }
+
+    /// Learns from `widgets` and stores `preset` in a single all-or-nothing write, so a
+    /// failure cannot leave the widgets learned without the preset or the other way round.
+    /// A failed write also leaves the in-memory engine as it was.
+    pub fn store_widgets_with_preset(
+        &mut self,
+        widgets: Vec<Widget>,
+        preset: Preset,
+    ) -> Result<(), WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let initial_next_id = self.engine.next_id;
+        let name = preset.name.clone();
+
+        // Also kept to roll the engine back to if the write fails
+        let initial_preset = self.current_preset(&name);
+
+        let mut batch = WriteBatch::default();
+        if let Some(previous) = &initial_preset {
+            let mut revisions = self.persistence.load_preset_revisions(&name)?;
+            push_bounded_revision(
+                &mut revisions,
+                PresetRevision::replaced_now(previous.clone()),
+            );
+            batch.preset_revisions.push((name.clone(), revisions));
+        }
+
+        let (touched, previous) = self.engine.store_widgets_keeping_previous(widgets);
+        self.engine.store_preset(preset);
+
+        batch.widgets = self
+            .engine
+            .records
+            .iter()
+            .filter(|r| touched.contains(&r.id))
+            .collect();
+        batch.presets = self
+            .engine
+            .presets
+            .iter()
+            .filter(|p| p.name == name)
+            .collect();
+        if self.engine.next_id != initial_next_id {
+            batch
+                .metadata
+                .push(("next_id".to_string(), self.engine.next_id.to_metadata()));
+        }
+
+        if let Err(e) = self.persistence.apply_batch(&batch) {
+            // The engine learned ahead of the write; undo exactly what it learned
+            let engine = &mut self.engine;
+            engine.records.retain(|r| r.id < initial_next_id);
+            for record in previous {
+                if let Some(slot) = engine.records.iter_mut().find(|r| r.id == record.id) {
+                    *slot = record;
+                }
+            }
+            engine.next_id = initial_next_id;
+            match initial_preset {
+                Some(preset) => {
+                    if let Some(slot) = engine.presets.iter_mut().find(|p| p.name == name) {
+                        *slot = preset;
+                    }
+                }
+                None => engine.presets.retain(|p| p.name != name),
+            }
+            return Err(e);
+        }
+        self.enforce_capacity()?;
+        Ok(())
+    }
+
+    /// Stores a preset without the values lying outside their widget's cached range
+    ///
+    /// Returns the validation report; a preset whose every value was rejected is not stored.
+    pub fn store_preset_validated(
+        &mut self,
+        mut preset: Preset,
+        extractor: &KymaWidgetExtractor,
+    ) -> Result<PresetValidationReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let report = extractor.validate_preset(&preset);
+        if !report.is_valid() {
+            log::warn!(
+                "Preset '{}' has {} out-of-range values, leaving them out",
+                preset.name,
+                report.out_of_range.len()
+            );
+            preset.widget_values.retain(|widget_value| {
+                widget_value
+                    .widget_id
+                    .parse::<i64>()
+                    .map_or(true, |event_id| !report.is_out_of_range(event_id))
+            });
+            if preset.widget_values.is_empty() {
+                return Ok(report);
+            }
+        }
+
+        self.store_preset(preset)?;
+        Ok(report)
+    }
+
+    pub fn presets_with_tags(&self, tags: &[&str]) -> Vec<&Preset> {
+        self.engine.presets_with_tags(tags)
+    }
+
+    /// Adds a tag to a preset and persists it, returning `false` if the preset does not exist
+    pub fn tag_preset(&mut self, name: &str, tag: &str) -> Result<bool, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        if !self.engine.tag_preset(name, tag) {
+            return Ok(false);
+        }
+        self.persist_preset(name)?;
+        Ok(true)
+    }
+
+    /// Removes a tag from a preset and persists it, returning `false` if the preset does not exist
+    pub fn untag_preset(&mut self, name: &str, tag: &str) -> Result<bool, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        if !self.engine.untag_preset(name, tag) {
+            return Ok(false);
+        }
+        self.persist_preset(name)?;
+        Ok(true)
+    }
+
+    /// Stars or unstars a preset and persists it, returning `false` if the preset does not exist
+    pub fn set_preset_favorite(
+        &mut self,
+        name: &str,
+        favorite: bool,
+    ) -> Result<bool, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        if !self.engine.set_preset_favorite(name, favorite) {
+            return Ok(false);
+        }
+        self.persist_preset(name)?;
+        Ok(true)
+    }
+
+    /// Flips a preset's star and persists it, returning the new state
+    pub fn toggle_preset_favorite(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<bool>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let favorite = self.engine.toggle_preset_favorite(name);
+        if favorite.is_some() {
+            self.persist_preset(name)?;
+        }
+        Ok(favorite)
+    }
+
+    pub fn favorite_presets(&self) -> Vec<&Preset> {
+        self.engine.favorite_presets()
+    }
+
+    /// Deletes a preset and its history, returning it if it existed
+    pub fn delete_preset(&mut self, name: &str) -> Result<Option<Preset>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let removed = self.engine.delete_preset(name);
+        if removed.is_some() {
+            self.persistence.delete_preset(name)?;
+        }
+        Ok(removed)
+    }
+
+    /// Renames a preset, keeping its usage statistics and history
+    ///
+    /// Returns `false` if `old_name` does not exist or `new_name` is already taken.
+    pub fn rename_preset(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<bool, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        if !self.engine.rename_preset(old_name, new_name) {
+            return Ok(false);
+        }
+
+        self.persist_preset(new_name)?;
+        self.persistence
+            .rename_preset_revisions(old_name, new_name)?;
+        self.persistence.delete_preset(old_name)?;
+        Ok(true)
+    }
+
+    /// Previous versions of a preset, oldest first
+    pub fn preset_history(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+        self.persistence.load_preset_revisions(name)
+    }
+
+    /// Restores the widget values and description of a previous version of a preset
+    ///
+    /// The version being replaced is itself kept in the history, so a restore can be undone.
+    /// Returns `None` if there is no revision at `index`.
+    pub fn restore_preset_revision(
+        &mut self,
+        name: &str,
+        index: usize,
+    ) -> Result<Option<Preset>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let Some(revision) = self
+            .persistence
+            .load_preset_revisions(name)?
+            .into_iter()
+            .nth(index)
+        else {
+            return Ok(None);
+        };
+
+        self.archive_preset(self.current_preset(name))?;
+        match self.engine.presets.iter_mut().find(|p| p.name == name) {
+            Some(existing) => {
+                existing.widget_values = revision.preset.widget_values;
+                existing.description = revision.preset.description;
+            }
+            None => self.engine.presets.push(revision.preset),
+        }
+        self.persist_preset(name)?;
+
+        Ok(self.current_preset(name))
+    }
+
+    fn current_preset(&self, name: &str) -> Option<Preset> {
+        self.engine.presets.iter().find(|p| p.name == name).cloned()
+    }
+
+    /// Keeps the version of a preset that is being overwritten in its history
+    fn archive_preset(&self, previous: Option<Preset>) -> Result<(), WidgetIntelligenceError> {
+        if let Some(preset) = previous {
+            self.persistence
+                .push_preset_revision(PresetRevision::replaced_now(preset))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the engine's current version of a preset to the database
+    fn persist_preset(&self, name: &str) -> Result<(), WidgetIntelligenceError> {
+        if let Some(stored) = self.engine.presets.iter().find(|p| p.name == name) {
+            self.persistence.store_preset(stored)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_record(&self, id: u64) -> Option<&WidgetRecord> {
+        self.loaded().get_record(id)
+    }
+
+    /// Updates a record, e.g. after a control was renamed, and persists it
+    pub fn update_record(
+        &mut self,
+        id: u64,
+        update: RecordUpdate,
+    ) -> Result<Option<&WidgetRecord>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let Some(record) = self.engine.update_record(id, update) else {
+            return Ok(None);
+        };
+
+        self.persistence.store_widget(record)?;
+        Ok(Some(record))
+    }
+
+    /// Removes a record from the engine and the database
+    pub fn delete_record(
+        &mut self,
+        id: u64,
+    ) -> Result<Option<WidgetRecord>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let removed = self.engine.remove_record(id);
+        if removed.is_some() {
+            self.persistence.delete_widget(id)?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes every record whose label matches a case-insensitive `*` glob
+    pub fn delete_records_by_label(
+        &mut self,
+        pattern: &str,
+    ) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let removed = self.engine.remove_records_by_label(pattern);
+        for record in &removed {
+            self.persistence.delete_widget(record.id)?;
+        }
+        Ok(removed)
+    }
+
+    /// Forgets stale, rarely used records and deletes them from the database
+    pub fn prune_stale(
+        &mut self,
+        older_than: std::time::Duration,
+        min_frequency: u32,
+    ) -> Result<Vec<u64>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let pruned = self.engine.prune_stale(older_than, min_frequency);
+        for id in &pruned {
+            self.persistence.delete_widget(*id)?;
+        }
+
+        if !pruned.is_empty() {
+            log::info!("Pruned {} stale widget records", pruned.len());
+        }
+
+        Ok(pruned)
+    }
+
+    /// Records feedback on a suggested value and persists the record, log and calibration
+    pub fn record_feedback(
+        &mut self,
+        record_id: u64,
+        suggested_value: f64,
+        accepted: bool,
+    ) -> Result<Option<FeedbackEntry>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let Some(entry) = self
+            .engine
+            .record_feedback(record_id, suggested_value, accepted)
+        else {
+            return Ok(None);
+        };
+
+        if let Some(record) = self.engine.records.iter().find(|r| r.id == record_id) {
+            self.persistence.store_widget(record)?;
+        }
+        self.persistence.store_feedback(&entry)?;
+
+        self.persistence
+            .metadata()
+            .set_json("calibration", &self.engine.calibration)?;
+
+        Ok(Some(entry))
+    }
+
+    pub fn feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError> {
+        self.persistence.load_feedback_log()
+    }
+
+    /// Generates a preset from the best suggestions for `event_ids` and persists it
+    pub fn generate_preset(
+        &mut self,
+        name: &str,
+        event_ids: &[u64],
+    ) -> Result<Option<Preset>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let previous = self.current_preset(name);
+        let Some(preset) = self.engine.generate_preset(name, event_ids) else {
+            return Ok(None);
+        };
+
+        self.archive_preset(previous)?;
+        self.persist_preset(name)?;
+        Ok(Some(preset))
+    }
+
+    pub fn get_suggestions(
+        &self,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+    ) -> Vec<Suggestion> {
+        match self.candidate_engine(partial_widget, max_suggestions, false) {
+            Some(engine) => engine.get_suggestions(partial_widget, max_suggestions),
+            None => self.engine.get_suggestions(partial_widget, max_suggestions),
+        }
+    }
+
+    pub fn get_suggestions_with_context(
+        &self,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+        context: &str,
+    ) -> Vec<Suggestion> {
+        match self.candidate_engine(partial_widget, max_suggestions, true) {
+            Some(engine) => {
+                engine.get_suggestions_with_context(partial_widget, max_suggestions, context)
+            }
+            None => {
+                self.engine
+                    .get_suggestions_with_context(partial_widget, max_suggestions, context)
+            }
+        }
+    }
+
+    pub fn get_suggestions_by_event_id(
+        &self,
+        event_id: u64,
+        max_suggestions: usize,
+    ) -> Vec<Suggestion> {
+        let query = Widget {
+            event_id: Some(event_id),
+            ..Default::default()
+        };
+        match self.candidate_engine(&query, max_suggestions, false) {
+            Some(engine) => engine.get_suggestions_by_event_id(event_id, max_suggestions),
+            None => self
+                .engine
+                .get_suggestions_by_event_id(event_id, max_suggestions),
+        }
+    }
+
+    /// Validates a Kyma widget description, caches it in `extractor` and keeps it in the
+    /// database for `restore_widget_descriptions`. A description its event name was
+    /// stored under before, e.g. until the sound was recompiled, is deleted.
+    pub fn cache_widget_description(
+        &self,
+        extractor: &KymaWidgetExtractor,
+        description: HashMap<String, serde_json::Value>,
+    ) -> Result<(), WidgetIntelligenceError> {
+        self.reject_read_only()?;
+        if let Some(reason) = extractor.classify_description(&description) {
+            log::debug!("Not caching widget description: {reason}");
+            return Ok(());
+        }
+        let event_id = extractor.validated_event_id(&description)?;
         self.persistence
-            .store_metadata("next_id", &self.engine.next_id.to_string())?;
+            .store_widget_description(event_id, &description)?;
+        for stale in extractor.replaced_event_ids(&description) {
+            self.persistence.delete_widget_description(stale)?;
+        }
+        extractor.cache_widget_description(description);
+        Ok(())
+    }
+
+    /// Carries learned records and preset values over to the event IDs of a recompiled
+    /// sound, see `WidgetSuggestionEngine::remap_event_ids`
+    ///
+    /// `previous_event_ids` are the sound's event IDs before it was recompiled, e.g. the
+    /// `removed` entries of a `CacheDiff`. The layout is every widget cached in `source`.
+    /// Rewritten records and presets are persisted together with the remaps, which are
+    /// appended to the log returned by `event_id_remaps`.
+    pub fn remap_event_ids<S: WidgetSource + ?Sized>(
+        &mut self,
+        source: &S,
+        previous_event_ids: &[i64],
+    ) -> Result<Vec<EventIdRemap>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let previous_event_ids: Vec<u64> = previous_event_ids
+            .iter()
+            .filter_map(|&event_id| u64::try_from(event_id).ok())
+            .collect();
+        let layout: Vec<Widget> = source
+            .cached_event_ids()
+            .into_iter()
+            .filter_map(|event_id| source.widget_metadata(event_id))
+            .map(|metadata| metadata.to_widget(metadata.default_value.unwrap_or_default()))
+            .collect();
+
+        let remaps = self
+            .engine
+            .remap_event_ids(&previous_event_ids, &layout, REMAP_MIN_SCORE);
+        if remaps.is_empty() {
+            return Ok(remaps);
+        }
+
+        let mut log = self.event_id_remaps()?;
+        log.extend(remaps.iter().cloned());
+        let log = serde_json::to_string(&log)
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))?;
+
+        let record_ids: HashSet<u64> = remaps
+            .iter()
+            .flat_map(|remap| remap.record_ids.iter().copied())
+            .collect();
+        let preset_names: HashSet<&str> = remaps
+            .iter()
+            .flat_map(|remap| remap.presets.iter().map(String::as_str))
+            .collect();
+        let batch = WriteBatch {
+            widgets: self
+                .engine
+                .records
+                .iter()
+                .filter(|r| record_ids.contains(&r.id))
+                .collect(),
+            presets: self
+                .engine
+                .presets
+                .iter()
+                .filter(|p| preset_names.contains(p.name.as_str()))
+                .collect(),
+            metadata: vec![(EVENT_ID_REMAPS_KEY.to_string(), log)],
+            ..WriteBatch::default()
+        };
+        self.persistence.apply_batch(&batch)?;
+
+        Ok(remaps)
+    }
+
+    /// Every event ID remap made so far, oldest first
+    pub fn event_id_remaps(&self) -> Result<Vec<EventIdRemap>, WidgetIntelligenceError> {
+        Ok(self
+            .persistence
+            .metadata()
+            .get_json(EVENT_ID_REMAPS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Caches the stored widget descriptions in `source`, e.g. after a restart, and
+    /// returns how many there were
+    pub fn restore_widget_descriptions<S: WidgetSource + ?Sized>(
+        &self,
+        source: &S,
+    ) -> Result<usize, WidgetIntelligenceError> {
+        let descriptions = self.persistence.load_widget_descriptions()?;
+        let count = descriptions.len();
+        for description in descriptions {
+            source.cache_description(description);
+        }
+        Ok(count)
+    }
+
+    /// Recalls a preset as concrete values by event ID, ready to send back to Kyma
+    ///
+    /// Normalized values are denormalized through the cached `WidgetMetadata` range when
+    /// `extractor` knows the event ID, otherwise through the range of the learned record.
+    /// Values without a known range are returned as stored. Returns `None` for unknown presets.
+    pub fn recall_preset(
+        &self,
+        name: &str,
+        extractor: Option<&KymaWidgetExtractor>,
+    ) -> Option<HashMap<i64, f64>> {
+        let preset = self.engine.presets.iter().find(|p| p.name == name)?;
+
+        let values = preset
+            .widget_values
+            .iter()
+            .filter_map(|widget_value| {
+                let event_id = widget_value.widget_id.parse::<i64>().ok()?;
+                let value = widget_value.value;
+
+                let concrete = extractor
+                    .and_then(|e| e.extract_widget_metadata(event_id))
+                    .and_then(|metadata| metadata.denormalize_value(value))
+                    .or_else(|| {
+                        let record = self
+                            .loaded()
+                            .records
+                            .iter()
+                            .find(|r| r.widget.event_id == u64::try_from(event_id).ok())?;
+                        match (record.widget.minimum, record.widget.maximum) {
+                            (Some(min), Some(max)) if max > min => Some(min + value * (max - min)),
+                            _ => None,
+                        }
+                    })
+                    .unwrap_or(value);
+
+                Some((event_id, concrete))
+            })
+            .collect();
+
+        Some(values)
+    }
+
+    /// Recalls a preset's values like `recall_preset` and counts the use, updating its
+    /// `usage_count` and `last_used`; `None` if the preset does not exist
+    pub fn apply_preset(
+        &mut self,
+        name: &str,
+        extractor: Option<&KymaWidgetExtractor>,
+    ) -> Result<Option<HashMap<i64, f64>>, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let Some(values) = self.recall_preset(name, extractor) else {
+            return Ok(None);
+        };
+
+        self.engine.mark_preset_used(name);
+        self.persist_preset(name)?;
+        Ok(Some(values))
+    }
+
+    /// Imports presets from a file holding the JSON `import_presets_from_json` takes
+    pub fn import_presets_from_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+        extractor: Option<&KymaWidgetExtractor>,
+        policy: PresetConflictPolicy,
+    ) -> Result<PresetImportReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let json = std::fs::read_to_string(path)?;
+        self.import_presets_from_json(&json, extractor, policy)
+    }
+
+    /// Imports presets from a JSON array of `PresetData`-like objects
+    ///
+    /// Widget IDs must be event IDs, known to `extractor` when one is given, and values
+    /// must be normalized (0.0 to 1.0); other entries are reported and left out. Presets
+    /// without `last_used` count as used at the time of the import.
+    pub fn import_presets_from_json(
+        &mut self,
+        json: &str,
+        extractor: Option<&KymaWidgetExtractor>,
+        policy: PresetConflictPolicy,
+    ) -> Result<PresetImportReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let imported: Vec<ImportedPreset> = serde_json::from_str(json)
+            .map_err(|e| WidgetIntelligenceError::DeserializationError(e.to_string()))?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut report = PresetImportReport::default();
+        for preset in imported {
+            let mut widget_values = Vec::new();
+            for (widget_id, value) in preset.widget_values {
+                // Valid entries resolve to the widget label, if the extractor knows it
+                let checked = match widget_id.parse::<i64>() {
+                    Err(_) => Err("widget ID is not an event ID".to_string()),
+                    Ok(_) if !(0.0..=1.0).contains(&value) => {
+                        Err(format!("value {value} is not normalized"))
+                    }
+                    Ok(event_id) => match extractor {
+                        Some(e) if e.get_cached_description(event_id).is_none() => {
+                            Err("event ID has no cached widget description".to_string())
+                        }
+                        Some(e) => Ok(e
+                            .extract_widget_metadata(event_id)
+                            .and_then(|metadata| metadata.label)),
+                        None => Ok(None),
+                    },
+                };
+
+                match checked {
+                    Ok(label) => widget_values.push(WidgetValue {
+                        widget_id,
+                        label,
+                        value,
+                        confidence: 1.0,
+                    }),
+                    Err(reason) => report.rejected_values.push(RejectedValue {
+                        preset: preset.name.clone(),
+                        widget_id,
+                        reason,
+                    }),
+                }
+            }
+
+            if widget_values.is_empty() {
+                report.skipped.push(preset.name);
+                continue;
+            }
+            widget_values.sort_by(|a, b| a.widget_id.cmp(&b.widget_id));
+
+            let incoming = Preset {
+                name: preset.name.clone(),
+                description: preset.description,
+                widget_values,
+                created_by: preset.created_by,
+                usage_count: 1,
+                last_used: preset.last_used.unwrap_or(now),
+                tags: preset.tags,
+                favorite: preset.favorite,
+            };
+            match self.merge_preset(incoming, policy)? {
+                Some(name) => report.imported.push(name),
+                None => report.skipped.push(preset.name),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Adds a preset, resolving a name clash with an existing one according to `policy`
+    ///
+    /// A replaced preset is kept in the revision history. Returns the name the preset was
+    /// stored under, or `None` if the existing preset was kept unchanged.
+    fn merge_preset(
+        &mut self,
+        mut incoming: Preset,
+        policy: PresetConflictPolicy,
+    ) -> Result<Option<String>, WidgetIntelligenceError> {
+        let Some(index) = self
+            .engine
+            .presets
+            .iter()
+            .position(|p| p.name == incoming.name)
+        else {
+            let name = incoming.name.clone();
+            self.engine.presets.push(incoming);
+            self.persist_preset(&name)?;
+            return Ok(Some(name));
+        };
+
+        let existing = &self.engine.presets[index];
+        let incoming_is_newer = incoming.last_used > existing.last_used;
+        let usage_count = existing.usage_count.saturating_add(incoming.usage_count);
+
+        match policy {
+            PresetConflictPolicy::Skip => return Ok(None),
+            PresetConflictPolicy::Overwrite => {}
+            PresetConflictPolicy::KeepNewest => {
+                if !incoming_is_newer {
+                    return Ok(None);
+                }
+            }
+            PresetConflictPolicy::SumUsageCounts => {
+                if !incoming_is_newer {
+                    self.engine.presets[index].usage_count = usage_count;
+                    self.persist_preset(&incoming.name)?;
+                    return Ok(Some(incoming.name));
+                }
+                incoming.usage_count = usage_count;
+            }
+            PresetConflictPolicy::KeepBothWithSuffix => {
+                let exists = |name: &str| self.engine.presets.iter().any(|p| p.name == name);
+                let mut n = 2;
+                while exists(&format!("{} ({n})", incoming.name)) {
+                    n += 1;
+                }
+                incoming.name = format!("{} ({n})", incoming.name);
+
+                let name = incoming.name.clone();
+                self.engine.presets.push(incoming);
+                self.persist_preset(&name)?;
+                return Ok(Some(name));
+            }
+        }
+
+        let name = incoming.name.clone();
+        let previous = std::mem::replace(&mut self.engine.presets[index], incoming);
+        self.archive_preset(Some(previous))?;
+        self.persist_preset(&name)?;
+        Ok(Some(name))
+    }
+
+    /// Exports a preset as a JSON object mapping each concreteEventID to its raw value,
+    /// ready to push back to Kyma
+    pub fn export_preset_kyma(
+        &self,
+        name: &str,
+        extractor: Option<&KymaWidgetExtractor>,
+    ) -> Option<serde_json::Value> {
+        let object = self
+            .recall_preset(name, extractor)?
+            .into_iter()
+            .map(|(event_id, value)| (event_id.to_string(), serde_json::json!(value)))
+            .collect();
+        Some(serde_json::Value::Object(object))
+    }
+
+    pub fn get_preset_insights(&self, widget: &Widget) -> Vec<PresetInsight> {
+        self.loaded().get_preset_insights(widget)
+    }
+
+    pub fn recommend_presets(
+        &self,
+        current_state: &HashMap<u64, f64>,
+        k: usize,
+    ) -> Vec<PresetRecommendation> {
+        self.loaded().recommend_presets(current_state, k)
+    }
+
+    /// See `WidgetSuggestionEngine::export_kyma_json`
+    pub fn export_kyma_json(&self) -> serde_json::Value {
+        self.loaded().export_kyma_json()
+    }
+
+    pub fn related_widgets(&self, event_id: u64) -> Vec<RelatedWidget> {
+        self.loaded().related_widgets(event_id)
+    }
+
+    /// `related_widgets`, favouring the widgets placed next to `event_id` on the VCS as
+    /// cached in `extractor`
+    pub fn related_widgets_near(
+        &self,
+        event_id: u64,
+        extractor: &KymaWidgetExtractor,
+    ) -> Vec<RelatedWidget> {
+        let nearby: Vec<u64> = i64::try_from(event_id)
+            .map(|event_id| extractor.widgets_near(event_id))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|widget| u64::try_from(widget.event_id).ok())
+            .collect();
+        self.loaded().related_widgets_near(event_id, &nearby)
+    }
+
+    pub fn export_widgets_csv<W: std::io::Write>(&self, writer: W) -> std::io::Result<()> {
+        self.loaded().export_widgets_csv(writer)
+    }
+
+    pub fn get_preset_clusters(&self) -> Vec<PresetCluster> {
+        self.engine.get_preset_clusters()
+    }
+
+    pub fn get_stats(&self) -> HashMap<String, usize> {
+        let mut stats = self.engine.get_stats();
+        if let Some(index) = &self.index {
+            stats.insert("total_widgets".to_string(), index.len());
+        }
+        stats
+    }
+
+    pub fn list_widgets(
+        &self,
+        offset: usize,
+        limit: usize,
+        sort: WidgetSort,
+    ) -> Page<&WidgetRecord> {
+        self.loaded().list_widgets(offset, limit, sort)
+    }
+
+    pub fn list_presets(&self, offset: usize, limit: usize) -> Page<&Preset> {
+        self.engine.list_presets(offset, limit)
+    }
+
+    pub fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        self.persistence.flush()
+    }
+
+    pub fn compact(&self) -> Result<(), WidgetIntelligenceError> {
+        self.persistence.compact()
+    }
+
+    /// Deletes everything learned and stored, including the configuration and widget
+    /// descriptions, and flushes; the engine is left as a new database would open it
+    pub fn clear_all_data(&mut self) -> Result<(), WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        self.persistence.clear_all()?;
+
+        self.engine = WidgetSuggestionEngine::new();
+        self.capacity = CapacityLimits::default();
+        self.retention = RetentionPolicy::default();
+        self.last_eviction = None;
+        self.flush()
+    }
+
+    pub fn size_on_disk(&self) -> Result<u64, WidgetIntelligenceError> {
+        self.persistence.size_on_disk()
+    }
+
+    pub fn export_data(&self) -> Result<ExportData, WidgetIntelligenceError> {
+        let engine = self.loaded();
+        Ok(ExportData {
+            widgets: engine.records.clone(),
+            presets: engine.presets.clone(),
+            display_types: engine.display_types.clone(),
+            next_id: engine.next_id,
+        })
+    }
+
+    /// The records seen and presets used at or after `since`, in seconds since the Unix
+    /// epoch, e.g. the time of the previous sync. Changes made in that same second show
+    /// up in both exports. Deletions are not part of an export.
+    pub fn export_changes_since(&self, since: u64) -> Result<ExportData, WidgetIntelligenceError> {
+        Ok(ExportData {
+            widgets: self
+                .loaded()
+                .records
+                .iter()
+                .filter(|r| r.last_seen >= since)
+                .cloned()
+                .collect(),
+            presets: self
+                .engine
+                .presets
+                .iter()
+                .filter(|p| p.last_used >= since)
+                .cloned()
+                .collect(),
+            display_types: self.engine.display_types.clone(),
+            next_id: self.engine.next_id,
+        })
+    }
+
+    /// Everything learned so far with names removed, for sharing a corpus for debugging
+    /// or benchmarking. Labels, preset names and tags have each word replaced by a hash,
+    /// so equal words still match across records; `created_by` and `description` are
+    /// dropped. Numeric features, value histories and event ids are kept as they are.
+    ///
+    /// The hashes are unsalted: anyone who can guess a word can confirm it.
+    pub fn export_anonymized(&self) -> Result<ExportData, WidgetIntelligenceError> {
+        let mut data = self.export_data()?;
+
+        for record in &mut data.widgets {
+            record.widget.label = record.widget.label.as_deref().map(anonymize_words);
+            record.features.label_tokens = record
+                .features
+                .label_tokens
+                .iter()
+                .map(|token| anonymize_word(token))
+                .collect();
+        }
+
+        for preset in &mut data.presets {
+            preset.name = anonymize_words(&preset.name);
+            preset.description = None;
+            preset.created_by = None;
+            preset.tags = preset.tags.iter().map(|tag| anonymize_words(tag)).collect();
+            for value in &mut preset.widget_values {
+                // Ids are event ids, except in presets keyed by label
+                if value.widget_id.parse::<u64>().is_err() {
+                    value.widget_id = anonymize_words(&value.widget_id);
+                }
+                value.label = value.label.as_deref().map(anonymize_words);
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Writes everything learned so far to `path`
+    pub fn export_to_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        format: ExportFormat,
+    ) -> Result<(), WidgetIntelligenceError> {
+        write_export(path.as_ref(), &self.export_data()?, format)
+    }
+
+    /// Writes the changes made since `since` to `path`, see `export_changes_since`
+    pub fn export_changes_to_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        since: u64,
+        format: ExportFormat,
+    ) -> Result<(), WidgetIntelligenceError> {
+        write_export(path.as_ref(), &self.export_changes_since(since)?, format)
+    }
+
+    /// Writes the anonymized export to `path`, see `export_anonymized`
+    pub fn export_anonymized_to_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        format: ExportFormat,
+    ) -> Result<(), WidgetIntelligenceError> {
+        write_export(path.as_ref(), &self.export_anonymized()?, format)
+    }
+
+    /// Writes a snapshot of everything learned so far to a tar archive at `path`
+    pub fn backup_to<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<BackupManifest, WidgetIntelligenceError> {
+        let data = self.export_data()?;
+        let bytes = bincode::encode_to_vec(&data, bincode::config::standard())?;
+
+        let manifest = BackupManifest {
+            format_version: BACKUP_FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            widgets: data.widgets.len(),
+            presets: data.presets.len(),
+            checksum: sha256_hex(&bytes),
+        };
+        let manifest_bytes = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))?;
+
+        let mut archive = tar::Builder::new(std::fs::File::create(path)?);
+        append_backup_entry(&mut archive, BACKUP_MANIFEST_ENTRY, &manifest_bytes)?;
+        append_backup_entry(&mut archive, BACKUP_DATA_ENTRY, &bytes)?;
+        archive.into_inner()?.sync_all()?;
+
+        Ok(manifest)
+    }
+
+    /// Replaces the learned state with a snapshot written by `backup_to`. Nothing is
+    /// touched unless the archive passes `verify_backup`.
+    pub fn restore_from<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<BackupManifest, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let (manifest, data) = read_backup(path.as_ref())?;
+        self.replace_all(data)?;
+
+        log::info!(
+            "Restored {} widget records and {} presets from backup",
+            manifest.widgets,
+            manifest.presets
+        );
+        Ok(manifest)
+    }
+
+    /// Replaces every record, preset and the id counter with `data`, in memory and in
+    /// the backend
+    fn replace_all(&mut self, data: ExportData) -> Result<(), WidgetIntelligenceError> {
+        let restored_ids: HashSet<u64> = data.widgets.iter().map(|r| r.id).collect();
+        for record in &self.engine.records {
+            if !restored_ids.contains(&record.id) {
+                self.persistence.delete_widget(record.id)?;
+            }
+        }
+        let restored_names: HashSet<&str> = data.presets.iter().map(|p| p.name.as_str()).collect();
+        for preset in &self.engine.presets {
+            if !restored_names.contains(preset.name.as_str()) {
+                self.persistence.delete_preset(&preset.name)?;
+            }
+        }
+
+        let batch = WriteBatch {
+            widgets: data.widgets.iter().collect(),
+            metadata: vec![("next_id".to_string(), data.next_id.to_metadata())],
+            ..WriteBatch::default()
+        };
+        self.persistence.apply_batch(&batch)?;
+        for preset in &data.presets {
+            self.persistence.store_preset(preset)?;
+        }
+
+        self.engine.records = data.widgets;
+        self.engine.presets = data.presets;
+        self.engine.display_types = data.display_types;
+        self.engine.next_id = data.next_id;
+        self.flush()
+    }
+
+    /// Imports a file written by `export_to_file` in either format
+    pub fn import_from_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+        strategy: ImportStrategy,
+        policy: PresetConflictPolicy,
+    ) -> Result<ImportReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let bytes = std::fs::read(path)?;
+
+        let config = bincode::config::standard();
+        let data = match serde_json::from_slice::<ExportData>(&bytes) {
+            Ok(data) => data,
+            Err(json_error) => match bincode::decode_from_slice::<ExportData, _>(&bytes, config) {
+                Ok((data, read)) if read == bytes.len() => data,
+                _ => {
+                    return Err(WidgetIntelligenceError::DeserializationError(format!(
+                        "Not an exported JSON or bincode file: {json_error}"
+                    )))
+                }
+            },
+        };
+
+        self.import_data(data, strategy, policy)
+    }
+
+    /// Combines `data` with what was learned locally according to `strategy`, applying
+    /// the result to memory and the backend alike
+    ///
+    /// When merging, records are matched like in `merge_from` and presets by name, with
+    /// name clashes resolved by `policy`; `ReplaceAll` takes the imported presets as they
+    /// are. Imported records without a counterpart get new ids, so they cannot collide
+    /// with local ones.
+    pub fn import_data(
+        &mut self,
+        data: ExportData,
+        strategy: ImportStrategy,
+        policy: PresetConflictPolicy,
+    ) -> Result<ImportReport, WidgetIntelligenceError> {
+        self.ensure_writable()?;
+        let mut report = ImportReport::default();
+
+        if strategy == ImportStrategy::ReplaceAll {
+            report.added_widgets = data.widgets.len();
+            report.presets.imported = data.presets.iter().map(|p| p.name.clone()).collect();
+            self.replace_all(data)?;
+            return Ok(report);
+        }
+
+        let initial_next_id = self.engine.next_id;
+        let mut touched = Vec::new();
+        for record in data.widgets {
+            match self.engine.find_matching_record(&record) {
+                Some(index) if strategy == ImportStrategy::MergePreferImported => {
+                    let id = self.engine.records[index].id;
+                    self.engine.records[index] = WidgetRecord { id, ..record };
+                    touched.push(id);
+                    report.replaced_widgets += 1;
+                }
+                Some(_) => report.skipped_widgets += 1,
+                None => {
+                    let id = self.engine.next_id;
+                    self.engine.next_id += 1;
+                    self.engine.records.push(WidgetRecord { id, ..record });
+                    touched.push(id);
+                    report.added_widgets += 1;
+                }
+            }
+        }
+
+        for (display_type, id) in data.display_types {
+            match strategy {
+                ImportStrategy::MergePreferImported => {
+                    self.engine.display_types.insert(display_type, id);
+                }
+                _ => {
+                    self.engine.display_types.entry(display_type).or_insert(id);
+                }
+            }
+        }
+
+        let records: Vec<&WidgetRecord> = self
+            .engine
+            .records
+            .iter()
+            .filter(|r| touched.contains(&r.id))
+            .collect();
+        self.store_records(records, initial_next_id)?;
+
+        for preset in data.presets {
+            let original_name = preset.name.clone();
+            match self.merge_preset(preset, policy)? {
+                Some(name) => report.presets.imported.push(name),
+                None => report.presets.skipped.push(original_name),
+            }
+        }
+
+        self.enforce_capacity()?;
         self.flush()?;
 
-        Ok(())
+        log::info!(
+            "Imported {} new and {} replacing widget records, skipped {}",
+            report.added_widgets,
+            report.replaced_widgets,
+            report.skipped_widgets
+        );
+        Ok(report)
     }
 }
 
@@ -296,3 +4018,589 @@ pub struct ExportData {
     pub display_types: HashMap<String, u64>,
     pub next_id: u64,
 }
+
+/// Describes the snapshot stored next to it in a backup archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub crate_version: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub widgets: usize,
+    pub presets: usize,
+    /// Hex-encoded SHA-256 of the snapshot
+    pub checksum: String,
+}
+
+/// Compares configurations by their serialized form, as `EngineConfig` has no `PartialEq`
+fn same_config(a: &EngineConfig, b: &EngineConfig) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+fn write_export(
+    path: &std::path::Path,
+    data: &ExportData,
+    format: ExportFormat,
+) -> Result<(), WidgetIntelligenceError> {
+    let bytes = match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(data)
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))?,
+        ExportFormat::Bincode => bincode::encode_to_vec(data, bincode::config::standard())?,
+    };
+
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Checks a backup archive without restoring it
+pub fn verify_backup<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<BackupManifest, WidgetIntelligenceError> {
+    read_backup(path.as_ref()).map(|(manifest, _)| manifest)
+}
+
+fn read_backup(
+    path: &std::path::Path,
+) -> Result<(BackupManifest, ExportData), WidgetIntelligenceError> {
+    let mut manifest_bytes = None;
+    let mut data_bytes = None;
+
+    let mut archive = tar::Archive::new(std::fs::File::open(path)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut bytes)?;
+        match name.as_str() {
+            BACKUP_MANIFEST_ENTRY => manifest_bytes = Some(bytes),
+            BACKUP_DATA_ENTRY => data_bytes = Some(bytes),
+            _ => log::warn!("Ignoring unexpected backup entry {name}"),
+        }
+    }
+
+    let integrity = |message: &str| WidgetIntelligenceError::IntegrityError(message.to_string());
+    let manifest_bytes = manifest_bytes.ok_or_else(|| integrity("backup has no manifest"))?;
+    let bytes = data_bytes.ok_or_else(|| integrity("backup has no data"))?;
+
+    let manifest: BackupManifest = serde_json::from_slice(&manifest_bytes).map_err(|e| {
+        WidgetIntelligenceError::IntegrityError(format!("unreadable manifest: {e}"))
+    })?;
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(WidgetIntelligenceError::IntegrityError(format!(
+            "backup format {} is newer than the supported {BACKUP_FORMAT_VERSION}",
+            manifest.format_version
+        )));
+    }
+    if sha256_hex(&bytes) != manifest.checksum {
+        return Err(integrity("checksum mismatch"));
+    }
+
+    let (data, read) =
+        bincode::decode_from_slice::<ExportData, _>(&bytes, bincode::config::standard())?;
+    if read != bytes.len()
+        || data.widgets.len() != manifest.widgets
+        || data.presets.len() != manifest.presets
+    {
+        return Err(integrity("snapshot does not match its manifest"));
+    }
+
+    Ok((manifest, data))
+}
+
+fn append_backup_entry<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    bytes: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    archive.append_data(&mut header, name, bytes)
+}
+
+/// Replaces each whitespace-separated word of `text`, keeping the word structure the
+/// label tokenizer sees
+fn anonymize_words(text: &str) -> String {
+    text.split_whitespace()
+        .map(anonymize_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A stable stand-in for `word`; case is ignored, as labels are tokenized lowercase
+fn anonymize_word(word: &str) -> String {
+    let digest = sha256_hex(word.to_lowercase().as_bytes());
+    format!("w{}", &digest[..12])
+}
+
+/// Opens `config`, reporting the lock sled takes on a database as `DatabaseBusy`
+fn open_locked(config: sled::Config, db_path: &Path) -> Result<Db, WidgetIntelligenceError> {
+    let is_locked = |e: &sled::Error| {
+        matches!(e, sled::Error::Io(io)
+            if io.kind() == std::io::ErrorKind::WouldBlock
+                || io.to_string().contains("could not acquire lock"))
+    };
+
+    let deadline = std::time::Instant::now() + LOCK_WAIT;
+    loop {
+        match config.open() {
+            Ok(db) => return Ok(db),
+            Err(e) if is_locked(&e) && std::time::Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) if is_locked(&e) => {
+                return Err(WidgetIntelligenceError::DatabaseBusy {
+                    path: db_path.to_path_buf(),
+                    pid: std::fs::read_to_string(db_path.join(OWNER_PID_FILE))
+                        .ok()
+                        .and_then(|pid| pid.trim().parse().ok()),
+                })
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[derive(Encode)]
+    struct LegacyWidgetEncoding {
+        label: Option<String>,
+        minimum: Option<f64>,
+        maximum: Option<f64>,
+        is_generated: Option<bool>,
+        display_type: Option<String>,
+        current_value: Option<f64>,
+    }
+
+    #[derive(Encode)]
+    struct LegacyRecordEncoding {
+        id: u64,
+        widget: LegacyWidgetEncoding,
+        features: WidgetFeatures,
+        frequency: u32,
+        last_seen: u64,
+        value_stats: Option<ValueStats>,
+    }
+
+    #[test]
+    fn test_legacy_widget_layout_migration() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_legacy_layout");
+
+        let legacy = LegacyRecordEncoding {
+            id: 7,
+            widget: LegacyWidgetEncoding {
+                label: Some("Amp_01".to_string()),
+                minimum: Some(0.0),
+                maximum: Some(1.0),
+                is_generated: Some(false),
+                display_type: Some("slider".to_string()),
+                current_value: Some(0.8),
+            },
+            features: WidgetFeatures::default(),
+            frequency: 3,
+            last_seen: 0,
+            value_stats: None,
+        };
+
+        {
+            let persistence = SledPersistenceManager::new(&db_path)?;
+            let bytes = persistence.pack(&legacy)?;
+            persistence.widgets_tree.insert(7u64.to_be_bytes(), bytes)?;
+            persistence.flush()?;
+        }
+
+        let persistence = SledPersistenceManager::new(&db_path)?;
+        let records = persistence.load_all_widgets()?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, 7);
+        assert_eq!(records[0].frequency, 3);
+        assert_eq!(records[0].widget.label.as_deref(), Some("Amp_01"));
+        assert_eq!(records[0].widget.event_id, None);
+        assert_eq!(records[0].widget.values, vec![0.8]);
+
+        // The record has been rewritten in the current layout
+        let stored = persistence.widgets_tree.get(7u64.to_be_bytes())?.unwrap();
+        assert!(!decode_widget_record(&persistence.unpack(&stored)?)?.1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_only_open_leaves_database_untouched() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_read_only_untouched");
+
+        let legacy = LegacyRecordEncoding {
+            id: 7,
+            widget: LegacyWidgetEncoding {
+                label: Some("Amp_01".to_string()),
+                minimum: Some(0.0),
+                maximum: Some(1.0),
+                is_generated: Some(false),
+                display_type: Some("slider".to_string()),
+                current_value: Some(0.8),
+            },
+            features: WidgetFeatures::default(),
+            frequency: 3,
+            last_seen: 0,
+            value_stats: None,
+        };
+        let legacy_bytes = bincode::encode_to_vec(&legacy, bincode::config::standard())?;
+
+        // A database from before the codec was recorded
+        {
+            let db = sled::open(&db_path)?;
+            db.open_tree("widgets_v1")?
+                .insert(7u64.to_be_bytes(), legacy_bytes.clone())?;
+            db.flush()?;
+        }
+
+        let system = PersistentWidgetSuggestionEngine::open_read_only(&db_path)?;
+        assert_eq!(system.engine.records.len(), 1);
+        assert_eq!(system.engine.records[0].widget.values, vec![0.8]);
+        // Not even the snapshot is migrated
+        let snapshot = system.persistence.inner();
+        let stored = snapshot.widgets_tree.get(7u64.to_be_bytes())?.unwrap();
+        assert!(decode_widget_record(&snapshot.unpack(&stored)?)?.1);
+        drop(system);
+
+        let db = sled::open(&db_path)?;
+        let stored = db
+            .open_tree("widgets_v1")?
+            .get(7u64.to_be_bytes())?
+            .unwrap();
+        assert_eq!(stored.as_ref(), legacy_bytes.as_slice());
+        assert!(db
+            .open_tree("metadata")?
+            .get(PayloadCodec::METADATA_KEY)?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[derive(Encode)]
+    struct LegacyPresetEncoding {
+        name: String,
+        description: Option<String>,
+        widget_values: Vec<WidgetValue>,
+        created_by: Option<String>,
+        usage_count: u32,
+        last_used: u64,
+    }
+
+    #[derive(Encode)]
+    struct LegacyTaggedPresetEncoding {
+        name: String,
+        description: Option<String>,
+        widget_values: Vec<WidgetValue>,
+        created_by: Option<String>,
+        usage_count: u32,
+        last_used: u64,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_legacy_preset_layout_migration() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_legacy_preset_layout");
+
+        let legacy = LegacyPresetEncoding {
+            name: "Old Show".to_string(),
+            description: None,
+            widget_values: vec![WidgetValue {
+                widget_id: "12".to_string(),
+                label: None,
+                value: 0.4,
+                confidence: 1.0,
+            }],
+            created_by: None,
+            usage_count: 5,
+            last_used: 0,
+        };
+
+        {
+            let persistence = SledPersistenceManager::new(&db_path)?;
+            let bytes = persistence.pack(&legacy)?;
+            persistence.presets_tree.insert("Old Show", bytes)?;
+            persistence.flush()?;
+        }
+
+        let persistence = SledPersistenceManager::new(&db_path)?;
+        let presets = persistence.load_all_presets()?;
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].usage_count, 5);
+        assert!(presets[0].tags.is_empty());
+
+        let stored = persistence.presets_tree.get("Old Show")?.unwrap();
+        assert!(!decode_preset(&persistence.unpack(&stored)?)?.1);
+
+        // Tagged presets stored before favorites keep their tags
+        let tagged = LegacyTaggedPresetEncoding {
+            name: "Tagged Show".to_string(),
+            description: None,
+            widget_values: Vec::new(),
+            created_by: None,
+            usage_count: 2,
+            last_used: 0,
+            tags: vec!["live".to_string()],
+        };
+        let bytes = bincode::encode_to_vec(&tagged, bincode::config::standard())?;
+        let (preset, migrated) = decode_preset(&bytes)?;
+        assert!(migrated);
+        assert_eq!(preset.tags, vec!["live".to_string()]);
+        assert!(!preset.favorite);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_payload_codec_is_recorded() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_payload_codec");
+
+        let record = WidgetRecord {
+            id: 3,
+            widget: Widget::simplified(Some("Amp_01".to_string()), Some(3), vec![0.5; 64]),
+            features: WidgetFeatures {
+                value_patterns: vec![0.5; 64],
+                ..Default::default()
+            },
+            frequency: 1,
+            last_seen: 0,
+            value_stats: None,
+        };
+
+        {
+            let persistence = SledPersistenceManager::new(&db_path)?;
+            assert_eq!(persistence.payload_codec(), PayloadCodec::preferred());
+            persistence.store_widget(&record)?;
+            persistence.flush()?;
+        }
+
+        let persistence = SledPersistenceManager::new(&db_path)?;
+        assert_eq!(
+            persistence
+                .load_metadata(PayloadCodec::METADATA_KEY)?
+                .as_deref(),
+            Some(PayloadCodec::preferred().as_str())
+        );
+        let records = persistence.load_all_widgets()?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].widget.values.len(), 64);
+
+        // Data written before the codec was recorded is read as plain bincode
+        persistence
+            .metadata_tree
+            .remove(PayloadCodec::METADATA_KEY)?;
+        persistence.widgets_tree.insert(
+            3u64.to_be_bytes(),
+            bincode::encode_to_vec(&record, bincode::config::standard())?,
+        )?;
+        persistence.flush()?;
+        drop(persistence);
+
+        let persistence = SledPersistenceManager::new(&db_path)?;
+        assert_eq!(persistence.payload_codec(), PayloadCodec::Bincode);
+        assert_eq!(persistence.load_all_widgets()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_legacy_json_trees() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_migrate_legacy");
+
+        let record = WidgetRecord {
+            id: 7,
+            widget: Widget::simplified(Some("Cutoff".to_string()), Some(12), vec![0.25; 4]),
+            features: WidgetFeatures::default(),
+            frequency: 3,
+            last_seen: 0,
+            value_stats: None,
+        };
+        let preset = Preset {
+            name: "Old Show".to_string(),
+            description: None,
+            widget_values: Vec::new(),
+            created_by: None,
+            usage_count: 4,
+            last_used: 0,
+            tags: Vec::new(),
+            favorite: false,
+        };
+
+        {
+            let db = sled::open(&db_path)?;
+            let widgets = db.open_tree(LEGACY_WIDGETS_TREE)?;
+            widgets.insert("7", serde_json::to_vec(&record)?)?;
+            widgets.insert("broken", "{not json")?;
+            db.open_tree(LEGACY_PRESETS_TREE)?
+                .insert("Old Show", serde_json::to_vec(&preset)?)?;
+            db.flush()?;
+        }
+
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let status = engine.migration_status()?;
+        assert!(status.migration_needed);
+        assert_eq!((status.legacy_widgets, status.legacy_presets), (2, 1));
+        assert_eq!((status.new_widgets, status.new_presets), (0, 0));
+
+        let report = engine.migrate_legacy()?;
+        assert_eq!(report.migrated_widgets, 1);
+        assert_eq!(report.migrated_presets, 1);
+        assert_eq!(report.failed_widgets, 1);
+        assert_eq!(engine.engine.records.len(), 1);
+        assert_eq!(engine.engine.presets[0].usage_count, 4);
+        assert_eq!(engine.engine.next_id, 8);
+
+        // The widget tree kept its unreadable entry, the preset tree is gone
+        let status = engine.migration_status()?;
+        assert_eq!((status.legacy_widgets, status.legacy_presets), (2, 0));
+
+        // Running again leaves already migrated entries alone
+        let report = engine.migrate_legacy()?;
+        assert_eq!(report.migrated_widgets, 0);
+        assert_eq!(report.skipped_widgets, 1);
+        drop(engine);
+
+        let engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        assert_eq!(
+            engine.engine.records[0].widget.label.as_deref(),
+            Some("Cutoff")
+        );
+        assert_eq!(engine.engine.next_id, 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_quarantines_corrupt_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_verify");
+
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        engine.store_widget(Widget::simplified(
+            Some("Cutoff".to_string()),
+            Some(12),
+            vec![0.25],
+        ))?;
+        assert!(engine.verify(false)?.is_clean());
+
+        let persistence = &engine.persistence;
+        persistence
+            .widgets_tree
+            .insert(99u64.to_be_bytes(), &[0xff, 0x00, 0x13][..])?;
+        persistence.presets_tree.insert("Broken", &[0x01][..])?;
+
+        let report = engine.verify(false)?;
+        assert_eq!(report.corrupt.len(), 2);
+        assert_eq!(report.quarantined, 0);
+        assert_eq!(report.corrupt[0].tree, "widgets_v1");
+        assert_eq!(report.corrupt[0].key, 99u64.to_be_bytes().to_vec());
+        assert_eq!(report.corrupt[1].tree, "presets_v1");
+
+        let report = engine.verify(true)?;
+        assert_eq!(report.quarantined, 2);
+        assert!(engine.verify(false)?.is_clean());
+        assert_eq!(engine.persistence.load_all_widgets()?.len(), 1);
+
+        let corrupt = engine.persistence.db.open_tree(CORRUPT_TREE)?;
+        let mut key = b"presets_v1".to_vec();
+        key.push(0);
+        key.extend_from_slice(b"Broken");
+        assert_eq!(corrupt.get(key)?.as_deref(), Some(&[0x01][..]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_learning_writes_activity_only() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("test_activity");
+        let cutoff =
+            |value: f64| Widget::simplified(Some("Cutoff".to_string()), Some(12), vec![value]);
+
+        {
+            let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+            engine.store_widget(cutoff(0.1))?;
+            let full = engine
+                .persistence
+                .widgets_tree
+                .get(1u64.to_be_bytes())?
+                .unwrap();
+
+            for step in 2..=10 {
+                engine.store_widget(cutoff(step as f64 / 10.0))?;
+            }
+            // The full record was left alone, the observations went to its activity
+            let persistence = &engine.persistence;
+            assert_eq!(
+                persistence.widgets_tree.get(1u64.to_be_bytes())?.unwrap(),
+                full
+            );
+            assert_eq!(persistence.widget_activity_tree.len(), 1);
+            assert!(engine.verify(false)?.is_clean());
+
+            let loaded = persistence.load_widget(1)?.unwrap();
+            let record = &engine.engine.records[0];
+            assert_eq!(loaded.frequency, 10);
+            assert_eq!(loaded.widget.values, record.widget.values);
+            assert_eq!(
+                loaded.features.value_patterns,
+                record.features.value_patterns
+            );
+            assert_eq!(
+                loaded.value_stats.map(|stats| stats.count),
+                record.value_stats.as_ref().map(|stats| stats.count)
+            );
+        }
+
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let record = &engine.engine.records[0];
+        assert_eq!(record.frequency, 10);
+        assert_eq!(record.widget.values.len(), 10);
+
+        // Enough new values fold the activity back into the full record
+        for step in 0..MAX_ACTIVITY_VALUES {
+            engine.store_widget(cutoff(2.0 + step as f64))?;
+        }
+        let full = engine
+            .persistence
+            .widgets_tree
+            .get(1u64.to_be_bytes())?
+            .unwrap();
+        let (full, _) = decode_widget_record(&engine.persistence.unpack(&full)?)?;
+        assert!(full.widget.values.len() > 10);
+        let loaded = engine.persistence.load_widget(1)?.unwrap();
+        assert_eq!(loaded.widget.values.len(), 10 + MAX_ACTIVITY_VALUES);
+
+        Ok(())
+    }
+}
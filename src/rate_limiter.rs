@@ -0,0 +1,89 @@
+//! Per event ID rate limiting of learned values
+//!
+//! A knob being turned sends a stream of values. Storing each of them would flood the
+//! database and weight the widget towards wherever the knob passed slowly. Both the OSC
+//! listener and `StandaloneIntelligenceService::record_widget_value` store at most one
+//! value per event ID and interval, and the last value held back once the interval has
+//! passed.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Limits how often each event ID is stored, keeping the latest held-back value
+#[derive(Debug)]
+pub struct RateLimiter {
+    min_interval: Duration,
+    last_stored: HashMap<i64, Instant>,
+    pending: HashMap<i64, f64>,
+}
+
+impl RateLimiter {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_stored: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Returns the value if it is due to be stored now, holding it back otherwise
+    pub fn offer(&mut self, event_id: i64, value: f64, now: Instant) -> Option<f64> {
+        if self.is_due(event_id, now) {
+            self.pending.remove(&event_id);
+            self.last_stored.insert(event_id, now);
+            Some(value)
+        } else {
+            self.pending.insert(event_id, value);
+            None
+        }
+    }
+
+    /// Takes the held-back values whose interval has passed
+    pub fn take_due(&mut self, now: Instant) -> Vec<(i64, f64)> {
+        let due: Vec<i64> = self
+            .pending
+            .keys()
+            .copied()
+            .filter(|&event_id| self.is_due(event_id, now))
+            .collect();
+
+        due.into_iter()
+            .filter_map(|event_id| {
+                let value = self.pending.remove(&event_id)?;
+                self.last_stored.insert(event_id, now);
+                Some((event_id, value))
+            })
+            .collect()
+    }
+
+    /// When the value held back for `event_id` is due, if there is one
+    pub fn due_at(&self, event_id: i64) -> Option<Instant> {
+        if !self.pending.contains_key(&event_id) {
+            return None;
+        }
+        self.last_stored
+            .get(&event_id)
+            .map(|&last| last + self.min_interval)
+    }
+
+    /// Takes every held-back value, e.g. when stopping
+    pub fn take_all(&mut self, now: Instant) -> Vec<(i64, f64)> {
+        let values: Vec<(i64, f64)> = self.pending.drain().collect();
+        for &(event_id, _) in &values {
+            self.last_stored.insert(event_id, now);
+        }
+        values
+    }
+
+    /// Forgets every stored and held-back value
+    pub fn clear(&mut self) {
+        self.last_stored.clear();
+        self.pending.clear();
+    }
+
+    fn is_due(&self, event_id: i64, now: Instant) -> bool {
+        self.last_stored
+            .get(&event_id)
+            .is_none_or(|&last| now.duration_since(last) >= self.min_interval)
+    }
+}
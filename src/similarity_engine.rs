@@ -1,7 +1,10 @@
+use crate::error::WidgetIntelligenceError;
+use crate::fields::FieldAliases;
+use crate::labels::LabelSanitizer;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::time::{SystemTime, UNIX_EPOCH};
 use strsim::jaro_winkler;
@@ -10,7 +13,10 @@ use strsim::jaro_winkler;
 pub type FilteredWidgetDescription = HashMap<String, serde_json::Value>;
 
 /// Represents a widget with its properties and normalized current value (0.0-1.0 or -1.0-1.0)
-#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
+///
+/// `event_id` and `values` were added after the first storage layout; serde defaults
+/// them when absent and the persistence layer migrates older bincode records.
+#[derive(Debug, Clone, Default, Encode, Decode, Serialize, Deserialize)]
 pub struct Widget {
     pub label: Option<String>,
     pub minimum: Option<f64>,
@@ -18,29 +24,159 @@ pub struct Widget {
     pub is_generated: Option<bool>,
     pub display_type: Option<String>,
     pub current_value: Option<f64>,
+    #[serde(default)]
     pub event_id: Option<u64>,
+    #[serde(default)]
     pub values: Vec<f64>,
+    #[serde(default)]
+    pub value_kind: Option<ValueKind>,
+    #[serde(default)]
+    pub taper: Option<Taper>,
+}
+
+/// Response curve of a control, mapping a value's linear position in its range
+/// to the perceptual position of the fader
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub enum Taper {
+    Linear,
+    /// Equal fader travel per ratio of the value, e.g. frequency; needs `0 < minimum < maximum`
+    Logarithmic,
+    /// Quadratic curve, finer resolution near the bottom of the range, e.g. amplitude
+    Exponential,
+}
+
+impl Taper {
+    /// Maps a normalized (linear) value to its perceptual fader position
+    pub fn to_position(&self, normalized: f64, minimum: Option<f64>, maximum: Option<f64>) -> f64 {
+        match self {
+            Taper::Linear => normalized,
+            Taper::Logarithmic => match log_range(minimum, maximum) {
+                Some((min, max)) => {
+                    let value = min + normalized.max(0.0) * (max - min);
+                    (value / min).ln() / (max / min).ln()
+                }
+                None => normalized,
+            },
+            Taper::Exponential => normalized.signum() * normalized.abs().sqrt(),
+        }
+    }
+
+    /// Maps a perceptual fader position back to a normalized (linear) value
+    pub fn from_position(&self, position: f64, minimum: Option<f64>, maximum: Option<f64>) -> f64 {
+        match self {
+            Taper::Linear => position,
+            Taper::Logarithmic => match log_range(minimum, maximum) {
+                Some((min, max)) => {
+                    let value = min * (max / min).powf(position);
+                    (value - min) / (max - min)
+                }
+                None => position,
+            },
+            Taper::Exponential => position * position.abs(),
+        }
+    }
+}
+
+/// The range bounds if a logarithmic taper can be applied to them
+fn log_range(minimum: Option<f64>, maximum: Option<f64>) -> Option<(f64, f64)> {
+    match (minimum, maximum) {
+        (Some(min), Some(max)) if min > 0.0 && max > min => Some((min, max)),
+        _ => None,
+    }
+}
+
+/// The set of normalized values a widget can actually take
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub enum ValueKind {
+    Continuous,
+    /// Off/on, stored as 0.0/1.0
+    Boolean,
+    /// `steps` evenly spaced positions across 0.0-1.0, both ends included
+    Stepped {
+        steps: u32,
+    },
+}
+
+impl ValueKind {
+    /// Snaps a normalized value onto the nearest valid position
+    pub fn quantize(&self, value: f64) -> f64 {
+        match *self {
+            ValueKind::Continuous => value,
+            ValueKind::Boolean => {
+                if value >= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            ValueKind::Stepped { steps } => {
+                let intervals = steps.max(2) as f64 - 1.0;
+                ((value.clamp(0.0, 1.0) * intervals).round() / intervals).clamp(0.0, 1.0)
+            }
+        }
+    }
 }
 
+// Display types that only ever take an off/on value
+const BOOLEAN_DISPLAY_TYPES: &[&str] =
+    &["toggle", "button", "checkbox", "switch", "gate", "boolean"];
+
 impl Widget {
     /// Creates a simplified widget with only label, event_id, and values
+    ///
+    /// `values` is an observation history, oldest first; the most recent value
+    /// becomes the current value. All of them are learned by `store_widget`.
     pub fn simplified(label: Option<String>, event_id: Option<u64>, values: Vec<f64>) -> Self {
-        let current_value = if !values.is_empty() {
-            Some(values[0])
-        } else {
-            None
-        };
+        let current_value = values.last().copied();
 
         Self {
             label,
             event_id,
-            values: values.clone(),
+            values,
             minimum: None,
             maximum: None,
             is_generated: None,
             display_type: None,
             current_value,
+            value_kind: None,
+            taper: None,
+        }
+    }
+
+    /// The value kind, inferred from the display type when not set explicitly
+    pub fn effective_value_kind(&self) -> ValueKind {
+        if let Some(kind) = self.value_kind {
+            return kind;
         }
+
+        match &self.display_type {
+            Some(display_type)
+                if BOOLEAN_DISPLAY_TYPES.contains(&display_type.to_lowercase().as_str()) =>
+            {
+                ValueKind::Boolean
+            }
+            _ => ValueKind::Continuous,
+        }
+    }
+
+    /// Drops non-finite numbers so hostile input cannot poison similarity or ordering
+    pub fn sanitized(mut self) -> Self {
+        let finite = |value: Option<f64>| value.filter(|v| v.is_finite());
+        self.minimum = finite(self.minimum);
+        self.maximum = finite(self.maximum);
+        self.current_value = finite(self.current_value);
+        self.values.retain(|v| v.is_finite());
+        self
+    }
+
+    /// Snaps the values of a boolean widget to 0.0/1.0, so only off and on are learned
+    pub fn with_boolean_values(mut self) -> Self {
+        if self.effective_value_kind() == ValueKind::Boolean {
+            let kind = ValueKind::Boolean;
+            self.current_value = self.current_value.map(|v| kind.quantize(v));
+            self.values.iter_mut().for_each(|v| *v = kind.quantize(*v));
+        }
+        self
     }
 
     /// Gets the values vector, including the current_value if available
@@ -65,6 +201,9 @@ pub struct WidgetValue {
 }
 
 /// Represents a preset collection of widget values
+///
+/// `tags` was appended after the first stored layout; the persistence layer decodes
+/// records without it.
 #[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
 pub struct Preset {
     pub name: String,
@@ -73,6 +212,207 @@ pub struct Preset {
     pub created_by: Option<String>,
     pub usage_count: u32,
     pub last_used: u64,
+    /// Free-form labels such as a show, song or sound category
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Starred by the user; favorites win ties in insights and recommendations
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+/// `created_by` of presets generated from suggestions rather than saved by a user
+pub const GENERATED_PRESET_AUTHOR: &str = "widget-intelligence";
+
+impl Preset {
+    /// Whether the preset was generated by `WidgetSuggestionEngine::generate_preset`
+    pub fn is_generated(&self) -> bool {
+        self.created_by.as_deref() == Some(GENERATED_PRESET_AUTHOR)
+    }
+
+    /// Whether the preset carries `tag`, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+/// Largest average distance between presets merged into one cluster by default
+const PRESET_CLUSTER_DISTANCE: f64 = 0.2;
+
+/// A group of presets whose widget values lie close together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetCluster {
+    /// Names of the member presets, sorted
+    pub presets: Vec<String>,
+    /// Mean value per widget ID over the member presets
+    pub centroid: HashMap<String, f64>,
+    /// The widget that most sets the cluster apart from all presets, e.g. "cutoff high"
+    pub label: String,
+}
+
+/// A stored preset ranked by how close it is to the live widget state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetRecommendation {
+    pub preset: String,
+    /// Root mean square difference over the preset's widgets; 0.0 is an exact match
+    pub distance: f64,
+    /// Number of the preset's widgets present in the live state
+    pub matched_widgets: usize,
+    pub favorite: bool,
+}
+
+/// Label similarity above which a preset value counts as set on a similar control
+const PRESET_INSIGHT_THRESHOLD: f64 = 0.8;
+
+/// A preset that sets a control similar to the queried widget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetInsight {
+    pub preset: String,
+    /// Label of the preset's widget value that matched
+    pub label: String,
+    pub value: f64,
+    pub usage_count: u32,
+    /// Jaro-Winkler similarity between the queried and matched labels
+    pub similarity: f64,
+    pub favorite: bool,
+}
+
+impl std::fmt::Display for PresetInsight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "This widget is often set to {} in the '{}' preset",
+            self.value, self.preset
+        )
+    }
+}
+
+/// Upper bounds on the learned records; `None` leaves a dimension unbounded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapacityLimits {
+    pub max_records: Option<usize>,
+    /// Budget for the records' encoded size, which is what they take up in storage
+    pub max_bytes: Option<usize>,
+}
+
+impl CapacityLimits {
+    pub fn is_unbounded(&self) -> bool {
+        self.max_records.is_none() && self.max_bytes.is_none()
+    }
+}
+
+/// How long learned records are kept without being seen again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Records not seen for longer are dropped; `None` keeps them forever
+    pub max_age: Option<std::time::Duration>,
+    /// Keep expired records of widgets that some preset sets
+    pub keep_preset_widgets: bool,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            keep_preset_widgets: true,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Drops records not seen for `max_age`, except those of preset widgets
+    pub fn max_age(max_age: std::time::Duration) -> Self {
+        Self {
+            max_age: Some(max_age),
+            ..Default::default()
+        }
+    }
+}
+
+/// Records dropped to get back within `CapacityLimits`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EvictionReport {
+    /// Ids of the evicted records, in eviction order
+    pub evicted: Vec<u64>,
+    pub records_before: usize,
+    pub records_after: usize,
+    /// Encoded size of the records; left at 0 when no limit was set, as nothing was measured
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+impl EvictionReport {
+    pub fn is_empty(&self) -> bool {
+        self.evicted.is_empty()
+    }
+}
+
+/// Order of the records returned by `list_widgets`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WidgetSort {
+    /// Oldest record first
+    #[default]
+    Id,
+    /// Alphabetical, ignoring case; unlabelled records last
+    Label,
+    /// Highest frequency first
+    MostUsed,
+    /// Most recently seen first
+    RecentlySeen,
+}
+
+/// One page of a listing, with the size of the whole listing for paging controls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub offset: usize,
+    pub limit: usize,
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    fn slice(all: Vec<T>, offset: usize, limit: usize) -> Self {
+        let total = all.len();
+        Self {
+            items: all.into_iter().skip(offset).take(limit).collect(),
+            offset,
+            limit,
+            total,
+        }
+    }
+
+    /// Whether a page after this one has items
+    pub fn has_more(&self) -> bool {
+        self.offset.saturating_add(self.items.len()) < self.total
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            offset: self.offset,
+            limit: self.limit,
+            total: self.total,
+        }
+    }
+}
+
+/// Presets two widgets must share before their values are correlated
+const MIN_PRESETS_FOR_CORRELATION: usize = 3;
+
+/// Score boost `related_widgets_near` gives the nearest widget; the next nearest get a
+/// half, a third and so on of it
+const ADJACENCY_BOOST: f64 = 0.5;
+
+/// A widget that tends to be set together with another one across presets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedWidget {
+    pub event_id: u64,
+    /// Share of presets containing either widget that contain both
+    pub co_occurrence: f64,
+    /// Pearson correlation of the two widgets' values over the presets they share,
+    /// `None` until they share enough presets with varying values
+    pub correlation: Option<f64>,
+    /// `co_occurrence` weighted by the strength of the correlation, 0.5 if unknown
+    pub score: f64,
 }
 
 /// Features extracted from a widget for similarity calculation
@@ -104,14 +444,163 @@ impl Default for WidgetFeatures {
     }
 }
 
+/// Number of most frequent values reported in `ValueStats::common_values`
+const COMMON_VALUE_COUNT: usize = 3;
+
 /// Statistical information about widget values
-#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Encode, Decode, Serialize, Deserialize)]
 pub struct ValueStats {
     pub common_values: Vec<f64>,
+    /// Observation counts keyed by value rounded to two decimals
     pub frequency_map: HashMap<String, u32>,
     pub mean: f64,
     pub std_dev: f64,
+    /// 25th, 50th and 75th percentiles
     pub percentiles: Vec<f64>,
+    #[serde(default)]
+    pub count: u64,
+    /// Running sum of squared deviations, for Welford's update
+    #[serde(default)]
+    pub m2: f64,
+}
+
+impl ValueStats {
+    /// Statistics over observed values
+    pub fn from_values(values: &[f64]) -> Self {
+        let mut stats = ValueStats::default();
+        for &value in values {
+            stats.observe(value);
+        }
+        stats.update_percentiles(values);
+        stats
+    }
+
+    /// Folds one observation into the running mean, deviation and frequencies
+    pub fn observe(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.std_dev = (self.m2 / self.count as f64).sqrt();
+
+        *self.frequency_map.entry(format!("{value:.2}")).or_insert(0) += 1;
+        self.update_common_values();
+    }
+
+    /// Takes back one observation of `value`, e.g. a rejected suggestion; returns how
+    /// many observations of its rounded value remain
+    pub fn forget(&mut self, value: f64) -> u32 {
+        let key = format!("{value:.2}");
+        let Some(observed) = self.frequency_map.get_mut(&key) else {
+            return 0;
+        };
+        *observed -= 1;
+        let remaining = *observed;
+        if remaining == 0 {
+            self.frequency_map.remove(&key);
+        }
+
+        self.count = self.count.saturating_sub(1);
+        if self.count == 0 {
+            self.mean = 0.0;
+            self.m2 = 0.0;
+        } else {
+            // Welford's update in reverse
+            let mean = self.mean;
+            self.mean = (mean * (self.count + 1) as f64 - value) / self.count as f64;
+            self.m2 = (self.m2 - (value - mean) * (value - self.mean)).max(0.0);
+        }
+        self.std_dev = (self.m2 / self.count.max(1) as f64).sqrt();
+        self.update_common_values();
+        remaining
+    }
+
+    /// The statistics of the observations within `low..=high`, e.g. without outliers.
+    /// `values` are the distinct values the percentiles are taken over.
+    ///
+    /// The bounds apply to the exact observed values. Observations are only counted by
+    /// their rounded `frequency_map` key, which values sharing a key split between them.
+    pub fn within(&self, low: f64, high: f64, values: &[f64]) -> Self {
+        let counts = observation_counts(values, std::slice::from_ref(self));
+        let kept: Vec<(f64, u32)> = values
+            .iter()
+            .copied()
+            .zip(counts)
+            .filter(|&(v, _)| v >= low && v <= high)
+            .collect();
+
+        let mut stats = ValueStats {
+            count: kept.iter().map(|&(_, count)| u64::from(count)).sum(),
+            ..Default::default()
+        };
+        for &(v, count) in &kept {
+            *stats.frequency_map.entry(format!("{v:.2}")).or_insert(0) += count;
+        }
+        if stats.count > 0 {
+            let n = stats.count as f64;
+            stats.mean = kept.iter().map(|&(v, c)| v * f64::from(c)).sum::<f64>() / n;
+            stats.m2 = kept
+                .iter()
+                .map(|&(v, c)| f64::from(c) * (v - stats.mean).powi(2))
+                .sum();
+            stats.std_dev = (stats.m2 / n).sqrt();
+        }
+        stats.update_common_values();
+
+        let kept_values: Vec<f64> = kept.iter().map(|&(v, _)| v).collect();
+        stats.update_percentiles(&kept_values);
+        stats
+    }
+
+    /// Folds in the statistics of other observations, e.g. of another record of the same
+    /// widget. Percentiles are left to `update_percentiles` over the combined values.
+    pub fn merge(&mut self, other: &ValueStats) {
+        let count = self.count + other.count;
+        if count == 0 {
+            return;
+        }
+        // Chan et al.'s pairwise combination of the running mean and squared deviations
+        let delta = other.mean - self.mean;
+        let (n_a, n_b, n) = (self.count as f64, other.count as f64, count as f64);
+        self.mean += delta * n_b / n;
+        self.m2 += other.m2 + delta * delta * n_a * n_b / n;
+        self.count = count;
+        self.std_dev = (self.m2 / n).sqrt();
+
+        for (key, observed) in &other.frequency_map {
+            *self.frequency_map.entry(key.clone()).or_insert(0) += observed;
+        }
+        self.update_common_values();
+    }
+
+    fn update_common_values(&mut self) {
+        let mut counted: Vec<(f64, u32)> = self
+            .frequency_map
+            .iter()
+            .filter_map(|(key, &count)| key.parse::<f64>().ok().map(|v| (v, count)))
+            .collect();
+        counted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.total_cmp(&b.0)));
+        self.common_values = counted
+            .into_iter()
+            .take(COMMON_VALUE_COUNT)
+            .map(|(v, _)| v)
+            .collect();
+    }
+
+    /// Recomputes the quartiles from the values the statistics describe
+    pub fn update_percentiles(&mut self, values: &[f64]) {
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        self.percentiles = if sorted.is_empty() {
+            Vec::new()
+        } else {
+            [0.25, 0.5, 0.75]
+                .iter()
+                .map(|&p| percentile(&sorted, p))
+                .collect()
+        };
+    }
 }
 
 /// A stored widget record with features and usage statistics
@@ -125,48 +614,122 @@ pub struct WidgetRecord {
     pub value_stats: Option<ValueStats>,
 }
 
-impl From<FilteredWidgetDescription> for WidgetRecord {
-    fn from(filtered: FilteredWidgetDescription) -> Self {
-        // Helper function to extract string values from JSON
-        fn extract_string(map: &HashMap<String, serde_json::Value>, key: &str) -> Option<String> {
-            map.get(key).and_then(|v| v.as_str().map(|s| s.to_string()))
-        }
+impl WidgetRecord {
+    /// Bytes the record takes up bincode-encoded
+    pub fn encoded_size(&self) -> usize {
+        bincode::encode_to_vec(self, bincode::config::standard())
+            .map(|bytes| bytes.len())
+            .unwrap_or(0)
+    }
 
-        // Helper function to extract f64 values from JSON
-        fn extract_f64(map: &HashMap<String, serde_json::Value>, key: &str) -> Option<f64> {
-            map.get(key).and_then(|v| v.as_f64())
+    /// Updates the statistics for a value just observed
+    fn observe_value(&mut self, value: f64) {
+        match &mut self.value_stats {
+            Some(stats) => stats.observe(value),
+            None => self.refresh_value_stats(),
         }
+    }
 
-        // Helper function to extract bool values from JSON
-        fn extract_bool(map: &HashMap<String, serde_json::Value>, key: &str) -> Option<bool> {
-            map.get(key).and_then(|v| v.as_bool())
-        }
+    /// Recomputes the statistics from this record's values, each counted once. Label
+    /// priors in `features.value_patterns` were never observed and are left out.
+    pub fn refresh_value_stats(&mut self) {
+        self.value_stats = Some(ValueStats::from_values(&self.widget.get_values()));
+    }
+
+    /// The widget as a Kyma description under the canonical field names, the reverse of
+    /// `From<FilteredWidgetDescription>`
+    ///
+    /// `current_value` stays normalized, as that conversion reads it. Stepped widgets
+    /// get the `grid` their steps imply. See `WidgetSuggestionEngine::kyma_description`
+    /// for a description that also carries a suggested `defaultValue`.
+    pub fn to_description(&self) -> FilteredWidgetDescription {
+        let widget = &self.widget;
+        let mut description = FilteredWidgetDescription::new();
+        let mut set = |key: &str, value: Option<serde_json::Value>| {
+            if let Some(value) = value {
+                description.insert(key.to_string(), value);
+            }
+        };
 
-        // Helper function to extract u64 values from JSON
-        fn extract_u64(map: &HashMap<String, serde_json::Value>, key: &str) -> Option<u64> {
-            map.get(key).and_then(|v| v.as_u64())
+        set("concreteEventID", widget.event_id.map(Into::into));
+        set("label", widget.label.clone().map(Into::into));
+        set("minimum", widget.minimum.map(Into::into));
+        set("maximum", widget.maximum.map(Into::into));
+        set("displayType", widget.display_type.clone().map(Into::into));
+        set("isGenerated", widget.is_generated.map(Into::into));
+        set("current_value", widget.current_value.map(Into::into));
+        match widget.value_kind {
+            Some(ValueKind::Boolean) => set("isBoolean", Some(true.into())),
+            Some(ValueKind::Stepped { steps }) if steps > 1 => {
+                let grid = match (widget.minimum, widget.maximum) {
+                    (Some(min), Some(max)) if max > min => Some((max - min) / f64::from(steps - 1)),
+                    _ => None,
+                };
+                set("grid", grid.map(Into::into));
+            }
+            _ => {}
         }
+        set(
+            "taper",
+            widget.taper.map(|taper| {
+                match taper {
+                    Taper::Linear => "linear",
+                    Taper::Logarithmic => "log",
+                    Taper::Exponential => "exp",
+                }
+                .into()
+            }),
+        );
+        description
+    }
+}
+
+impl From<FilteredWidgetDescription> for WidgetRecord {
+    fn from(filtered: FilteredWidgetDescription) -> Self {
+        // Read with the same aliases, coercion and label cleanup as the extractor
+        let fields = FieldAliases::default();
+        let labels = LabelSanitizer::default();
 
         // Extract widget data from the filtered description
-        let current_value = extract_f64(&filtered, "current_value");
-        let event_id = extract_u64(&filtered, "concreteEventID");
+        let current_value = fields.f64(&filtered, "current_value");
+        let event_id = fields
+            .i64(&filtered, "concreteEventID")
+            .and_then(|id| u64::try_from(id).ok());
 
         let widget = Widget {
-            label: extract_string(&filtered, "label"),
-            minimum: extract_f64(&filtered, "minimum"),
-            maximum: extract_f64(&filtered, "maximum"),
+            label: fields
+                .string(&filtered, "label")
+                .and_then(|label| labels.sanitize(&label)),
+            minimum: fields.f64(&filtered, "minimum"),
+            maximum: fields.f64(&filtered, "maximum"),
             current_value,
-            is_generated: extract_bool(&filtered, "isGenerated"),
-            display_type: extract_string(&filtered, "displayType"),
+            is_generated: fields.bool(&filtered, "isGenerated"),
+            display_type: fields.string(&filtered, "displayType").or_else(|| {
+                crate::kyma_extractor::infer_display_type(
+                    fields.bool(&filtered, "isBoolean"),
+                    fields.bool(&filtered, "isFullRange"),
+                    fields.bool(&filtered, "isEventSource"),
+                )
+                .map(str::to_string)
+            }),
             event_id,
-            values: if let Some(val) = current_value { vec![val] } else { Vec::new() },
+            values: if let Some(val) = current_value {
+                vec![val]
+            } else {
+                Vec::new()
+            },
+            value_kind: match fields.bool(&filtered, "isBoolean") {
+                Some(true) => Some(ValueKind::Boolean),
+                _ => None,
+            },
+            taper: None,
         };
 
         // Create basic features from the widget data
         let label_tokens = if let Some(ref label) = widget.label {
-            label
-                .to_lowercase()
-                .split_whitespace()
+            labels
+                .tokens(label)
+                .into_iter()
                 .map(|s| s.chars().filter(|c| c.is_alphanumeric()).collect())
                 .filter(|s: &String| !s.is_empty())
                 .collect()
@@ -203,7 +766,7 @@ impl From<FilteredWidgetDescription> for WidgetRecord {
             } else {
                 Vec::new()
             },
-            normalized_position: widget.current_value.unwrap_or(0.5)
+            normalized_position: widget.current_value.unwrap_or(0.5),
         };
 
         // Get current timestamp
@@ -213,7 +776,7 @@ impl From<FilteredWidgetDescription> for WidgetRecord {
             .as_secs();
 
         // Extract ID from concreteEventID if available, otherwise use 0
-        let id = extract_u64(&filtered, "concreteEventID").unwrap_or(0);
+        let id = event_id.unwrap_or(0);
 
         WidgetRecord {
             id,
@@ -226,383 +789,2112 @@ impl From<FilteredWidgetDescription> for WidgetRecord {
     }
 }
 
+/// Machine-readable explanation of why a suggestion was produced
+/// Frontends can match on the variant to render localized explanations;
+/// `Display` yields the default English description
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum SuggestionReason {
+    MatchedLabel {
+        label: String,
+        score: f64,
+        frequency: u32,
+    },
+    MatchedEventId {
+        event_id: u64,
+        label: Option<String>,
+    },
+    SimilarWidget {
+        label: Option<String>,
+        score: f64,
+        frequency: u32,
+    },
+    SimilarToEventId {
+        event_id: u64,
+        label: Option<String>,
+        score: f64,
+    },
+    /// Nothing was learned for the widget yet; the value is its default
+    DefaultValue { label: Option<String> },
+}
+
+impl std::fmt::Display for SuggestionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuggestionReason::MatchedLabel {
+                label, frequency, ..
+            } => write!(
+                f,
+                "Exact label match for '{label}' (frequency: {frequency})"
+            ),
+            SuggestionReason::MatchedEventId { event_id, label } => write!(
+                f,
+                "Exact match for event ID {event_id} ({})",
+                label.as_deref().unwrap_or("unnamed widget")
+            ),
+            SuggestionReason::SimilarWidget {
+                label,
+                score,
+                frequency,
+            } => write!(
+                f,
+                "Similar to {} (similarity: {score:.2}, frequency: {frequency})",
+                label.as_deref().unwrap_or("unnamed widget")
+            ),
+            SuggestionReason::SimilarToEventId {
+                event_id,
+                label,
+                score,
+            } => write!(
+                f,
+                "Similar to event ID {event_id} ({}) with similarity {score:.2}",
+                label.as_deref().unwrap_or("unnamed widget")
+            ),
+            SuggestionReason::DefaultValue { label } => write!(
+                f,
+                "Default value of {}, nothing learned yet",
+                label.as_deref().unwrap_or("unnamed widget")
+            ),
+        }
+    }
+}
+
 /// A suggestion for a widget value with confidence and reasoning
 /// All suggested values are normalized (0.0-1.0 or -1.0-1.0)
 #[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
 pub struct Suggestion {
     pub widget: Widget,
     pub confidence: f64,
-    pub reason: String,
+    pub reason: SuggestionReason,
     pub suggested_value: Option<f64>,
     pub value_confidence: f64,
     pub alternative_values: Vec<f64>,
+    /// For boolean widgets, the share of observations in the state opposite to
+    /// `suggested_value`
+    #[serde(default)]
+    pub flip_probability: Option<f64>,
 }
 
-/// The main engine for widget suggestions and learning
-pub struct WidgetSuggestionEngine {
-    pub records: Vec<WidgetRecord>,
-    pub presets: Vec<Preset>,
-    pub display_types: HashMap<String, u64>,
-    pub next_id: u64,
+/// Weights of the terms of the similarity score, see `SimilarityBreakdown`
+///
+/// The score is the weighted sum clamped to 0.0..=1.0, so weights summing to 1.0 keep
+/// every term's contribution meaningful.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct SimilarityWeights {
+    pub label: f64,
+    pub range: f64,
+    pub display_type: f64,
+    pub generated: f64,
+    /// Value patterns are reported for explainability but do not affect the score by
+    /// default
+    pub value_pattern: f64,
 }
 
-impl WidgetSuggestionEngine {
-    pub fn new() -> Self {
+impl Default for SimilarityWeights {
+    fn default() -> Self {
         Self {
-            records: Vec::new(),
-            presets: Vec::new(),
-            display_types: HashMap::new(),
-            next_id: 1,
+            label: 0.4,
+            range: 0.3,
+            display_type: 0.2,
+            generated: 0.1,
+            value_pattern: 0.0,
         }
     }
+}
 
-    pub fn store_widget(&mut self, widget: Widget) {
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+/// Similarity scores above which records count as the same widget or get suggested
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct SimilarityThresholds {
+    /// A stored or merged widget is folded into a record this similar instead of
+    /// starting a new one
+    pub merge: f64,
+    /// Records this similar to a partially described widget are suggested for it
+    pub suggestion: f64,
+    /// Records this similar to the widget of a known event ID are suggested after it
+    pub event_id_suggestion: f64,
+}
 
-        // Extract features
-        let features = self.extract_features(&widget);
+impl Default for SimilarityThresholds {
+    fn default() -> Self {
+        Self {
+            merge: 0.85,
+            suggestion: 0.3,
+            event_id_suggestion: 0.5,
+        }
+    }
+}
 
-        // First, check if we have an exact match by event_id
-        if let Some(event_id) = widget.event_id {
-            for i in 0..self.records.len() {
-                if self.records[i].widget.event_id == Some(event_id) {
-                    // Update existing record with the same event_id
-                    self.records[i].frequency += 1;
-                    self.records[i].last_seen = current_time;
+/// A single weighted term of the similarity score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityComponent {
+    pub score: f64,
+    pub weight: f64,
+    pub contribution: f64,
+}
 
-                    // Update label if new one is provided
-                    if widget.label.is_some() && self.records[i].widget.label.is_none() {
-                        self.records[i].widget.label = widget.label.clone();
-                    }
+impl SimilarityComponent {
+    fn new(score: f64, weight: f64) -> Self {
+        Self {
+            score,
+            weight,
+            contribution: score * weight,
+        }
+    }
+}
 
-                    // Add new values to the existing values vector
-                    for &value in &widget.values {
-                        if !self.records[i].widget.values.contains(&value) {
-                            self.records[i].widget.values.push(value);
-                            // Also add to feature's value_patterns for backward compatibility
-                            self.records[i].features.value_patterns.push(value);
-                        }
-                    }
+/// Per-feature breakdown of the similarity between two widgets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityBreakdown {
+    pub label: SimilarityComponent,
+    pub range: SimilarityComponent,
+    pub display_type: SimilarityComponent,
+    pub generated: SimilarityComponent,
+    pub value_pattern: SimilarityComponent,
+    pub total: f64,
+}
 
-                    return;
-                }
+/// How the primary suggested value is picked from observed values
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub enum ValueStrategy {
+    /// Most common value after grouping into bins of `bin_width`, median if nothing repeats
+    Mode { bin_width: f64 },
+    /// Median of the observed values
+    Median,
+    /// Lowest observed value (legacy behaviour)
+    Lowest,
+}
+
+impl Default for ValueStrategy {
+    fn default() -> Self {
+        ValueStrategy::Mode { bin_width: 0.05 }
+    }
+}
+
+/// Outlier rejection applied to observed values before suggesting;
+/// the raw history on each record is always retained
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub enum OutlierFilter {
+    None,
+    /// Drop values outside `[Q1 - multiplier * IQR, Q3 + multiplier * IQR]`
+    Iqr {
+        multiplier: f64,
+    },
+    /// Drop values more than `threshold` standard deviations from the mean
+    ZScore {
+        threshold: f64,
+    },
+}
+
+impl Default for OutlierFilter {
+    fn default() -> Self {
+        OutlierFilter::Iqr { multiplier: 1.5 }
+    }
+}
+
+// Below this many observations there is too little data to call anything an outlier
+const MIN_VALUES_FOR_OUTLIER_FILTER: usize = 4;
+
+/// Tunable parameters for ranking and learning
+#[derive(Debug, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Weight of the log-scaled record frequency when ordering suggestions
+    pub frequency_weight: f64,
+    /// Strategy for the primary suggested value
+    pub value_strategy: ValueStrategy,
+    /// Outlier rejection applied when computing suggestions and statistics
+    pub outlier_filter: OutlierFilter,
+    /// Prior values keyed by lowercase label token, e.g. "volume" -> 0.75
+    pub value_priors: HashMap<String, f64>,
+    /// Cleanup applied to labels before they are tokenized; keep it the same as the
+    /// extractor's `label_sanitizer`
+    #[serde(default)]
+    pub label_sanitizer: LabelSanitizer,
+    #[serde(default)]
+    pub similarity_weights: SimilarityWeights,
+    #[serde(default)]
+    pub similarity_thresholds: SimilarityThresholds,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            frequency_weight: 0.1,
+            value_strategy: ValueStrategy::default(),
+            outlier_filter: OutlierFilter::default(),
+            value_priors: default_value_priors(),
+            label_sanitizer: LabelSanitizer::default(),
+            similarity_weights: SimilarityWeights::default(),
+            similarity_thresholds: SimilarityThresholds::default(),
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Merges priors from a JSON object of `token: value` pairs over the current ones
+    pub fn load_value_priors_json(&mut self, json: &str) -> Result<(), WidgetIntelligenceError> {
+        let priors: HashMap<String, f64> = serde_json::from_str(json).map_err(|e| {
+            WidgetIntelligenceError::ParseError(format!("Failed to parse value priors: {e}"))
+        })?;
+
+        for (token, value) in priors {
+            if !value.is_finite() {
+                return Err(WidgetIntelligenceError::ValidationError(format!(
+                    "Value prior for '{token}' must be finite"
+                )));
             }
+            self.value_priors.insert(token.to_lowercase(), value);
         }
 
-        // Next, check if we have an exact match by label
-        if let Some(label) = &widget.label {
-            for i in 0..self.records.len() {
-                if let Some(record_label) = &self.records[i].widget.label {
-                    if record_label == label {
-                        // Update existing record with the same label
-                        self.records[i].frequency += 1;
-                        self.records[i].last_seen = current_time;
+        Ok(())
+    }
+
+    /// Merges priors from a JSON file over the current ones
+    pub fn load_value_priors_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let json = std::fs::read_to_string(path.as_ref())?;
+        self.load_value_priors_json(&json)
+    }
+}
+
+/// Built-in priors for common audio control names
+pub fn default_value_priors() -> HashMap<String, f64> {
+    [
+        ("volume", 0.75),
+        ("level", 0.75),
+        ("gain", 0.75),
+        ("bass", 0.6),
+        ("low", 0.6),
+        ("treble", 0.7),
+        ("high", 0.7),
+        ("mid", 0.5),
+        ("middle", 0.5),
+        ("pan", 0.5),
+        ("reverb", 0.3),
+        ("delay", 0.3),
+    ]
+    .into_iter()
+    .map(|(token, value)| (token.to_string(), value))
+    .collect()
+}
+
+/// Uncalibrated value confidence by number of observed values: 1-2, 3-5, 6+
+const RAW_VALUE_CONFIDENCE: [f64; 3] = [0.5, 0.7, 0.9];
+
+/// Confidence of a suggestion made from a widget's default value alone, below that of
+/// any learned value
+const COLD_START_CONFIDENCE: f64 = 0.2;
+
+/// Pseudo-observations given to the raw confidence before feedback outweighs it
+const CALIBRATION_PRIOR_WEIGHT: f64 = 10.0;
+
+/// Offered and accepted counts for one confidence bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct CalibrationBucket {
+    pub offered: u32,
+    pub accepted: u32,
+}
+
+/// Calibrates value confidence against how often suggestions were accepted
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct ConfidenceCalibration {
+    pub buckets: [CalibrationBucket; 3],
+}
+
+impl ConfidenceCalibration {
+    fn bucket(sample_count: usize) -> usize {
+        match sample_count {
+            0..=2 => 0,
+            3..=5 => 1,
+            _ => 2,
+        }
+    }
+
+    /// Records whether a suggestion backed by `sample_count` values was accepted
+    pub fn record(&mut self, sample_count: usize, accepted: bool) {
+        let bucket = &mut self.buckets[Self::bucket(sample_count)];
+        bucket.offered = bucket.offered.saturating_add(1);
+        if accepted {
+            bucket.accepted = bucket.accepted.saturating_add(1);
+        }
+    }
+
+    /// Acceptance rate for the bucket, smoothed towards the raw confidence
+    pub fn confidence(&self, sample_count: usize) -> f64 {
+        let index = Self::bucket(sample_count);
+        let bucket = self.buckets[index];
+        let prior = RAW_VALUE_CONFIDENCE[index];
+        if bucket.offered == 0 {
+            return prior;
+        }
+
+        (bucket.accepted as f64 + CALIBRATION_PRIOR_WEIGHT * prior)
+            / (bucket.offered as f64 + CALIBRATION_PRIOR_WEIGHT)
+    }
+}
+
+/// Maximum distance between a rejected value and the observation it attenuates
+const FEEDBACK_TOLERANCE: f64 = 0.025;
+
+/// Whether a suggested value was accepted or rejected for a record
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct FeedbackEntry {
+    pub record_id: u64,
+    pub suggested_value: f64,
+    pub accepted: bool,
+    pub timestamp: u64,
+}
+
+/// An event ID rewritten after a sound was recompiled, kept as an audit trail
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventIdRemap {
+    pub old_event_id: u64,
+    pub new_event_id: u64,
+    /// Label of the widget in the recompiled sound
+    pub label: Option<String>,
+    /// Records whose event ID was rewritten
+    pub record_ids: Vec<u64>,
+    /// Presets whose values were rewritten
+    pub presets: Vec<String>,
+    /// Label, range and display type similarity the match was made on
+    pub score: f64,
+    pub timestamp: u64,
+}
+
+/// Lowest score at which `remap_event_ids` matches a widget to an orphaned event ID
+pub const REMAP_MIN_SCORE: f64 = 0.8;
+
+/// Changes to apply to a stored record; fields left as `None` are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordUpdate {
+    pub label: Option<String>,
+    pub display_type: Option<String>,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    pub event_id: Option<u64>,
+}
+
+/// The main engine for widget suggestions and learning
+pub struct WidgetSuggestionEngine {
+    pub records: Vec<WidgetRecord>,
+    pub presets: Vec<Preset>,
+    pub display_types: HashMap<String, u64>,
+    pub next_id: u64,
+    pub config: EngineConfig,
+    pub calibration: ConfidenceCalibration,
+}
+
+impl WidgetSuggestionEngine {
+    pub fn new() -> Self {
+        Self::with_config(EngineConfig::default())
+    }
+
+    pub fn with_config(config: EngineConfig) -> Self {
+        Self {
+            records: Vec::new(),
+            presets: Vec::new(),
+            display_types: HashMap::new(),
+            next_id: 1,
+            config,
+            calibration: ConfidenceCalibration::default(),
+        }
+    }
+
+    pub fn store_widget(&mut self, widget: Widget) {
+        self.absorb_widget(widget);
+    }
+
+    /// Stores many widgets in one pass, e.g. every control of a preset
+    ///
+    /// Returns the ids of the records that were created or updated, in first-touched order.
+    pub fn store_widgets(&mut self, widgets: Vec<Widget>) -> Vec<u64> {
+        let mut touched = Vec::new();
+        for widget in widgets {
+            let id = self.absorb_widget(widget);
+            if !touched.contains(&id) {
+                touched.push(id);
+            }
+        }
+        touched
+    }
+
+    /// `store_widgets`, also returning the records it changed as they were before, e.g. to
+    /// roll back when writing them fails. Records it created are not among them.
+    pub fn store_widgets_keeping_previous(
+        &mut self,
+        widgets: Vec<Widget>,
+    ) -> (Vec<u64>, Vec<WidgetRecord>) {
+        let mut touched = Vec::new();
+        let mut previous: Vec<WidgetRecord> = Vec::new();
+        for widget in widgets {
+            let sanitized = widget.clone().sanitized().with_boolean_values();
+            let features = self.extract_features(&sanitized);
+            if let Some(index) = self.matching_index(&sanitized, &features) {
+                let record = &self.records[index];
+                // Records touched before are either kept already or new in this batch
+                if !touched.contains(&record.id) {
+                    previous.push(record.clone());
+                }
+            }
+
+            let id = self.absorb_widget(widget);
+            if !touched.contains(&id) {
+                touched.push(id);
+            }
+        }
+        (touched, previous)
+    }
+
+    /// Merges `widget` into a matching record or creates a new one, returning the record id
+    fn absorb_widget(&mut self, widget: Widget) -> u64 {
+        let widget = widget.sanitized().with_boolean_values();
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| std::time::Duration::from_secs(0))
+            .as_secs();
+
+        // Extract features
+        let features = self.extract_features(&widget);
+
+        // First, check if we have an exact match by event_id
+        if let Some(event_id) = widget.event_id {
+            for i in 0..self.records.len() {
+                if self.records[i].widget.event_id == Some(event_id) {
+                    // Update existing record with the same event_id
+                    self.records[i].frequency += 1;
+                    self.records[i].last_seen = current_time;
+
+                    // Update label if new one is provided
+                    if widget.label.is_some() && self.records[i].widget.label.is_none() {
+                        self.records[i].widget.label = widget.label.clone();
+                    }
+
+                    // Add new values to the existing values vector
+                    Self::fold_values(&mut self.records[i], &widget);
+
+                    return self.records[i].id;
+                }
+            }
+        }
+
+        // Next, check if we have an exact match by label
+        if let Some(label) = &widget.label {
+            for i in 0..self.records.len() {
+                if let Some(record_label) = &self.records[i].widget.label {
+                    if record_label == label {
+                        // Update existing record with the same label
+                        self.records[i].frequency += 1;
+                        self.records[i].last_seen = current_time;
+
+                        // Update event_id if new one is provided
+                        if widget.event_id.is_some() && self.records[i].widget.event_id.is_none() {
+                            self.records[i].widget.event_id = widget.event_id;
+                        }
+
+                        // Add new values to the existing values vector
+                        Self::fold_values(&mut self.records[i], &widget);
+
+                        return self.records[i].id;
+                    }
+                }
+            }
+        }
+
+        // Finally, check for similar widgets
+        for i in 0..self.records.len() {
+            let similarity = self.calculate_similarity(&features, &self.records[i].features);
+
+            if similarity > self.config.similarity_thresholds.merge {
+                self.records[i].frequency += 1;
+                self.records[i].last_seen = current_time;
+
+                // Update widget if new one has more complete information
+                if widget.label.is_some() && self.records[i].widget.label.is_none() {
+                    self.records[i].widget.label = widget.label.clone();
+                }
+
+                if widget.event_id.is_some() && self.records[i].widget.event_id.is_none() {
+                    self.records[i].widget.event_id = widget.event_id;
+                }
+
+                // Add new values to the existing values vector
+                Self::fold_values(&mut self.records[i], &widget);
+
+                return self.records[i].id;
+            }
+        }
+
+        let id = self.next_id;
+        let value_stats = Some(ValueStats::from_values(&widget.get_values()));
+        self.records.push(WidgetRecord {
+            id,
+            widget,
+            features,
+            frequency: 1,
+            last_seen: current_time,
+            value_stats,
+        });
+        self.next_id += 1;
+        id
+    }
+
+    /// Merges a record learned elsewhere, e.g. in another database
+    ///
+    /// A record matching by event ID, label or similarity like in `store_widget` absorbs
+    /// the incoming frequency and value history; otherwise the record is added under a
+    /// fresh id. Returns the id it ended up under and whether it was merged.
+    pub fn merge_record(&mut self, incoming: WidgetRecord) -> (u64, bool) {
+        let Some(index) = self.find_matching_record(&incoming) else {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.records.push(WidgetRecord { id, ..incoming });
+            return (id, false);
+        };
+
+        let record = &mut self.records[index];
+        record.frequency = record.frequency.saturating_add(incoming.frequency);
+        record.last_seen = record.last_seen.max(incoming.last_seen);
+        if record.widget.label.is_none() {
+            record.widget.label = incoming.widget.label.clone();
+        }
+        if record.widget.event_id.is_none() {
+            record.widget.event_id = incoming.widget.event_id;
+        }
+        Self::fold_values(record, &incoming.widget);
+
+        (record.id, true)
+    }
+
+    /// Index of the record a record learned elsewhere corresponds to, by event ID, then
+    /// label, then similarity
+    pub fn find_matching_record(&self, incoming: &WidgetRecord) -> Option<usize> {
+        self.matching_index(&incoming.widget, &incoming.features)
+    }
+
+    /// Index of the record `widget` with `features` is learned into, as `store_widget`
+    /// matches them
+    fn matching_index(&self, widget: &Widget, features: &WidgetFeatures) -> Option<usize> {
+        let incoming_label = widget.label.as_ref();
+        widget
+            .event_id
+            .and_then(|event_id| {
+                self.records
+                    .iter()
+                    .position(|r| r.widget.event_id == Some(event_id))
+            })
+            .or_else(|| {
+                incoming_label.and_then(|label| {
+                    self.records
+                        .iter()
+                        .position(|r| r.widget.label.as_ref() == Some(label))
+                })
+            })
+            .or_else(|| {
+                self.records.iter().position(|r| {
+                    self.calculate_similarity(features, &r.features)
+                        > self.config.similarity_thresholds.merge
+                })
+            })
+    }
+
+    /// Registers or replaces the prior value for a label token at runtime
+    pub fn register_value_prior(&mut self, token: &str, value: f64) {
+        if value.is_finite() {
+            self.config.value_priors.insert(token.to_lowercase(), value);
+        }
+    }
+
+    /// Folds every observed value of `widget` into an existing record's history
+    fn fold_values(record: &mut WidgetRecord, widget: &Widget) {
+        let mut folded = false;
+        for value in widget.get_values() {
+            if !record.widget.values.contains(&value) {
+                record.widget.values.push(value);
+                // Also add to feature's value_patterns for backward compatibility
+                record.features.value_patterns.push(value);
+                folded = true;
+            }
+            // The history keeps each value once, the statistics count every observation
+            record.observe_value(value);
+        }
+
+        if folded {
+            if let Some(stats) = &mut record.value_stats {
+                stats.update_percentiles(&record.widget.values);
+            }
+        }
+    }
+
+    pub fn store_preset(&mut self, preset: Preset) {
+        // Store or update preset
+        if let Some(existing) = self.presets.iter_mut().find(|p| p.name == preset.name) {
+            existing.usage_count += 1;
+            existing.last_used = preset.last_used;
+            existing.widget_values = preset.widget_values;
+            existing.description = preset.description;
+            // Re-saving without tags keeps the ones already assigned
+            if !preset.tags.is_empty() {
+                existing.tags = preset.tags;
+            }
+            // Likewise a star is only removed through `set_preset_favorite`
+            existing.favorite |= preset.favorite;
+        } else {
+            self.presets.push(preset);
+        }
+    }
+
+    /// Presets carrying every one of `tags`, ignoring case
+    pub fn presets_with_tags(&self, tags: &[&str]) -> Vec<&Preset> {
+        self.presets
+            .iter()
+            .filter(|preset| tags.iter().all(|tag| preset.has_tag(tag)))
+            .collect()
+    }
+
+    /// Adds a tag to a preset, returning `false` if the preset does not exist
+    pub fn tag_preset(&mut self, name: &str, tag: &str) -> bool {
+        let tag = tag.trim();
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(preset) => {
+                if !tag.is_empty() && !preset.has_tag(tag) {
+                    preset.tags.push(tag.to_string());
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a tag from a preset, returning `false` if the preset does not exist
+    pub fn untag_preset(&mut self, name: &str, tag: &str) -> bool {
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(preset) => {
+                preset.tags.retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stars or unstars a preset, returning `false` if the preset does not exist
+    pub fn set_preset_favorite(&mut self, name: &str, favorite: bool) -> bool {
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(preset) => {
+                preset.favorite = favorite;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flips a preset's star, returning the new state or `None` if the preset does not exist
+    pub fn toggle_preset_favorite(&mut self, name: &str) -> Option<bool> {
+        let preset = self.presets.iter_mut().find(|p| p.name == name)?;
+        preset.favorite = !preset.favorite;
+        Some(preset.favorite)
+    }
+
+    /// Counts a use of a preset now, returning `false` if the preset does not exist
+    pub fn mark_preset_used(&mut self, name: &str) -> bool {
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(preset) => {
+                preset.usage_count += 1;
+                preset.last_used = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn favorite_presets(&self) -> Vec<&Preset> {
+        self.presets.iter().filter(|p| p.favorite).collect()
+    }
+
+    pub fn delete_preset(&mut self, name: &str) -> Option<Preset> {
+        let index = self.presets.iter().position(|p| p.name == name)?;
+        Some(self.presets.remove(index))
+    }
+
+    /// Renames a preset, keeping its values and usage statistics
+    ///
+    /// Returns `false` if `old_name` does not exist or `new_name` is already taken.
+    pub fn rename_preset(&mut self, old_name: &str, new_name: &str) -> bool {
+        if old_name == new_name || self.presets.iter().any(|p| p.name == new_name) {
+            return false;
+        }
+
+        match self.presets.iter_mut().find(|p| p.name == old_name) {
+            Some(preset) => {
+                preset.name = new_name.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stores a preset filled with the best suggested value for each event ID
+    ///
+    /// Event IDs without a learned value are left out. Returns `None`, storing nothing,
+    /// if none of them has one.
+    pub fn generate_preset(&mut self, name: &str, event_ids: &[u64]) -> Option<Preset> {
+        let widget_values: Vec<WidgetValue> = event_ids
+            .iter()
+            .filter_map(|&event_id| {
+                let suggestion = self.get_suggestions_by_event_id(event_id, 1).pop()?;
+                Some(WidgetValue {
+                    widget_id: event_id.to_string(),
+                    label: suggestion.widget.label,
+                    value: suggestion.suggested_value?,
+                    confidence: suggestion.value_confidence,
+                })
+            })
+            .collect();
+
+        if widget_values.is_empty() {
+            return None;
+        }
+
+        let preset = Preset {
+            name: name.to_string(),
+            description: Some("Generated from learned suggestions".to_string()),
+            widget_values,
+            created_by: Some(GENERATED_PRESET_AUTHOR.to_string()),
+            usage_count: 1,
+            last_used: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            tags: Vec::new(),
+            favorite: false,
+        };
+        self.store_preset(preset.clone());
+        Some(preset)
+    }
+
+    /// Suggestions for a partially described widget, like `get_suggestions`, with the
+    /// values drawn from the preset named `context` and the presets near it where they
+    /// set the widget. Widgets they do not set, or an unknown preset, fall back to the
+    /// global history.
+    pub fn get_suggestions_with_context(
+        &self,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+        context: &str,
+    ) -> Vec<Suggestion> {
+        let mut suggestions = self.get_suggestions(partial_widget, max_suggestions);
+
+        if let Some(neighborhood) = self.preset_neighborhood_values(context) {
+            for suggestion in &mut suggestions {
+                self.apply_context(suggestion, &neighborhood);
+            }
+        }
+
+        suggestions
+    }
+
+    pub fn get_suggestions(
+        &self,
+        partial_widget: &Widget,
+        max_suggestions: usize,
+    ) -> Vec<Suggestion> {
+        // If the partial widget has a known event_id, use that for suggestions;
+        // unknown ids fall through to label similarity
+        if let Some(event_id) = partial_widget.event_id {
+            if self.has_event_id(event_id) {
+                return self.get_suggestions_by_event_id(event_id, max_suggestions);
+            }
+        }
+
+        let partial_widget = &partial_widget.clone().sanitized();
+        let features = self.extract_features_partial(partial_widget);
+        let mut suggestions = Vec::new();
+
+        // First, try to find widgets with matching label
+        if let Some(label) = &partial_widget.label {
+            for record in &self.records {
+                if let Some(record_label) = &record.widget.label {
+                    if record_label == label {
+                        let (suggested_value, value_confidence, alternative_values) = self
+                            .suggest_values_from_vector(
+                                &record.widget,
+                                record.value_stats.as_slice(),
+                            );
+
+                        let reason = SuggestionReason::MatchedLabel {
+                            label: label.clone(),
+                            score: 1.0,
+                            frequency: record.frequency,
+                        };
+
+                        suggestions.push((
+                            Suggestion {
+                                widget: record.widget.clone(),
+                                confidence: 1.0, // Highest confidence for exact matches
+                                reason,
+                                suggested_value,
+                                value_confidence,
+                                alternative_values,
+                                flip_probability: flip_probability(
+                                    &record.widget,
+                                    record.value_stats.as_slice(),
+                                ),
+                            },
+                            record.frequency,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // If we don't have enough suggestions from exact matches, add similar widgets
+        if suggestions.len() < max_suggestions {
+            for record in &self.records {
+                // Skip records we've already included
+                if suggestions
+                    .iter()
+                    .any(|(s, _)| s.widget.label == record.widget.label)
+                {
+                    continue;
+                }
+
+                let similarity = self.calculate_similarity(&features, &record.features);
+
+                if similarity > self.config.similarity_thresholds.suggestion {
+                    let (suggested_value, value_confidence, alternative_values) = self
+                        .suggest_values_from_vector(&record.widget, record.value_stats.as_slice());
+
+                    let reason = SuggestionReason::SimilarWidget {
+                        label: record.widget.label.clone(),
+                        score: similarity,
+                        frequency: record.frequency,
+                    };
+
+                    suggestions.push((
+                        Suggestion {
+                            widget: record.widget.clone(),
+                            confidence: similarity,
+                            reason,
+                            suggested_value,
+                            value_confidence,
+                            alternative_values,
+                            flip_probability: flip_probability(
+                                &record.widget,
+                                record.value_stats.as_slice(),
+                            ),
+                        },
+                        record.frequency,
+                    ));
+                }
+            }
+        }
+
+        self.rank_suggestions(suggestions, max_suggestions)
+    }
+
+    /// Values per event ID set by the preset named `name` and the presets within
+    /// clustering distance of it, or `None` if there is no such preset
+    fn preset_neighborhood_values(&self, name: &str) -> Option<HashMap<u64, Vec<f64>>> {
+        let context = self.presets.iter().find(|p| p.name == name)?;
+
+        let mut values: HashMap<u64, Vec<f64>> = HashMap::new();
+        for preset in &self.presets {
+            if preset.name != name && preset_distance(context, preset) > PRESET_CLUSTER_DISTANCE {
+                continue;
+            }
+            for widget_value in &preset.widget_values {
+                if let Ok(event_id) = widget_value.widget_id.parse::<u64>() {
+                    values.entry(event_id).or_default().push(widget_value.value);
+                }
+            }
+        }
+
+        Some(values)
+    }
+
+    /// Replaces a suggestion's value with the one typical of the preset neighborhood,
+    /// keeping the global alternatives after the neighborhood's own
+    ///
+    /// Preset values are normalized like learned values, so suggestions stay normalized.
+    fn apply_context(&self, suggestion: &mut Suggestion, neighborhood: &HashMap<u64, Vec<f64>>) {
+        let Some(values) = suggestion
+            .widget
+            .event_id
+            .and_then(|event_id| neighborhood.get(&event_id))
+        else {
+            return;
+        };
+
+        let widget = &suggestion.widget;
+        let kind = widget.effective_value_kind();
+
+        let mut alternatives = quantized_alternatives(kind, values);
+        for value in suggestion.alternative_values.drain(..) {
+            if !alternatives.contains(&value) {
+                alternatives.push(value);
+            }
+        }
+
+        match boolean_majority(kind, boolean_counts(values), widget) {
+            Some((state, flip)) => {
+                suggestion.suggested_value = Some(state);
+                suggestion.flip_probability = Some(flip);
+            }
+            None => {
+                let observations: Vec<(f64, u32)> = values.iter().map(|&v| (v, 1)).collect();
+                suggestion.suggested_value = Some(kind.quantize(self.primary_value(&observations)));
+            }
+        }
+        suggestion.value_confidence = self.calibration.confidence(values.len());
+        suggestion.alternative_values = alternatives;
+    }
+
+    /// A suggestion of `default_value`, normalized, for a widget nothing was learned for
+    /// yet, e.g. from the `defaultValue` of its Kyma description
+    pub fn default_value_suggestion(&self, widget: &Widget, default_value: f64) -> Suggestion {
+        let kind = widget.effective_value_kind();
+        let value = kind.quantize(default_value);
+
+        Suggestion {
+            widget: widget.clone(),
+            confidence: COLD_START_CONFIDENCE,
+            reason: SuggestionReason::DefaultValue {
+                label: widget.label.clone(),
+            },
+            suggested_value: Some(value),
+            value_confidence: COLD_START_CONFIDENCE,
+            alternative_values: vec![value],
+            flip_probability: None,
+        }
+    }
+
+    /// `WidgetRecord::to_description` with the value the engine would suggest for the
+    /// widget as its `defaultValue`, in the widget's own range
+    ///
+    /// Records sharing the event ID are folded into the suggestion like in
+    /// `get_suggestions_by_event_id`. Widgets without a range get no default, as
+    /// suggestions are normalized.
+    pub fn kyma_description(&self, record: &WidgetRecord) -> FilteredWidgetDescription {
+        let mut description = record.to_description();
+
+        let suggested = match record.widget.event_id {
+            Some(event_id) => self
+                .get_suggestions_by_event_id(event_id, 1)
+                .first()
+                .and_then(|suggestion| suggestion.suggested_value),
+            None => {
+                self.suggest_values_from_vector(&record.widget, record.value_stats.as_slice())
+                    .0
+            }
+        };
+        let default_value = match (suggested, record.widget.minimum, record.widget.maximum) {
+            (Some(value), Some(min), Some(max)) if max > min => Some(min + value * (max - min)),
+            _ => None,
+        };
+        if let Some(default_value) = default_value {
+            description.insert("defaultValue".to_string(), default_value.into());
+        }
+        description
+    }
+
+    /// Every learned widget as a Kyma description, one per event ID, in the order the
+    /// widgets were first stored; widgets without an event ID are left out
+    ///
+    /// The result is an array in the format `KymaWidgetExtractor` reads, so the learned
+    /// widgets can be handed back to a host app that already understands it.
+    pub fn export_kyma_json(&self) -> serde_json::Value {
+        let mut seen = HashSet::new();
+        self.records
+            .iter()
+            .filter(|record| {
+                record
+                    .widget
+                    .event_id
+                    .is_some_and(|event_id| seen.insert(event_id))
+            })
+            .map(|record| {
+                serde_json::Value::Object(self.kyma_description(record).into_iter().collect())
+            })
+            .collect()
+    }
+
+    /// Returns true if any stored record carries the given event ID
+    pub fn has_event_id(&self, event_id: u64) -> bool {
+        self.records
+            .iter()
+            .any(|r| r.widget.event_id == Some(event_id) || r.id == event_id)
+    }
+
+    /// Suggestions keyed by Kyma event ID
+    ///
+    /// All records sharing the event ID are folded into a single exact-match
+    /// suggestion whose value is the most frequently observed one, followed by
+    /// similar widgets. Returns an empty list when the event ID is unknown; use
+    /// `get_suggestions` with a label to fall back to label similarity.
+    pub fn get_suggestions_by_event_id(
+        &self,
+        event_id: u64,
+        max_suggestions: usize,
+    ) -> Vec<Suggestion> {
+        // Find records with matching event ID
+        let matching_records: Vec<&WidgetRecord> = self
+            .records
+            .iter()
+            .filter(|r| r.widget.event_id == Some(event_id) || r.id == event_id)
+            .collect();
+
+        let Some(&template) = matching_records.first() else {
+            return Vec::new();
+        };
+
+        let mut suggestions = Vec::new();
+
+        // Aggregate the observed values of every record with this event ID
+        let mut aggregate = template.widget.clone();
+        aggregate.values = matching_records
+            .iter()
+            .flat_map(|record| record.widget.get_values())
+            .collect();
+        let total_frequency = matching_records
+            .iter()
+            .fold(0u32, |total, r| total.saturating_add(r.frequency));
+
+        let stats: Vec<ValueStats> = matching_records
+            .iter()
+            .filter_map(|record| record.value_stats.clone())
+            .collect();
+        let (suggested_value, value_confidence, alternative_values) =
+            self.suggest_values_from_vector(&aggregate, &stats);
+
+        let reason = SuggestionReason::MatchedEventId {
+            event_id,
+            label: aggregate.label.clone(),
+        };
+
+        suggestions.push((
+            Suggestion {
+                flip_probability: flip_probability(&aggregate, &stats),
+                widget: aggregate,
+                confidence: 1.0, // Highest confidence for exact matches
+                reason,
+                suggested_value,
+                value_confidence,
+                alternative_values,
+            },
+            total_frequency,
+        ));
+
+        // If we don't have enough suggestions from exact matches, add similar widgets
+        if suggestions.len() < max_suggestions {
+            // Use the first matching record as a template for finding similar widgets
+            let features = &template.features;
+
+            for record in &self.records {
+                // Skip records we've already included
+                if record.widget.event_id == Some(event_id) || record.id == event_id {
+                    continue;
+                }
+
+                let similarity = self.calculate_similarity(features, &record.features);
+
+                if similarity > self.config.similarity_thresholds.event_id_suggestion {
+                    let (suggested_value, value_confidence, alternative_values) = self
+                        .suggest_values_from_vector(&record.widget, record.value_stats.as_slice());
+
+                    let reason = SuggestionReason::SimilarToEventId {
+                        event_id,
+                        label: template.widget.label.clone(),
+                        score: similarity,
+                    };
+
+                    suggestions.push((
+                        Suggestion {
+                            widget: record.widget.clone(),
+                            confidence: similarity,
+                            reason,
+                            suggested_value,
+                            value_confidence,
+                            alternative_values,
+                            flip_probability: flip_probability(
+                                &record.widget,
+                                record.value_stats.as_slice(),
+                            ),
+                        },
+                        record.frequency,
+                    ));
+                }
+            }
+        }
+
+        self.rank_suggestions(suggestions, max_suggestions)
+    }
+
+    /// Orders suggestions by confidence plus a log-scaled frequency bonus, so a
+    /// frequently observed record outranks a one-off at equal similarity.
+    /// Exact label and event ID matches always lead.
+    fn rank_suggestions(
+        &self,
+        suggestions: Vec<(Suggestion, u32)>,
+        max_suggestions: usize,
+    ) -> Vec<Suggestion> {
+        let mut ranked: Vec<(bool, f64, Suggestion)> = suggestions
+            .into_iter()
+            .map(|(suggestion, frequency)| {
+                let exact = matches!(
+                    suggestion.reason,
+                    SuggestionReason::MatchedLabel { .. } | SuggestionReason::MatchedEventId { .. }
+                );
+                let score = suggestion.confidence
+                    + self.config.frequency_weight * (frequency as f64).ln_1p();
+                (exact, score, suggestion)
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)));
+        ranked.truncate(max_suggestions);
+        ranked
+            .into_iter()
+            .map(|(_, _, suggestion)| suggestion)
+            .collect()
+    }
+
+    pub fn get_record(&self, id: u64) -> Option<&WidgetRecord> {
+        self.records.iter().find(|r| r.id == id)
+    }
+
+    /// Relabels or otherwise updates a record, recomputing its features while keeping
+    /// its value history, frequency and statistics
+    pub fn update_record(&mut self, id: u64, update: RecordUpdate) -> Option<&WidgetRecord> {
+        let index = self.records.iter().position(|r| r.id == id)?;
+
+        let mut widget = self.records[index].widget.clone();
+        if update.label.is_some() {
+            widget.label = update.label;
+        }
+        if update.display_type.is_some() {
+            widget.display_type = update.display_type;
+        }
+        if update.minimum.is_some() {
+            widget.minimum = update.minimum;
+        }
+        if update.maximum.is_some() {
+            widget.maximum = update.maximum;
+        }
+        if update.event_id.is_some() {
+            widget.event_id = update.event_id;
+        }
+        let widget = widget.sanitized().with_boolean_values();
+
+        let features = self.extract_features(&widget);
+        let record = &mut self.records[index];
+        // Statistics count repeated observations the history keeps once; they are only
+        // rebuilt if the values themselves changed, e.g. snapped to a switch's states
+        let values_changed = record.widget.get_values() != widget.get_values();
+        record.widget = widget;
+        record.features = features;
+        if values_changed || record.value_stats.is_none() {
+            record.refresh_value_stats();
+        }
+
+        Some(record)
+    }
+
+    /// Carries learned records over to the event IDs of a recompiled sound
+    ///
+    /// `previous_event_ids` are the event IDs of the sound before it was recompiled and
+    /// `layout` holds every widget of it as it is now. Previous IDs that records were
+    /// learned under and that are missing from `layout` are orphaned; IDs of other sounds
+    /// are never touched. Each orphan is matched to the most similar widget of `layout` no
+    /// record has learned yet, by label, range and display type, and its records and
+    /// preset values are rewritten to the new ID. Matches scoring below `min_score` are
+    /// left alone, and each widget takes over at most one old ID.
+    pub fn remap_event_ids(
+        &mut self,
+        previous_event_ids: &[u64],
+        layout: &[Widget],
+        min_score: f64,
+    ) -> Vec<EventIdRemap> {
+        let layout_ids: HashSet<u64> = layout.iter().filter_map(|w| w.event_id).collect();
+        let learned_ids: HashSet<u64> = self
+            .records
+            .iter()
+            .filter_map(|r| r.widget.event_id)
+            .collect();
+        let previous_ids: HashSet<u64> = previous_event_ids.iter().copied().collect();
+
+        let new_widgets: Vec<(u64, &Widget)> = layout
+            .iter()
+            .filter_map(|widget| Some((widget.event_id?, widget)))
+            .filter(|(event_id, _)| !learned_ids.contains(event_id))
+            .collect();
+        let mut orphaned: Vec<u64> = learned_ids
+            .intersection(&previous_ids)
+            .filter(|event_id| !layout_ids.contains(event_id))
+            .copied()
+            .collect();
+        orphaned.sort_unstable();
+
+        let mut candidates = Vec::new();
+        for &old_event_id in &orphaned {
+            for &(new_event_id, widget) in &new_widgets {
+                let score = self
+                    .records
+                    .iter()
+                    .filter(|r| r.widget.event_id == Some(old_event_id))
+                    .map(|r| self.remap_score(widget, &r.widget))
+                    .fold(0.0, f64::max);
+                if score >= min_score {
+                    candidates.push((score, old_event_id, new_event_id, widget.label.clone()));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut taken_old = HashSet::new();
+        let mut taken_new = HashSet::new();
+        let mut remaps = Vec::new();
+        for (score, old_event_id, new_event_id, label) in candidates {
+            if taken_old.contains(&old_event_id) || !taken_new.insert(new_event_id) {
+                continue;
+            }
+            taken_old.insert(old_event_id);
+
+            let record_ids: Vec<u64> = self
+                .records
+                .iter()
+                .filter(|r| r.widget.event_id == Some(old_event_id))
+                .map(|r| r.id)
+                .collect();
+            for &id in &record_ids {
+                self.update_record(
+                    id,
+                    RecordUpdate {
+                        event_id: Some(new_event_id),
+                        ..Default::default()
+                    },
+                );
+            }
+
+            let (old_id, new_id) = (old_event_id.to_string(), new_event_id.to_string());
+            let mut presets = Vec::new();
+            for preset in &mut self.presets {
+                let mut rewritten = false;
+                for widget_value in &mut preset.widget_values {
+                    if widget_value.widget_id == old_id {
+                        widget_value.widget_id = new_id.clone();
+                        rewritten = true;
+                    }
+                }
+                if rewritten {
+                    presets.push(preset.name.clone());
+                }
+            }
+
+            log::info!(
+                "Remapped event ID {old_event_id} to {new_event_id} ({:?}, score {score:.2})",
+                label
+            );
+            remaps.push(EventIdRemap {
+                old_event_id,
+                new_event_id,
+                label,
+                record_ids,
+                presets,
+                score,
+                timestamp,
+            });
+        }
+
+        remaps
+    }
+
+    /// Similarity of a widget to a record's widget by label, range and display type only,
+    /// as values and generation flags say nothing about identity
+    fn remap_score(&self, widget: &Widget, recorded: &Widget) -> f64 {
+        let breakdown = self.explain_similarity(widget, recorded);
+        let display_type = if widget.display_type.is_none() && recorded.display_type.is_none() {
+            1.0
+        } else {
+            breakdown.display_type.score
+        };
+
+        let weights = &self.config.similarity_weights;
+        let total_weight = weights.label + weights.range + weights.display_type;
+        if total_weight <= 0.0 {
+            return 0.0;
+        }
+        (breakdown.label.contribution
+            + breakdown.range.contribution
+            + display_type * weights.display_type)
+            / total_weight
+    }
+
+    pub fn remove_record(&mut self, id: u64) -> Option<WidgetRecord> {
+        let index = self.records.iter().position(|r| r.id == id)?;
+        Some(self.records.remove(index))
+    }
+
+    /// Removes every record whose label matches `pattern`, a case-insensitive glob where
+    /// `*` matches any run of characters
+    pub fn remove_records_by_label(&mut self, pattern: &str) -> Vec<WidgetRecord> {
+        let pattern = pattern.to_lowercase();
+        let (removed, kept) = std::mem::take(&mut self.records)
+            .into_iter()
+            .partition(|record| {
+                record
+                    .widget
+                    .label
+                    .as_ref()
+                    .is_some_and(|label| glob_matches(&pattern, &label.to_lowercase()))
+            });
+        self.records = kept;
+        removed
+    }
+
+    /// Evicts records until `limits` hold, least recently seen first and the least
+    /// frequently used among equally recent ones
+    pub fn enforce_capacity(&mut self, limits: &CapacityLimits) -> EvictionReport {
+        if limits.is_unbounded() {
+            return EvictionReport {
+                records_before: self.records.len(),
+                records_after: self.records.len(),
+                ..Default::default()
+            };
+        }
+
+        let sizes: HashMap<u64, usize> = self
+            .records
+            .iter()
+            .map(|record| (record.id, record.encoded_size()))
+            .collect();
+        let bytes_before: usize = sizes.values().sum();
+        let mut report = EvictionReport {
+            records_before: self.records.len(),
+            records_after: self.records.len(),
+            bytes_before,
+            bytes_after: bytes_before,
+            ..Default::default()
+        };
+
+        let mut candidates: Vec<&WidgetRecord> = self.records.iter().collect();
+        candidates.sort_by(|a, b| {
+            a.last_seen
+                .cmp(&b.last_seen)
+                .then(a.frequency.cmp(&b.frequency))
+                .then(a.id.cmp(&b.id))
+        });
+
+        let over = |count: usize, bytes: usize| {
+            limits.max_records.is_some_and(|max| count > max)
+                || limits.max_bytes.is_some_and(|max| bytes > max)
+        };
+        for record in candidates {
+            if !over(report.records_after, report.bytes_after) {
+                break;
+            }
+            report.evicted.push(record.id);
+            report.records_after -= 1;
+            report.bytes_after -= sizes[&record.id];
+        }
+
+        if !report.evicted.is_empty() {
+            let evicted: HashSet<u64> = report.evicted.iter().copied().collect();
+            self.records.retain(|record| !evicted.contains(&record.id));
+        }
+        report
+    }
+
+    /// Forgets records that expired under `policy`, returning their ids
+    pub fn apply_retention(&mut self, policy: &RetentionPolicy) -> Vec<u64> {
+        let Some(max_age) = policy.max_age else {
+            return Vec::new();
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| std::time::Duration::from_secs(0))
+            .as_secs();
+        let cutoff = now.saturating_sub(max_age.as_secs());
 
-                        // Update event_id if new one is provided
-                        if widget.event_id.is_some() && self.records[i].widget.event_id.is_none() {
-                            self.records[i].widget.event_id = widget.event_id;
-                        }
+        let preset_ids: HashSet<u64> = if policy.keep_preset_widgets {
+            self.presets
+                .iter()
+                .flat_map(|preset| &preset.widget_values)
+                .filter_map(|widget_value| widget_value.widget_id.parse::<u64>().ok())
+                .collect()
+        } else {
+            HashSet::new()
+        };
 
-                        // Add new values to the existing values vector
-                        for &value in &widget.values {
-                            if !self.records[i].widget.values.contains(&value) {
-                                self.records[i].widget.values.push(value);
-                                // Also add to feature's value_patterns for backward compatibility
-                                self.records[i].features.value_patterns.push(value);
-                            }
-                        }
+        let mut expired = Vec::new();
+        self.records.retain(|record| {
+            let in_preset = preset_ids.contains(&record.id)
+                || record
+                    .widget
+                    .event_id
+                    .is_some_and(|event_id| preset_ids.contains(&event_id));
+            let keep = record.last_seen >= cutoff || in_preset;
+            if !keep {
+                expired.push(record.id);
+            }
+            keep
+        });
 
-                        return;
-                    }
-                }
+        expired
+    }
+
+    /// Forgets records not seen within `older_than` whose frequency is below `min_frequency`
+    ///
+    /// Returns the ids of the removed records.
+    pub fn prune_stale(&mut self, older_than: std::time::Duration, min_frequency: u32) -> Vec<u64> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| std::time::Duration::from_secs(0))
+            .as_secs();
+        let cutoff = now.saturating_sub(older_than.as_secs());
+
+        let mut pruned = Vec::new();
+        self.records.retain(|record| {
+            let stale = record.last_seen < cutoff && record.frequency < min_frequency;
+            if stale {
+                pruned.push(record.id);
             }
-        }
+            !stale
+        });
 
-        // Finally, check for similar widgets
-        let mut found_similar = false;
+        pruned
+    }
 
-        for i in 0..self.records.len() {
-            let similarity = self.calculate_similarity(&features, &self.records[i].features);
+    /// Reinforces an accepted value or attenuates a rejected one for the record with `record_id`
+    ///
+    /// An accepted value counts as another observation. A rejected value takes back one
+    /// observation of its nearest value within tolerance, which leaves the history once it
+    /// is no longer observed; the last value is kept. Returns `None` for unknown records.
+    pub fn record_feedback(
+        &mut self,
+        record_id: u64,
+        suggested_value: f64,
+        accepted: bool,
+    ) -> Option<FeedbackEntry> {
+        if !suggested_value.is_finite() {
+            return None;
+        }
 
-            if similarity > 0.85 {
-                self.records[i].frequency += 1;
-                self.records[i].last_seen = current_time;
+        let index = self.records.iter().position(|r| r.id == record_id)?;
+        let sample_count = self.observed_positions(&self.records[index].widget).len();
+        if sample_count > 0 {
+            self.calibration.record(sample_count, accepted);
+        }
 
-                // Update widget if new one has more complete information
-                if widget.label.is_some() && self.records[i].widget.label.is_none() {
-                    self.records[i].widget.label = widget.label.clone();
+        let record = &mut self.records[index];
+        if accepted {
+            // The history keeps each value once, the statistics count every observation
+            let new_value = !record.widget.values.contains(&suggested_value);
+            if new_value {
+                record.widget.values.push(suggested_value);
+                record.features.value_patterns.push(suggested_value);
+            }
+            record.observe_value(suggested_value);
+            if new_value {
+                if let Some(stats) = &mut record.value_stats {
+                    stats.update_percentiles(&record.widget.values);
                 }
-
-                if widget.event_id.is_some() && self.records[i].widget.event_id.is_none() {
-                    self.records[i].widget.event_id = widget.event_id;
+            }
+        } else if let Some(index) = nearest_index(&record.widget.values, suggested_value) {
+            let value = record.widget.values[index];
+            let observed = record
+                .value_stats
+                .as_ref()
+                .and_then(|stats| stats.frequency_map.get(&format!("{value:.2}")).copied())
+                .unwrap_or(1);
+            // The last value of the history is kept
+            if observed > 1 || record.widget.values.len() > 1 {
+                if let Some(stats) = &mut record.value_stats {
+                    stats.forget(value);
                 }
-
-                // Add new values to the existing values vector
-                for &value in &widget.values {
-                    if !self.records[i].widget.values.contains(&value) {
-                        self.records[i].widget.values.push(value);
-                        // Also add to feature's value_patterns for backward compatibility
-                        self.records[i].features.value_patterns.push(value);
+                if observed <= 1 {
+                    record.widget.values.remove(index);
+                    if let Some(index) = nearest_index(&record.features.value_patterns, value) {
+                        record.features.value_patterns.remove(index);
+                    }
+                    if let Some(stats) = &mut record.value_stats {
+                        stats.update_percentiles(&record.widget.values);
                     }
                 }
-
-                found_similar = true;
-                break;
             }
         }
 
-        if !found_similar {
-            let record = WidgetRecord {
-                id: self.next_id,
-                widget,
-                features,
-                frequency: 1,
-                last_seen: current_time,
-                value_stats: None,
-            };
-            self.records.push(record);
-            self.next_id += 1;
-        }
+        Some(FeedbackEntry {
+            record_id,
+            suggested_value,
+            accepted,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        })
     }
 
-    pub fn store_preset(&mut self, preset: Preset) {
-        // Store or update preset
-        if let Some(existing) = self.presets.iter_mut().find(|p| p.name == preset.name) {
-            existing.usage_count += 1;
-            existing.last_used = preset.last_used;
-            existing.widget_values = preset.widget_values;
-            existing.description = preset.description;
-        } else {
-            self.presets.push(preset);
+    /// Observed values of `widget` paired with their fader positions, outliers removed
+    fn observed_positions(&self, widget: &Widget) -> Vec<(f64, f64)> {
+        let taper = widget.taper.unwrap_or(Taper::Linear);
+
+        // Statistics are taken on perceptual fader positions, so a log-taper cutoff
+        // suggests a sensible position rather than a linear midpoint
+        let observed: Vec<(f64, f64)> = widget
+            .get_values()
+            .into_iter()
+            .map(|v| (v, taper.to_position(v, widget.minimum, widget.maximum)))
+            .collect();
+        let positions: Vec<f64> = observed.iter().map(|&(_, p)| p).collect();
+
+        match self.outlier_bounds(&positions) {
+            Some((low, high)) => observed
+                .into_iter()
+                .filter(|&(_, p)| p >= low && p <= high)
+                .collect(),
+            None => observed,
         }
     }
 
-    pub fn get_suggestions(
+    /// Suggest values based on the widget's values vector. `stats` are the statistics of
+    /// the records behind it, which count repeated observations that `widget.values`
+    /// keeps once.
+    fn suggest_values_from_vector(
         &self,
-        partial_widget: &Widget,
-        max_suggestions: usize,
-    ) -> Vec<Suggestion> {
-        // If the partial widget has an event_id, use that for suggestions
-        if let Some(event_id) = partial_widget.event_id {
-            return self.get_suggestions_by_event_id(event_id, max_suggestions);
+        widget: &Widget,
+        stats: &[ValueStats],
+    ) -> (Option<f64>, f64, Vec<f64>) {
+        let kind = widget.effective_value_kind();
+        let taper = widget.taper.unwrap_or(Taper::Linear);
+
+        let kept = self.observed_positions(widget);
+        let values: Vec<f64> = kept.iter().map(|&(v, _)| v).collect();
+
+        if values.is_empty() {
+            // Default fallback
+            return (None, 0.3, quantized_alternatives(kind, &[0.5, 0.3, 0.7]));
         }
 
-        let features = self.extract_features_partial(partial_widget);
-        let mut suggestions = Vec::new();
+        // Confidence grows with the number of observed values, calibrated by feedback
+        let confidence = self.calibration.confidence(values.len());
+
+        // Switches and stepped widgets only suggest values they can actually take. A switch
+        // suggests its majority state over every observation; outlier rejection would
+        // discard the minority state altogether.
+        let primary_value = match boolean_majority(kind, observed_states(widget, stats), widget) {
+            Some((state, _)) => state,
+            None => {
+                // Each distinct value stands for every time it was observed
+                let counts = observation_counts(&values, stats);
+                let positions: Vec<(f64, u32)> =
+                    kept.iter().map(|&(_, p)| p).zip(counts).collect();
+                let primary_position = self.primary_value(&positions);
+                kind.quantize(taper.from_position(primary_position, widget.minimum, widget.maximum))
+            }
+        };
 
-        // First, try to find widgets with matching label
-        if let Some(label) = &partial_widget.label {
-            for record in &self.records {
-                if let Some(record_label) = &record.widget.label {
-                    if record_label == label {
-                        let (suggested_value, value_confidence, alternative_values) =
-                            self.suggest_values_from_vector(&record.widget);
+        // Return the most common value and all unique values
+        let unique_values = quantized_alternatives(kind, &values);
 
-                        let reason = format!(
-                            "Exact label match for '{}' (frequency: {})",
-                            label,
-                            record.frequency
-                        );
+        (Some(primary_value), confidence, unique_values)
+    }
 
-                        suggestions.push(Suggestion {
-                            widget: record.widget.clone(),
-                            confidence: 1.0,  // Highest confidence for exact matches
-                            reason,
-                            suggested_value,
-                            value_confidence,
-                            alternative_values,
-                        });
-                    }
-                }
-            }
+    /// Records whether the value of `suggestion` was accepted, calibrating future confidence
+    pub fn record_suggestion_outcome(&mut self, suggestion: &Suggestion, accepted: bool) {
+        let sample_count = self.observed_positions(&suggestion.widget).len();
+        if sample_count > 0 {
+            self.calibration.record(sample_count, accepted);
         }
+    }
 
-        // If we don't have enough suggestions from exact matches, add similar widgets
-        if suggestions.len() < max_suggestions {
-            for record in &self.records {
-                // Skip records we've already included
-                if suggestions.iter().any(|s| s.widget.label == record.widget.label) {
-                    continue;
-                }
+    /// Statistics of a record's observations without the outliers the configured filter
+    /// finds in its history, for reporting like suggestions are made. The record keeps
+    /// every observation.
+    pub fn value_stats(&self, record: &WidgetRecord) -> ValueStats {
+        self.filtered_stats(record).0
+    }
 
-                let similarity = self.calculate_similarity(&features, &record.features);
+    /// `value_stats` over every record learned for `event_id`, e.g. one matched by label
+    /// and one by event ID before they were known to be the same widget; `None` when no
+    /// record has the event ID
+    pub fn event_value_stats(&self, event_id: u64) -> Option<ValueStats> {
+        let mut records = self
+            .records
+            .iter()
+            .filter(|r| r.widget.event_id == Some(event_id));
+        let (mut stats, mut values) = self.filtered_stats(records.next()?);
+        for record in records {
+            let (other, other_values) = self.filtered_stats(record);
+            stats.merge(&other);
+            values.extend(other_values);
+        }
+        stats.update_percentiles(&values);
+        Some(stats)
+    }
 
-                if similarity > 0.3 {
-                    let (suggested_value, value_confidence, alternative_values) =
-                        self.suggest_values_from_vector(&record.widget);
-
-                    let reason = format!(
-                        "Similar to {} (similarity: {:.2}, frequency: {})",
-                        record.widget.label.as_deref().unwrap_or("unnamed widget"),
-                        similarity,
-                        record.frequency
-                    );
-
-                    suggestions.push(Suggestion {
-                        widget: record.widget.clone(),
-                        confidence: similarity,
-                        reason,
-                        suggested_value,
-                        value_confidence,
-                        alternative_values,
-                    });
-                }
-            }
+    /// `value_stats` together with the distinct values they were taken over
+    fn filtered_stats(&self, record: &WidgetRecord) -> (ValueStats, Vec<f64>) {
+        let values = record.widget.get_values();
+        let raw = match &record.value_stats {
+            Some(stats) => stats.clone(),
+            None => ValueStats::from_values(&values),
+        };
+
+        // Outliers are found on fader positions like for suggestions; as tapers are
+        // monotonic, the values kept span a range in value terms as well
+        let kept: Vec<f64> = self
+            .observed_positions(&record.widget)
+            .into_iter()
+            .map(|(v, _)| v)
+            .collect();
+        if kept.is_empty() || kept.len() == values.len() {
+            return (raw, values);
         }
+        let low = kept.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = kept.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        (raw.within(low, high, &values), kept)
+    }
 
-        suggestions.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
-        suggestions.truncate(max_suggestions);
-        suggestions
+    /// Removes outliers according to the configured filter, preserving order
+    pub fn filter_outliers(&self, values: &[f64]) -> Vec<f64> {
+        match self.outlier_bounds(values) {
+            Some((low, high)) => values
+                .iter()
+                .copied()
+                .filter(|v| *v >= low && *v <= high)
+                .collect(),
+            None => values.to_vec(),
+        }
     }
 
-    pub fn get_suggestions_by_event_id(
-        &self,
-        event_id: u64,
-        max_suggestions: usize,
-    ) -> Vec<Suggestion> {
-        // Find records with matching event ID
-        let matching_records: Vec<&WidgetRecord> = self.records.iter()
-            .filter(|r| r.widget.event_id == Some(event_id) || r.id == event_id)
-            .collect();
+    /// Inclusive bounds outside which values count as outliers, if filtering applies
+    fn outlier_bounds(&self, values: &[f64]) -> Option<(f64, f64)> {
+        if values.len() < MIN_VALUES_FOR_OUTLIER_FILTER {
+            return None;
+        }
 
-        if matching_records.is_empty() {
-            // If no exact match, fall back to regular suggestions
-            if let Some(first_record) = self.records.first() {
-                return self.get_suggestions(&first_record.widget, max_suggestions);
-            } else {
-                return Vec::new();
+        let bounds = match self.config.outlier_filter {
+            OutlierFilter::None => return None,
+            OutlierFilter::Iqr { multiplier } => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(f64::total_cmp);
+
+                // Tukey's hinges: medians of the lower and upper halves
+                let half = sorted.len() / 2;
+                let q1 = median(&sorted[..half]);
+                let q3 = median(&sorted[sorted.len() - half..]);
+                let iqr = q3 - q1;
+                (q1 - multiplier * iqr, q3 + multiplier * iqr)
             }
-        }
+            OutlierFilter::ZScore { threshold } => {
+                let n = values.len() as f64;
+                let mean = values.iter().sum::<f64>() / n;
+                let std_dev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+                if std_dev == 0.0 {
+                    return None;
+                }
+                (mean - threshold * std_dev, mean + threshold * std_dev)
+            }
+        };
 
-        let mut suggestions = Vec::new();
+        Some(bounds)
+    }
 
-        // First, process exact matches
-        for &record in &matching_records {
-            // For exact event ID matches, use the observed values directly
-            let (suggested_value, value_confidence, alternative_values) =
-                self.suggest_values_from_vector(&record.widget);
+    /// Picks the primary suggested value from an observation history of values and how
+    /// many times each was observed
+    fn primary_value(&self, observations: &[(f64, u32)]) -> f64 {
+        let mut sorted: Vec<f64> = observations.iter().map(|&(v, _)| v).collect();
+        sorted.sort_by(f64::total_cmp);
+
+        match self.config.value_strategy {
+            ValueStrategy::Lowest => sorted[0],
+            ValueStrategy::Median => median(&sorted),
+            ValueStrategy::Mode { bin_width } => {
+                let bin_width = if bin_width > 0.0 { bin_width } else { 1e-4 };
+
+                // Group values into bins weighted by their observation counts;
+                // BTreeMap keeps tie-breaking deterministic
+                let mut bins: BTreeMap<i64, (u64, f64)> = BTreeMap::new();
+                for &(value, count) in observations {
+                    let count = u64::from(count.max(1));
+                    let bin = bins.entry((value / bin_width).round() as i64).or_default();
+                    bin.0 += count;
+                    bin.1 += value * count as f64;
+                }
 
-            let reason = format!(
-                "Exact match for event ID {} ({})",
-                event_id,
-                record.widget.label.as_deref().unwrap_or("unnamed widget")
-            );
+                let median_value = median(&sorted);
+                let mode_bin = bins
+                    .values()
+                    .filter(|(count, _)| *count > 1)
+                    .map(|&(count, sum)| (count, sum / count as f64))
+                    .max_by(|a, b| {
+                        a.0.cmp(&b.0).then(
+                            // Prefer the bin nearest the median on ties
+                            (b.1 - median_value)
+                                .abs()
+                                .total_cmp(&(a.1 - median_value).abs()),
+                        )
+                    });
 
-            suggestions.push(Suggestion {
-                widget: record.widget.clone(),
-                confidence: 1.0,  // Highest confidence for exact matches
-                reason,
-                suggested_value,
-                value_confidence,
-                alternative_values,
-            });
+                // Without any repeated observation there is no mode; use the median instead
+                mode_bin.map_or(median_value, |(_, mean)| mean)
+            }
         }
+    }
 
-        // If we don't have enough suggestions from exact matches, add similar widgets
-        if suggestions.len() < max_suggestions {
-            // Use the first matching record as a template for finding similar widgets
-            if let Some(&template) = matching_records.first() {
-                let features = &template.features;
+    /// Every preset setting a control whose label resembles the widget's, most relevant
+    /// first: by similarity, then favorites, then usage
+    pub fn get_preset_insights(&self, widget: &Widget) -> Vec<PresetInsight> {
+        let Some(label) = &widget.label else {
+            return Vec::new();
+        };
 
-                for record in &self.records {
-                    // Skip records we've already included
-                    if record.widget.event_id == Some(event_id) || record.id == event_id {
-                        continue;
-                    }
+        let mut insights: Vec<PresetInsight> = self
+            .presets
+            .iter()
+            .filter_map(|preset| {
+                // The preset's closest control stands for the whole preset
+                let (similarity, widget_value) = preset
+                    .widget_values
+                    .iter()
+                    .filter_map(|widget_value| {
+                        let preset_label = widget_value.label.as_ref()?;
+                        Some((jaro_winkler(label, preset_label), widget_value))
+                    })
+                    .filter(|(similarity, _)| *similarity > PRESET_INSIGHT_THRESHOLD)
+                    .max_by(|a, b| a.0.total_cmp(&b.0))?;
+
+                Some(PresetInsight {
+                    preset: preset.name.clone(),
+                    label: widget_value.label.clone().unwrap_or_default(),
+                    value: widget_value.value,
+                    usage_count: preset.usage_count,
+                    similarity,
+                    favorite: preset.favorite,
+                })
+            })
+            .collect();
 
-                    let similarity = self.calculate_similarity(features, &record.features);
+        insights.sort_by(|a, b| {
+            b.similarity
+                .total_cmp(&a.similarity)
+                .then_with(|| b.favorite.cmp(&a.favorite))
+                .then_with(|| b.usage_count.cmp(&a.usage_count))
+                .then_with(|| a.preset.cmp(&b.preset))
+        });
+        insights
+    }
 
-                    if similarity > 0.5 {  // Higher threshold for event ID-based suggestions
-                        let (suggested_value, value_confidence, alternative_values) =
-                            self.suggest_values_from_vector(&record.widget);
+    /// Groups stored presets by the similarity of their widget values
+    pub fn get_preset_clusters(&self) -> Vec<PresetCluster> {
+        self.cluster_presets(PRESET_CLUSTER_DISTANCE)
+    }
 
-                        let reason = format!(
-                            "Similar to event ID {} ({}) with similarity {:.2}",
-                            event_id,
-                            template.widget.label.as_deref().unwrap_or("unnamed widget"),
-                            similarity
-                        );
+    /// Agglomerative clustering with average linkage, merging clusters while their
+    /// average RMS distance is at most `max_distance`
+    pub fn cluster_presets(&self, max_distance: f64) -> Vec<PresetCluster> {
+        let n = self.presets.len();
+        let distances: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| preset_distance(&self.presets[i], &self.presets[j]))
+                    .collect()
+            })
+            .collect();
 
-                        suggestions.push(Suggestion {
-                            widget: record.widget.clone(),
-                            confidence: similarity,
-                            reason,
-                            suggested_value,
-                            value_confidence,
-                            alternative_values,
-                        });
+        let mut clusters: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+        loop {
+            let mut closest: Option<(usize, usize, f64)> = None;
+            for a in 0..clusters.len() {
+                for b in a + 1..clusters.len() {
+                    let total: f64 = clusters[a]
+                        .iter()
+                        .flat_map(|&i| clusters[b].iter().map(move |&j| (i, j)))
+                        .map(|(i, j)| distances[i][j])
+                        .sum();
+                    let linkage = total / (clusters[a].len() * clusters[b].len()) as f64;
+
+                    if linkage <= max_distance && closest.is_none_or(|(_, _, d)| linkage < d) {
+                        closest = Some((a, b, linkage));
                     }
                 }
             }
+
+            let Some((a, b, _)) = closest else {
+                break;
+            };
+            let merged = clusters.remove(b);
+            clusters[a].extend(merged);
         }
 
-        suggestions.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
-        suggestions.truncate(max_suggestions);
-        suggestions
+        let all: Vec<&Preset> = self.presets.iter().collect();
+        let overall = preset_centroid(&all);
+
+        let mut result: Vec<PresetCluster> = clusters
+            .into_iter()
+            .map(|members| {
+                let members: Vec<&Preset> = members.iter().map(|&i| &self.presets[i]).collect();
+                let centroid = preset_centroid(&members);
+
+                // Name the cluster after its most distinctive widget
+                let label = centroid
+                    .iter()
+                    .filter_map(|(id, value)| overall.get(id).map(|mean| (id, value - mean)))
+                    .filter(|(_, deviation)| deviation.abs() > f64::EPSILON)
+                    .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()).then(b.0.cmp(a.0)))
+                    .map(|(id, deviation)| {
+                        let name = members
+                            .iter()
+                            .flat_map(|p| &p.widget_values)
+                            .find(|v| &v.widget_id == id)
+                            .and_then(|v| v.label.clone())
+                            .unwrap_or_else(|| id.clone());
+                        let direction = if deviation > 0.0 { "high" } else { "low" };
+                        format!("{name} {direction}")
+                    })
+                    .unwrap_or_else(|| "typical".to_string());
+
+                let mut presets: Vec<String> = members.iter().map(|p| p.name.clone()).collect();
+                presets.sort();
+
+                PresetCluster {
+                    presets,
+                    centroid,
+                    label,
+                }
+            })
+            .collect();
+
+        result.sort_by(|a, b| {
+            b.presets
+                .len()
+                .cmp(&a.presets.len())
+                .then_with(|| a.presets.cmp(&b.presets))
+        });
+        result
     }
 
-    /// Suggest values based on the widget's values vector
-    fn suggest_values_from_vector(&self, widget: &Widget) -> (Option<f64>, f64, Vec<f64>) {
-        let values = widget.get_values();
+    /// Ranks stored presets by proximity to the current value of each event ID
+    ///
+    /// Widgets of a preset that are missing from `current_state` count as the largest
+    /// difference, so a preset only matches well if the live state covers it.
+    pub fn recommend_presets(
+        &self,
+        current_state: &HashMap<u64, f64>,
+        k: usize,
+    ) -> Vec<PresetRecommendation> {
+        let mut recommendations: Vec<PresetRecommendation> = self
+            .presets
+            .iter()
+            .filter(|preset| !preset.widget_values.is_empty())
+            .filter_map(|preset| {
+                let mut matched_widgets = 0;
+                let squared: f64 = preset
+                    .widget_values
+                    .iter()
+                    .map(|widget_value| {
+                        let current = widget_value
+                            .widget_id
+                            .parse::<u64>()
+                            .ok()
+                            .and_then(|id| current_state.get(&id))
+                            .filter(|v| v.is_finite());
+                        match current {
+                            Some(current) => {
+                                matched_widgets += 1;
+                                (widget_value.value - current).powi(2)
+                            }
+                            None => 1.0,
+                        }
+                    })
+                    .sum();
+
+                (matched_widgets > 0).then(|| PresetRecommendation {
+                    preset: preset.name.clone(),
+                    distance: (squared / preset.widget_values.len() as f64).sqrt(),
+                    matched_widgets,
+                    favorite: preset.favorite,
+                })
+            })
+            .collect();
 
-        if values.is_empty() {
-            return (None, 0.3, vec![0.5, 0.3, 0.7]);  // Default fallback
-        }
+        recommendations.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then_with(|| b.matched_widgets.cmp(&a.matched_widgets))
+                .then_with(|| b.favorite.cmp(&a.favorite))
+                .then_with(|| a.preset.cmp(&b.preset))
+        });
+        recommendations.truncate(k);
+        recommendations
+    }
 
-        // Calculate confidence based on number of observed values
-        let confidence = match values.len() {
-            0 => 0.3,
-            1..=2 => 0.5,
-            3..=5 => 0.7,
-            _ => 0.9,
-        };
+    /// Widgets that tend to change together with `event_id` across stored presets,
+    /// strongest first
+    pub fn related_widgets(&self, event_id: u64) -> Vec<RelatedWidget> {
+        let preset_values: Vec<HashMap<u64, f64>> = self
+            .presets
+            .iter()
+            .map(|preset| {
+                preset
+                    .widget_values
+                    .iter()
+                    .filter_map(|v| Some((v.widget_id.parse::<u64>().ok()?, v.value)))
+                    .collect()
+            })
+            .collect();
 
-        // Find the most common value
-        let mut value_counts: HashMap<String, u32> = HashMap::new();
-        for &val in &values {
-            let key = format!("{:.4}", val);
-            *value_counts.entry(key).or_insert(0) += 1;
+        let containing = preset_values
+            .iter()
+            .filter(|values| values.contains_key(&event_id))
+            .count();
+        if containing == 0 {
+            return Vec::new();
         }
 
-        let mut most_common_value = values[0];
-        let mut max_count = 1;
-
-        for (val_str, count) in value_counts.iter() {
-            if *count > max_count {
-                if let Ok(val) = val_str.parse::<f64>() {
-                    most_common_value = val;
-                    max_count = *count;
+        let mut others: Vec<u64> = preset_values
+            .iter()
+            .flat_map(|values| values.keys().copied())
+            .filter(|&id| id != event_id)
+            .collect();
+        others.sort_unstable();
+        others.dedup();
+
+        let mut related: Vec<RelatedWidget> = others
+            .into_iter()
+            .filter_map(|other| {
+                let pairs: Vec<(f64, f64)> = preset_values
+                    .iter()
+                    .filter_map(|values| Some((*values.get(&event_id)?, *values.get(&other)?)))
+                    .collect();
+                if pairs.is_empty() {
+                    return None;
                 }
+
+                let either = preset_values
+                    .iter()
+                    .filter(|values| values.contains_key(&event_id) || values.contains_key(&other))
+                    .count();
+                let co_occurrence = pairs.len() as f64 / either as f64;
+                let correlation = pearson_correlation(&pairs);
+                let score = co_occurrence * correlation.map_or(0.5, f64::abs);
+
+                Some(RelatedWidget {
+                    event_id: other,
+                    co_occurrence,
+                    correlation,
+                    score,
+                })
+            })
+            .collect();
+
+        related.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.event_id.cmp(&b.event_id))
+        });
+        related
+    }
+
+    /// Like `related_widgets`, favouring the widgets in `nearby`, nearest first, as
+    /// controls placed next to each other on the VCS tend to be set together
+    pub fn related_widgets_near(&self, event_id: u64, nearby: &[u64]) -> Vec<RelatedWidget> {
+        let mut related = self.related_widgets(event_id);
+        for widget in &mut related {
+            if let Some(rank) = nearby.iter().position(|&id| id == widget.event_id) {
+                widget.score *= 1.0 + ADJACENCY_BOOST / (rank + 1) as f64;
             }
         }
 
-        // Return the most common value and all unique values
-        let mut unique_values = values.clone();
-        unique_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        unique_values.dedup();
+        related.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.event_id.cmp(&b.event_id))
+        });
+        related
+    }
+
+    /// Writes one CSV row per record, with a header, for auditing in a spreadsheet
+    ///
+    /// Common values are separated by semicolons within their column.
+    pub fn export_widgets_csv<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "id,label,event_id,minimum,maximum,frequency,last_seen,mean,std_dev,common_values"
+        )?;
+
+        for record in &self.records {
+            let stats = self.value_stats(record);
+            let common_values = stats
+                .common_values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(";");
+
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{}",
+                record.id,
+                csv_field(record.widget.label.as_deref().unwrap_or("")),
+                csv_optional(record.widget.event_id),
+                csv_optional(record.widget.minimum),
+                csv_optional(record.widget.maximum),
+                record.frequency,
+                record.last_seen,
+                stats.mean,
+                stats.std_dev,
+                common_values
+            )?;
+        }
 
-        (Some(most_common_value), confidence, unique_values)
+        writer.flush()
     }
 
-    pub fn get_preset_insights(&self, widget: &Widget) -> Option<String> {
-        for preset in &self.presets {
-            for widget_value in &preset.widget_values {
-                if let Some(label) = &widget.label {
-                    if let Some(preset_label) = &widget_value.label {
-                        if jaro_winkler(label, preset_label) > 0.8 {
-                            return Some(format!(
-                                "This widget is often set to {} in the '{}' preset",
-                                widget_value.value, preset.name
-                            ));
-                        }
-                    }
+    /// A page of the learned records in `sort` order
+    pub fn list_widgets(
+        &self,
+        offset: usize,
+        limit: usize,
+        sort: WidgetSort,
+    ) -> Page<&WidgetRecord> {
+        let mut records: Vec<&WidgetRecord> = self.records.iter().collect();
+        match sort {
+            WidgetSort::Id => records.sort_by_key(|r| r.id),
+            WidgetSort::Label => records.sort_by(|a, b| {
+                let key = |r: &WidgetRecord| r.widget.label.as_ref().map(|l| l.to_lowercase());
+                match (key(a), key(b)) {
+                    (Some(a_label), Some(b_label)) => a_label.cmp(&b_label),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
                 }
+                .then(a.id.cmp(&b.id))
+            }),
+            WidgetSort::MostUsed => {
+                records.sort_by(|a, b| b.frequency.cmp(&a.frequency).then(a.id.cmp(&b.id)))
+            }
+            WidgetSort::RecentlySeen => {
+                records.sort_by(|a, b| b.last_seen.cmp(&a.last_seen).then(a.id.cmp(&b.id)))
             }
         }
-        None
+
+        Page::slice(records, offset, limit)
+    }
+
+    /// A page of the stored presets in name order
+    pub fn list_presets(&self, offset: usize, limit: usize) -> Page<&Preset> {
+        let mut presets: Vec<&Preset> = self.presets.iter().collect();
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Page::slice(presets, offset, limit)
     }
 
     pub fn get_stats(&self) -> HashMap<String, usize> {
@@ -644,10 +2936,8 @@ impl WidgetSuggestionEngine {
 
         let mut value_patterns = self.extract_value_patterns(&label_tokens, &widget.display_type);
 
-        // Add the normalized current_value to value_patterns if available
-        if let Some(current) = widget.current_value {
-            value_patterns.push(current);
-        }
+        // Add every observed normalized value, including current_value
+        value_patterns.extend(widget.get_values());
 
         // current_value is already normalized, use it directly
         let normalized_position = widget.current_value.unwrap_or(0.5);
@@ -691,10 +2981,8 @@ impl WidgetSuggestionEngine {
 
         let mut value_patterns = self.extract_value_patterns(&label_tokens, &widget.display_type);
 
-        // Add the normalized current_value to value_patterns if available
-        if let Some(current) = widget.current_value {
-            value_patterns.push(current);
-        }
+        // Add every observed normalized value, including current_value
+        value_patterns.extend(widget.get_values());
 
         // current_value is already normalized, use it directly
         let normalized_position = widget.current_value.unwrap_or(0.5);
@@ -712,15 +3000,36 @@ impl WidgetSuggestionEngine {
     }
 
     fn tokenize_label(&self, label: &str) -> Vec<String> {
-        label
-            .to_lowercase()
-            .split_whitespace()
-            .filter(|word| !word.is_empty())
-            .map(|word| word.to_string())
-            .collect()
+        self.config.label_sanitizer.tokens(label)
+    }
+
+    /// Features of a partially described widget, as compared against stored records
+    pub fn partial_features(&self, partial_widget: &Widget) -> WidgetFeatures {
+        self.extract_features_partial(&partial_widget.clone().sanitized())
+    }
+
+    /// Weighted similarity of two feature sets, the score behind `SimilarWidget` reasons
+    pub fn feature_similarity(&self, a: &WidgetFeatures, b: &WidgetFeatures) -> f64 {
+        self.calculate_similarity(a, b)
+    }
+
+    /// Explains how the similarity between two widgets is composed, so integrators
+    /// can see which features drove a suggestion
+    pub fn explain_similarity(&self, a: &Widget, b: &Widget) -> SimilarityBreakdown {
+        let features_a = self.extract_features_partial(&a.clone().sanitized());
+        let features_b = self.extract_features_partial(&b.clone().sanitized());
+        self.similarity_breakdown(&features_a, &features_b)
     }
 
     fn calculate_similarity(&self, features1: &WidgetFeatures, features2: &WidgetFeatures) -> f64 {
+        self.similarity_breakdown(features1, features2).total
+    }
+
+    fn similarity_breakdown(
+        &self,
+        features1: &WidgetFeatures,
+        features2: &WidgetFeatures,
+    ) -> SimilarityBreakdown {
         let label_similarity =
             self.calculate_label_similarity(&features1.label_tokens, &features2.label_tokens);
         let range_similarity = self.calculate_range_similarity(features1, features2);
@@ -732,14 +3041,49 @@ impl WidgetSuggestionEngine {
             0.0
         };
         let generated_similarity = 1.0 - (features1.is_generated - features2.is_generated).abs();
+        let value_pattern_similarity = self.calculate_value_pattern_similarity(
+            &features1.value_patterns,
+            &features2.value_patterns,
+        );
 
         // Weighted combination
-        let similarity = (label_similarity * 0.4)
-            + (range_similarity * 0.3)
-            + (display_type_similarity * 0.2)
-            + (generated_similarity * 0.1);
+        let weights = &self.config.similarity_weights;
+        let label = SimilarityComponent::new(label_similarity, weights.label);
+        let range = SimilarityComponent::new(range_similarity, weights.range);
+        let display_type = SimilarityComponent::new(display_type_similarity, weights.display_type);
+        let generated = SimilarityComponent::new(generated_similarity, weights.generated);
+        let value_pattern =
+            SimilarityComponent::new(value_pattern_similarity, weights.value_pattern);
+
+        let total = (label.contribution
+            + range.contribution
+            + display_type.contribution
+            + generated.contribution
+            + value_pattern.contribution)
+            .clamp(0.0, 1.0);
+
+        SimilarityBreakdown {
+            label,
+            range,
+            display_type,
+            generated,
+            value_pattern,
+            total,
+        }
+    }
+
+    fn calculate_value_pattern_similarity(&self, patterns1: &[f64], patterns2: &[f64]) -> f64 {
+        if patterns1.is_empty() || patterns2.is_empty() {
+            return if patterns1.is_empty() && patterns2.is_empty() {
+                1.0
+            } else {
+                0.0
+            };
+        }
 
-        similarity.clamp(0.0, 1.0)
+        let mean1 = patterns1.iter().sum::<f64>() / patterns1.len() as f64;
+        let mean2 = patterns2.iter().sum::<f64>() / patterns2.len() as f64;
+        1.0 - (mean1 - mean2).abs().min(1.0)
     }
 
     fn calculate_label_similarity(&self, tokens1: &[String], tokens2: &[String]) -> f64 {
@@ -785,7 +3129,7 @@ impl WidgetSuggestionEngine {
         let range_diff = (features1.range - features2.range).abs();
 
         let max_range = features1.range.max(features2.range);
-        if max_range == 0.0 {
+        if max_range <= 0.0 {
             return 1.0;
         }
 
@@ -802,14 +3146,8 @@ impl WidgetSuggestionEngine {
 
         // Common value patterns based on label tokens
         for token in label_tokens {
-            match token.as_str() {
-                "volume" | "level" | "gain" => patterns.push(0.75),
-                "bass" | "low" => patterns.push(0.6),
-                "treble" | "high" => patterns.push(0.7),
-                "mid" | "middle" => patterns.push(0.5),
-                "pan" => patterns.push(0.5),
-                "reverb" | "delay" => patterns.push(0.3),
-                _ => {}
+            if let Some(&prior) = self.config.value_priors.get(token) {
+                patterns.push(prior);
             }
         }
 
@@ -819,7 +3157,224 @@ impl WidgetSuggestionEngine {
 
         patterns
     }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// An optional CSV field, empty when absent
+fn csv_optional<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Observations of each state of a boolean widget, `(off, on)`
+fn boolean_counts(values: &[f64]) -> (u32, u32) {
+    let on = values
+        .iter()
+        .filter(|&&v| ValueKind::Boolean.quantize(v) == 1.0)
+        .count() as u32;
+    (values.len() as u32 - on, on)
+}
+
+/// How many times each of `values` was observed according to the histograms in
+/// `stats`, at least once each. Distinct values sharing a rounded histogram key split its
+/// count, the first of them taking any remainder.
+fn observation_counts(values: &[f64], stats: &[ValueStats]) -> Vec<u32> {
+    let keys: Vec<String> = values.iter().map(|v| format!("{v:.2}")).collect();
+    keys.iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let observed = stats
+                .iter()
+                .filter_map(|stats| stats.frequency_map.get(key))
+                .fold(0u32, |total, &count| total.saturating_add(count));
+            let sharing = keys.iter().filter(|other| *other == key).count() as u32;
+            let rank = keys[..i].iter().filter(|other| *other == key).count() as u32;
+            let remainder = u32::from(rank < observed % sharing);
+            (observed / sharing + remainder).max(1)
+        })
+        .collect()
+}
+
+/// Observations of each state of a boolean widget from the histograms in `stats`, or
+/// from its distinct values if it has no statistics
+fn observed_states(widget: &Widget, stats: &[ValueStats]) -> (u32, u32) {
+    if stats.iter().all(|stats| stats.frequency_map.is_empty()) {
+        return boolean_counts(&widget.get_values());
+    }
+    stats
+        .iter()
+        .flat_map(|stats| &stats.frequency_map)
+        .filter_map(|(key, &count)| key.parse::<f64>().ok().map(|v| (v, count)))
+        .fold((0, 0), |(off, on), (value, count)| {
+            if ValueKind::Boolean.quantize(value) == 1.0 {
+                (off, on + count)
+            } else {
+                (off + count, on)
+            }
+        })
+}
+
+/// The majority state of a boolean widget from its `(off, on)` observation counts and
+/// the share of the other state, or `None` for other widgets. Ties go to the widget's
+/// latest value.
+fn boolean_majority(kind: ValueKind, (off, on): (u32, u32), widget: &Widget) -> Option<(f64, f64)> {
+    if kind != ValueKind::Boolean || off + on == 0 {
+        return None;
+    }
+
+    let on_share = on as f64 / (off + on) as f64;
+    let state = if on_share == 0.5 {
+        let latest = widget.current_value.or(widget.values.last().copied());
+        kind.quantize(latest.unwrap_or(0.0))
+    } else if on_share > 0.5 {
+        1.0
+    } else {
+        0.0
+    };
+    let flip = if state == 1.0 {
+        1.0 - on_share
+    } else {
+        on_share
+    };
+    Some((state, flip))
+}
+
+fn flip_probability(widget: &Widget, stats: &[ValueStats]) -> Option<f64> {
+    let kind = widget.effective_value_kind();
+    boolean_majority(kind, observed_states(widget, stats), widget).map(|(_, flip)| flip)
+}
+
+/// Quantizes values to the widget's valid positions, sorted and deduplicated
+fn quantized_alternatives(kind: ValueKind, values: &[f64]) -> Vec<f64> {
+    let mut unique_values: Vec<f64> = values.iter().map(|&v| kind.quantize(v)).collect();
+    unique_values.sort_by(f64::total_cmp);
+    unique_values.dedup();
+    unique_values
+}
+
+/// Root mean square difference over the widgets two presets share, 1.0 if none
+fn preset_distance(a: &Preset, b: &Preset) -> f64 {
+    let b_values: HashMap<&str, f64> = b
+        .widget_values
+        .iter()
+        .map(|v| (v.widget_id.as_str(), v.value))
+        .collect();
+
+    let squared: Vec<f64> = a
+        .widget_values
+        .iter()
+        .filter_map(|v| {
+            b_values
+                .get(v.widget_id.as_str())
+                .map(|w| (v.value - w).powi(2))
+        })
+        .collect();
+
+    if squared.is_empty() {
+        return 1.0;
+    }
+    (squared.iter().sum::<f64>() / squared.len() as f64).sqrt()
+}
+
+/// Pearson correlation of paired values, if there are enough pairs and both sides vary
+fn pearson_correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < MIN_PRESETS_FOR_CORRELATION {
+        return None;
+    }
+
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for &(x, y) in pairs {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+
+    if variance_x <= f64::EPSILON || variance_y <= f64::EPSILON {
+        return None;
+    }
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
+
+/// Mean value per widget ID over `presets`
+fn preset_centroid(presets: &[&Preset]) -> HashMap<String, f64> {
+    let mut sums: HashMap<String, (f64, u32)> = HashMap::new();
+    for preset in presets {
+        for widget_value in &preset.widget_values {
+            let entry = sums
+                .entry(widget_value.widget_id.clone())
+                .or_insert((0.0, 0));
+            entry.0 += widget_value.value;
+            entry.1 += 1;
+        }
+    }
+
+    sums.into_iter()
+        .map(|(id, (sum, count))| (id, sum / count as f64))
+        .collect()
+}
+
+/// Linearly interpolated percentile of a sorted, non-empty slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Median of an already sorted, non-empty slice
+fn median(sorted: &[f64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Matches `text` against a glob `pattern` in which `*` matches any run of characters
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard, so the pattern must match exactly
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
 
+/// Index of the value nearest `target` within `FEEDBACK_TOLERANCE`
+fn nearest_index(values: &[f64], target: f64) -> Option<usize> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i, (v - target).abs()))
+        .filter(|&(_, distance)| distance <= FEEDBACK_TOLERANCE)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
 }
 
 impl Default for WidgetSuggestionEngine {
@@ -933,6 +3488,8 @@ mod conversion_tests {
             display_type: Some("slider".to_string()),
             event_id: None,
             values: vec![0.7],
+            value_kind: None,
+            taper: None,
         };
 
         // Store first widget
@@ -0,0 +1,320 @@
+use crate::error::WidgetIntelligenceError;
+use crate::persistence::{
+    PersistenceBackend, PersistentWidgetSuggestionEngine, PresetRevision, WriteBatch,
+};
+use crate::similarity_engine::{FeedbackEntry, Preset, WidgetRecord};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+// Records are stored bincode-encoded like in sled. The columns next to them duplicate
+// the fields worth querying with plain SQL.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS widgets (
+        id INTEGER PRIMARY KEY,
+        label TEXT,
+        event_id INTEGER,
+        frequency INTEGER NOT NULL,
+        record BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS presets (
+        name TEXT PRIMARY KEY,
+        usage_count INTEGER NOT NULL,
+        last_used INTEGER NOT NULL,
+        preset BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS preset_history (
+        name TEXT PRIMARY KEY,
+        revisions BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feedback (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        record_id INTEGER NOT NULL,
+        accepted INTEGER NOT NULL,
+        entry BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS metadata (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// A backend storing everything in a single SQLite file
+pub struct SqliteBackend {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl SqliteBackend {
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self, WidgetIntelligenceError> {
+        let path = db_path.as_ref().to_path_buf();
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self { conn, path })
+    }
+
+    fn insert_widget(
+        conn: &Connection,
+        record: &WidgetRecord,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let value = bincode::encode_to_vec(record, bincode::config::standard())?;
+        conn.execute(
+            "INSERT OR REPLACE INTO widgets (id, label, event_id, frequency, record)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                record.id as i64,
+                record.widget.label,
+                record.widget.event_id.and_then(|id| i64::try_from(id).ok()),
+                record.frequency,
+                value
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn load_blobs(&self, sql: &str) -> Result<Vec<Vec<u8>>, WidgetIntelligenceError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let blobs = stmt
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(blobs)
+    }
+}
+
+impl PersistenceBackend for SqliteBackend {
+    fn store_widget(&self, record: &WidgetRecord) -> Result<(), WidgetIntelligenceError> {
+        Self::insert_widget(&self.conn, record)
+    }
+
+    /// Writes several records in a single transaction
+    fn store_widgets(&self, records: &[&WidgetRecord]) -> Result<(), WidgetIntelligenceError> {
+        let tx = self.conn.unchecked_transaction()?;
+        for record in records {
+            Self::insert_widget(&tx, record)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Applies the batch in a single transaction
+    fn apply_batch(&self, batch: &WriteBatch<'_>) -> Result<(), WidgetIntelligenceError> {
+        // Statements on the connection run inside the open transaction; dropping it
+        // without committing rolls them back
+        let tx = self.conn.unchecked_transaction()?;
+        for record in &batch.widgets {
+            Self::insert_widget(&tx, record)?;
+        }
+        for preset in &batch.presets {
+            self.store_preset(preset)?;
+        }
+        for (name, revisions) in &batch.preset_revisions {
+            self.store_preset_revisions(name, revisions)?;
+        }
+        for (key, value) in &batch.metadata {
+            self.store_metadata(key, value)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn delete_widget(&self, id: u64) -> Result<(), WidgetIntelligenceError> {
+        self.conn
+            .execute("DELETE FROM widgets WHERE id = ?1", params![id as i64])?;
+        Ok(())
+    }
+
+    fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError> {
+        let mut records = Vec::new();
+        for blob in self.load_blobs("SELECT record FROM widgets ORDER BY id")? {
+            match bincode::decode_from_slice(&blob, bincode::config::standard()) {
+                Ok((record, _)) => records.push(record),
+                Err(e) => log::warn!("Failed to decode widget record with bincode: {e}"),
+            }
+        }
+        Ok(records)
+    }
+
+    fn load_widget(&self, id: u64) -> Result<Option<WidgetRecord>, WidgetIntelligenceError> {
+        let blob: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT record FROM widgets WHERE id = ?1",
+                params![id as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match blob {
+            Some(blob) => Ok(Some(
+                bincode::decode_from_slice(&blob, bincode::config::standard())?.0,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn for_each_widget(
+        &self,
+        visit: &mut dyn FnMut(WidgetRecord),
+    ) -> Result<(), WidgetIntelligenceError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT record FROM widgets ORDER BY id")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let blob: Vec<u8> = row.get(0)?;
+            match bincode::decode_from_slice(&blob, bincode::config::standard()) {
+                Ok((record, _)) => visit(record),
+                Err(e) => log::warn!("Failed to decode widget record with bincode: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn store_preset(&self, preset: &Preset) -> Result<(), WidgetIntelligenceError> {
+        let value = bincode::encode_to_vec(preset, bincode::config::standard())?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO presets (name, usage_count, last_used, preset)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                preset.name,
+                preset.usage_count,
+                preset.last_used as i64,
+                value
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn delete_preset(&self, name: &str) -> Result<(), WidgetIntelligenceError> {
+        self.conn
+            .execute("DELETE FROM presets WHERE name = ?1", params![name])?;
+        self.conn
+            .execute("DELETE FROM preset_history WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+
+    fn load_all_presets(&self) -> Result<Vec<Preset>, WidgetIntelligenceError> {
+        let mut presets = Vec::new();
+        for blob in self.load_blobs("SELECT preset FROM presets ORDER BY name")? {
+            match bincode::decode_from_slice(&blob, bincode::config::standard()) {
+                Ok((preset, _)) => presets.push(preset),
+                Err(e) => log::warn!("Failed to decode preset with bincode: {e}"),
+            }
+        }
+        Ok(presets)
+    }
+
+    fn store_preset_revisions(
+        &self,
+        name: &str,
+        revisions: &[PresetRevision],
+    ) -> Result<(), WidgetIntelligenceError> {
+        if revisions.is_empty() {
+            self.conn
+                .execute("DELETE FROM preset_history WHERE name = ?1", params![name])?;
+        } else {
+            let value = bincode::encode_to_vec(revisions, bincode::config::standard())?;
+            self.conn.execute(
+                "INSERT OR REPLACE INTO preset_history (name, revisions) VALUES (?1, ?2)",
+                params![name, value],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn load_preset_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+        let blob: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT revisions FROM preset_history WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match blob {
+            Some(blob) => Ok(bincode::decode_from_slice::<Vec<PresetRevision>, _>(
+                &blob,
+                bincode::config::standard(),
+            )?
+            .0),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn store_feedback(&self, entry: &FeedbackEntry) -> Result<(), WidgetIntelligenceError> {
+        let value = bincode::encode_to_vec(entry, bincode::config::standard())?;
+        self.conn.execute(
+            "INSERT INTO feedback (record_id, accepted, entry) VALUES (?1, ?2, ?3)",
+            params![entry.record_id as i64, entry.accepted, value],
+        )?;
+        Ok(())
+    }
+
+    fn load_feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError> {
+        let mut entries = Vec::new();
+        for blob in self.load_blobs("SELECT entry FROM feedback ORDER BY seq")? {
+            match bincode::decode_from_slice(&blob, bincode::config::standard()) {
+                Ok((entry, _)) => entries.push(entry),
+                Err(e) => log::warn!("Failed to decode feedback entry with bincode: {e}"),
+            }
+        }
+        Ok(entries)
+    }
+
+    fn store_metadata(&self, key: &str, value: &str) -> Result<(), WidgetIntelligenceError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    fn load_metadata(&self, key: &str) -> Result<Option<String>, WidgetIntelligenceError> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn clear_all(&self) -> Result<(), WidgetIntelligenceError> {
+        self.conn.execute_batch(
+            "BEGIN;
+             DELETE FROM widgets;
+             DELETE FROM presets;
+             DELETE FROM preset_history;
+             DELETE FROM feedback;
+             DELETE FROM metadata;
+             COMMIT;",
+        )?;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        // Every statement outside a transaction is committed as it runs
+        Ok(())
+    }
+
+    fn compact(&self) -> Result<(), WidgetIntelligenceError> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    fn size_on_disk(&self) -> Result<u64, WidgetIntelligenceError> {
+        Ok(std::fs::metadata(&self.path)?.len())
+    }
+}
+
+impl PersistentWidgetSuggestionEngine<SqliteBackend> {
+    /// Opens or creates an engine stored in the SQLite file at `db_path`
+    pub fn open_sqlite<P: AsRef<Path>>(db_path: P) -> Result<Self, WidgetIntelligenceError> {
+        Self::with_backend(SqliteBackend::new(db_path)?)
+    }
+}
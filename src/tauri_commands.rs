@@ -0,0 +1,308 @@
+//! Tauri commands over `StandaloneIntelligenceService`
+//!
+//! `tauri_examples` shows the patterns; this module ships them. Mount everything with one
+//! line:
+//!
+//! ```ignore
+//! tauri::Builder::default()
+//!     .plugin(widget_intelligence::tauri_commands::init_plugin())
+//! ```
+//!
+//! and call the commands from the frontend as
+//! `invoke("plugin:widget-intelligence|get_widget_value_suggestions", { eventId })`.
+//! Every command takes the same arguments as the service method of the same name.
+//! Errors reach the frontend as their message. The service's `IntelligenceEvent`s are
+//! emitted as `EVENT_NAME`.
+//!
+//! The frontend may only invoke commands its capability grants, e.g.
+//! `"widget-intelligence:default"`, which allows every command except `clear_all_data`.
+//! The build script generates an `allow-*` permission per command for finer grants.
+
+use crate::error::WidgetIntelligenceError;
+use crate::tauri_examples::{
+    IntelligenceEvent, IntelligenceStats, PresetData, PresetSummaryResponse,
+    StandaloneIntelligenceService, SuggestionResponse, WidgetInsightResponse,
+    WidgetSummaryResponse,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::broadcast;
+
+/// Name the plugin's commands are invoked under, as in `plugin:widget-intelligence|...`
+pub const PLUGIN_NAME: &str = "widget-intelligence";
+
+/// Frontend event carrying each `IntelligenceEvent`, to `listen` for
+pub const EVENT_NAME: &str = "widget-intelligence://event";
+
+/// Database file `init_plugin` opens in the app's data directory
+pub const DEFAULT_DB_NAME: &str = "widget_intelligence.db";
+
+/// The service as the plugin manages it; take it as a command argument to use the same
+/// service from the app's own commands
+pub type IntelligenceState<'a> = tauri::State<'a, StandaloneIntelligenceService>;
+
+/// The plugin with its database in the app's data directory
+pub fn init_plugin<R: Runtime>() -> TauriPlugin<R> {
+    plugin(None)
+}
+
+/// The plugin with its database at `db_path`
+pub fn init_plugin_at<R: Runtime>(db_path: impl Into<PathBuf>) -> TauriPlugin<R> {
+    plugin(Some(db_path.into()))
+}
+
+fn plugin<R: Runtime>(db_path: Option<PathBuf>) -> TauriPlugin<R> {
+    Builder::new(PLUGIN_NAME)
+        .invoke_handler(tauri::generate_handler![
+            cache_widget_description,
+            remap_event_ids,
+            get_description_issues,
+            save_preset_and_learn,
+            delete_preset,
+            snapshot_preset,
+            record_widget_value,
+            flush_widget_values,
+            get_widget_value_suggestions,
+            get_widget_value_suggestions_batch,
+            get_widget_insight,
+            recall_preset,
+            apply_preset,
+            get_preset,
+            export_preset_kyma,
+            export_widgets_kyma,
+            import_kyma_presets,
+            get_intelligence_stats,
+            list_widgets,
+            list_presets,
+            flush,
+            clear_all_data,
+        ])
+        .setup(move |app, _api| {
+            let db_path = match db_path {
+                Some(path) => path,
+                None => {
+                    let dir = app.path().app_data_dir()?;
+                    std::fs::create_dir_all(&dir)?;
+                    dir.join(DEFAULT_DB_NAME)
+                }
+            };
+            let db_path = db_path.to_str().ok_or_else(|| {
+                WidgetIntelligenceError::ValidationError(format!(
+                    "Database path is not valid UTF-8: {}",
+                    db_path.display()
+                ))
+            })?;
+
+            let service = StandaloneIntelligenceService::new(db_path)?;
+            forward_events(app.clone(), service.subscribe());
+            app.manage(service);
+            Ok(())
+        })
+        .build()
+}
+
+/// Emits the service's events to the frontend until the service is gone
+fn forward_events<R: Runtime>(
+    app: AppHandle<R>,
+    mut events: broadcast::Receiver<IntelligenceEvent>,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if let Err(e) = app.emit(EVENT_NAME, event) {
+                        log::debug!("Failed to emit intelligence event: {e}");
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    log::debug!("Frontend missed {missed} intelligence events");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn cache_widget_description(
+    state: IntelligenceState<'_>,
+    event_id: i64,
+    kyma_json: String,
+) -> Result<(), WidgetIntelligenceError> {
+    state.cache_widget_description(event_id, kyma_json).await
+}
+
+#[tauri::command]
+pub async fn remap_event_ids(
+    state: IntelligenceState<'_>,
+    previous_event_ids: Vec<i64>,
+) -> Result<Vec<crate::EventIdRemap>, WidgetIntelligenceError> {
+    state.remap_event_ids(previous_event_ids).await
+}
+
+#[tauri::command]
+pub async fn get_description_issues(
+    state: IntelligenceState<'_>,
+) -> Result<Vec<crate::DescriptionReport>, WidgetIntelligenceError> {
+    state.get_description_issues().await
+}
+
+#[tauri::command]
+pub async fn save_preset_and_learn(
+    state: IntelligenceState<'_>,
+    preset_data: PresetData,
+) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+    state.save_preset_and_learn(preset_data).await
+}
+
+#[tauri::command]
+pub async fn delete_preset(
+    state: IntelligenceState<'_>,
+    name: String,
+) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+    state.delete_preset(name).await
+}
+
+#[tauri::command]
+pub async fn snapshot_preset(
+    state: IntelligenceState<'_>,
+    name: String,
+    values: HashMap<i64, f64>,
+) -> Result<crate::Preset, WidgetIntelligenceError> {
+    state.snapshot_preset(name, values).await
+}
+
+#[tauri::command]
+pub async fn get_widget_insight(
+    state: IntelligenceState<'_>,
+    event_id: i64,
+    label: Option<String>,
+) -> Result<WidgetInsightResponse, WidgetIntelligenceError> {
+    state.get_widget_insight(event_id, label).await
+}
+
+#[tauri::command]
+pub async fn record_widget_value(
+    state: IntelligenceState<'_>,
+    event_id: i64,
+    value: f64,
+) -> Result<bool, WidgetIntelligenceError> {
+    state.record_widget_value(event_id, value).await
+}
+
+#[tauri::command]
+pub async fn flush_widget_values(
+    state: IntelligenceState<'_>,
+) -> Result<usize, WidgetIntelligenceError> {
+    state.flush_widget_values().await
+}
+
+#[tauri::command]
+pub async fn get_widget_value_suggestions(
+    state: IntelligenceState<'_>,
+    event_id: i64,
+    partial_label: Option<String>,
+    display_type: Option<String>,
+) -> Result<Vec<SuggestionResponse>, WidgetIntelligenceError> {
+    state
+        .get_widget_value_suggestions(event_id, partial_label, display_type)
+        .await
+}
+
+#[tauri::command]
+pub async fn get_widget_value_suggestions_batch(
+    state: IntelligenceState<'_>,
+    requests: Vec<(i64, Option<String>, Option<String>)>,
+) -> Result<HashMap<i64, Vec<SuggestionResponse>>, WidgetIntelligenceError> {
+    state.get_widget_value_suggestions_batch(requests).await
+}
+
+#[tauri::command]
+pub async fn recall_preset(
+    state: IntelligenceState<'_>,
+    name: String,
+) -> Result<HashMap<i64, f64>, WidgetIntelligenceError> {
+    state.recall_preset(name).await
+}
+
+#[tauri::command]
+pub async fn apply_preset(
+    state: IntelligenceState<'_>,
+    name: String,
+) -> Result<HashMap<i64, f64>, WidgetIntelligenceError> {
+    state.apply_preset(name).await
+}
+
+#[tauri::command]
+pub async fn get_preset(
+    state: IntelligenceState<'_>,
+    name: String,
+) -> Result<crate::Preset, WidgetIntelligenceError> {
+    state.get_preset(name).await
+}
+
+#[tauri::command]
+pub async fn export_preset_kyma(
+    state: IntelligenceState<'_>,
+    name: String,
+) -> Result<String, WidgetIntelligenceError> {
+    state.export_preset_kyma(name).await
+}
+
+#[tauri::command]
+pub async fn export_widgets_kyma(
+    state: IntelligenceState<'_>,
+) -> Result<String, WidgetIntelligenceError> {
+    state.export_widgets_kyma().await
+}
+
+#[tauri::command]
+pub async fn import_kyma_presets(
+    state: IntelligenceState<'_>,
+    path: String,
+) -> Result<Vec<String>, WidgetIntelligenceError> {
+    state.import_kyma_presets(path).await
+}
+
+#[tauri::command]
+pub async fn get_intelligence_stats(
+    state: IntelligenceState<'_>,
+) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+    state.get_intelligence_stats().await
+}
+
+#[tauri::command]
+pub async fn list_widgets(
+    state: IntelligenceState<'_>,
+    offset: usize,
+    limit: usize,
+    sort: Option<crate::WidgetSort>,
+) -> Result<crate::Page<WidgetSummaryResponse>, WidgetIntelligenceError> {
+    state
+        .list_widgets(offset, limit, sort.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub async fn flush(state: IntelligenceState<'_>) -> Result<(), WidgetIntelligenceError> {
+    state.flush().await
+}
+
+#[tauri::command]
+pub async fn clear_all_data(
+    state: IntelligenceState<'_>,
+    confirm_token: String,
+) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+    state.clear_all_data(confirm_token).await
+}
+
+#[tauri::command]
+pub async fn list_presets(
+    state: IntelligenceState<'_>,
+    offset: usize,
+    limit: usize,
+) -> Result<crate::Page<PresetSummaryResponse>, WidgetIntelligenceError> {
+    state.list_presets(offset, limit).await
+}
@@ -1,5 +1,21 @@
+use crate::error::WidgetIntelligenceError;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+
+/// Shortest time between two values of one widget learned by `record_widget_value`
+pub const VALUE_LEARNING_INTERVAL: Duration = Duration::from_millis(500);
+
+// Preset insights summarized by `get_widget_insight`, most similar first
+const MAX_PRESET_INSIGHT_LINES: usize = 3;
+
+/// What `clear_all_data` must be given, so a stray call cannot wipe what was learned
+pub const CLEAR_ALL_DATA_CONFIRMATION: &str = "CLEAR ALL DATA";
+
+/// Events a subscriber can fall behind by before it misses the oldest ones
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
 
 // Response types - copy these to your Tauri app
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,6 +24,9 @@ pub struct SuggestionResponse {
     pub confidence: f64,
     pub alternative_values: Vec<f64>,
     pub reason: String,
+    pub reason_kind: crate::SuggestionReason,
+    /// For switches, how often the opposite state was chosen
+    pub flip_probability: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,62 +52,271 @@ pub struct WidgetInsightResponse {
     pub confidence_scores: Vec<f64>,
 }
 
+/// A learned widget as listed on a settings page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WidgetSummaryResponse {
+    pub id: u64,
+    pub label: Option<String>,
+    pub event_id: Option<u64>,
+    pub display_type: Option<String>,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    pub frequency: u32,
+    pub last_seen: u64,
+    pub observed_values: usize,
+}
+
+/// A stored preset as listed on a settings page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetSummaryResponse {
+    pub name: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub usage_count: u32,
+    pub last_used: u64,
+    pub widget_count: usize,
+}
+
+/// What the service reports to subscribers, see `subscribe`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IntelligenceEvent {
+    /// A widget record was written, by learning a value or a preset
+    WidgetLearned {
+        id: u64,
+        event_id: Option<u64>,
+        label: Option<String>,
+    },
+    /// A preset was written, when saved, applied or changed
+    PresetStored { name: String },
+    /// Suggestions were returned for a widget
+    SuggestionServed { event_id: i64, count: usize },
+    /// Writes were made durable
+    DatabaseFlushed { writes: usize },
+}
+
 /// Standalone service for non-Tauri applications
 ///
 /// This provides the same functionality as the Tauri commands but without Tauri dependencies.
 /// Use this if you want to integrate the intelligence system into other types of applications.
 pub struct StandaloneIntelligenceService {
-    system: std::sync::Mutex<crate::PersistentWidgetSuggestionEngine>,
-    extractor: std::sync::Mutex<crate::KymaWidgetExtractor>,
+    /// Suggestion, listing and stats requests share a read lock; learning takes the write
+    /// lock. Guards are async, so waiting callers yield instead of blocking a runtime thread
+    system: Arc<RwLock<crate::PersistentWidgetSuggestionEngine>>,
+    /// Locks internally, so reads of the cache never wait for the engine
+    extractor: Arc<crate::KymaWidgetExtractor>,
+    /// Values of `record_widget_value` held back until their interval has passed
+    recorded_values: Arc<Mutex<RecordedValues>>,
+    events: broadcast::Sender<IntelligenceEvent>,
+}
+
+/// Values held back by `record_widget_value`, and the event IDs a trailing store is
+/// scheduled for
+struct RecordedValues {
+    limiter: crate::RateLimiter,
+    scheduled: HashSet<i64>,
 }
 
 impl StandaloneIntelligenceService {
-    pub fn new(db_path: &str) -> Result<Self, String> {
-        let system = crate::PersistentWidgetSuggestionEngine::new(db_path)
-            .map_err(|e| format!("Failed to initialize intelligence system: {e:?}"))?;
+    pub fn new(db_path: &str) -> Result<Self, WidgetIntelligenceError> {
+        let system = crate::PersistentWidgetSuggestionEngine::new(db_path)?;
 
         let extractor = crate::KymaWidgetExtractor::new();
+        let restored = system.restore_widget_descriptions(&extractor)?;
+        log::debug!("Restored {restored} cached widget descriptions");
+
+        // Sending only fails without subscribers, when there is no one to tell
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let sender = events.clone();
+        system.persistence.on_widget_stored(move |record| {
+            let _ = sender.send(IntelligenceEvent::WidgetLearned {
+                id: record.id,
+                event_id: record.widget.event_id,
+                label: record.widget.label.clone(),
+            });
+        });
+        let sender = events.clone();
+        system.persistence.on_preset_stored(move |preset| {
+            let _ = sender.send(IntelligenceEvent::PresetStored {
+                name: preset.name.clone(),
+            });
+        });
+        let sender = events.clone();
+        system.persistence.on_flush(move |writes| {
+            let _ = sender.send(IntelligenceEvent::DatabaseFlushed { writes });
+        });
 
         Ok(Self {
-            system: std::sync::Mutex::new(system),
-            extractor: std::sync::Mutex::new(extractor),
+            system: Arc::new(RwLock::new(system)),
+            extractor: Arc::new(extractor),
+            recorded_values: Arc::new(Mutex::new(RecordedValues {
+                limiter: crate::RateLimiter::new(VALUE_LEARNING_INTERVAL),
+                scheduled: HashSet::new(),
+            })),
+            events,
         })
     }
 
+    /// Events from now on, so a host can update its UI instead of polling stats
+    ///
+    /// A receiver that falls more than `EVENT_CHANNEL_CAPACITY` events behind misses the
+    /// oldest and gets `RecvError::Lagged` once.
+    pub fn subscribe(&self) -> broadcast::Receiver<IntelligenceEvent> {
+        self.events.subscribe()
+    }
+
+    /// Makes every write so far durable
+    pub async fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        self.system.read().await.flush()
+    }
+
     pub async fn cache_widget_description(
         &self,
         event_id: i64,
         kyma_json: String,
-    ) -> Result<(), String> {
-        let kyma_data: HashMap<String, serde_json::Value> =
-            serde_json::from_str(&kyma_json).map_err(|e| format!("Failed to parse JSON: {e}"))?;
+    ) -> Result<(), WidgetIntelligenceError> {
+        let kyma_data = crate::KymaWidgetExtractor::parse_kyma_json_string(&kyma_json)?;
 
-        crate::kyma_extractor::KymaWidgetExtractor::validate_kyma_data(&kyma_data)
-            .map_err(|e| format!("Invalid Kyma data: {e}"))?;
-
-        let mut extractor = self
-            .extractor
-            .lock()
-            .map_err(|_| "Failed to lock extractor")?;
+        let system = self.system.read().await;
 
-        extractor.cache_widget_description(kyma_data);
+        system.cache_widget_description(&self.extractor, kyma_data)?;
         log::debug!("Cached widget description for event ID: {event_id}");
         Ok(())
     }
 
-    pub async fn save_preset_and_learn(
+    /// Moves what was learned under the event IDs of a sound before it was recompiled to
+    /// the IDs of its cached widgets; call after caching the recompiled sound's widgets
+    /// with the event IDs the sound had before
+    pub async fn remap_event_ids(
         &self,
-        preset_data: PresetData,
-    ) -> Result<IntelligenceStats, String> {
-        let mut system = self
-            .system
-            .lock()
-            .map_err(|_| "Failed to lock intelligence system")?;
+        previous_event_ids: Vec<i64>,
+    ) -> Result<Vec<crate::EventIdRemap>, WidgetIntelligenceError> {
+        let mut system = self.system.write().await;
 
-        let extractor = self
-            .extractor
+        system.remap_event_ids(&*self.extractor, &previous_event_ids)
+    }
+
+    /// Data-quality problems of the cached widget descriptions, to show per widget
+    pub async fn get_description_issues(
+        &self,
+    ) -> Result<Vec<crate::DescriptionReport>, WidgetIntelligenceError> {
+        Ok(self.extractor.validate_cache())
+    }
+
+    /// Learns a single value change, e.g. a knob turned between preset saves, as Kyma
+    /// reports it for the widget of `event_id`; returns whether it was stored now
+    ///
+    /// At most one value per widget is stored every `VALUE_LEARNING_INTERVAL`. Values
+    /// arriving sooner are held back, and the last of them is stored once the interval has
+    /// passed, even if no further value arrives, or earlier by `flush_widget_values`.
+    pub async fn record_widget_value(
+        &self,
+        event_id: i64,
+        value: f64,
+    ) -> Result<bool, WidgetIntelligenceError> {
+        if !value.is_finite() {
+            return Err(WidgetIntelligenceError::ValidationError(format!(
+                "Value for event ID {event_id} must be finite"
+            )));
+        }
+        if self.extractor.extract_widget_metadata(event_id).is_none() {
+            return Err(WidgetIntelligenceError::NotFound(format!(
+                "widget description for event ID {event_id}"
+            )));
+        }
+
+        let (stored_now, values) = {
+            let mut recorded = self
+                .recorded_values
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let now = Instant::now();
+            let offered = recorded.limiter.offer(event_id, value, now);
+            let mut values = recorded.limiter.take_due(now);
+            values.extend(offered.map(|value| (event_id, value)));
+
+            if offered.is_none() && recorded.scheduled.insert(event_id) {
+                let due_at = recorded.limiter.due_at(event_id).unwrap_or(now);
+                self.schedule_trailing_store(event_id, due_at);
+            }
+            (offered.is_some(), values)
+        };
+
+        Self::learn_values(&self.system, &self.extractor, &values).await?;
+        Ok(stored_now)
+    }
+
+    /// Stores the value held back for `event_id` once it is due, unless a later call or
+    /// `flush_widget_values` stored it first
+    ///
+    /// The timer holds the engine weakly, so a dropped service is not kept open by it.
+    fn schedule_trailing_store(&self, event_id: i64, due_at: Instant) {
+        let system: Weak<_> = Arc::downgrade(&self.system);
+        let extractor = self.extractor.clone();
+        let recorded_values = self.recorded_values.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep_until(due_at.into()).await;
+
+            let values = {
+                let mut recorded = recorded_values
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                recorded.scheduled.remove(&event_id);
+                recorded.limiter.take_due(Instant::now())
+            };
+            let Some(system) = system.upgrade() else {
+                return;
+            };
+            if let Err(e) = Self::learn_values(&system, &extractor, &values).await {
+                log::warn!("Failed to learn held-back value of event ID {event_id}: {e}");
+            }
+        });
+    }
+
+    /// Stores the values `record_widget_value` is holding back, e.g. when a knob is
+    /// released or the app closes; returns how many were learned
+    pub async fn flush_widget_values(&self) -> Result<usize, WidgetIntelligenceError> {
+        let values = self
+            .recorded_values
             .lock()
-            .map_err(|_| "Failed to lock extractor")?;
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .limiter
+            .take_all(Instant::now());
+
+        Self::learn_values(&self.system, &self.extractor, &values).await
+    }
+
+    async fn learn_values(
+        system: &RwLock<crate::PersistentWidgetSuggestionEngine>,
+        extractor: &crate::KymaWidgetExtractor,
+        values: &[(i64, f64)],
+    ) -> Result<usize, WidgetIntelligenceError> {
+        // Values are learned normalized, like preset values, unless the widget has no range
+        let widgets: Vec<crate::Widget> = values
+            .iter()
+            .filter_map(|&(event_id, value)| {
+                let metadata = extractor.extract_widget_metadata(event_id)?;
+                let normalized = metadata.normalize_value(value).unwrap_or(value);
+                extractor.create_training_widget(event_id, normalized)
+            })
+            .collect();
+        if widgets.is_empty() {
+            return Ok(0);
+        }
+
+        let count = widgets.len();
+        system.write().await.store_widgets(widgets)?;
+        log::trace!("Learned {count} recorded widget values");
+        Ok(count)
+    }
+
+    pub async fn save_preset_and_learn(
+        &self,
+        preset_data: PresetData,
+    ) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+        let mut system = self.system.write().await;
 
         let event_values: HashMap<i64, f64> = preset_data
             .widget_values
@@ -96,47 +324,92 @@ impl StandaloneIntelligenceService {
             .filter_map(|(k, v)| k.parse::<i64>().ok().map(|id| (id, v)))
             .collect();
 
-        let mut widget_values = Vec::new();
-        for (event_id, current_value) in &event_values {
-            if let Some(training_widget) =
-                extractor.create_training_widget(*event_id, *current_value)
-            {
-                system
-                    .store_widget(training_widget.clone())
-                    .map_err(|e| format!("Failed to store widget: {e:?}"))?;
-
-                widget_values.push(crate::WidgetValue {
-                    widget_id: event_id.to_string(),
-                    label: training_widget.label,
-                    value: *current_value,
-                    confidence: 1.0,
-                });
-            }
+        let mut preset = self
+            .extractor
+            .snapshot_preset(&preset_data.name, &event_values);
+        preset.description = preset_data.description;
+        preset.created_by = preset_data.created_by;
+
+        Self::learn_preset(&mut system, &self.extractor, preset)?;
+
+        Ok(self.intelligence_stats(&system))
+    }
+
+    /// Deletes a preset and its history, returning the stats without it
+    pub async fn delete_preset(
+        &self,
+        name: String,
+    ) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+        let mut system = self.system.write().await;
+
+        if system.delete_preset(&name)?.is_none() {
+            return Err(WidgetIntelligenceError::NotFound(format!(
+                "preset '{name}'"
+            )));
         }
+        log::debug!("Deleted preset: {name}");
 
-        let preset = crate::Preset {
-            name: preset_data.name,
-            description: preset_data.description,
-            widget_values,
-            created_by: preset_data.created_by,
-            usage_count: 1,
-            last_used: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        };
+        Ok(self.intelligence_stats(&system))
+    }
+
+    /// Labels live values from the cached widget descriptions, then learns and saves them
+    /// as a preset, returning the preset as stored
+    pub async fn snapshot_preset(
+        &self,
+        name: String,
+        values: HashMap<i64, f64>,
+    ) -> Result<crate::Preset, WidgetIntelligenceError> {
+        let mut system = self.system.write().await;
+
+        let preset = self.extractor.snapshot_preset(&name, &values);
+        Self::learn_preset(&mut system, &self.extractor, preset)?;
 
         system
-            .store_preset(preset)
-            .map_err(|e| format!("Failed to store preset: {e:?}"))?;
+            .engine
+            .presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                WidgetIntelligenceError::ValidationError(format!(
+                    "No valid values to store in preset: {name}"
+                ))
+            })
+    }
 
-        let stats = system.get_stats();
-        Ok(IntelligenceStats {
-            total_widgets: stats.get("total_widgets").copied().unwrap_or(0),
-            total_presets: stats.get("total_presets").copied().unwrap_or(0),
-            last_updated: chrono::Utc::now().to_rfc3339(),
-            cache_size: extractor.cache_size(),
-        })
+    /// Trains on a preset's values and stores it, leaving out values outside their
+    /// widget's cached range
+    fn learn_preset(
+        system: &mut crate::PersistentWidgetSuggestionEngine,
+        extractor: &crate::KymaWidgetExtractor,
+        mut preset: crate::Preset,
+    ) -> Result<(), WidgetIntelligenceError> {
+        let report = extractor.validate_preset(&preset);
+        let training_widgets: Vec<crate::Widget> = preset
+            .widget_values
+            .iter()
+            .filter_map(|widget_value| {
+                let event_id = widget_value.widget_id.parse::<i64>().ok()?;
+                if report.is_out_of_range(event_id) {
+                    return None;
+                }
+                extractor.create_training_widget(event_id, widget_value.value)
+            })
+            .collect();
+
+        preset.widget_values.retain(|widget_value| {
+            widget_value
+                .widget_id
+                .parse::<i64>()
+                .map_or(true, |event_id| !report.is_out_of_range(event_id))
+        });
+
+        // Widgets and preset are committed together so a failure cannot leave only half
+        if preset.widget_values.is_empty() {
+            system.store_widgets(training_widgets)
+        } else {
+            system.store_widgets_with_preset(training_widgets, preset)
+        }
     }
 
     pub async fn get_widget_value_suggestions(
@@ -144,12 +417,103 @@ impl StandaloneIntelligenceService {
         event_id: i64,
         partial_label: Option<String>,
         display_type: Option<String>,
-    ) -> Result<Vec<SuggestionResponse>, String> {
-        let system = self
-            .system
-            .lock()
-            .map_err(|_| "Failed to lock intelligence system")?;
+    ) -> Result<Vec<SuggestionResponse>, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        let responses = self.suggestions_for(&system, event_id, partial_label, display_type);
+        log::debug!(
+            "Generated {} suggestions for event ID: {}",
+            responses.len(),
+            event_id
+        );
+        Ok(responses)
+    }
+
+    /// Suggestions for many widgets under one lock, e.g. a whole VCS page, keyed by event
+    /// ID; a widget requested twice gets the suggestions of its last request
+    pub async fn get_widget_value_suggestions_batch(
+        &self,
+        requests: Vec<(i64, Option<String>, Option<String>)>,
+    ) -> Result<HashMap<i64, Vec<SuggestionResponse>>, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        let responses: HashMap<i64, Vec<SuggestionResponse>> = requests
+            .into_iter()
+            .map(|(event_id, partial_label, display_type)| {
+                let suggestions =
+                    self.suggestions_for(&system, event_id, partial_label, display_type);
+                (event_id, suggestions)
+            })
+            .collect();
+
+        log::debug!("Generated suggestions for {} event IDs", responses.len());
+        Ok(responses)
+    }
+
+    /// What is known about a widget in one response: a summary of its learned values and
+    /// of the presets it appears in, and the top suggested values with their confidences
+    ///
+    /// `label` defaults to the label of the cached description. `insights` is `None`
+    /// when nothing was learned about the widget yet.
+    pub async fn get_widget_insight(
+        &self,
+        event_id: i64,
+        label: Option<String>,
+    ) -> Result<WidgetInsightResponse, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        let metadata = self.extractor.extract_widget_metadata(event_id);
+        let label = label.or_else(|| metadata.as_ref().and_then(|m| m.label.clone()));
+        let display_type = metadata.and_then(|m| m.display_type);
+
+        let mut lines = Vec::new();
+        // Over every observation of every record with the event ID, without the label's
+        // priors or outliers
+        let stats = u64::try_from(event_id)
+            .ok()
+            .and_then(|event_id| system.engine.event_value_stats(event_id));
+        if let Some(stats) = stats {
+            if let [p25, median, p75] = stats.percentiles[..] {
+                lines.push(format!(
+                    "{} values learned: mean {:.2}, median {:.2}, middle half {:.2} to {:.2}",
+                    stats.count, stats.mean, median, p25, p75
+                ));
+            }
+        }
+
+        let widget = crate::Widget {
+            label: label.clone(),
+            event_id: u64::try_from(event_id).ok(),
+            ..Default::default()
+        };
+        lines.extend(
+            system
+                .get_preset_insights(&widget)
+                .iter()
+                .take(MAX_PRESET_INSIGHT_LINES)
+                .map(ToString::to_string),
+        );
+
+        let (suggested_values, confidence_scores) = self
+            .suggestions_for(&system, event_id, label, display_type)
+            .into_iter()
+            .filter_map(|s| s.suggested_value.map(|value| (value, s.confidence)))
+            .unzip();
 
+        Ok(WidgetInsightResponse {
+            insights: (!lines.is_empty()).then(|| lines.join("\n")),
+            suggested_values,
+            confidence_scores,
+        })
+    }
+
+    fn suggestions_for(
+        &self,
+        system: &crate::PersistentWidgetSuggestionEngine,
+        event_id: i64,
+        partial_label: Option<String>,
+        display_type: Option<String>,
+    ) -> Vec<SuggestionResponse> {
         let partial_widget = crate::Widget {
             label: partial_label,
             minimum: None,
@@ -157,47 +521,229 @@ impl StandaloneIntelligenceService {
             current_value: None,
             is_generated: None,
             display_type,
-            event_id: Some(event_id as u64),
+            event_id: u64::try_from(event_id).ok(),
             values: Vec::new(),
+            value_kind: None,
+            taper: None,
         };
 
-        let suggestions = system.get_suggestions(&partial_widget, 5);
+        let mut suggestions = system.get_suggestions(&partial_widget, 5);
+
+        // Without a learning history the widget's own default beats similar widgets
+        let learned = u64::try_from(event_id).is_ok_and(|id| system.engine.has_event_id(id));
+        if !learned {
+            if let Some(metadata) = self.extractor.extract_widget_metadata(event_id) {
+                if let Some(default_value) = metadata.normalized_default() {
+                    let widget = metadata.to_widget(metadata.default_value.unwrap_or_default());
+                    suggestions.insert(
+                        0,
+                        system
+                            .engine
+                            .default_value_suggestion(&widget, default_value),
+                    );
+                    suggestions.truncate(5);
+                }
+            }
+        }
+
+        let _ = self.events.send(IntelligenceEvent::SuggestionServed {
+            event_id,
+            count: suggestions.len(),
+        });
 
-        let responses: Vec<SuggestionResponse> = suggestions
+        suggestions
             .into_iter()
             .map(|suggestion| SuggestionResponse {
                 suggested_value: suggestion.suggested_value,
                 confidence: suggestion.confidence,
                 alternative_values: suggestion.alternative_values,
-                reason: suggestion.reason,
+                reason: suggestion.reason.to_string(),
+                reason_kind: suggestion.reason,
+                flip_probability: suggestion.flip_probability,
             })
-            .collect();
+            .collect()
+    }
 
-        log::debug!(
-            "Generated {} suggestions for event ID: {}",
-            responses.len(),
-            event_id
-        );
-        Ok(responses)
+    pub async fn recall_preset(
+        &self,
+        name: String,
+    ) -> Result<HashMap<i64, f64>, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        system
+            .recall_preset(&name, Some(&self.extractor))
+            .ok_or_else(|| WidgetIntelligenceError::NotFound(format!("preset '{name}'")))
     }
 
-    pub async fn get_intelligence_stats(&self) -> Result<IntelligenceStats, String> {
-        let system = self
-            .system
-            .lock()
-            .map_err(|_| "Failed to lock intelligence system")?;
+    /// A preset's values as raw Kyma values by event ID, ready to send, counting the use
+    /// towards its `usage_count` and `last_used`
+    pub async fn apply_preset(
+        &self,
+        name: String,
+    ) -> Result<HashMap<i64, f64>, WidgetIntelligenceError> {
+        let mut system = self.system.write().await;
 
-        let extractor = self
-            .extractor
+        system
+            .apply_preset(&name, Some(&self.extractor))?
+            .ok_or_else(|| WidgetIntelligenceError::NotFound(format!("preset '{name}'")))
+    }
+
+    /// Everything stored for a preset, for a preset browser's detail view
+    pub async fn get_preset(&self, name: String) -> Result<crate::Preset, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        system
+            .engine
+            .presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| WidgetIntelligenceError::NotFound(format!("preset '{name}'")))
+    }
+
+    pub async fn export_preset_kyma(
+        &self,
+        name: String,
+    ) -> Result<String, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        let json = system
+            .export_preset_kyma(&name, Some(&self.extractor))
+            .ok_or_else(|| WidgetIntelligenceError::NotFound(format!("preset '{name}'")))?;
+        serde_json::to_string_pretty(&json)
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))
+    }
+
+    /// The learned widgets as Kyma widget descriptions with suggested default values
+    pub async fn export_widgets_kyma(&self) -> Result<String, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        serde_json::to_string_pretty(&system.export_kyma_json())
+            .map_err(|e| WidgetIntelligenceError::SerializationError(e.to_string()))
+    }
+
+    /// Learns from the presets of an exported Kyma preset file and stores them, returning
+    /// the names of those stored
+    ///
+    /// Values of widgets without a cached description are left out, so the sounds'
+    /// descriptions should be cached first. Presets left without values are skipped.
+    pub async fn import_kyma_presets(
+        &self,
+        path: String,
+    ) -> Result<Vec<String>, WidgetIntelligenceError> {
+        let mut system = self.system.write().await;
+
+        let mut imported = Vec::new();
+        for import in self.extractor.import_preset_file(&path)? {
+            if !import.unresolved.is_empty() {
+                log::debug!(
+                    "Preset '{}': no cached description for {}",
+                    import.preset.name,
+                    import.unresolved.join(", ")
+                );
+            }
+            if import.preset.widget_values.is_empty() {
+                continue;
+            }
+
+            let name = import.preset.name.clone();
+            Self::learn_preset(&mut system, &self.extractor, import.preset)?;
+            imported.push(name);
+        }
+
+        log::info!("Imported {} presets from {path}", imported.len());
+        Ok(imported)
+    }
+
+    pub async fn get_intelligence_stats(
+        &self,
+    ) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        Ok(self.intelligence_stats(&system))
+    }
+
+    /// Factory reset: wipes widgets, presets, metadata and the description cache, flushes
+    /// and returns the fresh stats
+    ///
+    /// `confirm_token` must be `CLEAR_ALL_DATA_CONFIRMATION`; anything else is rejected
+    /// and leaves the data as it is.
+    pub async fn clear_all_data(
+        &self,
+        confirm_token: String,
+    ) -> Result<IntelligenceStats, WidgetIntelligenceError> {
+        if confirm_token != CLEAR_ALL_DATA_CONFIRMATION {
+            return Err(WidgetIntelligenceError::ValidationError(format!(
+                "Clearing all data needs the confirmation token '{CLEAR_ALL_DATA_CONFIRMATION}'"
+            )));
+        }
+
+        let mut system = self.system.write().await;
+        system.clear_all_data()?;
+        self.extractor.clear_cache();
+        self.recorded_values
             .lock()
-            .map_err(|_| "Failed to lock extractor")?;
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .limiter
+            .clear();
+        log::info!("Cleared all widget intelligence data");
 
+        Ok(self.intelligence_stats(&system))
+    }
+
+    fn intelligence_stats(
+        &self,
+        system: &crate::PersistentWidgetSuggestionEngine,
+    ) -> IntelligenceStats {
         let stats = system.get_stats();
-        Ok(IntelligenceStats {
+        IntelligenceStats {
             total_widgets: stats.get("total_widgets").copied().unwrap_or(0),
-            total_presets: stats.get("total_presets").copied().unwrap_or(0), // <- Fixed: use "total_presets"
+            total_presets: stats.get("total_presets").copied().unwrap_or(0),
             last_updated: chrono::Utc::now().to_rfc3339(),
-            cache_size: extractor.cache_size(),
-        })
+            cache_size: self.extractor.cache_size(),
+        }
+    }
+
+    pub async fn list_widgets(
+        &self,
+        offset: usize,
+        limit: usize,
+        sort: crate::WidgetSort,
+    ) -> Result<crate::Page<WidgetSummaryResponse>, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        Ok(system
+            .list_widgets(offset, limit, sort)
+            .map(|record| WidgetSummaryResponse {
+                id: record.id,
+                label: record.widget.label.clone(),
+                event_id: record.widget.event_id,
+                display_type: record.widget.display_type.clone(),
+                minimum: record.widget.minimum,
+                maximum: record.widget.maximum,
+                frequency: record.frequency,
+                last_seen: record.last_seen,
+                observed_values: record.widget.values.len(),
+            }))
+    }
+
+    pub async fn list_presets(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<crate::Page<PresetSummaryResponse>, WidgetIntelligenceError> {
+        let system = self.system.read().await;
+
+        Ok(system
+            .list_presets(offset, limit)
+            .map(|preset| PresetSummaryResponse {
+                name: preset.name.clone(),
+                description: preset.description.clone(),
+                tags: preset.tags.clone(),
+                favorite: preset.favorite,
+                usage_count: preset.usage_count,
+                last_used: preset.last_used,
+                widget_count: preset.widget_values.len(),
+            }))
     }
 }
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Physical unit of a widget's values, parsed from the `units` of a Kyma description
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Units {
+    Hertz,
+    Decibels,
+    Seconds,
+    Milliseconds,
+    Cents,
+    Semitones,
+    /// Frequency or amplitude ratio, e.g. a transposition or gain factor
+    Ratio,
+    Percent,
+    BeatsPerMinute,
+    /// Any other unit, kept as written
+    Other(String),
+}
+
+impl Units {
+    /// Recognizes the usual spellings case-insensitively; `None` for a blank string
+    pub fn parse(units: &str) -> Option<Self> {
+        let trimmed = units.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        Some(match trimmed.to_lowercase().as_str() {
+            "hz" | "hertz" => Units::Hertz,
+            "db" | "decibel" | "decibels" => Units::Decibels,
+            "s" | "sec" | "secs" | "second" | "seconds" => Units::Seconds,
+            "ms" | "msec" | "millisecond" | "milliseconds" => Units::Milliseconds,
+            "cent" | "cents" | "ct" => Units::Cents,
+            "st" | "semi" | "semitone" | "semitones" => Units::Semitones,
+            "ratio" | "x" | "×" => Units::Ratio,
+            "%" | "percent" => Units::Percent,
+            "bpm" => Units::BeatsPerMinute,
+            _ => Units::Other(trimmed.to_string()),
+        })
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            Units::Hertz => "Hz",
+            Units::Decibels => "dB",
+            Units::Seconds => "s",
+            Units::Milliseconds => "ms",
+            Units::Cents => "cents",
+            Units::Semitones => "st",
+            Units::Ratio => "×",
+            Units::Percent => "%",
+            Units::BeatsPerMinute => "BPM",
+            Units::Other(units) => units,
+        }
+    }
+
+    /// Converts `value` to `target`, or `None` if the two measure different things.
+    /// Ratios convert to intervals as frequency ratios and to decibels as amplitude
+    /// ratios.
+    pub fn convert(&self, value: f64, target: &Units) -> Option<f64> {
+        use Units::*;
+
+        if self == target {
+            return Some(value);
+        }
+
+        let converted = match (self, target) {
+            (Seconds, Milliseconds) => value * 1000.0,
+            (Milliseconds, Seconds) => value / 1000.0,
+            (Semitones, Cents) => value * 100.0,
+            (Cents, Semitones) => value / 100.0,
+            (Ratio, Cents) if value > 0.0 => 1200.0 * value.log2(),
+            (Ratio, Semitones) if value > 0.0 => 12.0 * value.log2(),
+            (Cents, Ratio) => 2f64.powf(value / 1200.0),
+            (Semitones, Ratio) => 2f64.powf(value / 12.0),
+            (Ratio, Decibels) if value > 0.0 => 20.0 * value.log10(),
+            (Decibels, Ratio) => 10f64.powf(value / 20.0),
+            (Ratio, Percent) => value * 100.0,
+            (Percent, Ratio) => value / 100.0,
+            _ => return None,
+        };
+        Some(converted)
+    }
+
+    /// `value` with its unit, rounded for display, e.g. "630 Hz", "1.2 kHz" or "-6.0 dB"
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Units::Hertz if value.abs() >= 1000.0 => format!("{} kHz", trim(value / 1000.0, 2)),
+            Units::Hertz if value.abs() >= 100.0 => format!("{value:.0} Hz"),
+            Units::Hertz => format!("{} Hz", trim(value, 1)),
+            Units::Decibels => format!("{value:.1} dB"),
+            Units::Seconds if value.abs() < 1.0 => format!("{:.0} ms", value * 1000.0),
+            Units::Seconds => format!("{} s", trim(value, 2)),
+            Units::Milliseconds if value.abs() >= 1000.0 => {
+                format!("{} s", trim(value / 1000.0, 2))
+            }
+            Units::Milliseconds => format!("{value:.0} ms"),
+            Units::Cents => format!("{value:+.0} cents"),
+            Units::Semitones => format!("{} st", signed(trim(value, 1), value)),
+            Units::Ratio => format!("×{}", trim(value, 2)),
+            Units::Percent => format!("{value:.0}%"),
+            Units::BeatsPerMinute => format!("{} BPM", trim(value, 1)),
+            Units::Other(units) => format!("{} {units}", trim(value, 2)),
+        }
+    }
+
+    /// Like `format`, marked as approximate, e.g. "≈ 630 Hz" for a suggested value
+    pub fn format_approx(&self, value: f64) -> String {
+        format!("≈ {}", self.format(value))
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// `value` with at most `decimals` decimals and no trailing zeros
+fn trim(value: f64, decimals: usize) -> String {
+    let formatted = format!("{value:.decimals$}");
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        formatted
+    }
+}
+
+fn signed(formatted: String, value: f64) -> String {
+    if value > 0.0 {
+        format!("+{formatted}")
+    } else {
+        formatted
+    }
+}
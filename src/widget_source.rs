@@ -0,0 +1,35 @@
+//! Where widget descriptions come from
+//!
+//! Learning only needs to turn an event ID and a value into a `Widget`, and a few
+//! maintenance tasks need the metadata of the known widgets. `WidgetSource` is that
+//! interface. `KymaWidgetExtractor` is the provider for Kyma; other OSC or JSON control
+//! systems can plug in their own and use the same OSC listener and persistence helpers.
+
+use crate::kyma_extractor::WidgetMetadata;
+use crate::similarity_engine::Widget;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A cache of widget descriptions keyed by event ID
+///
+/// Implementations are shared between threads, e.g. with a running OSC listener, so
+/// every method takes `&self`.
+pub trait WidgetSource: Send + Sync {
+    /// Caches a description in the source's own format; descriptions it cannot key by an
+    /// event ID are ignored
+    fn cache_description(&self, description: HashMap<String, Value>);
+
+    /// Event IDs of the cached descriptions, in no particular order
+    fn cached_event_ids(&self) -> Vec<i64>;
+
+    /// What is known about the widget of `event_id`, `None` if it is not cached
+    fn widget_metadata(&self, event_id: i64) -> Option<WidgetMetadata>;
+
+    /// A widget to learn `current_value` from; `None` for event IDs without a cached
+    /// description and for aggregates, whose values are learned per sub-control
+    fn create_training_widget(&self, event_id: i64, current_value: f64) -> Option<Widget> {
+        self.widget_metadata(event_id)
+            .filter(|metadata| metadata.sub_control_ids.is_empty())
+            .map(|metadata| metadata.to_widget(current_value))
+    }
+}
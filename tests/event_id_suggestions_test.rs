@@ -128,3 +128,37 @@ fn test_event_id_suggestions() {
 
     println!("\n{}", "TEST PASSED".bold().green());
 }
+
+#[test]
+fn test_unknown_event_id_falls_back_to_label() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "UNKNOWN EVENT ID FALLBACK TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine
+        .records
+        .push(create_kyma_widget(42, "Volume", 0.0, 1.0, 0.7).into());
+    engine
+        .records
+        .push(create_kyma_widget(43, "Cutoff", -24.0, 24.0, 12.0).into());
+
+    // A bare event ID lookup has nothing to go on
+    assert!(engine.get_suggestions_by_event_id(999, 3).is_empty());
+
+    // With a label available, unknown event IDs fall back to label similarity
+    let query = Widget::simplified(Some("Volume".to_string()), Some(999), vec![]);
+    let suggestions = engine.get_suggestions(&query, 3);
+
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Got {} suggestions for unknown event ID 999", suggestions.len()).cyan()
+    );
+
+    assert!(!suggestions.is_empty());
+    assert_eq!(suggestions[0].widget.label.as_deref(), Some("Volume"));
+    assert_eq!(suggestions[0].suggested_value, Some(0.7));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
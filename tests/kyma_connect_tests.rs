@@ -0,0 +1,74 @@
+#![cfg(feature = "kyma-connect")]
+
+use colored::*;
+use serde_json::json;
+use std::sync::Arc;
+use widget_intelligence::*;
+
+#[test]
+fn test_kyma_connect_session() {
+    control::set_override(true);
+
+    println!("\n{}", "KYMA CONNECT SESSION TEST".bold().underline());
+
+    let extractor = Arc::new(KymaWidgetExtractor::new());
+    let config = KymaConnectConfig::new("ws://127.0.0.1:8080");
+    let describe_request = config.describe_request.to_string();
+    let session = KymaConnectSession::new(config, extractor.clone());
+
+    let stale = json!([{ "concreteEventID": 1, "label": "Old" }]).to_string();
+    session.handle_message(&stale);
+    assert_eq!(extractor.cache_size(), 1);
+
+    // Loading a sound clears the cache and asks for the new descriptions
+    let (events, reply) = session.handle_message(r#"{ "event": "/soundLoaded" }"#);
+    assert!(matches!(events[..], [KymaConnectEvent::SoundLoaded]));
+    assert_eq!(reply, Some(describe_request));
+    assert_eq!(extractor.cache_size(), 0);
+
+    let response = json!({
+        "widgets": [
+            { "concreteEventID": 10, "label": "Cutoff", "minimum": 20.0, "maximum": 20000.0 },
+            { "concreteEventID": 11, "label": "Resonance", "minimum": 0.0, "maximum": 1.0 }
+        ]
+    });
+    let (events, reply) = session.handle_message(&response.to_string());
+    println!("{} {}", "→".green(), format!("{events:?}").cyan());
+    match &events[..] {
+        [KymaConnectEvent::WidgetsCached(report), KymaConnectEvent::WidgetsChanged(diff)] => {
+            assert_eq!(report.cached, vec![10, 11]);
+            assert_eq!(
+                diff.removed,
+                vec![CacheDiffEntry {
+                    event_id: 1,
+                    label: Some("Old".to_string())
+                }]
+            );
+            assert_eq!(diff.added.len(), 2);
+        }
+        other => panic!("Expected WidgetsCached and WidgetsChanged, got {other:?}"),
+    }
+    assert!(reply.is_none());
+    assert_eq!(extractor.cache_size(), 2);
+
+    // Other notifications are ignored, broken ones reported
+    let (events, _) = session.handle_message(r#"{ "event": "/vcs", "eventID": 10 }"#);
+    assert!(events.is_empty());
+    let (events, _) = session.handle_message("{");
+    assert!(matches!(events[..], [KymaConnectEvent::Error(_)]));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_connect_kyma_unreachable() {
+    control::set_override(true);
+
+    let extractor = Arc::new(KymaWidgetExtractor::new());
+    // Nothing listens on the discard port
+    let result = connect_kyma(KymaConnectConfig::new("ws://127.0.0.1:9"), extractor).await;
+    assert!(matches!(
+        result,
+        Err(WidgetIntelligenceError::ConnectionError(_))
+    ));
+}
@@ -14,7 +14,7 @@ fn test_kyma_extractor_basic() {
     colored::control::set_override(true);
     println!("\n{}", "KYMA EXTRACTOR BASIC TEST".bold().underline());
 
-    let mut extractor = KymaWidgetExtractor::new();
+    let extractor = KymaWidgetExtractor::new();
 
     let kyma_data = json!({
         "concreteEventID": 100,
@@ -60,11 +60,18 @@ fn test_widget_metadata() {
     let metadata = WidgetMetadata {
         event_id: 100,
         label: Some("Test Widget".to_string()),
+        event_name: None,
+        aggregate_event_id: None,
+        sub_control_ids: Vec::new(),
         display_type: Some("knob".to_string()),
         minimum: Some(0.0),
         maximum: Some(100.0),
         default_value: Some(50.0),
         is_generated: Some(false),
+        value_kind: None,
+        taper: None,
+        grid: None,
+        layout: None,
         units: Some("dB".to_string()),
         category: Some("Audio".to_string()),
         description: Some("Test widget description".to_string()),
@@ -181,7 +188,7 @@ fn test_invalid_json() {
 fn test_extract_all_widgets() {
     println!("\n{}", "WIDGET EXTRACTION TEST".bold().underline());
 
-    let mut extractor = KymaWidgetExtractor::new();
+    let extractor = KymaWidgetExtractor::new();
 
     // Create test data
     let test_widgets = vec![("Amp_01", 0.0, 1.0, 13755), ("Pan", -1.0, 1.0, 13756)];
@@ -227,3 +234,1318 @@ fn test_extract_all_widgets() {
 
     println!("\n{}", "✓ Widget extraction test passed".green());
 }
+
+#[test]
+fn test_cache_widget_descriptions_from_json() {
+    colored::control::set_override(true);
+    println!("\n{}", "BATCH DESCRIPTION CACHING TEST".bold().underline());
+
+    let response = json!({
+        "vcs": {
+            "widgets": [
+                { "concreteEventID": 200, "label": "Cutoff", "minimum": 20.0, "maximum": 20000.0 },
+                { "label": "No event ID" },
+                "not an object",
+                { "concreteEventID": 201, "label": "Resonance", "minimum": 0.0, "maximum": 1.0 }
+            ]
+        }
+    });
+
+    let extractor = KymaWidgetExtractor::new();
+    let report = extractor
+        .cache_widget_descriptions_from_json(&response.to_string())
+        .unwrap();
+
+    println!("{} Cached {:?}", "→".green(), report.cached);
+    for error in &report.errors {
+        println!(
+            "{} {}",
+            " ".repeat(4),
+            format!("#{}: {}", error.index, error.message).cyan()
+        );
+    }
+
+    assert_eq!(report.cached, vec![200, 201]);
+    assert_eq!(
+        report.errors.iter().map(|e| e.index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert!(!report.is_complete());
+    assert_eq!(extractor.cache_size(), 2);
+
+    // A bare array and a single description work as well
+    let report = extractor
+        .cache_widget_descriptions_from_json(r#"[{ "concreteEventID": 202, "label": "Pan" }]"#)
+        .unwrap();
+    assert!(report.is_complete());
+    let report = extractor
+        .cache_widget_descriptions_from_json(r#"{ "concreteEventID": 203, "label": "Gain" }"#)
+        .unwrap();
+    assert_eq!(report.cached, vec![203]);
+    assert_eq!(extractor.cache_size(), 4);
+
+    let report = extractor
+        .cache_widget_descriptions_from_json(r#"{ "status": "ok" }"#)
+        .unwrap();
+    assert!(report.cached.is_empty());
+    assert_eq!(report.skipped[0].reason, SkipReason::Status);
+    assert!(extractor
+        .cache_widget_descriptions_from_json(r#"{ "result": "ok" }"#)
+        .is_err());
+    assert!(extractor.cache_widget_descriptions_from_json("[").is_err());
+
+    println!("\n{}", "✓ Batch caching test passed".green());
+}
+
+#[test]
+fn test_taper_and_grid_extraction() {
+    colored::control::set_override(true);
+    println!("\n{}", "TAPER AND GRID TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let descriptions = json!([
+        {
+            "concreteEventID": 300,
+            "label": "Cutoff",
+            "minimum": 20.0,
+            "maximum": 20000.0,
+            "taper": "log"
+        },
+        {
+            "concreteEventID": 301,
+            "label": "Voices",
+            "minimum": 1.0,
+            "maximum": 8.0,
+            "grid": 1.0
+        },
+        {
+            "concreteEventID": 302,
+            "label": "Detune",
+            "minimum": 0.0,
+            "maximum": 1.0,
+            "grid": 0.3
+        }
+    ]);
+    extractor
+        .cache_widget_descriptions_from_json(&descriptions.to_string())
+        .unwrap();
+
+    let cutoff = extractor.extract_widget_metadata(300).unwrap();
+    println!("{} {}", "→".green(), format!("{cutoff:?}").cyan());
+    assert_eq!(cutoff.taper, Some(Taper::Logarithmic));
+    assert_eq!(cutoff.value_kind, None);
+    // 632 Hz is halfway along a 20 Hz - 20 kHz log fader
+    let position = cutoff.fader_position(632.456).unwrap();
+    assert!((position - 0.5).abs() < 1e-3);
+    let value = cutoff.value_at_fader_position(0.5).unwrap();
+    assert!((value - 632.456).abs() < 0.01);
+    // Normalized values stay linear in the range
+    assert_eq!(cutoff.denormalize_value(0.5), Some(10010.0));
+
+    let voices = extractor.extract_widget_metadata(301).unwrap();
+    assert_eq!(voices.grid, Some(1.0));
+    assert_eq!(voices.value_kind, Some(ValueKind::Stepped { steps: 8 }));
+    assert_eq!(voices.denormalize_value(0.5), Some(5.0));
+    assert_eq!(voices.normalize_value(3.2), voices.normalize_value(3.0));
+
+    let widget = extractor.create_training_widget(301, 4.0).unwrap();
+    assert_eq!(widget.value_kind, Some(ValueKind::Stepped { steps: 8 }));
+    let widget = extractor.create_training_widget(300, 440.0).unwrap();
+    assert_eq!(widget.taper, Some(Taper::Logarithmic));
+
+    // A grid that does not divide the range still snaps, but is not stepped
+    let detune = extractor.extract_widget_metadata(302).unwrap();
+    assert_eq!(detune.value_kind, None);
+    assert!((detune.snap_to_grid(0.95) - 0.9).abs() < 1e-9);
+    // Past the end it lands on the last grid position, not on the maximum
+    assert!((detune.snap_to_grid(1.2) - 0.9).abs() < 1e-9);
+    assert_eq!(detune.snap_to_grid(-0.5), 0.0);
+    assert_eq!(voices.snap_to_grid(9.0), 8.0);
+
+    println!("\n{}", "✓ Taper and grid test passed".green());
+}
+
+#[test]
+fn test_event_names_survive_recompilation() {
+    colored::control::set_override(true);
+    println!("\n{}", "EVENT NAME TEST".bold().underline());
+
+    assert_eq!(parse_event_name("/vcs/!Amp"), Some("Amp".to_string()));
+    assert_eq!(
+        parse_event_name("!Cutoff_01"),
+        Some("Cutoff_01".to_string())
+    );
+    assert_eq!(parse_event_name("/vcs/Amp"), None);
+    assert_eq!(parse_event_name("/vcs/!"), None);
+
+    let extractor = KymaWidgetExtractor::new();
+    let description = |event_id: i64| -> HashMap<String, Value> {
+        serde_json::from_value(json!({
+            "concreteEventID": event_id,
+            "address": "/vcs/!Amp",
+            "minimum": 0.0,
+            "maximum": 1.0
+        }))
+        .unwrap()
+    };
+
+    extractor.cache_widget_description(description(400));
+    let metadata = extractor.extract_widget_metadata(400).unwrap();
+    println!("{} {}", "→".green(), format!("{metadata:?}").cyan());
+    assert_eq!(metadata.event_name, Some("Amp".to_string()));
+    assert_eq!(metadata.label, Some("Amp".to_string()));
+    assert_eq!(extractor.event_id_for_name("!Amp"), Some(400));
+
+    // The recompiled sound reports a new ID for the same name
+    extractor.cache_widget_description(description(512));
+    assert_eq!(extractor.event_id_for_name("Amp"), Some(512));
+    assert!(extractor.get_cached_description(400).is_none());
+    assert_eq!(extractor.cache_size(), 1);
+
+    // Both IDs train the same label, so learning carries over
+    let widget = extractor.create_training_widget(512, 0.5).unwrap();
+    assert_eq!(widget.label, Some("Amp".to_string()));
+
+    println!("\n{}", "✓ Event name test passed".green());
+}
+
+#[test]
+fn test_aggregate_widgets() {
+    colored::control::set_override(true);
+    println!("\n{}", "AGGREGATE WIDGET TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let aggregate = json!({
+        "concreteEventID": 500,
+        "label": "Harmonics",
+        "isAggregate": true,
+        "children": [
+            { "concreteEventID": 501, "minimum": 0.0, "maximum": 1.0 },
+            { "concreteEventID": 502, "label": "Fundamental", "minimum": 0.0, "maximum": 1.0 },
+            { "label": "No event ID" }
+        ]
+    });
+    let report = extractor
+        .cache_widget_descriptions_from_json(&aggregate.to_string())
+        .unwrap();
+    assert_eq!(report.cached, vec![500]);
+    assert_eq!(extractor.cache_size(), 3);
+
+    let metadata = extractor.extract_widget_metadata(500).unwrap();
+    assert_eq!(metadata.sub_control_ids, vec![501, 502]);
+
+    let sub_controls = extractor.sub_controls(500);
+    for sub_control in &sub_controls {
+        println!("{} {}", "→".green(), format!("{sub_control:?}").cyan());
+        assert_eq!(sub_control.aggregate_event_id, Some(500));
+    }
+    let labels: Vec<_> = sub_controls.iter().map(|m| m.label.clone()).collect();
+    assert_eq!(
+        labels,
+        vec![
+            Some("Harmonics 1".to_string()),
+            Some("Fundamental".to_string())
+        ]
+    );
+
+    // Values are learned per sub-control, not for the aggregate
+    assert!(extractor.create_training_widget(500, 0.5).is_none());
+    let widget = extractor.create_training_widget(501, 0.5).unwrap();
+    assert_eq!(widget.label, Some("Harmonics 1".to_string()));
+
+    println!("\n{}", "✓ Aggregate widget test passed".green());
+}
+
+#[test]
+fn test_units() {
+    colored::control::set_override(true);
+    println!("\n{}", "UNITS TEST".bold().underline());
+
+    assert_eq!(Units::parse("Hz"), Some(Units::Hertz));
+    assert_eq!(Units::parse(" dB "), Some(Units::Decibels));
+    assert_eq!(
+        Units::parse("furlongs"),
+        Some(Units::Other("furlongs".to_string()))
+    );
+    assert_eq!(Units::parse(""), None);
+
+    assert_eq!(
+        Units::Seconds.convert(0.25, &Units::Milliseconds),
+        Some(250.0)
+    );
+    assert_eq!(Units::Semitones.convert(7.0, &Units::Cents), Some(700.0));
+    let octave = Units::Ratio.convert(2.0, &Units::Cents).unwrap();
+    assert!((octave - 1200.0).abs() < 1e-9);
+    let half = Units::Ratio.convert(0.5, &Units::Decibels).unwrap();
+    assert!((half + 6.0206).abs() < 1e-3);
+    assert_eq!(Units::Hertz.convert(440.0, &Units::Seconds), None);
+
+    for (units, value, expected) in [
+        (Units::Hertz, 630.4, "630 Hz"),
+        (Units::Hertz, 1200.0, "1.2 kHz"),
+        (Units::Decibels, -6.0, "-6.0 dB"),
+        (Units::Seconds, 0.25, "250 ms"),
+        (Units::Milliseconds, 1500.0, "1.5 s"),
+        (Units::Cents, 25.0, "+25 cents"),
+        (Units::Ratio, 1.5, "×1.5"),
+    ] {
+        let formatted = units.format(value);
+        println!("{} {}", "→".green(), formatted.cyan());
+        assert_eq!(formatted, expected);
+    }
+
+    let extractor = KymaWidgetExtractor::new();
+    let description: HashMap<String, Value> = serde_json::from_value(json!({
+        "concreteEventID": 600,
+        "label": "Cutoff",
+        "minimum": 0.0,
+        "maximum": 3500.0,
+        "units": "hz"
+    }))
+    .unwrap();
+    extractor.cache_widget_description(description);
+    let metadata = extractor.extract_widget_metadata(600).unwrap();
+    assert_eq!(metadata.parsed_units(), Some(Units::Hertz));
+    assert_eq!(
+        metadata.format_normalized(0.18),
+        Some("≈ 630 Hz".to_string())
+    );
+
+    println!("\n{}", "✓ Units test passed".green());
+}
+
+#[test]
+fn test_import_kyma_presets() {
+    colored::control::set_override(true);
+    println!("\n{}", "PRESET IMPORT TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    for description in [
+        json!({"concreteEventID": 700, "address": "/vcs/!Cutoff", "minimum": 20.0, "maximum": 20000.0}),
+        json!({"concreteEventID": 701, "label": "Resonance", "minimum": 0.0, "maximum": 1.0}),
+    ] {
+        extractor.cache_widget_description(serde_json::from_value(description).unwrap());
+    }
+
+    // Old presets keyed by event name resolve to the recompiled sound's ID
+    let json_file = json!({
+        "presets": [
+            {"name": "Dark", "description": "Low and round", "values": {"!Cutoff": 400.0, "701": 0.2}},
+            {"values": [{"eventID": 99, "name": "Cutoff", "value": 8000.0}, {"eventID": 5, "value": 1.0}]}
+        ]
+    })
+    .to_string();
+    let imported = extractor.import_presets(&json_file).unwrap();
+    for import in &imported {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{}: {} values, unresolved {:?}",
+                import.preset.name,
+                import.preset.widget_values.len(),
+                import.unresolved
+            )
+            .cyan()
+        );
+    }
+    assert_eq!(imported.len(), 2);
+    assert_eq!(imported[0].preset.name, "Dark");
+    assert_eq!(
+        imported[0].preset.description.as_deref(),
+        Some("Low and round")
+    );
+    assert_eq!(imported[0].preset.widget_values.len(), 2);
+    assert_eq!(imported[0].training_widgets.len(), 2);
+    assert_eq!(imported[1].preset.name, "Imported preset 2");
+    assert_eq!(imported[1].preset.widget_values[0].widget_id, "700");
+    assert_eq!(imported[1].unresolved, vec!["5".to_string()]);
+
+    let xml_file = r#"<?xml version="1.0"?>
+        <presets>
+            <preset name="Bright">
+                <value eventID="700" name="!Cutoff">12000</value>
+                <widget eventID="701" value="0.7"/>
+            </preset>
+        </presets>"#;
+    let imported = extractor.import_presets(xml_file).unwrap();
+    assert_eq!(imported.len(), 1);
+    let widget = &imported[0].training_widgets[0];
+    assert_eq!(widget.label, Some("Cutoff".to_string()));
+    // Presets and training widgets hold the file's values normalized to the range
+    let normalized = (12000.0 - 20.0) / (20000.0 - 20.0);
+    assert!((widget.current_value.unwrap() - normalized).abs() < 1e-9);
+    assert!((imported[0].preset.widget_values[0].value - normalized).abs() < 1e-9);
+    assert_eq!(imported[0].preset.widget_values[1].value, 0.7);
+
+    // A file's only unnamed snapshot is named after the file
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("Morning Pads.json");
+    std::fs::write(&path, r#"{"values": {"701": 0.4}}"#).unwrap();
+    let imported = extractor.import_preset_file(&path).unwrap();
+    assert_eq!(imported[0].preset.name, "Morning Pads");
+
+    assert!(extractor.import_presets("<presets>").is_err());
+    assert!(extractor.import_presets("not a preset").is_err());
+
+    println!("\n{}", "✓ Preset import test passed".green());
+}
+
+#[test]
+fn test_description_validation_report() {
+    colored::control::set_override(true);
+    println!("\n{}", "DESCRIPTION VALIDATION TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let validate = |description: Value| {
+        let report = extractor.validate_description(&serde_json::from_value(description).unwrap());
+        for issue in &report.issues {
+            println!("{} {}", "→".green(), issue.to_string().cyan());
+        }
+        report
+    };
+
+    let report = validate(json!({"concreteEventID": 1, "minimum": 0.0, "maximum": 1.0}));
+    assert!(report.is_clean());
+
+    let report = validate(json!({"concreteEventID": 2, "label": "Pan"}));
+    assert!(report.is_valid());
+    assert_eq!(
+        report.issues,
+        vec![
+            DescriptionIssue::MissingMinimum,
+            DescriptionIssue::MissingMaximum
+        ]
+    );
+
+    let report = validate(json!({"concreteEventID": 3, "minimum": 5.0, "maximum": 5.0}));
+    assert!(!report.is_valid());
+    assert!(matches!(
+        report.issues[0],
+        DescriptionIssue::InvertedRange { .. }
+    ));
+
+    let report = validate(json!({"concreteEventID": 4, "minimum": "low", "maximum": 1.0}));
+    assert!(!report.is_valid());
+    assert!(report.error_message().contains("minimum"));
+
+    let report = validate(json!({"concreteEventID": 5, "minimum": 0.0, "maximum": 1e12}));
+    assert!(report.is_valid());
+    assert!(matches!(
+        report.issues[0],
+        DescriptionIssue::SuspiciousRange { .. }
+    ));
+
+    let report = validate(
+        json!({"concreteEventID": 6, "minimum": 0.0, "maximum": 1.0, "defaultValue": 2.0}),
+    );
+    assert!(matches!(
+        report.issues[0],
+        DescriptionIssue::DefaultOutOfRange { .. }
+    ));
+
+    let report = validate(json!({"minimum": 0.0, "maximum": 1.0}));
+    assert_eq!(report.event_id, None);
+    assert_eq!(report.issues, vec![DescriptionIssue::MissingEventId]);
+
+    // Batch caching rejects errors and reports doubtful descriptions as warnings
+    let extractor = KymaWidgetExtractor::new();
+    let report = extractor
+        .cache_widget_descriptions_from_json(
+            &json!([
+                {"concreteEventID": 10, "label": "Gain", "minimum": 0.0, "maximum": 1.0},
+                {"concreteEventID": 11, "label": "Broken", "minimum": 1.0, "maximum": 0.0},
+                {"concreteEventID": 12, "label": "Pan"}
+            ])
+            .to_string(),
+        )
+        .unwrap();
+    assert_eq!(report.cached, vec![10, 12]);
+    assert_eq!(report.errors[0].index, 1);
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].label, Some("Pan".to_string()));
+
+    let issues = extractor.validate_cache();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].event_id, Some(12));
+
+    println!("\n{}", "✓ Description validation test passed".green());
+}
+
+#[test]
+fn test_streaming_description_ingest() {
+    colored::control::set_override(true);
+    println!("\n{}", "STREAMING INGEST TEST".bold().underline());
+
+    let widgets: Vec<Value> = (0..5000)
+        .map(|i| {
+            json!({
+                "concreteEventID": 10_000 + i,
+                "label": format!("Fader_{i:04}"),
+                "minimum": 0.0,
+                "maximum": 1.0
+            })
+        })
+        .collect();
+    let payload = json!({ "sound": "Big Layout", "vcs": { "widgets": widgets } }).to_string();
+
+    let extractor = KymaWidgetExtractor::new();
+    let report = extractor
+        .cache_widget_descriptions_from_reader(payload.as_bytes())
+        .unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Streamed {} descriptions", report.cached.len()).cyan()
+    );
+    assert_eq!(report.cached.len(), 5000);
+    assert!(report.is_complete());
+    assert_eq!(extractor.cache_size(), 5000);
+
+    // A description with its own `widgets` is an aggregate, not an envelope, wherever
+    // its event ID comes in the object
+    let aggregates = [
+        r#"{"concreteEventID": 50, "label": "Mixer", "isAggregate": true,
+            "widgets": [{"concreteEventID": 51, "minimum": 0.0, "maximum": 1.0}]}"#,
+        r#"{"widgets": [{"concreteEventID": 51, "minimum": 0.0, "maximum": 1.0}],
+            "label": "Mixer", "isAggregate": true, "concreteEventID": 50}"#,
+    ];
+    for aggregate in aggregates {
+        let extractor = KymaWidgetExtractor::new();
+        let report = extractor
+            .cache_widget_descriptions_from_reader(aggregate.as_bytes())
+            .unwrap();
+        assert_eq!(report.cached, vec![50]);
+        assert_eq!(extractor.sub_controls(50).len(), 1);
+        assert_eq!(
+            report.cached,
+            KymaWidgetExtractor::new()
+                .cache_widget_descriptions_from_json(aggregate)
+                .unwrap()
+                .cached
+        );
+    }
+
+    // Entries before a break stay cached
+    let extractor = KymaWidgetExtractor::new();
+    let truncated =
+        r#"[{"concreteEventID": 1, "minimum": 0, "maximum": 1}, {"concreteEventID": 2, "min"#;
+    assert!(extractor
+        .cache_widget_descriptions_from_reader(truncated.as_bytes())
+        .is_err());
+    assert_eq!(extractor.cache_size(), 1);
+
+    let report = extractor
+        .cache_widget_descriptions_from_reader(r#"{ "status": "ok" }"#.as_bytes())
+        .unwrap();
+    assert_eq!(report.skipped[0].reason, SkipReason::Status);
+    assert!(extractor
+        .cache_widget_descriptions_from_reader(r#"{ "result": "ok" }"#.as_bytes())
+        .is_err());
+    assert!(extractor
+        .cache_widget_descriptions_from_reader("[] trailing".as_bytes())
+        .is_err());
+
+    println!("\n{}", "✓ Streaming ingest test passed".green());
+}
+
+#[test]
+fn test_layout_and_nearby_widgets() {
+    colored::control::set_override(true);
+    println!("\n{}", "LAYOUT TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    for description in [
+        json!({"concreteEventID": 1, "label": "Cutoff", "layout": {"x": 0, "y": 0, "width": 40, "height": 100}}),
+        json!({"concreteEventID": 2, "label": "Resonance", "layout": {"left": 50, "top": 0, "right": 90, "bottom": 100}}),
+        json!({"concreteEventID": 3, "label": "Reverb Mix", "x": 600, "y": 400, "width": 40, "height": 100}),
+        json!({"concreteEventID": 4, "label": "Drive", "column": 3, "row": 0}),
+        json!({"concreteEventID": 5, "label": "Unplaced"}),
+    ] {
+        extractor.cache_widget_description(serde_json::from_value(description).unwrap());
+    }
+
+    let layout = extractor
+        .extract_widget_metadata(2)
+        .unwrap()
+        .layout
+        .unwrap();
+    println!("{} {}", "→".green(), format!("{layout:?}").cyan());
+    assert_eq!(layout.width, 40.0);
+    assert_eq!(layout.center(), (70.0, 50.0));
+    assert_eq!(
+        extractor.extract_widget_metadata(4).unwrap().layout,
+        Some(WidgetLayout {
+            x: 3.0,
+            y: 0.0,
+            width: 1.0,
+            height: 1.0
+        })
+    );
+    assert!(extractor
+        .extract_widget_metadata(5)
+        .unwrap()
+        .layout
+        .is_none());
+
+    let nearby = extractor.widgets_near(1);
+    for widget in &nearby {
+        println!(
+            "{} {}",
+            " ".repeat(4),
+            format!("{:?} at {:.1}", widget.label, widget.distance).cyan()
+        );
+    }
+    assert_eq!(nearby[0].event_id, 2);
+    assert_eq!(nearby.last().unwrap().event_id, 3);
+    assert!(nearby.iter().all(|widget| widget.event_id != 5));
+    assert!(extractor.widgets_near(5).is_empty());
+
+    // Adjacent controls move up among equally related widgets
+    let mut engine = WidgetSuggestionEngine::new();
+    for (name, values) in [("A", [0.2, 0.4, 0.6]), ("B", [0.8, 0.1, 0.3])] {
+        engine.store_preset(Preset {
+            name: name.to_string(),
+            description: None,
+            widget_values: [1, 2, 3]
+                .into_iter()
+                .zip(values)
+                .map(|(event_id, value)| WidgetValue {
+                    widget_id: event_id.to_string(),
+                    label: None,
+                    value,
+                    confidence: 1.0,
+                })
+                .collect(),
+            created_by: None,
+            usage_count: 1,
+            last_used: 0,
+            tags: Vec::new(),
+            favorite: false,
+        });
+    }
+    let unbiased = engine.related_widgets(3);
+    assert_eq!(unbiased[0].score, unbiased[1].score);
+    let nearby: Vec<u64> = extractor
+        .widgets_near(3)
+        .iter()
+        .map(|widget| widget.event_id as u64)
+        .collect();
+    let biased = engine.related_widgets_near(3, &nearby);
+    assert_eq!(biased[0].event_id, nearby[0]);
+    assert!(biased[0].score > biased[1].score);
+
+    println!("\n{}", "✓ Layout test passed".green());
+}
+
+#[test]
+fn test_snapshot_with_flagged_values() {
+    colored::control::set_override(true);
+    println!("\n{}", "SNAPSHOT TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    extractor.cache_widget_description(
+        serde_json::from_value(
+            json!({"concreteEventID": 10, "label": "Gain", "minimum": 0.0, "maximum": 1.0}),
+        )
+        .unwrap(),
+    );
+    extractor.cache_widget_description(
+        serde_json::from_value(
+            json!({"concreteEventID": 11, "label": "Pitch", "minimum": -24.0, "maximum": 24.0}),
+        )
+        .unwrap(),
+    );
+
+    let values = HashMap::from([(10, 0.8), (11, 36.0), (12, 0.5)]);
+    let preset = extractor.snapshot(&values, "Live");
+    for value in &preset.widget_values {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{} {:?} = {} (confidence {})",
+                value.widget_id, value.label, value.value, value.confidence
+            )
+            .cyan()
+        );
+    }
+
+    assert_eq!(preset.name, "Live");
+    assert_eq!(preset.widget_values.len(), 3);
+    assert_eq!(preset.widget_values[0].label, Some("Gain".to_string()));
+    assert_eq!(preset.widget_values[0].confidence, 1.0);
+    assert_eq!(preset.widget_values[1].confidence, OUT_OF_RANGE_CONFIDENCE);
+    // Values are normalized like stored presets; 36 lies past the top of -24..24
+    assert_eq!(preset.widget_values[0].value, 0.8);
+    assert_eq!(preset.widget_values[1].value, 1.25);
+    assert_eq!(preset.widget_values[2].label, None);
+    assert_eq!(
+        preset.widget_values[2].confidence,
+        UNCHECKED_VALUE_CONFIDENCE
+    );
+
+    let report = extractor.validate_preset(&preset);
+    assert!(report.is_out_of_range(11));
+    assert_eq!(report.unchecked, vec!["12".to_string()]);
+
+    println!("\n{}", "✓ Snapshot test passed".green());
+}
+
+#[test]
+fn test_bounded_cache_eviction() {
+    colored::control::set_override(true);
+    println!("\n{}", "BOUNDED CACHE TEST".bold().underline());
+
+    let description = |event_id: i64| -> HashMap<String, Value> {
+        serde_json::from_value(json!({
+            "concreteEventID": event_id,
+            "address": format!("/vcs/!Fader{event_id}"),
+            "minimum": 0.0,
+            "maximum": 1.0
+        }))
+        .unwrap()
+    };
+
+    let extractor = KymaWidgetExtractor::with_max_cache_size(3);
+    extractor.pin(1);
+    for event_id in 1..=3 {
+        extractor.cache_widget_description(description(event_id));
+    }
+
+    // A lookup makes 2 more recent than 3
+    assert!(extractor.extract_widget_metadata(2).is_some());
+    extractor.cache_widget_description(description(4));
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Cached after eviction: {:?}", {
+            let mut ids = extractor.get_cached_event_ids();
+            ids.sort_unstable();
+            ids
+        })
+        .cyan()
+    );
+    assert_eq!(extractor.cache_size(), 3);
+    assert!(extractor.get_cached_description(3).is_none());
+    assert_eq!(extractor.event_id_for_name("Fader3"), None);
+    assert!(extractor.get_cached_description(1).is_some());
+
+    // The pinned description outlives everything else
+    for event_id in 5..=10 {
+        extractor.cache_widget_description(description(event_id));
+    }
+    assert!(extractor.get_cached_description(1).is_some());
+    assert_eq!(extractor.cache_size(), 3);
+
+    // Pinning a new sound's IDs releases the old pin
+    let evicted = extractor.set_pinned([9, 10]);
+    assert!(evicted.is_empty());
+    let evicted = extractor.set_max_cache_size(Some(2));
+    assert_eq!(evicted, vec![1]);
+    assert!(extractor.is_pinned(9) && !extractor.is_pinned(1));
+
+    assert!(extractor.set_max_cache_size(None).is_empty());
+    assert_eq!(extractor.max_cache_size(), None);
+
+    println!("\n{}", "✓ Bounded cache test passed".green());
+}
+
+#[test]
+fn test_concurrent_extractor_access() {
+    colored::control::set_override(true);
+    println!("\n{}", "CONCURRENT EXTRACTOR TEST".bold().underline());
+
+    let extractor = std::sync::Arc::new(KymaWidgetExtractor::new());
+    let description = |event_id: i64| -> HashMap<String, Value> {
+        serde_json::from_value(json!({
+            "concreteEventID": event_id,
+            "label": format!("Fader{event_id}"),
+            "minimum": 0.0,
+            "maximum": 1.0
+        }))
+        .unwrap()
+    };
+    extractor.cache_widget_description(description(0));
+
+    let writer = {
+        let extractor = extractor.clone();
+        std::thread::spawn(move || {
+            for event_id in 1..=200 {
+                extractor.cache_widget_description(description(event_id));
+            }
+        })
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let extractor = extractor.clone();
+            std::thread::spawn(move || {
+                for _ in 0..200 {
+                    assert!(extractor.extract_widget_metadata(0).is_some());
+                    assert!(extractor.create_training_widget(0, 0.5).is_some());
+                }
+            })
+        })
+        .collect();
+
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Cached while reading: {}", extractor.cache_size()).cyan()
+    );
+    assert_eq!(extractor.cache_size(), 201);
+
+    println!("\n{}", "✓ Concurrent extractor test passed".green());
+}
+
+#[test]
+fn test_inferred_display_type() {
+    colored::control::set_override(true);
+    println!("\n{}", "INFERRED DISPLAY TYPE TEST".bold().underline());
+
+    assert_eq!(
+        infer_display_type(Some(true), None, Some(true)),
+        Some("gate")
+    );
+    assert_eq!(
+        infer_display_type(Some(true), Some(false), None),
+        Some("toggle")
+    );
+    assert_eq!(infer_display_type(None, Some(true), None), Some("slider"));
+    assert_eq!(infer_display_type(Some(false), None, None), Some("slider"));
+    assert_eq!(infer_display_type(None, None, None), None);
+
+    let extractor = KymaWidgetExtractor::new();
+    let descriptions = json!([
+        { "concreteEventID": 1, "label": "Mute", "isBoolean": true },
+        { "concreteEventID": 2, "label": "Bypass", "isBoolean": "true", "isEventSource": false },
+        { "concreteEventID": 3, "label": "Trigger", "isBoolean": true, "isEventSource": true },
+        { "concreteEventID": 4, "label": "Pan", "minimum": -1.0, "maximum": 1.0, "isFullRange": true },
+        { "concreteEventID": 5, "label": "Level", "displayType": "Rotary", "isBoolean": false },
+        { "concreteEventID": 6, "label": "Plain" }
+    ]);
+    extractor
+        .cache_widget_descriptions_from_json(&descriptions.to_string())
+        .unwrap();
+
+    for (event_id, expected) in [
+        (1, Some("toggle")),
+        (2, Some("toggle")),
+        (3, Some("gate")),
+        (4, Some("slider")),
+        (5, Some("Rotary")),
+        (6, None),
+    ] {
+        let metadata = extractor.extract_widget_metadata(event_id).unwrap();
+        println!(
+            "{} {}",
+            "→".green(),
+            format!("{:?}: {:?}", metadata.label, metadata.display_type).cyan()
+        );
+        assert_eq!(metadata.display_type.as_deref(), expected);
+    }
+
+    // Inferred types take part in display-type similarity
+    let engine = WidgetSuggestionEngine::new();
+    let mute = extractor.create_training_widget(1, 1.0).unwrap();
+    let bypass = extractor.create_training_widget(2, 0.0).unwrap();
+    let breakdown = engine.explain_similarity(&mute, &bypass);
+    assert_eq!(breakdown.display_type.score, 1.0);
+
+    // Filtered descriptions infer the same way
+    let filtered: FilteredWidgetDescription = serde_json::from_value(json!({
+        "concreteEventID": 7,
+        "label": "Hold",
+        "isBoolean": true
+    }))
+    .unwrap();
+    let record = WidgetRecord::from(filtered);
+    assert_eq!(record.widget.display_type.as_deref(), Some("toggle"));
+
+    println!("\n{}", "✓ Inferred display type test passed".green());
+}
+
+#[test]
+fn test_field_aliases_and_coercion() {
+    colored::control::set_override(true);
+    println!("\n{}", "FIELD ALIASES TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let report = extractor
+        .cache_widget_descriptions_from_json(
+            &json!([
+                { "eventID": "42", "label": "Cutoff", "min": "20", "max": 20000, "default": "1000" },
+                { "concreteEventID": 43.0, "label": "Mix", "minimum": 0, "maximum": "1", "step": "0.25" },
+                { "eventID": 44, "label": "Bypass", "isBoolean": "yes", "widgetType": "Toggle" }
+            ])
+            .to_string(),
+        )
+        .unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Cached: {:?}", report.cached).cyan()
+    );
+    assert_eq!(report.cached, vec![42, 43, 44]);
+    assert!(report.errors.is_empty());
+
+    let cutoff = extractor.extract_widget_metadata(42).unwrap();
+    assert_eq!(cutoff.minimum, Some(20.0));
+    assert_eq!(cutoff.maximum, Some(20000.0));
+    assert_eq!(cutoff.default_value, Some(1000.0));
+
+    let mix = extractor.extract_widget_metadata(43).unwrap();
+    assert_eq!(mix.grid, Some(0.25));
+    assert_eq!(mix.value_kind, Some(ValueKind::Stepped { steps: 5 }));
+
+    let bypass = extractor.extract_widget_metadata(44).unwrap();
+    assert_eq!(bypass.display_type.as_deref(), Some("Toggle"));
+    assert_eq!(bypass.value_kind, Some(ValueKind::Boolean));
+
+    // The canonical name wins over an alias, and an uncoercible value falls through
+    let fields = FieldAliases::default();
+    let data: HashMap<String, Value> =
+        serde_json::from_value(json!({ "minimum": "n/a", "min": 5, "maximum": 10, "max": 99 }))
+            .unwrap();
+    assert_eq!(fields.f64(&data, "minimum"), Some(5.0));
+    assert_eq!(fields.f64(&data, "maximum"), Some(10.0));
+    assert!(fields.contains(&data, "minimum"));
+    assert_eq!(fields.i64(&data, "concreteEventID"), None);
+
+    // Custom aliases are used by the extractor and its validation
+    let mut custom = FieldAliases::none();
+    custom.set_aliases("concreteEventID", ["uid"]);
+    custom.set_aliases("minimum", ["lo"]);
+    custom.set_aliases("maximum", ["hi"]);
+    let custom = KymaWidgetExtractor::new().with_field_aliases(custom);
+    let data: HashMap<String, Value> =
+        serde_json::from_value(json!({ "uid": " 7 ", "label": "Gain", "lo": -60, "hi": "6" }))
+            .unwrap();
+    assert_eq!(custom.validated_event_id(&data).unwrap(), 7);
+    assert!(extractor.validated_event_id(&data).is_err());
+    assert!(custom.validate_description(&data).is_clean());
+    custom.cache_widget_description(data);
+    let gain = custom.extract_widget_metadata(7).unwrap();
+    assert_eq!((gain.minimum, gain.maximum), (Some(-60.0), Some(6.0)));
+
+    // The engine reads filtered descriptions the same way
+    let filtered: FilteredWidgetDescription = serde_json::from_value(json!({
+        "eventID": "12",
+        "label": "Cutoff",
+        "min": "0",
+        "max": 1,
+        "isGenerated": "false"
+    }))
+    .unwrap();
+    let record = WidgetRecord::from(filtered);
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Record {}: {:?}", record.id, record.widget.label).cyan()
+    );
+    assert_eq!(record.id, 12);
+    assert_eq!(record.widget.event_id, Some(12));
+    assert_eq!(
+        (record.widget.minimum, record.widget.maximum),
+        (Some(0.0), Some(1.0))
+    );
+    assert_eq!(record.widget.is_generated, Some(false));
+
+    println!("\n{}", "✓ Field aliases test passed".green());
+}
+
+/// A generic OSC control surface: faders addressed by number, all ranging 0-127
+#[derive(Default)]
+struct FaderSurface {
+    faders: std::sync::RwLock<HashMap<i64, String>>,
+}
+
+impl WidgetSource for FaderSurface {
+    fn cache_description(&self, description: HashMap<String, Value>) {
+        let (Some(id), Some(name)) = (
+            description.get("fader").and_then(Value::as_i64),
+            description.get("name").and_then(Value::as_str),
+        ) else {
+            return;
+        };
+        self.faders.write().unwrap().insert(id, name.to_string());
+    }
+
+    fn cached_event_ids(&self) -> Vec<i64> {
+        self.faders.read().unwrap().keys().copied().collect()
+    }
+
+    fn widget_metadata(&self, event_id: i64) -> Option<WidgetMetadata> {
+        let label = self.faders.read().unwrap().get(&event_id)?.clone();
+        Some(WidgetMetadata {
+            event_id,
+            label: Some(label),
+            display_type: Some("fader".to_string()),
+            minimum: Some(0.0),
+            maximum: Some(127.0),
+            ..Default::default()
+        })
+    }
+}
+
+#[test]
+fn test_custom_widget_source() {
+    colored::control::set_override(true);
+    println!("\n{}", "CUSTOM WIDGET SOURCE TEST".bold().underline());
+
+    let surface = FaderSurface::default();
+    let kyma = KymaWidgetExtractor::new();
+    let sources: [&dyn WidgetSource; 2] = [&surface, &kyma];
+
+    let descriptions = [
+        json!({ "fader": 3, "name": "Reverb Send" }),
+        json!({ "concreteEventID": 3, "label": "Reverb Mix", "minimum": 0.0, "maximum": 1.0 }),
+    ];
+    for (source, description) in sources.iter().zip(descriptions) {
+        source.cache_description(serde_json::from_value(description).unwrap());
+        assert_eq!(source.cached_event_ids(), vec![3]);
+    }
+
+    let widgets: Vec<Widget> = sources
+        .iter()
+        .map(|source| {
+            assert!(source.create_training_widget(4, 1.0).is_none());
+            source.create_training_widget(3, 1.0).unwrap()
+        })
+        .collect();
+    for widget in &widgets {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{:?}: {:?}-{:?}",
+                widget.label, widget.minimum, widget.maximum
+            )
+            .cyan()
+        );
+    }
+    assert_eq!(widgets[0].label.as_deref(), Some("Reverb Send"));
+    assert_eq!(widgets[0].maximum, Some(127.0));
+    assert_eq!(widgets[0].event_id, Some(3));
+    assert_eq!(widgets[1].label.as_deref(), Some("Reverb Mix"));
+    assert_eq!(widgets[1].maximum, Some(1.0));
+    assert_eq!(
+        surface.widget_metadata(3).unwrap().display_type.as_deref(),
+        Some("fader")
+    );
+
+    println!("\n{}", "✓ Custom widget source test passed".green());
+}
+
+#[test]
+fn test_alternate_keys_and_composite_identity() {
+    colored::control::set_override(true);
+    println!("\n{}", "COMPOSITE IDENTITY TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let report = extractor
+        .cache_widget_descriptions_from_json(
+            &json!([
+                { "id": 21, "label": "Drive", "minimum": 0.0, "maximum": 1.0 },
+                { "eventID": "22", "label": "Tone", "minimum": 0.0, "maximum": 1.0 },
+                { "label": "Shape", "min": 0.0, "max": 10.0, "displayType": "rotary" },
+                { "label": "Nameless range" }
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+    let composite = composite_event_id("Shape", 0.0, 10.0, Some("rotary"));
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Cached {:?}, composite ID {composite}", report.cached).cyan()
+    );
+    assert!(composite >= COMPOSITE_EVENT_ID_BASE);
+    assert_eq!(report.cached, vec![21, 22, composite]);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].index, 3);
+    assert!(matches!(
+        report.warnings[0].issues[0],
+        DescriptionIssue::CompositeEventId { event_id } if event_id == composite
+    ));
+
+    // The same widget gets the same ID, a different one another
+    assert_eq!(
+        composite,
+        composite_event_id(" Shape ", 0.0, 10.0, Some("Rotary"))
+    );
+    assert_ne!(
+        composite,
+        composite_event_id("Shape", 0.0, 11.0, Some("rotary"))
+    );
+    assert_ne!(composite, composite_event_id("Shape", 0.0, 10.0, None));
+
+    // Widgets identified this way are learned like any other
+    let widget = extractor.create_training_widget(composite, 5.0).unwrap();
+    assert_eq!(widget.label.as_deref(), Some("Shape"));
+    assert_eq!(widget.event_id, u64::try_from(composite).ok());
+    assert!(widget.event_id.is_some());
+    let metadata = extractor.extract_widget_metadata(composite).unwrap();
+    assert_eq!(metadata.to_widget(5.0).event_id, widget.event_id);
+
+    // The ID survives an export and re-import of the learned record
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(widget);
+    let exported = engine.export_kyma_json();
+    let description: HashMap<String, Value> = serde_json::from_value(exported[0].clone()).unwrap();
+    assert_eq!(
+        fields::coerce_i64(&description["concreteEventID"]),
+        Some(composite)
+    );
+    let record = WidgetRecord::from(engine.records[0].to_description());
+    assert_eq!(record.widget.event_id, u64::try_from(composite).ok());
+    let record = WidgetRecord::from(description);
+    assert_eq!(record.widget.event_id, u64::try_from(composite).ok());
+    assert_eq!(
+        extractor
+            .extract_widget_metadata(21)
+            .unwrap()
+            .label
+            .as_deref(),
+        Some("Drive")
+    );
+
+    let data: HashMap<String, Value> = serde_json::from_value(
+        json!({ "label": "Shape", "minimum": 0, "maximum": 10, "displayType": "rotary" }),
+    )
+    .unwrap();
+    assert_eq!(extractor.validated_event_id(&data).unwrap(), composite);
+    let data: HashMap<String, Value> = serde_json::from_value(
+        json!({ "id": "n/a", "label": "Shape", "minimum": 0, "maximum": 10 }),
+    )
+    .unwrap();
+    assert!(extractor.validated_event_id(&data).is_err());
+
+    println!("\n{}", "✓ Composite identity test passed".green());
+}
+
+#[test]
+fn test_non_widget_messages_skipped() {
+    colored::control::set_override(true);
+    println!("\n{}", "NON-WIDGET MESSAGES TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let report = extractor
+        .cache_widget_descriptions_from_json(
+            &json!({
+                "widgets": [
+                    { "concreteEventID": 1, "label": "Cutoff", "minimum": 0.0, "maximum": 1.0 },
+                    { "concreteEventID": 2, "label": "Out L", "displayType": "VU Meter" },
+                    { "concreteEventID": 3, "label": "Title", "displayType": "Text" },
+                    { "text": "Hold the sustain pedal to freeze" },
+                    { "pacarana": "Paca(rana) 1", "dspLoad": 0.42 },
+                    { "label": "Broken" }
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+    for skipped in &report.skipped {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!("Skipped entry {}: {}", skipped.index, skipped.reason).cyan()
+        );
+    }
+    assert_eq!(report.cached, vec![1]);
+    let skipped: Vec<(usize, SkipReason)> = report
+        .skipped
+        .iter()
+        .map(|entry| (entry.index, entry.reason))
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            (1, SkipReason::Meter),
+            (2, SkipReason::Text),
+            (3, SkipReason::Text),
+            (4, SkipReason::Status)
+        ]
+    );
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].index, 5);
+    assert_eq!(extractor.cache_size(), 1);
+
+    // One description at a time, with a typed outcome
+    let meter: HashMap<String, Value> = serde_json::from_value(
+        json!({ "concreteEventID": 7, "label": "Level", "displayType": "meter" }),
+    )
+    .unwrap();
+    assert!(matches!(
+        extractor.try_cache_widget_description(meter.clone()),
+        CacheOutcome::Skipped(SkipReason::Meter)
+    ));
+    // Also kept out when cached without checks
+    extractor.cache_widget_description(meter);
+    assert!(extractor.get_cached_description(7).is_none());
+
+    let fader: HashMap<String, Value> = serde_json::from_value(
+        json!({ "concreteEventID": 8, "label": "Mix", "minimum": 0.0, "maximum": 1.0 }),
+    )
+    .unwrap();
+    assert!(matches!(
+        extractor.try_cache_widget_description(fader),
+        CacheOutcome::Cached(report) if report.event_id == Some(8)
+    ));
+    let broken: HashMap<String, Value> =
+        serde_json::from_value(json!({ "label": "Broken" })).unwrap();
+    assert!(matches!(
+        extractor.try_cache_widget_description(broken),
+        CacheOutcome::Rejected(_)
+    ));
+
+    println!("\n{}", "✓ Non-widget messages test passed".green());
+}
+
+#[test]
+fn test_cache_diff_between_sounds() {
+    colored::control::set_override(true);
+    println!("\n{}", "CACHE DIFF TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    extractor
+        .cache_widget_descriptions_from_json(
+            &json!([
+                { "concreteEventID": 1, "label": "Cutoff", "minimum": 20.0, "maximum": 20000.0 },
+                { "concreteEventID": 2, "label": "Resonance", "minimum": 0.0, "maximum": 1.0 },
+                { "concreteEventID": 3, "label": "Drive", "minimum": 0.0, "maximum": 1.0 }
+            ])
+            .to_string(),
+        )
+        .unwrap();
+    let previous = extractor.cache_snapshot();
+    assert_eq!(previous.len(), 3);
+    assert!(extractor.diff_against(&previous).is_empty());
+
+    // The next sound drops Drive, widens Resonance and adds Mix
+    extractor.clear_cache();
+    extractor
+        .cache_widget_descriptions_from_json(
+            &json!([
+                { "concreteEventID": 1, "label": "Cutoff", "minimum": 20.0, "maximum": 20000.0 },
+                { "concreteEventID": 2, "label": "Resonance", "minimum": 0.0, "maximum": 2.0, "units": "Q" },
+                { "concreteEventID": 4, "label": "Mix", "minimum": 0.0, "maximum": 1.0 }
+            ])
+            .to_string(),
+        )
+        .unwrap();
+    assert!(previous.contains(3) && !previous.contains(4));
+
+    let diff = extractor.diff_against(&previous);
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Added {:?}, removed {:?}, changed {:?}",
+            diff.added, diff.removed, diff.changed
+        )
+        .cyan()
+    );
+    assert_eq!(
+        diff.added,
+        vec![CacheDiffEntry {
+            event_id: 4,
+            label: Some("Mix".to_string())
+        }]
+    );
+    assert_eq!(
+        diff.removed,
+        vec![CacheDiffEntry {
+            event_id: 3,
+            label: Some("Drive".to_string())
+        }]
+    );
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].event_id, 2);
+    assert_eq!(diff.changed[0].fields, vec!["maximum", "units"]);
+
+    println!("\n{}", "✓ Cache diff test passed".green());
+}
+
+#[test]
+fn test_label_sanitizer_shared_with_engine() {
+    colored::control::set_override(true);
+    println!("\n{}", "LABEL SANITIZER TEST".bold().underline());
+
+    let sanitizer = LabelSanitizer::default();
+    assert_eq!(
+        sanitizer.sanitize("  vcs/Filter__Cutoff "),
+        Some("Filter_Cutoff".to_string())
+    );
+    assert_eq!(
+        sanitizer.sanitize("/vcs/!Amp  -  Gain"),
+        Some("Amp Gain".to_string())
+    );
+    assert_eq!(sanitizer.sanitize(" vcs/ "), None);
+    assert_eq!(sanitizer.sanitize(&"x".repeat(100)).unwrap().len(), 64);
+    assert_eq!(
+        LabelSanitizer::none().sanitize(" vcs/A  B"),
+        Some(" vcs/A  B".to_string())
+    );
+
+    let custom = LabelSanitizer::default()
+        .with_prefix("Synth:")
+        .with_max_length(Some(6));
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Custom sanitizer: {custom:?}").cyan()
+    );
+
+    let extractor = KymaWidgetExtractor::new().with_label_sanitizer(custom.clone());
+    let description: HashMap<String, Value> = serde_json::from_value(json!({
+        "concreteEventID": 1,
+        "label": "Synth: Reverb   Mix",
+        "minimum": 0.0,
+        "maximum": 1.0
+    }))
+    .unwrap();
+    extractor.cache_widget_description(description);
+
+    let widget = extractor.create_training_widget(1, 0.5).unwrap();
+    assert_eq!(widget.label.as_deref(), Some("Reverb"));
+
+    // A label that sanitizes to nothing falls back to the next source
+    let extractor = KymaWidgetExtractor::new();
+    let description: HashMap<String, Value> = serde_json::from_value(json!({
+        "concreteEventID": 2,
+        "label": "vcs/",
+        "name": "Pan",
+        "minimum": -1.0,
+        "maximum": 1.0
+    }))
+    .unwrap();
+    extractor.cache_widget_description(description);
+    let widget = extractor.create_training_widget(2, 0.0).unwrap();
+    assert_eq!(widget.label.as_deref(), Some("Pan"));
+
+    // The engine tokenizes raw labels the way the extractor cleans them
+    let engine = WidgetSuggestionEngine::with_config(EngineConfig {
+        label_sanitizer: custom,
+        ..EngineConfig::default()
+    });
+    let features = engine.partial_features(&Widget {
+        label: Some("Synth: Reverb   Mix".to_string()),
+        ..Default::default()
+    });
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Engine tokens: {:?}", features.label_tokens).cyan()
+    );
+    assert_eq!(features.label_tokens, vec!["reverb"]);
+
+    println!("\n{}", "✓ Label sanitizer test passed".green());
+}
@@ -0,0 +1,201 @@
+#![cfg(feature = "osc")]
+
+use colored::*;
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+use widget_intelligence::*;
+
+fn vcs_message(pairs: &[(i32, f32)]) -> OscPacket {
+    OscPacket::Message(OscMessage {
+        addr: "/vcs".to_string(),
+        args: pairs
+            .iter()
+            .flat_map(|&(event_id, value)| [OscType::Int(event_id), OscType::Float(value)])
+            .collect(),
+    })
+}
+
+#[test]
+fn test_osc_values_and_rate_limit() {
+    control::set_override(true);
+
+    println!("\n{}", "OSC DECODING TEST".bold().underline());
+
+    let bundle = OscPacket::Bundle(OscBundle {
+        timetag: OscTime::from((0, 1)),
+        content: vec![
+            vcs_message(&[(100, 0.25), (101, 0.5)]),
+            OscPacket::Message(OscMessage {
+                addr: "/status".to_string(),
+                args: vec![OscType::Int(1), OscType::Float(1.0)],
+            }),
+        ],
+    });
+    let values = osc_values(&bundle, "/vcs");
+    println!("{} {}", "→".green(), format!("{values:?}").cyan());
+    assert_eq!(values, vec![(100, 0.25), (101, 0.5)]);
+
+    let mut limiter = OscRateLimiter::new(Duration::from_millis(100));
+    let start = Instant::now();
+    assert_eq!(limiter.offer(100, 0.1, start), Some(0.1));
+    assert_eq!(
+        limiter.offer(100, 0.2, start + Duration::from_millis(10)),
+        None
+    );
+    assert_eq!(
+        limiter.offer(100, 0.3, start + Duration::from_millis(20)),
+        None
+    );
+    assert!(limiter
+        .take_due(start + Duration::from_millis(50))
+        .is_empty());
+
+    // The last held-back value is stored once the interval has passed
+    assert_eq!(
+        limiter.take_due(start + Duration::from_millis(120)),
+        vec![(100, 0.3)]
+    );
+    assert!(limiter
+        .take_due(start + Duration::from_millis(300))
+        .is_empty());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_osc_listener_learns() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "OSC LISTENER TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let engine = Arc::new(Mutex::new(PersistentWidgetSuggestionEngine::new(
+        temp_dir.path().join("osc"),
+    )?));
+    let extractor = Arc::new(KymaWidgetExtractor::new());
+    let description: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+        "concreteEventID": 100,
+        "label": "Cutoff",
+        "minimum": 0.0,
+        "maximum": 1.0
+    }))?;
+    extractor.cache_widget_description(description);
+    let frequency: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+        "concreteEventID": 101,
+        "label": "Frequency",
+        "minimum": 20.0,
+        "maximum": 20020.0
+    }))?;
+    extractor.cache_widget_description(frequency);
+
+    let config = OscListenerConfig {
+        bind_addr: "127.0.0.1:0".parse()?,
+        min_interval: Duration::from_millis(50),
+        ..OscListenerConfig::default()
+    };
+    let listener = spawn_osc_listener(config, extractor, engine.clone()).await?;
+
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await?;
+    // 999 has no cached description and is ignored
+    let packet = rosc::encoder::encode(&vcs_message(&[(100, 0.75), (101, 10020.0), (999, 0.5)]))
+        .map_err(|e| format!("{e:?}"))?;
+    socket.send_to(&packet, listener.local_addr()).await?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while listener.learned() < 2 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Learned {} values", listener.learned()).cyan()
+    );
+    assert_eq!(listener.learned(), 2);
+    listener.stop().await;
+
+    let engine = engine.lock().unwrap();
+    assert_eq!(engine.engine.records.len(), 2);
+    let record = |label: &str| {
+        engine
+            .engine
+            .records
+            .iter()
+            .find(|r| r.widget.label.as_deref() == Some(label))
+            .unwrap()
+    };
+    assert_eq!(record("Cutoff").widget.values, vec![0.75]);
+    // Values are learned normalized to the widget's range
+    assert_eq!(record("Frequency").widget.values, vec![0.5]);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_osc_learning_does_not_block_the_runtime() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "OSC NON-BLOCKING LEARNING TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let engine = Arc::new(Mutex::new(PersistentWidgetSuggestionEngine::new(
+        temp_dir.path().join("osc"),
+    )?));
+    let extractor = Arc::new(KymaWidgetExtractor::new());
+    let description: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+        "concreteEventID": 100,
+        "label": "Cutoff",
+        "minimum": 0.0,
+        "maximum": 1.0
+    }))?;
+    extractor.cache_widget_description(description);
+
+    let config = OscListenerConfig {
+        bind_addr: "127.0.0.1:0".parse()?,
+        ..OscListenerConfig::default()
+    };
+    let listener = spawn_osc_listener(config, extractor, engine.clone()).await?;
+
+    // Another thread holds the engine while the value arrives
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+    let holder = {
+        let engine = engine.clone();
+        std::thread::spawn(move || {
+            let _guard = engine.lock().unwrap();
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(500));
+        })
+    };
+    locked_rx.recv()?;
+
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await?;
+    let packet =
+        rosc::encoder::encode(&vcs_message(&[(100, 0.75)])).map_err(|e| format!("{e:?}"))?;
+    socket.send_to(&packet, listener.local_addr()).await?;
+
+    // The test runs on a single-threaded runtime, which the listener must not stall
+    let start = Instant::now();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let elapsed = start.elapsed();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Woke after {elapsed:?} while the engine was locked").cyan()
+    );
+    assert!(elapsed < Duration::from_millis(400));
+
+    holder.join().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while listener.learned() == 0 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(listener.learned(), 1);
+    listener.stop().await;
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
@@ -16,6 +16,8 @@ fn create_kyma_widget(label: &str, min: f64, max: f64, current: f64) -> Widget {
         display_type: Some("slider".to_string()),
         event_id: None,
         values: vec![current],
+        value_kind: None,
+        taper: None,
     }
 }
 
@@ -40,6 +42,8 @@ fn create_kyma_preset(name: &str, widget_values: HashMap<String, f64>) -> Preset
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        tags: Vec::new(),
+        favorite: false,
     }
 }
 
@@ -266,7 +270,11 @@ fn test_kyma_export_import() -> Result<(), Box<dyn std::error::Error>> {
         "Importing to new database...".yellow()
     );
     let mut system2 = PersistentWidgetSuggestionEngine::new(&db_path2)?;
-    system2.import_data(export_data)?;
+    system2.import_data(
+        export_data,
+        ImportStrategy::ReplaceAll,
+        PresetConflictPolicy::Overwrite,
+    )?;
 
     let stats1 = system1.get_stats();
     let stats2 = system2.get_stats();
@@ -385,3 +393,2384 @@ fn test_kyma_suggestions_persistence() -> Result<(), Box<dyn std::error::Error>>
     println!("\n{}", "TEST PASSED".bold().green());
     Ok(())
 }
+
+#[test]
+fn test_feedback_persistence() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "FEEDBACK PERSISTENCE TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_feedback_persistence");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    system.store_widget(Widget::simplified(
+        Some("Amp_01".to_string()),
+        Some(51),
+        vec![0.6, 0.7, 0.8],
+    ))?;
+    let record_id = system.engine.records[0].id;
+
+    println!("{} {}", "→".green(), "Recording feedback...".yellow());
+    assert!(system.record_feedback(record_id, 0.75, true)?.is_some());
+    assert!(system.record_feedback(record_id, 0.61, false)?.is_some());
+    assert!(system.record_feedback(999, 0.5, true)?.is_none());
+
+    let values = system.engine.records[0].widget.values.clone();
+    assert_eq!(values, vec![0.7, 0.8, 0.75]);
+
+    system.flush()?;
+    drop(system);
+
+    println!("{} {}", "→".green(), "Reopening database...".yellow());
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    let log = system.feedback_log()?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Feedback log: {} entries", log.len()).cyan()
+    );
+    assert_eq!(log.len(), 2);
+    assert!(log[0].accepted);
+    assert!(!log[1].accepted);
+
+    assert_eq!(system.engine.records[0].widget.values, values);
+    assert_eq!(system.engine.calibration.buckets[1].offered, 2);
+    assert_eq!(system.engine.calibration.buckets[1].accepted, 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_feedback_keeps_observation_counts() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "FEEDBACK OBSERVATION COUNTS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let mut system = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("counts"))?;
+    for value in [0.6, 0.6, 0.6, 0.8] {
+        system.store_widget(Widget::simplified(
+            Some("Amp_01".to_string()),
+            Some(52),
+            vec![value],
+        ))?;
+    }
+    let record_id = system.engine.records[0].id;
+    let count = |system: &PersistentWidgetSuggestionEngine, key: &str| {
+        let stats = system.engine.records[0].value_stats.as_ref().unwrap();
+        (stats.count, stats.frequency_map.get(key).copied())
+    };
+
+    // An accepted value already in the history is counted, not repeated
+    system.record_feedback(record_id, 0.6, true)?;
+    assert_eq!(system.engine.records[0].widget.values, vec![0.6, 0.8]);
+    assert_eq!(count(&system, "0.60"), (5, Some(4)));
+
+    // A rejection takes back one observation and keeps the others
+    system.record_feedback(record_id, 0.6, false)?;
+    assert_eq!(system.engine.records[0].widget.values, vec![0.6, 0.8]);
+    assert_eq!(count(&system, "0.60"), (4, Some(3)));
+
+    // The last observation of a value takes it out of the history
+    system.record_feedback(record_id, 0.8, false)?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("History: {:?}", system.engine.records[0].widget.values).cyan()
+    );
+    assert_eq!(system.engine.records[0].widget.values, vec![0.6]);
+    assert_eq!(count(&system, "0.80"), (3, None));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_prune_stale_records() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "PRUNE STALE RECORDS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_prune_stale");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.75))?;
+    system.store_widget(create_kyma_widget("rate", 30.0, 90.0, 65.0))?;
+    system.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 8.5))?;
+
+    // Age every record by 60 days, then make one of them frequently used
+    let sixty_days = 60 * 24 * 60 * 60;
+    for record in &mut system.engine.records {
+        record.last_seen -= sixty_days;
+    }
+    system.engine.records[2].frequency = 10;
+    let retired_id = system.engine.records[0].id;
+
+    let thirty_days = std::time::Duration::from_secs(30 * 24 * 60 * 60);
+    let pruned = system.prune_stale(thirty_days, 5)?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Pruned records: {:?}", pruned).cyan()
+    );
+    assert_eq!(pruned.len(), 2);
+    assert!(pruned.contains(&retired_id));
+    assert_eq!(system.engine.records.len(), 1);
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.records.len(), 1);
+    assert_eq!(
+        system.engine.records[0].widget.label.as_deref(),
+        Some("cutoff")
+    );
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_delete_and_inspect_records() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "DELETE AND INSPECT RECORDS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_delete_records");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.75))?;
+    system.store_widget(create_kyma_widget("Amp_02", -24.0, 24.0, 3.0))?;
+    system.store_widget(create_kyma_widget("rate", 30.0, 90.0, 65.0))?;
+    system.store_widget(create_kyma_widget("Gate", 1000.0, 2000.0, 1200.0))?;
+
+    let rate_id = system.engine.records[2].id;
+    let rate = system.get_record(rate_id).expect("record exists");
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Inspected record {}: {:?}", rate_id, rate.widget.label).cyan()
+    );
+    assert_eq!(rate.widget.label.as_deref(), Some("rate"));
+
+    let removed = system.delete_records_by_label("amp_*")?;
+    assert_eq!(removed.len(), 2);
+
+    assert!(system.delete_record(rate_id)?.is_some());
+    assert!(system.delete_record(rate_id)?.is_none());
+    assert!(system.get_record(rate_id).is_none());
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.records.len(), 1);
+    assert_eq!(
+        system.engine.records[0].widget.label.as_deref(),
+        Some("Gate")
+    );
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_relabel_record() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "RELABEL RECORD TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_relabel_record");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    system.store_widget(Widget::simplified(
+        Some("Amp".to_string()),
+        Some(61),
+        vec![0.2, 0.4],
+    ))?;
+    system.store_widget(Widget::simplified(
+        Some("Amp".to_string()),
+        Some(61),
+        vec![0.6],
+    ))?;
+    let id = system.engine.records[0].id;
+
+    // The sound revision renamed "Amp" to "Level"
+    let update = RecordUpdate {
+        label: Some("Level".to_string()),
+        ..Default::default()
+    };
+    let record = system.update_record(id, update)?.expect("record exists");
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Relabelled tokens: {:?}", record.features.label_tokens).cyan()
+    );
+    assert_eq!(record.features.label_tokens, vec!["level".to_string()]);
+    assert_eq!(record.frequency, 2);
+    assert_eq!(record.widget.values, vec![0.2, 0.4, 0.6]);
+
+    assert!(system
+        .update_record(999, RecordUpdate::default())?
+        .is_none());
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    let record = system.get_record(id).expect("record persisted");
+    assert_eq!(record.widget.label.as_deref(), Some("Level"));
+    assert_eq!(record.widget.values, vec![0.2, 0.4, 0.6]);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_batch_store_widgets() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "BATCH STORE WIDGETS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_batch_store");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    // One preset's worth of controls, with the first one touched twice
+    let widgets = vec![
+        Widget::simplified(Some("Amp_01".to_string()), Some(71), vec![0.5]),
+        Widget::simplified(Some("cutoff".to_string()), Some(72), vec![0.3]),
+        Widget::simplified(Some("rate".to_string()), Some(73), vec![0.9]),
+        Widget::simplified(Some("Amp_01".to_string()), Some(71), vec![0.6]),
+    ];
+    system.store_widgets(widgets)?;
+
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Stored {} records", system.engine.records.len()).cyan()
+    );
+    assert_eq!(system.engine.records.len(), 3);
+    assert_eq!(system.engine.records[0].frequency, 2);
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.records.len(), 3);
+    assert_eq!(system.engine.records[0].widget.values, vec![0.5, 0.6]);
+    assert_eq!(system.engine.next_id, 4);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_recall_preset() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "RECALL PRESET TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_recall_preset");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    // 101 is only known to the extractor, 102 only as a learned record
+    let extractor = KymaWidgetExtractor::new();
+    extractor.cache_widget_description(serde_json::from_value(serde_json::json!({
+        "concreteEventID": 101,
+        "label": "Amp_01",
+        "minimum": 0.0,
+        "maximum": 200.0,
+    }))?);
+
+    let mut cutoff = create_kyma_widget("cutoff", -24.0, 24.0, 0.5);
+    cutoff.event_id = Some(102);
+    system.store_widget(cutoff)?;
+
+    let preset = create_kyma_preset(
+        "Bright Pad",
+        HashMap::from([
+            ("101".to_string(), 0.5),
+            ("102".to_string(), 0.25),
+            ("103".to_string(), 0.8),
+            ("not-an-id".to_string(), 0.1),
+        ]),
+    );
+    system.store_preset(preset)?;
+
+    let recalled = system
+        .recall_preset("Bright Pad", Some(&extractor))
+        .expect("preset exists");
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Recalled values: {:?}", recalled).cyan()
+    );
+
+    assert_eq!(recalled.len(), 3);
+    assert_eq!(recalled[&101], 100.0);
+    assert_eq!(recalled[&102], -12.0);
+    assert_eq!(recalled[&103], 0.8);
+
+    // Without cached metadata only the learned range is available
+    let recalled = system.recall_preset("Bright Pad", None).unwrap();
+    assert_eq!(recalled[&101], 0.5);
+
+    assert!(system.recall_preset("Missing", None).is_none());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_generate_preset() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "GENERATE PRESET TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_generate_preset");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    system.store_widget(Widget::simplified(
+        Some("Amp_01".to_string()),
+        Some(201),
+        vec![0.4, 0.4, 0.9],
+    ))?;
+    system.store_widget(Widget::simplified(
+        Some("cutoff".to_string()),
+        Some(202),
+        vec![0.8],
+    ))?;
+
+    let preset = system
+        .generate_preset("Smart Default", &[201, 202, 999])?
+        .expect("learned values exist");
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Generated preset: {:?}", preset.widget_values).cyan()
+    );
+
+    assert!(preset.is_generated());
+    assert_eq!(preset.widget_values.len(), 2);
+    assert_eq!(preset.widget_values[0].widget_id, "201");
+    assert_eq!(preset.widget_values[0].value, 0.4);
+    assert_eq!(preset.widget_values[1].value, 0.8);
+
+    assert!(system.generate_preset("Nothing", &[999])?.is_none());
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.presets.len(), 1);
+    assert!(system.engine.presets[0].is_generated());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_preset_version_history() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "PRESET VERSION HISTORY TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_preset_history");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    for value in [0.1, 0.2, 0.3] {
+        system.store_preset(create_kyma_preset(
+            "Evolving",
+            HashMap::from([("301".to_string(), value)]),
+        ))?;
+    }
+
+    let history = system.preset_history("Evolving")?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("{} previous versions", history.len()).cyan()
+    );
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].preset.widget_values[0].value, 0.1);
+    assert_eq!(history[1].preset.widget_values[0].value, 0.2);
+
+    let restored = system
+        .restore_preset_revision("Evolving", 0)?
+        .expect("revision exists");
+    assert_eq!(restored.widget_values[0].value, 0.1);
+    assert_eq!(restored.usage_count, 3);
+    assert!(system.restore_preset_revision("Evolving", 99)?.is_none());
+
+    // The overwritten version was kept, so the restore can be undone
+    let history = system.preset_history("Evolving")?;
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[2].preset.widget_values[0].value, 0.3);
+
+    for _ in 0..MAX_PRESET_REVISIONS {
+        system.store_preset(create_kyma_preset(
+            "Evolving",
+            HashMap::from([("301".to_string(), 0.5)]),
+        ))?;
+    }
+    assert_eq!(
+        system.preset_history("Evolving")?.len(),
+        MAX_PRESET_REVISIONS
+    );
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.presets[0].widget_values[0].value, 0.5);
+    assert_eq!(
+        system.preset_history("Evolving")?.len(),
+        MAX_PRESET_REVISIONS
+    );
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_delete_and_rename_presets() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "DELETE AND RENAME PRESETS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_delete_rename_presets");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    for value in [0.2, 0.4] {
+        system.store_preset(create_kyma_preset(
+            "Draft",
+            HashMap::from([("401".to_string(), value)]),
+        ))?;
+    }
+    system.store_preset(create_kyma_preset(
+        "Scratch",
+        HashMap::from([("402".to_string(), 0.9)]),
+    ))?;
+
+    println!("{} {}", "→".green(), "Renaming Draft to Final...".yellow());
+    assert!(system.rename_preset("Draft", "Final")?);
+    assert!(!system.rename_preset("Draft", "Other")?);
+    assert!(!system.rename_preset("Final", "Scratch")?);
+
+    println!("{} {}", "→".green(), "Deleting Scratch...".yellow());
+    assert!(system.delete_preset("Scratch")?.is_some());
+    assert!(system.delete_preset("Scratch")?.is_none());
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.presets.len(), 1);
+
+    let preset = &system.engine.presets[0];
+    assert_eq!(preset.name, "Final");
+    assert_eq!(preset.usage_count, 2);
+    assert_eq!(preset.widget_values[0].value, 0.4);
+
+    let history = system.preset_history("Final")?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].preset.name, "Final");
+    assert!(system.preset_history("Draft")?.is_empty());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_preset_tags() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "PRESET TAGS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_preset_tags");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    let mut opener = create_kyma_preset("Opener", HashMap::from([("501".to_string(), 0.3)]));
+    opener.tags = vec!["Tour 2025".to_string(), "drones".to_string()];
+    system.store_preset(opener)?;
+    system.store_preset(create_kyma_preset(
+        "Encore",
+        HashMap::from([("501".to_string(), 0.8)]),
+    ))?;
+
+    assert!(system.tag_preset("Encore", "tour 2025")?);
+    assert!(system.tag_preset("Encore", "Tour 2025")?);
+    assert!(!system.tag_preset("Missing", "drones")?);
+
+    let tour: Vec<&str> = system
+        .presets_with_tags(&["TOUR 2025"])
+        .into_iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Tagged 'Tour 2025': {:?}", tour).cyan()
+    );
+    assert_eq!(tour.len(), 2);
+    assert_eq!(system.presets_with_tags(&["tour 2025", "drones"]).len(), 1);
+
+    // Re-saving without tags keeps them
+    system.store_preset(create_kyma_preset(
+        "Opener",
+        HashMap::from([("501".to_string(), 0.35)]),
+    ))?;
+    assert!(system.untag_preset("Encore", "TOUR 2025")?);
+
+    system.flush()?;
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    let opener = system.presets_with_tags(&["drones"]);
+    assert_eq!(opener.len(), 1);
+    assert_eq!(opener[0].name, "Opener");
+    assert_eq!(opener[0].tags.len(), 2);
+    assert_eq!(system.presets_with_tags(&["tour 2025"]).len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_export_preset_kyma() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "EXPORT PRESET KYMA TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_export_preset_kyma");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    let extractor = KymaWidgetExtractor::new();
+    extractor.cache_widget_description(serde_json::from_value(serde_json::json!({
+        "concreteEventID": 601,
+        "label": "Frequency",
+        "minimum": 20.0,
+        "maximum": 220.0,
+    }))?);
+
+    system.store_preset(create_kyma_preset(
+        "Low Drone",
+        HashMap::from([("601".to_string(), 0.25), ("602".to_string(), 0.5)]),
+    ))?;
+
+    let json = system
+        .export_preset_kyma("Low Drone", Some(&extractor))
+        .expect("preset exists");
+    println!("{} {}", "→".green(), format!("{:#}", json).cyan());
+
+    assert_eq!(json["601"], 70.0);
+    assert_eq!(json["602"], 0.5);
+    assert!(system.export_preset_kyma("Missing", None).is_none());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_import_presets_from_json() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "IMPORT PRESETS FROM JSON TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_import_presets_json");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    system.store_preset(create_kyma_preset(
+        "Shared",
+        HashMap::from([("701".to_string(), 0.1)]),
+    ))?;
+
+    let json = r#"[
+        {
+            "name": "Shared",
+            "widget_values": { "701": 0.9, "702": 0.5 },
+            "tags": ["imported"]
+        },
+        {
+            "name": "Fresh",
+            "description": "From another machine",
+            "widget_values": { "701": 0.3, "volume": 0.5, "702": 4.0, "703": -0.5 }
+        },
+        {
+            "name": "Broken",
+            "widget_values": { "abc": 0.5 }
+        }
+    ]"#;
+
+    let report = system.import_presets_from_json(json, None, PresetConflictPolicy::Skip)?;
+    println!("{} {}", "→".green(), format!("{:?}", report).cyan());
+    assert_eq!(report.imported, vec!["Fresh"]);
+    assert_eq!(report.skipped, vec!["Shared", "Broken"]);
+    assert_eq!(report.rejected_values.len(), 4);
+    // Normalized values lie within 0.0..=1.0, so a negative one is rejected
+    assert!(report
+        .rejected_values
+        .iter()
+        .any(|r| r.widget_id == "703" && r.reason.contains("not normalized")));
+
+    // The same file again, now keeping both versions of conflicting presets
+    let path = temp_dir.path().join("presets.json");
+    fs::write(&path, json)?;
+    let report =
+        system.import_presets_from_file(&path, None, PresetConflictPolicy::KeepBothWithSuffix)?;
+    assert_eq!(report.imported, vec!["Shared (2)", "Fresh (2)"]);
+
+    let report = system.import_presets_from_json(json, None, PresetConflictPolicy::Overwrite)?;
+    assert_eq!(report.imported, vec!["Shared", "Fresh"]);
+    let shared = system.presets_with_tags(&["imported"]);
+    assert_eq!(shared.len(), 2);
+    assert_eq!(system.preset_history("Shared")?.len(), 1);
+
+    // With an extractor, only event IDs it knows are accepted
+    let extractor = KymaWidgetExtractor::new();
+    extractor.cache_widget_description(serde_json::from_value(serde_json::json!({
+        "concreteEventID": 701,
+        "label": "Amp_01",
+    }))?);
+    let report = system.import_presets_from_json(
+        json,
+        Some(&extractor),
+        PresetConflictPolicy::KeepBothWithSuffix,
+    )?;
+    assert_eq!(report.imported, vec!["Shared (3)", "Fresh (3)"]);
+    let stored = &system.presets_with_tags(&["imported"]);
+    let shared_3 = stored.iter().find(|p| p.name == "Shared (3)").unwrap();
+    assert_eq!(shared_3.widget_values.len(), 1);
+    assert_eq!(shared_3.widget_values[0].label.as_deref(), Some("Amp_01"));
+
+    // An older `last_used` loses against the stored preset when keeping the newest
+    let older = r#"[{ "name": "Shared", "widget_values": { "701": 0.4 }, "last_used": 1 }]"#;
+    let report = system.import_presets_from_json(older, None, PresetConflictPolicy::KeepNewest)?;
+    assert_eq!(report.skipped, vec!["Shared"]);
+
+    assert!(system
+        .import_presets_from_file("/does/not/exist.json", None, PresetConflictPolicy::Skip)
+        .is_err());
+    // JSON is never mistaken for a path, nor a path for JSON
+    assert!(matches!(
+        system.import_presets_from_json("  {}", None, PresetConflictPolicy::Skip),
+        Err(WidgetIntelligenceError::DeserializationError(_))
+    ));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_preset_favorites() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "PRESET FAVORITES TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_preset_favorites");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    // Two presets equally close to the live state and with the same labelled widget
+    for name in ["Alpha", "Beta"] {
+        let mut preset = create_kyma_preset(name, HashMap::from([("601".to_string(), 0.5)]));
+        preset.widget_values[0].label = Some("Grain Density".to_string());
+        system.store_preset(preset)?;
+    }
+
+    let live_state = HashMap::from([(601u64, 0.4)]);
+    assert_eq!(system.recommend_presets(&live_state, 2)[0].preset, "Alpha");
+
+    assert_eq!(system.toggle_preset_favorite("Beta")?, Some(true));
+    assert_eq!(system.toggle_preset_favorite("Missing")?, None);
+    assert!(!system.set_preset_favorite("Missing", true)?);
+
+    let recommendations = system.recommend_presets(&live_state, 2);
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Top recommendation: {}", recommendations[0].preset).cyan()
+    );
+    assert_eq!(recommendations[0].preset, "Beta");
+    assert!(recommendations[0].favorite);
+
+    let query = Widget {
+        label: Some("Grain Density".to_string()),
+        ..Default::default()
+    };
+    let insights = system.get_preset_insights(&query);
+    println!("{} {}", "→".green(), insights[0].to_string().cyan());
+    assert_eq!(insights.len(), 2);
+    assert_eq!(insights[0].preset, "Beta");
+
+    // Re-saving a favorite without the star keeps it
+    system.store_preset(create_kyma_preset(
+        "Beta",
+        HashMap::from([("601".to_string(), 0.5)]),
+    ))?;
+
+    system.flush()?;
+    drop(system);
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    let favorites: Vec<&str> = system
+        .favorite_presets()
+        .into_iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(favorites, vec!["Beta"]);
+
+    assert!(system.set_preset_favorite("Beta", false)?);
+    assert!(system.favorite_presets().is_empty());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_store_preset_validated() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "VALIDATED PRESET TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("test_store_preset_validated");
+
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+
+    let extractor = KymaWidgetExtractor::new();
+    extractor.cache_widget_description(serde_json::from_value(serde_json::json!({
+        "concreteEventID": 701,
+        "label": "Cutoff",
+        "minimum": 20.0,
+        "maximum": 20000.0,
+    }))?);
+    extractor.cache_widget_description(serde_json::from_value(serde_json::json!({
+        "concreteEventID": 702,
+        "label": "Resonance",
+        "minimum": 0.0,
+        "maximum": 1.0,
+    }))?);
+
+    // Preset values are normalized, so anything outside 0..1 is out of range
+    let report = system.store_preset_validated(
+        create_kyma_preset(
+            "Squelch",
+            HashMap::from([
+                ("701".to_string(), 0.4),
+                ("702".to_string(), 1.5),
+                ("799".to_string(), 0.5),
+            ]),
+        ),
+        &extractor,
+    )?;
+
+    for value in &report.out_of_range {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{:?} = {} outside {:?}..{:?}",
+                value.label, value.value, value.minimum, value.maximum
+            )
+            .cyan()
+        );
+    }
+
+    assert!(!report.is_valid());
+    assert_eq!(report.out_of_range.len(), 1);
+    assert_eq!(report.out_of_range[0].event_id, 702);
+    assert_eq!(report.out_of_range[0].maximum, Some(1.0));
+    assert_eq!(report.unchecked, vec!["799".to_string()]);
+
+    // The bad value is left out of the stored preset
+    let stored = system
+        .engine
+        .presets
+        .iter()
+        .find(|p| p.name == "Squelch")
+        .unwrap();
+    assert_eq!(stored.widget_values.len(), 2);
+    assert!(stored.widget_values.iter().all(|v| v.widget_id != "702"));
+
+    // A preset with nothing valid left is not stored at all
+    let report = system.store_preset_validated(
+        create_kyma_preset("Broken", HashMap::from([("701".to_string(), -5.0)])),
+        &extractor,
+    )?;
+    assert!(report.is_out_of_range(701));
+    assert!(system.engine.presets.iter().all(|p| p.name != "Broken"));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_import_data_strategies() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "IMPORT DATA STRATEGIES TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+
+    let mut source = WidgetSuggestionEngine::new();
+    source.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.9));
+    source.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0));
+    let data = ExportData {
+        widgets: source.records.clone(),
+        presets: vec![
+            create_kyma_preset("Shared", HashMap::from([("801".to_string(), 0.9)])),
+            create_kyma_preset("Other", HashMap::from([("802".to_string(), 0.5)])),
+        ],
+        display_types: source.display_types.clone(),
+        next_id: source.next_id,
+    };
+
+    for (strategy, policy, amp_values, shared_value) in [
+        (
+            ImportStrategy::ReplaceAll,
+            PresetConflictPolicy::Skip,
+            vec![0.9],
+            0.9,
+        ),
+        (
+            ImportStrategy::MergePreferLocal,
+            PresetConflictPolicy::Skip,
+            vec![0.2],
+            0.2,
+        ),
+        (
+            ImportStrategy::MergePreferImported,
+            PresetConflictPolicy::Overwrite,
+            vec![0.9],
+            0.9,
+        ),
+    ] {
+        let db_path = temp_dir.path().join(format!("test_import_{strategy:?}"));
+
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.2))?;
+        system.store_preset(create_kyma_preset(
+            "Shared",
+            HashMap::from([("801".to_string(), 0.2)]),
+        ))?;
+        let report = system.import_data(data.clone(), strategy, policy)?;
+        println!(
+            "{} {}",
+            "→".green(),
+            format!("{strategy:?}: {report:?}").cyan()
+        );
+        if strategy == ImportStrategy::MergePreferLocal {
+            assert_eq!(report.skipped_widgets, 1);
+            assert_eq!(report.presets.skipped, vec!["Shared"]);
+        }
+        if strategy == ImportStrategy::MergePreferImported {
+            assert_eq!(report.replaced_widgets, 1);
+            assert_eq!(report.added_widgets, 1);
+        }
+
+        let in_memory: Vec<(u64, Vec<f64>)> = system
+            .engine
+            .records
+            .iter()
+            .map(|r| (r.id, r.widget.values.clone()))
+            .collect();
+        drop(system);
+
+        // Memory and sled agree after a reopen
+        let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let on_disk: Vec<(u64, Vec<f64>)> = system
+            .engine
+            .records
+            .iter()
+            .map(|r| (r.id, r.widget.values.clone()))
+            .collect();
+        assert_eq!(on_disk, in_memory);
+        assert_eq!(on_disk.len(), 2);
+
+        let amp = system
+            .engine
+            .records
+            .iter()
+            .find(|r| r.widget.label.as_deref() == Some("Amp_01"))
+            .unwrap();
+        assert_eq!(amp.id, 1);
+        assert_eq!(amp.widget.values, amp_values);
+        assert_eq!(system.engine.next_id, 3);
+
+        let mut names: Vec<&str> = system
+            .engine
+            .presets
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Other", "Shared"]);
+        let shared = system
+            .engine
+            .presets
+            .iter()
+            .find(|p| p.name == "Shared")
+            .unwrap();
+        assert_eq!(shared.widget_values[0].value, shared_value);
+    }
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_import_data_merge_policies() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "IMPORT DATA MERGE POLICIES TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+
+    let mut local = create_kyma_preset("Shared", HashMap::from([("801".to_string(), 0.2)]));
+    local.usage_count = 3;
+    local.last_used = 100;
+
+    let mut imported = create_kyma_preset("Shared", HashMap::from([("801".to_string(), 0.9)]));
+    imported.usage_count = 2;
+    imported.last_used = 200;
+
+    let data = ExportData {
+        widgets: Vec::new(),
+        presets: vec![
+            imported,
+            create_kyma_preset("Other", HashMap::from([("802".to_string(), 0.5)])),
+        ],
+        display_types: HashMap::new(),
+        next_id: 1,
+    };
+
+    for (policy, expected_names, shared_value, shared_usage) in [
+        (PresetConflictPolicy::Skip, vec!["Other", "Shared"], 0.2, 3),
+        (
+            PresetConflictPolicy::Overwrite,
+            vec!["Other", "Shared"],
+            0.9,
+            2,
+        ),
+        (
+            PresetConflictPolicy::KeepNewest,
+            vec!["Other", "Shared"],
+            0.9,
+            2,
+        ),
+        (
+            PresetConflictPolicy::SumUsageCounts,
+            vec!["Other", "Shared"],
+            0.9,
+            5,
+        ),
+        (
+            PresetConflictPolicy::KeepBothWithSuffix,
+            vec!["Other", "Shared", "Shared (2)"],
+            0.2,
+            3,
+        ),
+    ] {
+        let db_path = temp_dir.path().join(format!("test_import_{policy:?}"));
+
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        system.store_preset(local.clone())?;
+        let report = system.import_data(data.clone(), ImportStrategy::MergePreferLocal, policy)?;
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{policy:?}: imported {:?}, skipped {:?}",
+                report.presets.imported, report.presets.skipped
+            )
+            .cyan()
+        );
+        drop(system);
+
+        // Memory and sled agree after a reopen
+        let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let mut names: Vec<&str> = system
+            .engine
+            .presets
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(names, expected_names);
+
+        let shared = system
+            .engine
+            .presets
+            .iter()
+            .find(|p| p.name == "Shared")
+            .unwrap();
+        assert_eq!(shared.widget_values[0].value, shared_value);
+        assert_eq!(shared.usage_count, shared_usage);
+    }
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_memory_backend() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "MEMORY BACKEND TEST".bold().underline());
+
+    let mut system = PersistentWidgetSuggestionEngine::in_memory()?;
+    system.store_widget(Widget::simplified(
+        Some("Amp_01".to_string()),
+        Some(901),
+        vec![0.6, 0.7],
+    ))?;
+    system.store_preset(create_kyma_preset(
+        "Quiet",
+        HashMap::from([("901".to_string(), 0.2)]),
+    ))?;
+    system.store_preset(create_kyma_preset(
+        "Quiet",
+        HashMap::from([("901".to_string(), 0.25)]),
+    ))?;
+    assert!(system.tag_preset("Quiet", "ambient")?);
+    let record_id = system.engine.records[0].id;
+    assert!(system.record_feedback(record_id, 0.7, true)?.is_some());
+    assert!(system.rename_preset("Quiet", "Hush")?);
+
+    // Hand the backend to a fresh engine, as reopening a database would
+    let backend = system.persistence;
+    let system = PersistentWidgetSuggestionEngine::with_backend(backend)?;
+
+    let stats = system.get_stats();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Reloaded {} widgets, {} presets",
+            stats.get("total_widgets").unwrap_or(&0),
+            system.engine.presets.len()
+        )
+        .cyan()
+    );
+
+    assert_eq!(stats.get("total_widgets"), Some(&1));
+    // The accepted value was observed again, not repeated in the history
+    assert_eq!(system.engine.records[0].widget.values, vec![0.6, 0.7]);
+    assert_eq!(
+        system.engine.records[0].value_stats.as_ref().map(|s| s.count),
+        Some(3)
+    );
+    assert_eq!(system.engine.presets.len(), 1);
+    assert_eq!(system.engine.presets[0].name, "Hush");
+    assert!(system.engine.presets[0].has_tag("ambient"));
+    assert_eq!(system.preset_history("Hush")?.len(), 1);
+    assert_eq!(system.feedback_log()?.len(), 1);
+    assert_eq!(system.size_on_disk()?, 0);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_export_import_files() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "EXPORT/IMPORT FILES TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let mut source = PersistentWidgetSuggestionEngine::in_memory()?;
+    source.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+    source.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+    source.store_preset(create_kyma_preset(
+        "Bright",
+        HashMap::from([("101".to_string(), 0.9)]),
+    ))?;
+
+    for (format, file_name) in [
+        (ExportFormat::Json, "learning.json"),
+        (ExportFormat::Bincode, "learning.bin"),
+    ] {
+        let path = temp_dir.path().join(file_name);
+        source.export_to_file(&path, format)?;
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{format:?} export: {} bytes",
+                std::fs::metadata(&path)?.len()
+            )
+            .cyan()
+        );
+
+        let mut target = PersistentWidgetSuggestionEngine::in_memory()?;
+        let report = target.import_from_file(
+            &path,
+            ImportStrategy::MergePreferLocal,
+            PresetConflictPolicy::Skip,
+        )?;
+        assert_eq!(report.presets.imported, vec!["Bright"]);
+        assert_eq!(target.get_stats(), source.get_stats());
+        assert_eq!(target.engine.records.len(), 2);
+    }
+
+    let json = std::fs::read_to_string(temp_dir.path().join("learning.json"))?;
+    assert!(json.contains("\"Amp_01\""));
+
+    let garbage = temp_dir.path().join("garbage.txt");
+    std::fs::write(&garbage, "not an export")?;
+    let mut target = PersistentWidgetSuggestionEngine::in_memory()?;
+    assert!(target
+        .import_from_file(
+            &garbage,
+            ImportStrategy::MergePreferLocal,
+            PresetConflictPolicy::Skip,
+        )
+        .is_err());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_backup_and_restore() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "BACKUP AND RESTORE TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let archive = temp_dir.path().join("learning.tar");
+    let mut engine = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("db"))?;
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+    engine.store_preset(create_kyma_preset(
+        "Bright",
+        HashMap::from([("101".to_string(), 0.9)]),
+    ))?;
+
+    let manifest = engine.backup_to(&archive)?;
+    assert_eq!(manifest.format_version, BACKUP_FORMAT_VERSION);
+    assert_eq!((manifest.widgets, manifest.presets), (1, 1));
+    assert_eq!(verify_backup(&archive)?, manifest);
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Backup checksum {}", manifest.checksum).cyan()
+    );
+
+    // Learning after the backup is rolled back by the restore
+    engine.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+    engine.store_preset(create_kyma_preset(
+        "Dark",
+        HashMap::from([("102".to_string(), 0.1)]),
+    ))?;
+    assert_eq!(engine.engine.records.len(), 2);
+
+    engine.restore_from(&archive)?;
+    assert_eq!(engine.engine.records.len(), 1);
+    assert_eq!(engine.engine.presets.len(), 1);
+    assert_eq!(engine.engine.presets[0].name, "Bright");
+    assert_eq!(engine.persistence.load_all_widgets()?.len(), 1);
+    assert_eq!(engine.persistence.load_all_presets()?.len(), 1);
+
+    // A damaged archive is rejected before anything changes
+    let mut bytes = fs::read(&archive)?;
+    let middle = bytes.len() / 2;
+    bytes[middle..].iter_mut().for_each(|byte| *byte ^= 0xff);
+    let damaged = temp_dir.path().join("damaged.tar");
+    fs::write(&damaged, bytes)?;
+    engine.store_widget(create_kyma_widget("resonance", 0.0, 1.0, 0.2))?;
+    assert!(verify_backup(&damaged).is_err());
+    assert!(engine.restore_from(&damaged).is_err());
+    assert_eq!(engine.engine.records.len(), 2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_auto_flush_policy() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "AUTO-FLUSH POLICY TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let mut system = PersistentWidgetSuggestionEngine::with_auto_flush(
+        temp_dir.path().join("db"),
+        AutoFlushPolicy::every_writes(3),
+    )?;
+    // Opening a new database stores its configuration; start counting from a flush
+    system.flush()?;
+
+    system.persistence.store_metadata("a", "1")?;
+    system.persistence.store_metadata("b", "2")?;
+    assert_eq!(system.persistence.pending_writes(), 2);
+    system.persistence.store_metadata("c", "3")?;
+    assert_eq!(system.persistence.pending_writes(), 0);
+    println!("{} {}", "→".green(), "Flushed after 3 writes".cyan());
+
+    system.set_auto_flush(AutoFlushPolicy::every(std::time::Duration::from_millis(10)));
+    system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while system.persistence.pending_writes() > 0 && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(system.persistence.pending_writes(), 0);
+    println!("{} {}", "→".green(), "Background thread flushed".cyan());
+
+    // Writes that were never flushed explicitly survive the engine going away
+    system.set_auto_flush(AutoFlushPolicy::default());
+    system.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+    assert!(system.persistence.pending_writes() > 0);
+    drop(system);
+
+    let system = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("db"))?;
+    assert_eq!(system.engine.records.len(), 2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_store_widgets_with_preset() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "ATOMIC PRESET WRITE TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    {
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        for cutoff in [0.2, 0.6] {
+            system.store_widgets_with_preset(
+                vec![
+                    create_kyma_widget("Amp_01", 0.0, 1.0, 0.7),
+                    create_kyma_widget("cutoff", -24.0, 24.0, 6.0),
+                ],
+                create_kyma_preset("Bright", HashMap::from([("102".to_string(), cutoff)])),
+            )?;
+        }
+        assert_eq!(system.engine.records.len(), 2);
+    }
+
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.engine.records.len(), 2);
+    assert_eq!(system.engine.presets.len(), 1);
+    assert_eq!(system.engine.presets[0].widget_values[0].value, 0.6);
+    assert_eq!(system.engine.next_id, 3);
+    println!(
+        "{} {}",
+        "→".green(),
+        "Widgets, preset and next id were committed together".cyan()
+    );
+
+    // The replaced version went into the history in the same write
+    let history = system.preset_history("Bright")?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].preset.widget_values[0].value, 0.2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+/// A memory backend whose batch writes fail once `failing` is set
+#[derive(Default)]
+struct FailingBatches {
+    inner: MemoryBackend,
+    failing: std::sync::atomic::AtomicBool,
+}
+
+impl PersistenceBackend for FailingBatches {
+    fn store_widget(&self, record: &WidgetRecord) -> Result<(), WidgetIntelligenceError> {
+        self.inner.store_widget(record)
+    }
+
+    fn delete_widget(&self, id: u64) -> Result<(), WidgetIntelligenceError> {
+        self.inner.delete_widget(id)
+    }
+
+    fn load_all_widgets(&self) -> Result<Vec<WidgetRecord>, WidgetIntelligenceError> {
+        self.inner.load_all_widgets()
+    }
+
+    fn store_preset(&self, preset: &Preset) -> Result<(), WidgetIntelligenceError> {
+        self.inner.store_preset(preset)
+    }
+
+    fn delete_preset(&self, name: &str) -> Result<(), WidgetIntelligenceError> {
+        self.inner.delete_preset(name)
+    }
+
+    fn load_all_presets(&self) -> Result<Vec<Preset>, WidgetIntelligenceError> {
+        self.inner.load_all_presets()
+    }
+
+    fn store_preset_revisions(
+        &self,
+        name: &str,
+        revisions: &[PresetRevision],
+    ) -> Result<(), WidgetIntelligenceError> {
+        self.inner.store_preset_revisions(name, revisions)
+    }
+
+    fn load_preset_revisions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<PresetRevision>, WidgetIntelligenceError> {
+        self.inner.load_preset_revisions(name)
+    }
+
+    fn store_feedback(&self, entry: &FeedbackEntry) -> Result<(), WidgetIntelligenceError> {
+        self.inner.store_feedback(entry)
+    }
+
+    fn load_feedback_log(&self) -> Result<Vec<FeedbackEntry>, WidgetIntelligenceError> {
+        self.inner.load_feedback_log()
+    }
+
+    fn store_metadata(&self, key: &str, value: &str) -> Result<(), WidgetIntelligenceError> {
+        self.inner.store_metadata(key, value)
+    }
+
+    fn load_metadata(&self, key: &str) -> Result<Option<String>, WidgetIntelligenceError> {
+        self.inner.load_metadata(key)
+    }
+
+    fn flush(&self) -> Result<(), WidgetIntelligenceError> {
+        self.inner.flush()
+    }
+
+    fn apply_batch(&self, batch: &WriteBatch<'_>) -> Result<(), WidgetIntelligenceError> {
+        if self.failing.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(WidgetIntelligenceError::IoError(std::io::Error::other(
+                "disk full",
+            )));
+        }
+        self.inner.apply_batch(batch)
+    }
+}
+
+#[test]
+fn test_failed_preset_write_leaves_engine_unchanged() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "FAILED ATOMIC PRESET WRITE TEST".bold().underline());
+
+    let mut system = PersistentWidgetSuggestionEngine::with_backend(FailingBatches::default())?;
+    system.store_widgets_with_preset(
+        vec![create_kyma_widget("Amp_01", 0.0, 1.0, 0.7)],
+        create_kyma_preset("Bright", HashMap::from([("101".to_string(), 0.2)])),
+    )?;
+    // Edited for this session only, which the rollback must not undo
+    system.engine.config.value_strategy = ValueStrategy::Median;
+    let record_before = format!("{:?}", system.engine.records[0]);
+
+    system
+        .persistence
+        .failing
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+    let result = system.store_widgets_with_preset(
+        vec![
+            create_kyma_widget("Amp_01", 0.0, 1.0, 0.9),
+            create_kyma_widget("cutoff", -24.0, 24.0, 6.0),
+        ],
+        create_kyma_preset("Bright", HashMap::from([("101".to_string(), 0.8)])),
+    );
+    assert!(result.is_err());
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Write failed: {}", result.unwrap_err()).cyan()
+    );
+
+    // Nothing of the failed write stuck in memory
+    assert_eq!(system.engine.records.len(), 1);
+    assert_eq!(system.engine.records[0].frequency, 1);
+    assert_eq!(format!("{:?}", system.engine.records[0]), record_before);
+    assert_eq!(system.engine.next_id, 2);
+    assert_eq!(system.engine.config.value_strategy, ValueStrategy::Median);
+    assert_eq!(system.engine.presets[0].widget_values[0].value, 0.2);
+    assert_eq!(system.engine.presets[0].usage_count, 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_lazy_engine() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "LAZY ENGINE TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    let query = Widget {
+        label: Some("Amp".to_string()),
+        ..Default::default()
+    };
+    let expected = {
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.8))?;
+        system.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+        let mut keyed = create_kyma_widget("resonance", 0.0, 1.0, 0.2);
+        keyed.event_id = Some(42);
+        system.store_widget(keyed)?;
+        system.get_suggestions(&query, 2)
+    };
+
+    let mut system = PersistentWidgetSuggestionEngine::open_lazy(&db_path)?;
+    assert!(system.is_lazy());
+    assert!(system.engine.records.is_empty());
+    assert_eq!(system.get_stats()["total_widgets"], 3);
+    let summaries = system.record_summaries();
+    let labels: Vec<_> = summaries.iter().filter_map(|s| s.label.clone()).collect();
+    println!("{} {}", "→".green(), format!("Indexed {labels:?}").cyan());
+    assert_eq!(summaries.len(), 3);
+
+    let suggestions = system.get_suggestions(&query, 2);
+    assert_eq!(suggestions.len(), expected.len());
+    for (lazy, full) in suggestions.iter().zip(&expected) {
+        assert_eq!(lazy.widget.label, full.widget.label);
+        assert_eq!(lazy.suggested_value, full.suggested_value);
+    }
+
+    let by_event_id = system.get_suggestions_by_event_id(42, 1);
+    assert_eq!(by_event_id[0].widget.label.as_deref(), Some("resonance"));
+    // Suggestions only loaded their candidates
+    assert!(system.is_lazy());
+
+    // Other reads load every record without giving up the index
+    let amp = summaries
+        .iter()
+        .find(|s| s.label.as_deref() == Some("Amp_01"))
+        .unwrap();
+    assert_eq!(system.get_record(amp.id).unwrap().widget.values.len(), 2);
+    assert!(system.get_record(999).is_none());
+    assert!(system.is_lazy());
+
+    // Learning loads everything and carries on as usual
+    system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.75))?;
+    assert!(!system.is_lazy());
+    assert_eq!(system.engine.records.len(), 3);
+    assert_eq!(system.get_record(amp.id).unwrap().widget.values.len(), 3);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_capacity_limits() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "CAPACITY LIMITS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    {
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+        system.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+        system.store_widget(create_kyma_widget("Resonance", 0.0, 10.0, 2.0))?;
+        system.store_widget(create_kyma_widget("Resonance", 0.0, 10.0, 3.0))?;
+
+        // Amp_01 was seen longest ago; cutoff and Resonance tie, cutoff is used less
+        for record in &mut system.engine.records {
+            record.last_seen = match record.widget.label.as_deref() {
+                Some("Amp_01") => 100,
+                _ => 200,
+            };
+        }
+
+        let report = system.set_capacity_limits(CapacityLimits {
+            max_records: Some(1),
+            max_bytes: None,
+        })?;
+        let evicted: Vec<_> = report
+            .evicted
+            .iter()
+            .map(|id| {
+                system
+                    .persistence
+                    .load_widget(*id)
+                    .map(|record| record.is_none())
+            })
+            .collect::<Result<_, _>>()?;
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "Evicted {} records, {} -> {} bytes",
+                report.evicted.len(),
+                report.bytes_before,
+                report.bytes_after
+            )
+            .cyan()
+        );
+        assert_eq!(report.evicted.len(), 2);
+        assert!(evicted.iter().all(|gone| *gone));
+        assert_eq!(report.records_after, 1);
+        assert_eq!(system.take_eviction_report(), Some(report));
+        assert_eq!(system.take_eviction_report(), None);
+        assert_eq!(
+            system.engine.records[0].widget.label.as_deref(),
+            Some("Resonance")
+        );
+
+        // Learning a new widget pushes the oldest one out again
+        system.store_widget(create_kyma_widget("Drive", 0.0, 2.0, 1.0))?;
+        assert_eq!(system.engine.records.len(), 1);
+        assert!(system.take_eviction_report().is_some());
+    }
+
+    // The limits are kept with the database
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.capacity_limits().max_records, Some(1));
+    assert_eq!(system.engine.records.len(), 1);
+
+    let bytes = system.engine.records[0].encoded_size();
+    let report = system.set_capacity_limits(CapacityLimits {
+        max_records: None,
+        max_bytes: Some(bytes - 1),
+    })?;
+    assert_eq!(report.evicted.len(), 1);
+    assert_eq!(report.bytes_after, 0);
+
+    // Without limits nothing is measured
+    let report = system.set_capacity_limits(CapacityLimits::default())?;
+    assert!(report.is_empty());
+    system.store_widget(create_kyma_widget("Drive", 0.0, 2.0, 1.0))?;
+    let report = system.enforce_capacity()?;
+    assert!(report.is_empty());
+    assert_eq!(report.records_after, 1);
+    assert_eq!(report.bytes_before, 0);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_retention_policy() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "RETENTION POLICY TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    let eighteen_months = std::time::Duration::from_secs(18 * 30 * 24 * 60 * 60);
+    {
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let mut keyed = create_kyma_widget("cutoff", -24.0, 24.0, 6.0);
+        keyed.event_id = Some(102);
+        system.store_widget(keyed)?;
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+        system.store_widget(create_kyma_widget("Resonance", 0.0, 10.0, 2.0))?;
+        system.store_preset(create_kyma_preset(
+            "Dark",
+            HashMap::from([("102".to_string(), -18.0)]),
+        ))?;
+
+        // Everything but Resonance was last seen two years ago
+        for record in &mut system.engine.records {
+            if record.widget.label.as_deref() != Some("Resonance") {
+                record.last_seen = 0;
+                system.persistence.store_widget(record)?;
+            }
+        }
+
+        let expired = system.set_retention_policy(RetentionPolicy::max_age(eighteen_months))?;
+        println!(
+            "{} {}",
+            "→".green(),
+            format!("Expired records {expired:?}").cyan()
+        );
+        assert_eq!(expired.len(), 1);
+        let labels: Vec<_> = system
+            .engine
+            .records
+            .iter()
+            .filter_map(|r| r.widget.label.as_deref())
+            .collect();
+        assert_eq!(labels, vec!["cutoff", "Resonance"]);
+    }
+
+    // The policy is stored with the database and enforced again on open
+    let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(system.retention_policy().max_age, Some(eighteen_months));
+    assert_eq!(system.engine.records.len(), 2);
+
+    system.delete_preset("Dark")?;
+    let report = system.run_maintenance()?;
+    assert_eq!(report.expired.len(), 1);
+    assert!(report.eviction.is_empty());
+    assert_eq!(system.persistence.load_all_widgets()?.len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_open_read_only() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "READ-ONLY OPEN TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    {
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+        system.store_preset(create_kyma_preset(
+            "Bright",
+            HashMap::from([("101".to_string(), 0.9)]),
+        ))?;
+    }
+
+    // The database does not need to be closed elsewhere
+    let owner = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    let mut system = PersistentWidgetSuggestionEngine::open_read_only(&db_path)?;
+    assert!(system.is_read_only());
+    assert_eq!(system.get_stats()["total_widgets"], 1);
+
+    let suggestions = system.get_suggestions(
+        &Widget {
+            label: Some("Amp_01".to_string()),
+            ..Default::default()
+        },
+        1,
+    );
+    assert_eq!(suggestions[0].widget.label.as_deref(), Some("Amp_01"));
+    println!("{} {}", "→".green(), "Suggestions are served".cyan());
+
+    let rejected = system.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0));
+    assert!(matches!(rejected, Err(WidgetIntelligenceError::ReadOnly)));
+    assert!(matches!(
+        system.delete_preset("Bright"),
+        Err(WidgetIntelligenceError::ReadOnly)
+    ));
+    // Rejected writes leave the in-memory state alone too
+    assert_eq!(system.engine.records.len(), 1);
+    assert_eq!(system.engine.presets.len(), 1);
+    println!("{} {}", "→".green(), "Writes are rejected".cyan());
+
+    drop(system);
+    assert_eq!(owner.engine.records.len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_recompiled_widget_descriptions() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!(
+        "\n{}",
+        "RECOMPILED WIDGET DESCRIPTIONS TEST".bold().underline()
+    );
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    let amp = |event_id: i64| {
+        serde_json::from_value(serde_json::json!({
+            "concreteEventID": event_id,
+            "address": "/vcs/!Amp",
+            "minimum": 0.0,
+            "maximum": 1.0,
+        }))
+    };
+    {
+        let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let extractor = KymaWidgetExtractor::new();
+        system.cache_widget_description(&extractor, amp(21)?)?;
+        // Recompiling the sound moved !Amp to a lower event ID
+        system.cache_widget_description(&extractor, amp(11)?)?;
+        assert_eq!(extractor.event_id_for_name("Amp"), Some(11));
+    }
+
+    // Only the current description comes back, so the stale ID cannot win on restore
+    let system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    let extractor = KymaWidgetExtractor::new();
+    let restored = system.restore_widget_descriptions(&extractor)?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Restored {restored} description(s)").cyan()
+    );
+    assert_eq!(restored, 1);
+    assert_eq!(extractor.event_id_for_name("Amp"), Some(11));
+    assert!(extractor.get_cached_description(21).is_none());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_named_profiles() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "NAMED PROFILES TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("db");
+    {
+        let mut system = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        assert_eq!(system.profile(), DEFAULT_PROFILE);
+        system.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+
+        system.switch_profile("Anna")?;
+        assert!(system.engine.records.is_empty());
+        system.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+        system.store_widget(create_kyma_widget("Resonance", 0.0, 10.0, 2.0))?;
+        system.store_preset(create_kyma_preset(
+            "Anna's Intro",
+            HashMap::from([("102".to_string(), 0.3)]),
+        ))?;
+
+        system.switch_profile(DEFAULT_PROFILE)?;
+        assert_eq!(system.engine.records.len(), 1);
+        assert!(system.engine.presets.is_empty());
+
+        assert_eq!(system.list_profiles(), vec![DEFAULT_PROFILE, "Anna"]);
+        let stats = system.profile_stats("Anna")?.unwrap();
+        println!("{} {}", "→".green(), format!("{stats:?}").cyan());
+        assert_eq!((stats.widgets, stats.presets), (2, 1));
+        assert!(system.profile_stats("Bob")?.is_none());
+
+        assert!(matches!(
+            system.switch_profile("  "),
+            Err(WidgetIntelligenceError::ValidationError(_))
+        ));
+        assert_eq!(system.profile(), DEFAULT_PROFILE);
+    }
+
+    let system = PersistentWidgetSuggestionEngine::with_profile(&db_path, "Anna")?;
+    assert_eq!(system.engine.records.len(), 2);
+    assert_eq!(system.engine.presets[0].name, "Anna's Intro");
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_merge_databases() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "MERGE DATABASES TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let studio_path = temp_dir.path().join("studio");
+    let laptop_path = temp_dir.path().join("laptop");
+    // The laptop database stays open while it is merged
+    let mut laptop = PersistentWidgetSuggestionEngine::new(&laptop_path)?;
+    laptop.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.8))?;
+    laptop.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+    laptop.store_preset(create_kyma_preset(
+        "Bright",
+        HashMap::from([("101".to_string(), 0.9)]),
+    ))?;
+    laptop.flush()?;
+
+    let mut studio = PersistentWidgetSuggestionEngine::new(&studio_path)?;
+    studio.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7))?;
+    studio.store_preset(create_kyma_preset(
+        "Bright",
+        HashMap::from([("101".to_string(), 0.5)]),
+    ))?;
+
+    let report = studio.merge_from(&laptop_path, PresetConflictPolicy::KeepBothWithSuffix)?;
+    println!("{} {}", "→".green(), format!("{report:?}").cyan());
+    assert_eq!(report.merged_widgets, 1);
+    assert_eq!(report.added_widgets, 1);
+    assert_eq!(report.presets.imported, vec!["Bright (2)"]);
+
+    let amp = studio
+        .engine
+        .records
+        .iter()
+        .find(|r| r.widget.label.as_deref() == Some("Amp_01"))
+        .unwrap();
+    assert_eq!(amp.frequency, 2);
+    assert_eq!(amp.widget.values, vec![0.7, 0.8]);
+
+    let ids: Vec<u64> = studio.engine.records.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    drop(studio);
+
+    let studio = PersistentWidgetSuggestionEngine::new(&studio_path)?;
+    assert_eq!(studio.engine.records.len(), 2);
+    assert_eq!(studio.engine.presets.len(), 2);
+
+    // Only read from
+    drop(laptop);
+    let laptop = PersistentWidgetSuggestionEngine::new(&laptop_path)?;
+    assert_eq!(laptop.engine.records.len(), 2);
+    assert_eq!(laptop.engine.presets.len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_persistence_hooks() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::{Arc, Mutex};
+
+    control::set_override(true);
+
+    println!("\n{}", "PERSISTENCE HOOKS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let mut engine = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("hooks"))?;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    engine.persistence.on_widget_stored(move |record| {
+        let label = record.widget.label.clone().unwrap_or_default();
+        log.lock().unwrap().push(format!("widget {label}"));
+    });
+    let log = events.clone();
+    engine.persistence.on_preset_stored(move |preset| {
+        log.lock().unwrap().push(format!("preset {}", preset.name))
+    });
+    let log = events.clone();
+    engine
+        .persistence
+        .on_flush(move |writes| log.lock().unwrap().push(format!("flush {writes}")));
+
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.5))?;
+    engine.store_preset(create_kyma_preset(
+        "Quiet",
+        HashMap::from([("101".to_string(), 0.1)]),
+    ))?;
+    engine.flush()?;
+
+    let events = events.lock().unwrap().clone();
+    for event in &events {
+        println!("{} {}", "→".green(), event.cyan());
+    }
+    assert!(events.contains(&"widget Amp_01".to_string()));
+    assert!(events.contains(&"preset Quiet".to_string()));
+    assert!(events.last().unwrap().starts_with("flush "));
+    assert_ne!(events.last().unwrap(), "flush 0");
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_typed_metadata_store() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "TYPED METADATA STORE TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("metadata");
+    {
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.5))?;
+        engine.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+
+        let metadata = engine.persistence.metadata();
+        assert_eq!(metadata.get::<u64>("next_id")?, Some(3));
+        assert_eq!(metadata.get::<bool>("missing")?, None);
+
+        metadata.set("schema_version", &2u64)?;
+        metadata.set("gain", &0.75f64)?;
+        metadata.set("learning", &true)?;
+        metadata.set_json("limits", &CapacityLimits::default())?;
+        assert_eq!(metadata.get::<u64>("schema_version")?, Some(2));
+        assert_eq!(metadata.get::<f64>("gain")?, Some(0.75));
+        assert_eq!(metadata.get::<bool>("learning")?, Some(true));
+        assert!(metadata.get_json::<CapacityLimits>("limits")?.is_some());
+
+        // Values of the wrong type are reported instead of read as missing
+        let error = metadata.get::<u64>("gain").unwrap_err();
+        println!("{} {}", "→".green(), error.to_string().cyan());
+        assert!(error.to_string().contains("gain"));
+
+        engine
+            .persistence
+            .store_metadata("next_id", "not a number")?;
+    }
+
+    // A broken id counter resumes after the stored records
+    let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(engine.engine.next_id, 3);
+    engine.store_widget(create_kyma_widget("resonance", 0.0, 1.0, 0.2))?;
+    assert_eq!(
+        engine.persistence.metadata().get::<u64>("next_id")?,
+        Some(4)
+    );
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_export_changes_since() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "INCREMENTAL EXPORT TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let mut engine = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("changes"))?;
+
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.5))?;
+    engine.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+    let mut old_preset = create_kyma_preset("Old", HashMap::from([("101".to_string(), 0.1)]));
+    old_preset.last_used = 1_000;
+    engine.store_preset(old_preset)?;
+    engine.store_preset(create_kyma_preset(
+        "Fresh",
+        HashMap::from([("101".to_string(), 0.9)]),
+    ))?;
+    // Pretend the first record was last seen long ago
+    engine.engine.records[0].last_seen = 1_000;
+
+    let since = 2_000;
+    let changes = engine.export_changes_since(since)?;
+    let labels: Vec<_> = changes
+        .widgets
+        .iter()
+        .filter_map(|r| r.widget.label.clone())
+        .collect();
+    let names: Vec<_> = changes.presets.iter().map(|p| p.name.clone()).collect();
+    println!("{} {}", "→".green(), format!("{labels:?} {names:?}").cyan());
+    assert_eq!(labels, vec!["cutoff"]);
+    assert_eq!(names, vec!["Fresh"]);
+    assert_eq!(changes.next_id, engine.engine.next_id);
+
+    assert_eq!(engine.export_changes_since(0)?.widgets.len(), 2);
+    assert!(engine.export_changes_since(u64::MAX)?.widgets.is_empty());
+
+    let path = temp_dir.path().join("changes.json");
+    engine.export_changes_to_file(&path, since, ExportFormat::Json)?;
+    let written: ExportData = serde_json::from_slice(&fs::read(&path)?)?;
+    assert_eq!(written.widgets.len(), 1);
+    assert_eq!(written.presets.len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_sled_options() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "SLED OPTIONS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("tuned");
+    let options = SledOptions {
+        flush_every_ms: None,
+        ..SledOptions::low_memory()
+    };
+    println!("{} {}", "→".green(), format!("{options:?}").cyan());
+    {
+        let mut engine = PersistentWidgetSuggestionEngine::with_options(&db_path, &options)?;
+        engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.5))?;
+    }
+
+    // The options only tune sled, the data stays readable with the defaults
+    let engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(engine.engine.records.len(), 1);
+    drop(engine);
+
+    let scratch_path = temp_dir.path().join("scratch");
+    let scratch = SledOptions {
+        temporary: true,
+        ..SledOptions::default()
+    };
+    {
+        let mut engine = PersistentWidgetSuggestionEngine::with_options(&scratch_path, &scratch)?;
+        engine.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+        assert_eq!(engine.engine.records.len(), 1);
+    }
+    let reopened = PersistentWidgetSuggestionEngine::new(&scratch_path)?;
+    assert!(reopened.engine.records.is_empty());
+
+    let compressed = SledOptions {
+        use_compression: true,
+        ..SledOptions::default()
+    };
+    let result = SledPersistenceManager::with_options(temp_dir.path().join("zstd"), &compressed);
+    assert_eq!(result.is_ok(), cfg!(feature = "sled-compression"));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_next_id_never_reuses_stored_ids() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "CRASH-SAFE ID ALLOCATION TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("ids");
+    {
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        engine.store_widgets(vec![
+            create_kyma_widget("Amp_01", 0.0, 1.0, 0.5),
+            create_kyma_widget("cutoff", -24.0, 24.0, 6.0),
+        ])?;
+        assert_eq!(
+            engine.persistence.metadata().get::<u64>("next_id")?,
+            Some(3)
+        );
+
+        // What a crash between the record and the counter write used to leave behind
+        engine.persistence.metadata().set("next_id", &1u64)?;
+    }
+
+    let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Resumed at id {}", engine.engine.next_id).cyan()
+    );
+    assert_eq!(engine.engine.next_id, 3);
+
+    engine.store_widget(create_kyma_widget("resonance", 0.0, 1.0, 0.2))?;
+    let ids: Vec<u64> = engine.engine.records.iter().map(|r| r.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    drop(engine);
+
+    let engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(engine.engine.records.len(), 3);
+    assert_eq!(engine.engine.next_id, 4);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_engine_config_persisted() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "ENGINE CONFIGURATION TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("config");
+    {
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let mut config = engine.config().clone();
+        config.frequency_weight = 0.4;
+        config.value_strategy = ValueStrategy::Median;
+        config.similarity_weights.label = 0.7;
+        config.similarity_thresholds.merge = 0.95;
+        engine.set_config(config)?;
+    }
+
+    let engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Restored frequency weight {}",
+            engine.config().frequency_weight
+        )
+        .cyan()
+    );
+    assert_eq!(engine.config().frequency_weight, 0.4);
+    assert!(matches!(
+        engine.config().value_strategy,
+        ValueStrategy::Median
+    ));
+    assert_eq!(engine.config().similarity_weights.label, 0.7);
+    assert_eq!(engine.config().similarity_thresholds.merge, 0.95);
+    drop(engine);
+
+    // The stored configuration wins over one passed on open
+    let engine = PersistentWidgetSuggestionEngine::with_config(&db_path, EngineConfig::default())?;
+    assert_eq!(engine.config().frequency_weight, 0.4);
+    drop(engine);
+
+    // A new database takes the configuration it is created with
+    let fresh_path = temp_dir.path().join("fresh");
+    let config = EngineConfig {
+        value_strategy: ValueStrategy::Lowest,
+        ..EngineConfig::default()
+    };
+    drop(PersistentWidgetSuggestionEngine::with_config(
+        &fresh_path,
+        config,
+    )?);
+    let engine = PersistentWidgetSuggestionEngine::new(&fresh_path)?;
+    assert!(matches!(
+        engine.config().value_strategy,
+        ValueStrategy::Lowest
+    ));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_export_anonymized() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "ANONYMIZED EXPORT TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let mut engine = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("anon"))?;
+    engine.store_widgets(vec![
+        create_kyma_widget("Secret Project Cutoff", 20.0, 20000.0, 1000.0),
+        create_kyma_widget("Secret Project Gain", 0.0, 1.0, 0.8),
+    ])?;
+    let mut values = HashMap::new();
+    values.insert("Secret Project Gain".to_string(), 0.8);
+    let mut preset = create_kyma_preset("Secret Project Mix", values);
+    preset.description = Some("Final mix for the secret project".to_string());
+    preset.created_by = Some("alice".to_string());
+    preset.tags = vec!["Secret Project".to_string()];
+    engine.store_preset(preset)?;
+
+    let data = engine.export_anonymized()?;
+    let json = serde_json::to_string(&data)?;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("{} bytes exported", json.len()).cyan()
+    );
+    assert!(!json.to_lowercase().contains("secret"));
+    assert!(!json.contains("alice"));
+
+    let original = engine.export_data()?;
+    assert_eq!(data.widgets.len(), original.widgets.len());
+    for (anonymized, record) in data.widgets.iter().zip(&original.widgets) {
+        assert_eq!(
+            anonymized.features.value_patterns,
+            record.features.value_patterns
+        );
+        assert_eq!(anonymized.widget.minimum, record.widget.minimum);
+        assert_eq!(anonymized.frequency, record.frequency);
+        assert_eq!(
+            anonymized.features.label_tokens.len(),
+            record.features.label_tokens.len()
+        );
+    }
+
+    // Shared words still match between records
+    let tokens: Vec<&Vec<String>> = data
+        .widgets
+        .iter()
+        .map(|r| &r.features.label_tokens)
+        .collect();
+    assert_eq!(tokens[0][..2], tokens[1][..2]);
+    assert_ne!(tokens[0][2], tokens[1][2]);
+
+    let preset = &data.presets[0];
+    assert!(preset.description.is_none());
+    assert!(preset.created_by.is_none());
+    assert_eq!(preset.tags[0], format!("{} {}", tokens[0][0], tokens[0][1]));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_database_busy_and_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "MULTI-PROCESS ACCESS TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("shared");
+    let mut owner = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    owner.store_widget(create_kyma_widget("cutoff", -24.0, 24.0, 6.0))?;
+    owner.flush()?;
+
+    // sled's lock also applies to a second handle within the same process
+    match PersistentWidgetSuggestionEngine::new(&db_path) {
+        Err(WidgetIntelligenceError::DatabaseBusy { pid, .. }) => {
+            println!("{} {}", "→".green(), format!("Held by {pid:?}").cyan());
+            assert_eq!(pid, Some(std::process::id()));
+        }
+        Err(e) => return Err(format!("Expected DatabaseBusy, got {e}").into()),
+        Ok(_) => return Err("Opened a database that is in use".into()),
+    }
+
+    let mut snapshot = PersistentWidgetSuggestionEngine::open_snapshot(&db_path)?;
+    assert!(snapshot.is_read_only());
+    assert_eq!(snapshot.engine.records.len(), 1);
+    assert!(matches!(
+        snapshot.store_widget(create_kyma_widget("resonance", 0.0, 1.0, 0.2)),
+        Err(WidgetIntelligenceError::ReadOnly)
+    ));
+    drop(snapshot);
+
+    drop(owner);
+    assert!(!db_path.join("owner.pid").exists());
+    let reopened = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert_eq!(reopened.engine.records.len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_remap_event_ids_after_recompile() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "EVENT ID REMAP TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("remap");
+    let description =
+        |event_id: i64, label: &str, max: f64| -> HashMap<String, serde_json::Value> {
+            serde_json::from_value(serde_json::json!({
+                "concreteEventID": event_id,
+                "label": label,
+                "minimum": 0.0,
+                "maximum": max,
+                "displayType": "slider"
+            }))
+            .unwrap()
+        };
+
+    {
+        let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+        let extractor = KymaWidgetExtractor::new();
+        extractor.cache_widget_description(description(100, "Cutoff", 20000.0));
+        extractor.cache_widget_description(description(101, "Resonance", 1.0));
+
+        let widgets = vec![
+            extractor.create_training_widget(100, 800.0).unwrap(),
+            extractor.create_training_widget(101, 0.4).unwrap(),
+        ];
+        engine.store_widgets(widgets)?;
+        let values = HashMap::from([(100, 800.0), (101, 0.4)]);
+        engine.store_preset(extractor.snapshot_preset("Warm", &values))?;
+    }
+
+    // The recompiled sound reports new IDs, plus a widget that is new altogether
+    let mut engine = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    let extractor = KymaWidgetExtractor::new();
+    extractor.cache_widget_description(description(300, "Cutoff", 20000.0));
+    extractor.cache_widget_description(description(301, "Resonance", 1.0));
+    extractor.cache_widget_description(description(302, "Chorus Depth", 127.0));
+
+    let mut remaps = engine.remap_event_ids(&extractor, &[100, 101])?;
+    remaps.sort_by_key(|remap| remap.old_event_id);
+    for remap in &remaps {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{} → {} {:?} (score {:.2})",
+                remap.old_event_id, remap.new_event_id, remap.label, remap.score
+            )
+            .cyan()
+        );
+    }
+    assert_eq!(
+        remaps
+            .iter()
+            .map(|r| (r.old_event_id, r.new_event_id))
+            .collect::<Vec<_>>(),
+        vec![(100, 300), (101, 301)]
+    );
+    assert_eq!(remaps[0].presets, vec!["Warm".to_string()]);
+
+    // Nothing is left to remap
+    assert!(engine.remap_event_ids(&extractor, &[100, 101])?.is_empty());
+    drop(engine);
+
+    let reopened = PersistentWidgetSuggestionEngine::new(&db_path)?;
+    assert!(reopened.engine.has_event_id(300));
+    assert!(!reopened.engine.has_event_id(100));
+    let preset = reopened
+        .engine
+        .presets
+        .iter()
+        .find(|p| p.name == "Warm")
+        .unwrap();
+    assert!(preset.widget_values.iter().any(|v| v.widget_id == "301"));
+    assert_eq!(reopened.event_id_remaps()?.len(), 2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
+
+#[test]
+fn test_remap_leaves_other_sounds_alone() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!(
+        "\n{}",
+        "EVENT ID REMAP OTHER SOUNDS TEST".bold().underline()
+    );
+
+    let temp_dir = tempdir()?;
+    let mut engine = PersistentWidgetSuggestionEngine::new(temp_dir.path().join("remap_sounds"))?;
+    let extractor = KymaWidgetExtractor::new();
+    let description = |event_id: i64, label: &str, address: &str| {
+        serde_json::from_value::<HashMap<String, serde_json::Value>>(serde_json::json!({
+            "concreteEventID": event_id,
+            "label": label,
+            "address": address,
+            "minimum": 0.0,
+            "maximum": 1.0,
+            "displayType": "slider"
+        }))
+        .unwrap()
+    };
+
+    // Sound A has a cutoff, sound B a resonance; only A is loaded when it is recompiled
+    extractor.cache_widget_description(description(100, "Cutoff", "!Cutoff"));
+    extractor.cache_widget_description(description(200, "Resonance", "!Resonance"));
+    let widgets = vec![
+        extractor.create_training_widget(100, 0.3).unwrap(),
+        extractor.create_training_widget(200, 0.6).unwrap(),
+    ];
+    engine.store_widgets(widgets)?;
+    extractor.clear_cache();
+    extractor.cache_widget_description(description(100, "Cutoff", "!Cutoff"));
+
+    let before = extractor.cache_snapshot();
+    extractor.cache_widget_description(description(300, "Cutoff", "!Cutoff"));
+    extractor.cache_widget_description(description(301, "Resonance", "!Reso"));
+    let diff = extractor.diff_against(&before);
+    let previous: Vec<i64> = diff.removed.iter().map(|entry| entry.event_id).collect();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Removed from sound A: {previous:?}").cyan()
+    );
+    assert_eq!(previous, vec![100]);
+
+    // B's resonance is missing from the layout too, yet it is not A's to take over
+    let remaps = engine.remap_event_ids(&extractor, &previous)?;
+    assert_eq!(
+        remaps
+            .iter()
+            .map(|r| (r.old_event_id, r.new_event_id))
+            .collect::<Vec<_>>(),
+        vec![(100, 300)]
+    );
+    assert!(engine.engine.has_event_id(200));
+    assert!(!engine.engine.has_event_id(301));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
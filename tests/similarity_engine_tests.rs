@@ -12,6 +12,8 @@ fn create_kyma_widget(label: &str, min: f64, max: f64, current: f64) -> Widget {
         display_type: Some("slider".to_string()),
         event_id: None,
         values: vec![current],
+        value_kind: None,
+        taper: None,
     }
 }
 
@@ -36,6 +38,8 @@ fn create_preset_data(name: &str, widget_values: HashMap<String, f64>) -> Preset
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        tags: Vec::new(),
+        favorite: false,
     }
 }
 
@@ -182,7 +186,7 @@ fn test_realistic_presets() {
         ..Default::default()
     };
 
-    if let Some(insight) = engine.get_preset_insights(&test_widget) {
+    for insight in engine.get_preset_insights(&test_widget) {
         println!(
             "{} {}",
             "→".green(),
@@ -403,6 +407,974 @@ fn test_amp_series() {
     println!("\n{}", "TEST PASSED".bold().green());
 }
 
+#[test]
+fn test_frequency_aware_ranking() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "FREQUENCY AWARE RANKING TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.8));
+
+    // A second record, identical in shape, that has been observed many times
+    let mut popular = engine.records[0].clone();
+    popular.id = 2;
+    popular.widget.label = Some("Amp_02".to_string());
+    popular.features.label_tokens = vec!["amp_02".to_string()];
+    popular.frequency = 200;
+    engine.records.push(popular);
+
+    let test_widget = Widget {
+        label: Some("Amp_03".to_string()),
+        minimum: Some(0.0),
+        maximum: Some(1.0),
+        display_type: Some("slider".to_string()),
+        ..Default::default()
+    };
+
+    let suggestions = engine.get_suggestions(&test_widget, 2);
+    for suggestion in &suggestions {
+        println!(
+            "  • {} (confidence: {:.4})",
+            suggestion
+                .widget
+                .label
+                .as_deref()
+                .unwrap_or("Unknown")
+                .cyan(),
+            suggestion.confidence
+        );
+    }
+
+    assert_eq!(suggestions.len(), 2);
+    assert_eq!(suggestions[0].confidence, suggestions[1].confidence);
+    assert_eq!(suggestions[0].widget.label.as_deref(), Some("Amp_02"));
+
+    // With the frequency weight disabled, ordering falls back to record order
+    engine.config.frequency_weight = 0.0;
+    let suggestions = engine.get_suggestions(&test_widget, 2);
+    assert_eq!(suggestions[0].widget.label.as_deref(), Some("Amp_01"));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_explain_similarity() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "EXPLAIN SIMILARITY TEST".bold().underline());
+
+    let engine = WidgetSuggestionEngine::new();
+    let a = create_kyma_widget("morph", -1.0, 1.0, 0.3);
+    let b = create_kyma_widget("morph", -1.0, 1.0, -0.2);
+    let c = create_kyma_widget("cutoff", 0.0, 8_000.0, 650.0);
+
+    let same = engine.explain_similarity(&a, &b);
+    println!("{} {}", "→".green(), format!("{:#?}", same).cyan());
+
+    assert_eq!(same.label.score, 1.0);
+    assert_eq!(same.range.score, 1.0);
+    assert_eq!(same.display_type.score, 1.0);
+    assert_eq!(
+        same.label.contribution,
+        same.label.score * same.label.weight
+    );
+
+    let sum = same.label.contribution
+        + same.range.contribution
+        + same.display_type.contribution
+        + same.generated.contribution
+        + same.value_pattern.contribution;
+    assert!((same.total - sum.clamp(0.0, 1.0)).abs() < 1e-9);
+
+    let different = engine.explain_similarity(&a, &c);
+    println!("{} {}", "→".green(), format!("{:#?}", different).cyan());
+    assert!(different.total < same.total);
+    assert!(different.label.score < 1.0);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_hostile_values_do_not_panic() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "HOSTILE VALUES TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.8));
+    engine.store_widget(create_kyma_widget("Amp_02", 0.0, f64::INFINITY, f64::NAN));
+    engine.store_widget(Widget::simplified(
+        Some("Gate".to_string()),
+        Some(7),
+        vec![f64::NAN, 0.5, f64::NEG_INFINITY],
+    ));
+    engine.store_widget(create_kyma_widget("degenerate", 1.0, 1.0, 1.0));
+
+    for record in &engine.records {
+        assert!(record.widget.values.iter().all(|v| v.is_finite()));
+    }
+
+    let query = Widget {
+        label: Some("Amp_03".to_string()),
+        minimum: Some(f64::NAN),
+        maximum: Some(1.0),
+        current_value: Some(f64::NAN),
+        ..Default::default()
+    };
+
+    let suggestions = engine.get_suggestions(&query, 5);
+    assert!(!suggestions.is_empty());
+    assert!(suggestions.iter().all(|s| s.confidence.is_finite()));
+
+    let gate = engine.get_suggestions_by_event_id(7, 1);
+    assert_eq!(gate[0].suggested_value, Some(0.5));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_value_strategies() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "VALUE STRATEGIES TEST".bold().underline());
+
+    let history = vec![0.2, 0.79, 0.8, 0.81, 0.5, 0.82];
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(Widget::simplified(
+        Some("Volume".to_string()),
+        Some(11),
+        history,
+    ));
+
+    let suggest = |engine: &WidgetSuggestionEngine| {
+        engine.get_suggestions_by_event_id(11, 1)[0]
+            .suggested_value
+            .unwrap()
+    };
+
+    // The volume usually sits around 0.8
+    let mode = suggest(&engine);
+    println!("{} {}", "→".green(), format!("Mode: {mode:.3}").cyan());
+    assert!((mode - 0.8).abs() < 0.02);
+
+    engine.config.value_strategy = ValueStrategy::Median;
+    let median = suggest(&engine);
+    println!("{} {}", "→".green(), format!("Median: {median:.3}").cyan());
+    assert!((median - 0.795).abs() < 1e-9);
+
+    engine.config.value_strategy = ValueStrategy::Lowest;
+    assert_eq!(suggest(&engine), 0.2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_mode_counts_repeated_observations() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "REPEATED OBSERVATIONS MODE TEST".bold().underline());
+
+    // The history keeps 0.8 once, but it was set forty times against a single 0.2
+    let mut engine = WidgetSuggestionEngine::new();
+    for _ in 0..40 {
+        engine.store_widget(Widget::simplified(
+            Some("Volume".to_string()),
+            Some(14),
+            vec![0.8],
+        ));
+    }
+    engine.store_widget(Widget::simplified(
+        Some("Volume".to_string()),
+        Some(14),
+        vec![0.2],
+    ));
+
+    let suggested = engine.get_suggestions_by_event_id(14, 1)[0]
+        .suggested_value
+        .unwrap();
+    println!("{} {}", "→".green(), format!("Mode: {suggested:.3}").cyan());
+    assert!((suggested - 0.8).abs() < 1e-9);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_remap_keeps_observation_counts() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "REMAP OBSERVATION COUNTS TEST".bold().underline());
+
+    let volume = |event_id: u64, values: Vec<f64>| Widget {
+        label: Some("Volume".to_string()),
+        event_id: Some(event_id),
+        minimum: Some(0.0),
+        maximum: Some(1.0),
+        values,
+        ..Default::default()
+    };
+
+    let mut engine = WidgetSuggestionEngine::new();
+    for _ in 0..5 {
+        engine.store_widget(volume(15, vec![0.8]));
+    }
+    engine.store_widget(volume(15, vec![0.2]));
+
+    // The recompiled sound reports the widget under a new ID
+    let remaps = engine.remap_event_ids(&[15], &[volume(16, Vec::new())], 0.5);
+    assert_eq!(remaps.len(), 1);
+
+    let stats = engine.records[0].value_stats.clone().unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Observations after remap: {}", stats.count).cyan()
+    );
+    assert_eq!(stats.count, 6);
+
+    let suggested = engine.get_suggestions_by_event_id(16, 1)[0]
+        .suggested_value
+        .unwrap();
+    assert!((suggested - 0.8).abs() < 1e-9);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_value_stats_within_exact_bounds() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "VALUE STATS BOUNDS TEST".bold().underline());
+
+    // Rounded to two decimals, the outer values would land on the bounds
+    let values = vec![0.794, 0.8, 0.806];
+    let stats = ValueStats::from_values(&values);
+
+    let kept = stats.within(0.795, 0.805, &values);
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Kept {} observations, mean {:.3}", kept.count, kept.mean).cyan()
+    );
+    assert_eq!(kept.count, 1);
+    assert!((kept.mean - 0.8).abs() < 1e-9);
+    assert_eq!(kept.frequency_map.len(), 1);
+
+    // Values exactly on a bound are kept
+    assert_eq!(stats.within(0.794, 0.8, &values).count, 2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_outlier_rejection() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "OUTLIER REJECTION TEST".bold().underline());
+
+    // One accidental fling to 1.0 among values that sit around 0.8
+    let history = vec![0.8, 0.81, 0.79, 0.8, 0.82, 0.78, 0.8, 1.0];
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(Widget::simplified(
+        Some("Volume".to_string()),
+        Some(12),
+        history,
+    ));
+
+    let suggestion = &engine.get_suggestions_by_event_id(12, 1)[0];
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Filtered alternatives: {:?}", suggestion.alternative_values).cyan()
+    );
+    assert!(!suggestion.alternative_values.contains(&1.0));
+
+    // The raw history is retained on the record
+    assert!(engine.records[0].widget.values.contains(&1.0));
+
+    // Reported statistics leave the fling out too, while the record's own count it
+    let stats = engine.value_stats(&engine.records[0]);
+    let raw = engine.records[0].value_stats.clone().unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Filtered mean {:.3} over {} observations, raw mean {:.3} over {}",
+            stats.mean, stats.count, raw.mean, raw.count
+        )
+        .cyan()
+    );
+    assert_eq!(stats.count, 7);
+    assert_eq!(raw.count, 8);
+    assert!((stats.mean - 0.8).abs() < 1e-9);
+    assert!(raw.mean > stats.mean);
+    assert!(stats.percentiles.iter().all(|p| *p < 0.9));
+    assert!(!stats.frequency_map.contains_key("1.00"));
+
+    engine.config.outlier_filter = OutlierFilter::None;
+    let suggestion = &engine.get_suggestions_by_event_id(12, 1)[0];
+    assert!(suggestion.alternative_values.contains(&1.0));
+    assert_eq!(engine.value_stats(&engine.records[0]).count, 8);
+
+    engine.config.outlier_filter = OutlierFilter::ZScore { threshold: 2.0 };
+    let suggestion = &engine.get_suggestions_by_event_id(12, 1)[0];
+    assert!(!suggestion.alternative_values.contains(&1.0));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_event_value_stats() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "EVENT VALUE STATS TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(Widget::simplified(
+        Some("Cutoff".to_string()),
+        Some(5),
+        vec![0.2, 0.2],
+    ));
+    // A second record of the same widget, e.g. merged in from another database
+    let mut other = engine.records[0].clone();
+    other.id = 2;
+    other.widget.values = vec![0.5];
+    other.widget.current_value = Some(0.5);
+    other.refresh_value_stats();
+    engine.records.push(other);
+
+    let stats = engine.event_value_stats(5).unwrap();
+    println!("{} {}", "→".green(), format!("{stats:?}").cyan());
+    assert_eq!(stats.count, 3);
+    assert!((stats.mean - 0.3).abs() < 1e-9);
+    assert!((stats.std_dev - 0.02f64.sqrt()).abs() < 1e-9);
+    assert_eq!(stats.frequency_map.get("0.20"), Some(&2));
+    assert_eq!(stats.percentiles.len(), 3);
+    assert!(engine.event_value_stats(6).is_none());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_value_priors() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "VALUE PRIORS TEST".bold().underline());
+
+    let mut config = EngineConfig::default();
+    assert_eq!(config.value_priors.get("volume"), Some(&0.75));
+
+    config
+        .load_value_priors_json(r#"{"Cutoff": 0.35, "volume": 0.6}"#)
+        .unwrap();
+    assert!(config.load_value_priors_json("not json").is_err());
+
+    let mut engine = WidgetSuggestionEngine::with_config(config);
+    engine.register_value_prior("morph", 0.42);
+
+    engine.store_widget(create_kyma_widget("cutoff", 0.0, 1.0, 0.9));
+    engine.store_widget(create_kyma_widget("Volume", -24.0, 24.0, 0.1));
+    engine.store_widget(create_kyma_widget("morph", -1.0, 1.0, -0.5));
+
+    let patterns = |label: &str| {
+        engine
+            .records
+            .iter()
+            .find(|r| r.widget.label.as_deref() == Some(label))
+            .unwrap()
+            .features
+            .value_patterns
+            .clone()
+    };
+
+    assert!(patterns("cutoff").contains(&0.35));
+    assert!(patterns("Volume").contains(&0.6));
+    assert!(!patterns("Volume").contains(&0.75));
+    assert!(patterns("morph").contains(&0.42));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_discrete_value_suggestions() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "DISCRETE VALUE SUGGESTIONS TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+
+    // A toggle detected from its display type
+    let mut toggle = Widget::simplified(
+        Some("sw_00".to_string()),
+        Some(21),
+        vec![1.0, 0.0, 1.0, 0.63],
+    );
+    toggle.display_type = Some("Toggle".to_string());
+    engine.store_widget(toggle);
+
+    // A five-position selector marked explicitly
+    let mut selector = Widget::simplified(Some("mode".to_string()), Some(22), vec![0.3, 0.3, 0.52]);
+    selector.value_kind = Some(ValueKind::Stepped { steps: 5 });
+    engine.store_widget(selector);
+
+    let toggle_suggestion = &engine.get_suggestions_by_event_id(21, 1)[0];
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Toggle: {:?} {:?}",
+            toggle_suggestion.suggested_value, toggle_suggestion.alternative_values
+        )
+        .cyan()
+    );
+    assert_eq!(toggle_suggestion.suggested_value, Some(1.0));
+    assert_eq!(toggle_suggestion.alternative_values, vec![0.0, 1.0]);
+
+    let selector_suggestion = &engine.get_suggestions_by_event_id(22, 1)[0];
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Selector: {:?} {:?}",
+            selector_suggestion.suggested_value, selector_suggestion.alternative_values
+        )
+        .cyan()
+    );
+    assert_eq!(selector_suggestion.suggested_value, Some(0.25));
+    assert_eq!(selector_suggestion.alternative_values, vec![0.25, 0.5]);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_taper_aware_suggestions() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "TAPER AWARE SUGGESTIONS TEST".bold().underline());
+
+    let (min, max) = (20.0, 20000.0);
+    let normalize = |hz: f64| (hz - min) / (max - min);
+
+    // A cutoff set to 100 Hz and 10 kHz, perceptually a decade either side of 1 kHz
+    let mut cutoff = Widget::simplified(
+        Some("cutoff".to_string()),
+        Some(31),
+        vec![normalize(100.0), normalize(10000.0)],
+    );
+    cutoff.minimum = Some(min);
+    cutoff.maximum = Some(max);
+    cutoff.taper = Some(Taper::Logarithmic);
+
+    let mut linear = cutoff.clone();
+    linear.taper = None;
+
+    let suggest = |widget: Widget| {
+        let mut engine = WidgetSuggestionEngine::new();
+        engine.store_widget(widget);
+        engine.get_suggestions_by_event_id(31, 1)[0]
+            .suggested_value
+            .unwrap()
+    };
+    let log_value = suggest(cutoff);
+    let linear_value = suggest(linear);
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Log taper: {:.1} Hz, linear: {:.1} Hz",
+            min + log_value * (max - min),
+            min + linear_value * (max - min)
+        )
+        .cyan()
+    );
+
+    assert!((log_value - normalize(1000.0)).abs() < 1e-9);
+    assert!((linear_value - normalize(5050.0)).abs() < 1e-9);
+
+    // Positions round-trip for every taper
+    for taper in [Taper::Linear, Taper::Logarithmic, Taper::Exponential] {
+        let position = taper.to_position(0.3, Some(min), Some(max));
+        assert!((taper.from_position(position, Some(min), Some(max)) - 0.3).abs() < 1e-9);
+    }
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_confidence_calibration() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "CONFIDENCE CALIBRATION TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(Widget::simplified(
+        Some("resonance".to_string()),
+        Some(41),
+        vec![0.4, 0.45, 0.5],
+    ));
+
+    let suggestion = engine.get_suggestions_by_event_id(41, 1)[0].clone();
+    assert_eq!(suggestion.value_confidence, 0.7);
+
+    // Repeated rejections pull the bucket towards its observed acceptance rate
+    for _ in 0..10 {
+        engine.record_suggestion_outcome(&suggestion, false);
+    }
+    let rejected = engine.get_suggestions_by_event_id(41, 1)[0].value_confidence;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("After 10 rejections: {rejected:.3}").cyan()
+    );
+    assert!((rejected - 0.35).abs() < 1e-9);
+
+    for _ in 0..30 {
+        engine.record_suggestion_outcome(&suggestion, true);
+    }
+    let accepted = engine.get_suggestions_by_event_id(41, 1)[0].value_confidence;
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("After 30 acceptances: {accepted:.3}").cyan()
+    );
+    assert!(accepted > 0.7);
+
+    // Other buckets are unaffected
+    assert_eq!(engine.calibration.confidence(1), 0.5);
+    assert_eq!(engine.calibration.buckets[1].offered, 40);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_per_record_value_statistics() {
+    colored::control::set_override(true);
+
+    println!(
+        "\n{}",
+        "PER RECORD VALUE STATISTICS TEST".bold().underline()
+    );
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(Widget::simplified(
+        Some("resonance".to_string()),
+        Some(81),
+        vec![0.2, 0.4],
+    ));
+    engine.store_widget(Widget::simplified(
+        Some("cutoff".to_string()),
+        Some(82),
+        vec![0.9],
+    ));
+    engine.store_widget(Widget::simplified(
+        Some("resonance".to_string()),
+        Some(81),
+        vec![0.6, 0.4],
+    ));
+
+    let stats = |label: &str| {
+        engine
+            .records
+            .iter()
+            .find(|r| r.widget.label.as_deref() == Some(label))
+            .and_then(|r| r.value_stats.clone())
+            .expect("stats are kept for every record")
+    };
+
+    // Statistics count observations, not the label priors of the value patterns
+    let resonance = stats("resonance");
+    let cutoff = stats("cutoff");
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "resonance: mean {:.3}, std {:.3}, quartiles {:?}",
+            resonance.mean, resonance.std_dev, resonance.percentiles
+        )
+        .cyan()
+    );
+
+    // Observed were 0.2, 0.4, 0.6 and 0.4 again; the repeat is counted, the history
+    // keeps it once
+    assert_eq!(resonance.count, 4);
+    assert!((resonance.mean - 0.4).abs() < 1e-9);
+    assert!((resonance.std_dev - 0.02f64.sqrt()).abs() < 1e-9);
+    assert_eq!(resonance.frequency_map.get("0.40"), Some(&2));
+    assert_eq!(resonance.percentiles.len(), 3);
+    assert!((resonance.percentiles[1] - 0.4).abs() < 1e-9);
+
+    // Each record describes only its own values
+    assert_eq!(cutoff.count, 1);
+    assert!((cutoff.mean - 0.9).abs() < 1e-9);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_preset_clustering() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "PRESET CLUSTERING TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    for (name, cutoff, resonance) in [
+        ("Bright A", 0.9, 0.8),
+        ("Dark A", 0.1, 0.2),
+        ("Bright B", 0.85, 0.75),
+        ("Dark B", 0.15, 0.25),
+    ] {
+        engine.store_preset(create_preset_data(
+            name,
+            HashMap::from([
+                ("cutoff".to_string(), cutoff),
+                ("resonance".to_string(), resonance),
+            ]),
+        ));
+    }
+
+    let clusters = engine.get_preset_clusters();
+    for cluster in &clusters {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!("{}: {:?}", cluster.label, cluster.presets).cyan()
+        );
+    }
+
+    assert_eq!(clusters.len(), 2);
+    assert_eq!(clusters[0].presets, vec!["Bright A", "Bright B"]);
+    assert_eq!(clusters[0].label, "cutoff high");
+    assert!((clusters[0].centroid["cutoff"] - 0.875).abs() < 1e-9);
+    assert_eq!(clusters[1].presets, vec!["Dark A", "Dark B"]);
+    assert_eq!(clusters[1].label, "cutoff low");
+
+    // A loose threshold puts everything together
+    assert_eq!(engine.cluster_presets(1.0).len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_preset_recommendation() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "PRESET RECOMMENDATION TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_preset(create_preset_data(
+        "SizzlingDrips",
+        HashMap::from([("10".to_string(), 0.9), ("11".to_string(), 0.7)]),
+    ));
+    engine.store_preset(create_preset_data(
+        "DeepSpace",
+        HashMap::from([("10".to_string(), 0.2), ("11".to_string(), 0.1)]),
+    ));
+    engine.store_preset(create_preset_data(
+        "Unrelated",
+        HashMap::from([("99".to_string(), 0.5)]),
+    ));
+
+    let current_state = HashMap::from([(10, 0.85), (11, 0.7), (12, 0.3)]);
+    let recommendations = engine.recommend_presets(&current_state, 5);
+    for recommendation in &recommendations {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{}: distance {:.3}",
+                recommendation.preset, recommendation.distance
+            )
+            .cyan()
+        );
+    }
+
+    assert_eq!(recommendations.len(), 2);
+    assert_eq!(recommendations[0].preset, "SizzlingDrips");
+    assert_eq!(recommendations[0].matched_widgets, 2);
+    assert!(recommendations[0].distance < 0.05);
+    assert_eq!(recommendations[1].preset, "DeepSpace");
+
+    assert_eq!(engine.recommend_presets(&current_state, 1).len(), 1);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_related_widgets() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "RELATED WIDGETS TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+
+    // Cutoff (1) and resonance (2) move together, drive (3) does its own thing
+    for (i, (cutoff, resonance, drive)) in [
+        (0.2, 0.25, 0.9),
+        (0.5, 0.55, 0.1),
+        (0.8, 0.85, 0.6),
+        (0.9, 0.9, 0.2),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        engine.store_preset(create_preset_data(
+            &format!("Patch {i}"),
+            HashMap::from([
+                ("1".to_string(), cutoff),
+                ("2".to_string(), resonance),
+                ("3".to_string(), drive),
+            ]),
+        ));
+    }
+    engine.store_preset(create_preset_data(
+        "Solo",
+        HashMap::from([("4".to_string(), 0.5)]),
+    ));
+
+    let related = engine.related_widgets(1);
+    for widget in &related {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{}: score {:.3}, correlation {:?}",
+                widget.event_id, widget.score, widget.correlation
+            )
+            .cyan()
+        );
+    }
+
+    assert_eq!(related.len(), 2);
+    assert_eq!(related[0].event_id, 2);
+    assert!(related[0].correlation.unwrap() > 0.95);
+    assert_eq!(related[0].co_occurrence, 1.0);
+    assert!(related[1].score < related[0].score);
+
+    assert!(engine.related_widgets(99).is_empty());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_context_conditioned_suggestions() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "CONTEXT SUGGESTIONS TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    // Learned values and preset values are both normalized
+    for value in [0.20, 0.22, 0.21] {
+        let mut widget = create_kyma_widget("Brightness", 0.0, 100.0, value);
+        widget.event_id = Some(7);
+        engine.store_widget(widget);
+    }
+
+    engine.store_preset(create_preset_data(
+        "Dark",
+        HashMap::from([("7".to_string(), 0.75), ("8".to_string(), 0.5)]),
+    ));
+    engine.store_preset(create_preset_data(
+        "Darker",
+        HashMap::from([("7".to_string(), 0.8125), ("8".to_string(), 0.5)]),
+    ));
+    engine.store_preset(create_preset_data(
+        "Bright",
+        HashMap::from([("7".to_string(), 0.25), ("8".to_string(), 0.1)]),
+    ));
+
+    let query = Widget {
+        event_id: Some(7),
+        ..Default::default()
+    };
+
+    let global = engine.get_suggestions(&query, 1);
+    let global_value = global[0].suggested_value.unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Global: {global_value}").cyan()
+    );
+    assert!((0.20..=0.22).contains(&global_value));
+
+    let dark = engine.get_suggestions_with_context(&query, 1, "Dark");
+    let dark_value = dark[0].suggested_value.unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("In 'Dark': {dark_value}").cyan()
+    );
+    assert!((0.75..=0.8125).contains(&dark_value));
+    assert!(dark[0].alternative_values.contains(&0.75));
+    assert!(!dark[0].alternative_values.contains(&0.25));
+    assert!(dark[0]
+        .alternative_values
+        .iter()
+        .all(|value| (0.0..=1.0).contains(value)));
+
+    let bright = engine.get_suggestions_with_context(&query, 1, "Bright");
+    assert_eq!(bright[0].suggested_value, Some(0.25));
+
+    // Unknown presets leave the global suggestion untouched
+    let unknown = engine.get_suggestions_with_context(&query, 1, "Missing");
+    assert_eq!(unknown[0].suggested_value, Some(global_value));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_export_widgets_csv() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "CSV EXPORT TEST".bold().underline());
+
+    let mut engine = WidgetSuggestionEngine::new();
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.5));
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.7));
+    engine.store_widget(create_kyma_widget(
+        "Delay \"long\", left",
+        0.0,
+        2000.0,
+        250.0,
+    ));
+
+    let mut csv = Vec::new();
+    engine.export_widgets_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    for line in csv.lines() {
+        println!("{} {}", "→".green(), line.cyan());
+    }
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("id,label,event_id,minimum,maximum,frequency"));
+    assert!(lines[1].contains(",Amp_01,,0,1,2,"));
+    assert!(lines[2].contains("\"Delay \"\"long\"\", left\""));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_boolean_majority_suggestions() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "BOOLEAN SUGGESTIONS TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    let description: HashMap<String, serde_json::Value> = serde_json::from_value(
+        serde_json::json!({ "concreteEventID": 31, "label": "Bypass", "isBoolean": true }),
+    )
+    .unwrap();
+    extractor.cache_widget_description(description);
+
+    let mut engine = WidgetSuggestionEngine::new();
+    for value in [1.0, 0.0, 0.0, 0.2, 1.0] {
+        engine.store_widget(extractor.create_training_widget(31, value).unwrap());
+    }
+
+    // Stored as off/on only
+    let record = &engine.records[0];
+    assert!(record
+        .widget
+        .get_values()
+        .iter()
+        .all(|&v| v == 0.0 || v == 1.0));
+
+    let suggestion = &engine.get_suggestions_by_event_id(31, 1)[0];
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Bypass: {:?}, flips {:?}",
+            suggestion.suggested_value, suggestion.flip_probability
+        )
+        .cyan()
+    );
+    assert_eq!(suggestion.suggested_value, Some(0.0));
+    let flip = suggestion.flip_probability.unwrap();
+    assert!(flip > 0.0 && flip < 0.5);
+
+    // Continuous widgets have no flip probability
+    engine.store_widget(create_kyma_widget("Amp_01", 0.0, 1.0, 0.5));
+    let suggestion = &engine.get_suggestions(&create_kyma_widget("Amp_01", 0.0, 1.0, 0.5), 1)[0];
+    assert!(suggestion.flip_probability.is_none());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
 fn print_separator() {
     println!("{}", "─".repeat(80).blue());
 }
+
+#[test]
+fn test_export_kyma_json_round_trip() {
+    colored::control::set_override(true);
+
+    println!("\n{}", "KYMA JSON EXPORT TEST".bold().underline());
+
+    let extractor = KymaWidgetExtractor::new();
+    extractor
+        .cache_widget_descriptions_from_json(
+            &serde_json::json!([
+                { "concreteEventID": 51, "label": "Cutoff", "minimum": 20.0, "maximum": 2020.0, "displayType": "slider" },
+                { "concreteEventID": 52, "label": "Bypass", "isBoolean": true },
+                { "concreteEventID": 53, "label": "Voices", "minimum": 1, "maximum": 5, "grid": 1 }
+            ])
+            .to_string(),
+        )
+        .unwrap();
+
+    // Training values are normalized, as Kyma sends them: 0.5 is 1020 Hz
+    let mut engine = WidgetSuggestionEngine::new();
+    for _ in 0..3 {
+        engine.store_widget(extractor.create_training_widget(51, 0.5).unwrap());
+    }
+    engine.store_widget(extractor.create_training_widget(52, 1.0).unwrap());
+    engine.store_widget(extractor.create_training_widget(53, 0.5).unwrap());
+    engine.store_widget(create_kyma_widget("Unbound", 0.0, 1.0, 0.5));
+
+    let json = engine.export_kyma_json();
+    println!(
+        "{} {}",
+        "→".green(),
+        serde_json::to_string(&json).unwrap().cyan()
+    );
+    let entries = json.as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+
+    let cutoff = &entries[0];
+    assert_eq!(cutoff["concreteEventID"], 51);
+    assert_eq!(cutoff["label"], "Cutoff");
+    assert_eq!(cutoff["displayType"], "slider");
+    assert!((cutoff["defaultValue"].as_f64().unwrap() - 1020.0).abs() < 1e-6);
+    assert_eq!(entries[1]["isBoolean"], true);
+    assert_eq!(entries[2]["grid"], 1.0);
+    assert!((entries[2]["defaultValue"].as_f64().unwrap() - 3.0).abs() < 1e-6);
+
+    // The export reads back into an extractor as the same widgets
+    let reread = KymaWidgetExtractor::new();
+    let report = reread
+        .cache_widget_descriptions_from_json(&json.to_string())
+        .unwrap();
+    assert_eq!(report.cached, vec![51, 52, 53]);
+    let metadata = reread.extract_widget_metadata(51).unwrap();
+    assert_eq!(metadata.label.as_deref(), Some("Cutoff"));
+    assert_eq!(
+        (metadata.minimum, metadata.maximum),
+        (Some(20.0), Some(2020.0))
+    );
+    assert_eq!(
+        reread.extract_widget_metadata(53).unwrap().value_kind,
+        Some(ValueKind::Stepped { steps: 5 })
+    );
+
+    // And back into an equivalent record
+    let record = WidgetRecord::from(engine.records[0].to_description());
+    assert_eq!(record.widget.event_id, Some(51));
+    assert_eq!(record.widget.label.as_deref(), Some("Cutoff"));
+    assert_eq!(
+        record.widget.current_value,
+        engine.records[0].widget.current_value
+    );
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
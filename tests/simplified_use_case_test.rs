@@ -87,5 +87,17 @@ fn test_simplified_use_case() {
         suggestion.alternative_values
     );
     
+    // Every value from the multi-value observations is learned in one call each
+    let volume = engine
+        .records
+        .iter()
+        .find(|r| r.widget.event_id == Some(101))
+        .expect("Volume record should exist");
+    for value in [0.7, 0.8, 0.75, 0.65, 0.85] {
+        assert!(volume.widget.values.contains(&value));
+        assert!(volume.features.value_patterns.contains(&value));
+    }
+    assert_eq!(volume.frequency, 2);
+
     println!("\n{}", "TEST PASSED".bold().green());
 }
\ No newline at end of file
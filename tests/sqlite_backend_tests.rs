@@ -0,0 +1,87 @@
+#![cfg(feature = "sqlite")]
+
+use colored::*;
+use std::collections::HashMap;
+use tempfile::tempdir;
+use widget_intelligence::*;
+
+#[test]
+fn test_sqlite_backend_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    control::set_override(true);
+
+    println!("\n{}", "SQLITE BACKEND TEST".bold().underline());
+
+    let temp_dir = tempdir()?;
+    let db_path = temp_dir.path().join("intelligence.sqlite");
+
+    {
+        let mut system = PersistentWidgetSuggestionEngine::open_sqlite(&db_path)?;
+        system.store_widgets(vec![
+            Widget::simplified(Some("Amp_01".to_string()), Some(11), vec![0.4, 0.5]),
+            Widget::simplified(Some("cutoff".to_string()), Some(12), vec![-6.0]),
+        ])?;
+
+        let widget_values = [WidgetValue {
+            widget_id: "11".to_string(),
+            label: Some("Amp_01".to_string()),
+            value: 0.5,
+            confidence: 1.0,
+        }];
+        for value in [0.5, 0.6] {
+            system.store_preset(Preset {
+                name: "Soft".to_string(),
+                description: None,
+                widget_values: widget_values
+                    .iter()
+                    .cloned()
+                    .map(|v| WidgetValue { value, ..v })
+                    .collect(),
+                created_by: None,
+                usage_count: 1,
+                last_used: 0,
+                tags: Vec::new(),
+                favorite: false,
+            })?;
+        }
+
+        let record_id = system.engine.records[0].id;
+        system.record_feedback(record_id, 0.5, true)?;
+        system.flush()?;
+    }
+
+    println!("{} {}", "→".green(), "Reopening SQLite file...".yellow());
+    let system = PersistentWidgetSuggestionEngine::open_sqlite(&db_path)?;
+
+    let stats = system.get_stats();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!(
+            "Reloaded {} widgets, {} bytes on disk",
+            stats.get("total_widgets").unwrap_or(&0),
+            system.size_on_disk()?
+        )
+        .cyan()
+    );
+
+    assert_eq!(stats.get("total_widgets"), Some(&2));
+    assert_eq!(system.engine.presets.len(), 1);
+    assert_eq!(system.engine.presets[0].usage_count, 2);
+    assert_eq!(system.preset_history("Soft")?.len(), 1);
+    assert_eq!(system.feedback_log()?.len(), 1);
+    assert!(system.size_on_disk()? > 0);
+
+    let suggestions = system.get_suggestions_by_event_id(11, 1);
+    assert!(!suggestions.is_empty());
+
+    let by_event: HashMap<u64, usize> = system
+        .engine
+        .records
+        .iter()
+        .filter_map(|r| Some((r.widget.event_id?, r.widget.values.len())))
+        .collect();
+    assert_eq!(by_event.get(&11), Some(&2));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+    Ok(())
+}
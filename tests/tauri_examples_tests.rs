@@ -492,3 +492,822 @@ async fn test_kyma_intelligence_stats() {
 
     println!("\n{}", "TEST PASSED".bold().green());
 }
+
+#[tokio::test]
+async fn test_snapshot_preset() {
+    control::set_override(true);
+
+    println!("\n{}", "SNAPSHOT PRESET TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_snapshot_preset");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+
+    for (event_id, kyma_json) in [
+        (
+            16001,
+            r#"{"concreteEventID": 16001, "label": "Amp_01", "minimum": 0.0, "maximum": 1.0, "displayType": "slider"}"#,
+        ),
+        (
+            16002,
+            r#"{"concreteEventID": 16002, "label": "cutoff", "minimum": -24.0, "maximum": 24.0, "displayType": "slider"}"#,
+        ),
+    ] {
+        service
+            .cache_widget_description(event_id, kyma_json.to_string())
+            .await
+            .unwrap();
+    }
+
+    // 16003 has no cached description and is left out
+    let values = HashMap::from([(16001, 0.8), (16002, -6.0), (16003, 0.5)]);
+    let preset = service
+        .snapshot_preset("Live Take".to_string(), values)
+        .await
+        .unwrap();
+
+    for widget_value in &preset.widget_values {
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "{} ({:?}) = {}",
+                widget_value.widget_id, widget_value.label, widget_value.value
+            )
+            .cyan()
+        );
+    }
+
+    assert_eq!(preset.widget_values.len(), 2);
+    assert_eq!(preset.widget_values[0].label.as_deref(), Some("Amp_01"));
+    assert_eq!(preset.widget_values[1].label.as_deref(), Some("cutoff"));
+    // Stored normalized: -6 is three eighths of the way through -24..24
+    assert_eq!(preset.widget_values[1].value, 0.375);
+
+    let stats = service.get_intelligence_stats().await.unwrap();
+    assert_eq!(stats.total_presets, 1);
+    assert_eq!(stats.total_widgets, 2);
+
+    // Nothing valid to snapshot
+    let empty = service
+        .snapshot_preset("Empty".to_string(), HashMap::from([(16001, 5.0)]))
+        .await;
+    assert!(empty.is_err());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_list_widgets_and_presets() {
+    control::set_override(true);
+
+    println!("\n{}", "PAGINATED LISTING TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_listing");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+
+    for (event_id, kyma_json) in [
+        (
+            17001,
+            r#"{"concreteEventID": 17001, "label": "Amp_01", "minimum": 0.0, "maximum": 1.0, "displayType": "slider"}"#,
+        ),
+        (
+            17002,
+            r#"{"concreteEventID": 17002, "label": "cutoff", "minimum": -24.0, "maximum": 24.0, "displayType": "slider"}"#,
+        ),
+        (
+            17003,
+            r#"{"concreteEventID": 17003, "label": "Resonance", "minimum": 0.0, "maximum": 10.0, "displayType": "knob"}"#,
+        ),
+    ] {
+        service
+            .cache_widget_description(event_id, kyma_json.to_string())
+            .await
+            .unwrap();
+    }
+
+    for (name, values) in [
+        (
+            "Warm",
+            vec![("17001", 0.4), ("17002", -6.0), ("17003", 2.0)],
+        ),
+        ("Bright", vec![("17001", 0.9), ("17002", 12.0)]),
+        ("Dark", vec![("17002", -18.0)]),
+    ] {
+        service
+            .save_preset_and_learn(PresetData {
+                name: name.to_string(),
+                description: None,
+                widget_values: values
+                    .into_iter()
+                    .map(|(id, value)| (id.to_string(), value))
+                    .collect(),
+                created_by: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let first = service.list_widgets(0, 2, WidgetSort::Label).await.unwrap();
+    let labels: Vec<_> = first.items.iter().map(|w| w.label.clone()).collect();
+    println!("{} {}", "→".green(), format!("Page 1: {labels:?}").cyan());
+    assert_eq!(first.total, 3);
+    assert_eq!(
+        labels,
+        vec![Some("Amp_01".to_string()), Some("cutoff".to_string())]
+    );
+    assert!(first.has_more());
+
+    let second = service.list_widgets(2, 2, WidgetSort::Label).await.unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.items[0].label.as_deref(), Some("Resonance"));
+    assert!(!second.has_more());
+
+    let most_used = service
+        .list_widgets(0, 1, WidgetSort::MostUsed)
+        .await
+        .unwrap();
+    assert_eq!(most_used.items[0].label.as_deref(), Some("cutoff"));
+    assert_eq!(most_used.items[0].observed_values, 3);
+
+    let presets = service.list_presets(1, 5).await.unwrap();
+    let names: Vec<_> = presets.items.iter().map(|p| p.name.as_str()).collect();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Presets from 1: {names:?}").cyan()
+    );
+    assert_eq!(presets.total, 3);
+    assert_eq!(names, vec!["Dark", "Warm"]);
+    assert_eq!(presets.items[1].widget_count, 3);
+
+    // The detail view has the full contents of a listed preset
+    let warm = service.get_preset("Warm".to_string()).await.unwrap();
+    assert_eq!(warm.widget_values.len(), presets.items[1].widget_count);
+    assert_eq!(warm.usage_count, presets.items[1].usage_count);
+    assert!(matches!(
+        service.get_preset("Missing".to_string()).await,
+        Err(WidgetIntelligenceError::NotFound(_))
+    ));
+
+    // Deleting returns the stats without the preset
+    let stats = service.delete_preset("Warm".to_string()).await.unwrap();
+    assert_eq!(stats.total_presets, 2);
+    assert!(service.get_preset("Warm".to_string()).await.is_err());
+    assert!(matches!(
+        service.delete_preset("Warm".to_string()).await,
+        Err(WidgetIntelligenceError::NotFound(_))
+    ));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_service_errors() {
+    control::set_override(true);
+
+    println!("\n{}", "SERVICE ERRORS TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_service_errors");
+    let service = StandaloneIntelligenceService::new(db_path_buf.to_str().unwrap()).unwrap();
+
+    let missing = service.recall_preset("Nope".to_string()).await.unwrap_err();
+    println!("{} {}", "→".green(), missing.to_string().cyan());
+    assert!(matches!(missing, WidgetIntelligenceError::NotFound(_)));
+    // Tauri hands errors to the frontend serialized, as their message
+    assert_eq!(
+        serde_json::to_value(&missing).unwrap(),
+        serde_json::json!(missing.to_string())
+    );
+
+    let malformed = service
+        .cache_widget_description(1, "{not json".to_string())
+        .await
+        .unwrap_err();
+    assert!(matches!(malformed, WidgetIntelligenceError::ParseError(_)));
+
+    let invalid = service
+        .cache_widget_description(1, r#"{"label": "Amp_01"}"#.to_string())
+        .await
+        .unwrap_err();
+    println!("{} {}", "→".green(), invalid.to_string().cyan());
+    assert!(matches!(
+        invalid,
+        WidgetIntelligenceError::ValidationError(_)
+    ));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_widget_descriptions_survive_restart() {
+    control::set_override(true);
+
+    println!(
+        "\n{}",
+        "PERSISTED DESCRIPTION CACHE TEST".bold().underline()
+    );
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_description_cache");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    {
+        let service = StandaloneIntelligenceService::new(db_path).unwrap();
+        service
+            .cache_widget_description(
+                13755,
+                r#"{"concreteEventID": 13755, "label": "Amp_01", "minimum": 0.0, "maximum": 2.0}"#
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    let stats = service.get_intelligence_stats().await.unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("{} descriptions after restart", stats.cache_size).cyan()
+    );
+    assert_eq!(stats.cache_size, 1);
+
+    // The restored range normalizes the snapshot and denormalizes the recall
+    let mut values = HashMap::new();
+    values.insert(13755, 1.5);
+    let preset = service
+        .snapshot_preset("Loud".to_string(), values)
+        .await
+        .unwrap();
+    assert_eq!(preset.widget_values[0].value, 0.75);
+    let recalled = service.recall_preset("Loud".to_string()).await.unwrap();
+    assert_eq!(recalled[&13755], 1.5);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_default_value_cold_start() {
+    control::set_override(true);
+
+    println!("\n{}", "COLD START DEFAULT VALUE TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_cold_start");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    service
+        .cache_widget_description(
+            4242,
+            r#"{"concreteEventID": 4242, "label": "Cutoff", "minimum": 0.0, "maximum": 200.0, "defaultValue": 50.0}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+    let suggestions = service
+        .get_widget_value_suggestions(4242, None, None)
+        .await
+        .unwrap();
+    let first = &suggestions[0];
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("{:?} ({})", first.suggested_value, first.reason).cyan()
+    );
+    assert_eq!(first.suggested_value, Some(0.25));
+    assert!(first.confidence < 0.3);
+    assert!(matches!(
+        first.reason_kind,
+        SuggestionReason::DefaultValue { .. }
+    ));
+
+    // Once something is learned the default no longer leads
+    let mut values = HashMap::new();
+    values.insert(4242, 150.0);
+    service
+        .snapshot_preset("Bright".to_string(), values)
+        .await
+        .unwrap();
+    let suggestions = service
+        .get_widget_value_suggestions(4242, None, None)
+        .await
+        .unwrap();
+    assert!(suggestions
+        .iter()
+        .all(|s| !matches!(s.reason_kind, SuggestionReason::DefaultValue { .. })));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_service_requests() {
+    control::set_override(true);
+
+    println!(
+        "\n{}",
+        "CONCURRENT SERVICE REQUESTS TEST".bold().underline()
+    );
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_concurrent_service");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = std::sync::Arc::new(StandaloneIntelligenceService::new(db_path).unwrap());
+    service
+        .cache_widget_description(
+            18001,
+            r#"{"concreteEventID": 18001, "label": "Cutoff", "minimum": 0.0, "maximum": 1.0}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+    // Suggestion and stats requests run alongside learning on other worker threads
+    let mut tasks = Vec::new();
+    for i in 0..8 {
+        let service = service.clone();
+        tasks.push(tokio::spawn(async move {
+            if i % 4 == 0 {
+                let mut values = HashMap::new();
+                values.insert(18001, 0.25 * (i / 4 + 1) as f64);
+                service
+                    .snapshot_preset(format!("Preset {i}"), values)
+                    .await
+                    .map(|_| ())
+            } else {
+                service
+                    .get_widget_value_suggestions(18001, Some("Cutoff".to_string()), None)
+                    .await?;
+                service.get_intelligence_stats().await.map(|_| ())
+            }
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+
+    let stats = service.get_intelligence_stats().await.unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("{} presets after concurrent requests", stats.total_presets).cyan()
+    );
+    assert_eq!(stats.total_presets, 2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_batch_suggestions() {
+    control::set_override(true);
+
+    println!("\n{}", "BATCH SUGGESTIONS TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_batch_suggestions");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    for (event_id, json) in [
+        (
+            19001,
+            r#"{"concreteEventID": 19001, "label": "Cutoff", "minimum": 0.0, "maximum": 1.0}"#,
+        ),
+        (
+            19002,
+            r#"{"concreteEventID": 19002, "label": "Resonance", "minimum": 0.0, "maximum": 1.0, "defaultValue": 0.2}"#,
+        ),
+    ] {
+        service
+            .cache_widget_description(event_id, json.to_string())
+            .await
+            .unwrap();
+    }
+
+    let mut values = HashMap::new();
+    values.insert(19001, 0.8);
+    service
+        .snapshot_preset("Bright".to_string(), values)
+        .await
+        .unwrap();
+
+    let batch = service
+        .get_widget_value_suggestions_batch(vec![
+            (19001, Some("Cutoff".to_string()), None),
+            (19002, None, None),
+            (19003, None, Some("slider".to_string())),
+        ])
+        .await
+        .unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Suggestions for {} event IDs", batch.len()).cyan()
+    );
+    assert_eq!(batch.len(), 3);
+
+    // Each entry is what the single-widget endpoint returns
+    let single = service
+        .get_widget_value_suggestions(19001, Some("Cutoff".to_string()), None)
+        .await
+        .unwrap();
+    assert_eq!(batch[&19001].len(), single.len());
+    assert_eq!(batch[&19001][0].suggested_value, single[0].suggested_value);
+    assert_eq!(batch[&19002][0].suggested_value, Some(0.2));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_record_widget_value() {
+    control::set_override(true);
+
+    println!("\n{}", "RECORD WIDGET VALUE TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_record_value");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    service
+        .cache_widget_description(
+            20001,
+            r#"{"concreteEventID": 20001, "label": "Drive", "minimum": 0.0, "maximum": 10.0}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+    // The first change is learned at once, the next ones within the interval are held back
+    assert!(service.record_widget_value(20001, 2.0).await.unwrap());
+    assert!(!service.record_widget_value(20001, 3.0).await.unwrap());
+    assert!(!service.record_widget_value(20001, 4.0).await.unwrap());
+
+    let widgets = service
+        .list_widgets(0, 10, WidgetSort::default())
+        .await
+        .unwrap();
+    assert_eq!(widgets.total, 1);
+    assert_eq!(widgets.items[0].observed_values, 1);
+
+    // Values are learned normalized to the widget's range, like preset values
+    let suggestions = service
+        .get_widget_value_suggestions(20001, None, None)
+        .await
+        .unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Learned values: {:?}", suggestions[0].alternative_values).cyan()
+    );
+    assert_eq!(suggestions[0].alternative_values, vec![0.2]);
+
+    // Only the last held-back value is stored
+    let flushed = service.flush_widget_values().await.unwrap();
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Flushed {flushed} held-back value").cyan()
+    );
+    assert_eq!(flushed, 1);
+    assert_eq!(service.flush_widget_values().await.unwrap(), 0);
+
+    let widgets = service
+        .list_widgets(0, 10, WidgetSort::default())
+        .await
+        .unwrap();
+    assert_eq!(widgets.items[0].event_id, Some(20001));
+    assert_eq!(widgets.items[0].observed_values, 2);
+    let suggestions = service
+        .get_widget_value_suggestions(20001, None, None)
+        .await
+        .unwrap();
+    assert!(suggestions[0].alternative_values.contains(&0.4));
+    assert!(!suggestions[0].alternative_values.contains(&4.0));
+
+    // Widgets without a cached description and non-finite values are rejected
+    assert!(matches!(
+        service.record_widget_value(20002, 1.0).await,
+        Err(WidgetIntelligenceError::NotFound(_))
+    ));
+    assert!(matches!(
+        service.record_widget_value(20001, f64::NAN).await,
+        Err(WidgetIntelligenceError::ValidationError(_))
+    ));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_record_widget_value_trailing_store() {
+    control::set_override(true);
+
+    println!(
+        "\n{}",
+        "RECORD WIDGET VALUE TRAILING STORE TEST".bold().underline()
+    );
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_record_value_trailing");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    service
+        .cache_widget_description(
+            20101,
+            r#"{"concreteEventID": 20101, "label": "Drive", "minimum": 0.0, "maximum": 10.0}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+    // The knob stops turning while a value is held back
+    assert!(service.record_widget_value(20101, 2.0).await.unwrap());
+    assert!(!service.record_widget_value(20101, 7.0).await.unwrap());
+
+    // The held-back value is stored once its interval has passed, without another call
+    let deadline = std::time::Instant::now() + VALUE_LEARNING_INTERVAL * 10;
+    let mut observed = 1;
+    while observed < 2 && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let widgets = service
+            .list_widgets(0, 10, WidgetSort::default())
+            .await
+            .unwrap();
+        observed = widgets.items[0].observed_values;
+    }
+    println!(
+        "{} {}",
+        "→".green(),
+        format!("Observed {observed} values without flushing").cyan()
+    );
+    assert_eq!(observed, 2);
+    assert_eq!(service.flush_widget_values().await.unwrap(), 0);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[test]
+fn test_rate_limiter_after_flush() {
+    control::set_override(true);
+
+    println!("\n{}", "RATE LIMITER FLUSH TEST".bold().underline());
+
+    let interval = std::time::Duration::from_millis(100);
+    let mut limiter = RateLimiter::new(interval);
+    let start = std::time::Instant::now();
+    assert_eq!(limiter.offer(300, 0.1, start), Some(0.1));
+    assert_eq!(limiter.offer(300, 0.2, start + interval / 2), None);
+
+    // The knob is released: the held-back value is stored now
+    let flushed_at = start + interval / 2;
+    assert_eq!(limiter.take_all(flushed_at), vec![(300, 0.2)]);
+
+    // The interval counts from the flush, so a change one interval later is stored at once
+    assert_eq!(limiter.offer(300, 0.3, flushed_at + interval / 2), None);
+    assert_eq!(limiter.offer(300, 0.4, flushed_at + interval), Some(0.4));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_apply_preset() {
+    control::set_override(true);
+
+    println!("\n{}", "APPLY PRESET TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_apply_preset");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    {
+        let service = StandaloneIntelligenceService::new(db_path).unwrap();
+        service
+            .cache_widget_description(
+                21001,
+                r#"{"concreteEventID": 21001, "label": "Frequency", "minimum": 20.0, "maximum": 220.0}"#
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+
+        // The live value is stored normalized as 0.5 and applied as 120 again
+        let mut values = HashMap::new();
+        values.insert(21001, 120.0);
+        let stored = service
+            .snapshot_preset("Mid".to_string(), values)
+            .await
+            .unwrap();
+        assert_eq!(stored.widget_values[0].value, 0.5);
+
+        // Values come back in the widget's own range, ready to send to Kyma
+        let applied = service.apply_preset("Mid".to_string()).await.unwrap();
+        println!(
+            "{} {}",
+            "→".green(),
+            format!("Applied values: {applied:?}").cyan()
+        );
+        assert!((applied[&21001] - 120.0).abs() < 1e-9);
+
+        let preset = service.get_preset("Mid".to_string()).await.unwrap();
+        assert_eq!(preset.usage_count, stored.usage_count + 1);
+        assert!(preset.last_used >= stored.last_used);
+
+        assert!(matches!(
+            service.apply_preset("Missing".to_string()).await,
+            Err(WidgetIntelligenceError::NotFound(_))
+        ));
+    }
+
+    // The use is persisted
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    let preset = service.get_preset("Mid".to_string()).await.unwrap();
+    assert_eq!(preset.usage_count, 2);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_event_subscription() {
+    control::set_override(true);
+
+    println!("\n{}", "EVENT SUBSCRIPTION TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_event_subscription");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    let mut events = service.subscribe();
+
+    service
+        .cache_widget_description(
+            22001,
+            r#"{"concreteEventID": 22001, "label": "Tone", "minimum": 0.0, "maximum": 1.0}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+
+    let mut values = HashMap::new();
+    values.insert(22001, 0.4);
+    service
+        .snapshot_preset("Soft".to_string(), values)
+        .await
+        .unwrap();
+    let suggestions = service
+        .get_widget_value_suggestions(22001, None, None)
+        .await
+        .unwrap();
+    service.flush().await.unwrap();
+
+    let mut received = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        println!("{} {}", "→".green(), format!("{event:?}").cyan());
+        received.push(event);
+    }
+
+    assert!(received.iter().any(|event| matches!(
+        event,
+        IntelligenceEvent::WidgetLearned { event_id: Some(22001), label: Some(label), .. }
+            if label == "Tone"
+    )));
+    assert!(received.contains(&IntelligenceEvent::PresetStored {
+        name: "Soft".to_string()
+    }));
+    assert!(received.contains(&IntelligenceEvent::SuggestionServed {
+        event_id: 22001,
+        count: suggestions.len()
+    }));
+    assert!(matches!(
+        received.last(),
+        Some(IntelligenceEvent::DatabaseFlushed { writes }) if *writes > 0
+    ));
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_clear_all_data() {
+    control::set_override(true);
+
+    println!("\n{}", "CLEAR ALL DATA TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_clear_all_data");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    {
+        let service = StandaloneIntelligenceService::new(db_path).unwrap();
+        service
+            .cache_widget_description(
+                23001,
+                r#"{"concreteEventID": 23001, "label": "Width", "minimum": 0.0, "maximum": 1.0}"#
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+        let mut values = HashMap::new();
+        values.insert(23001, 0.7);
+        service
+            .snapshot_preset("Wide".to_string(), values)
+            .await
+            .unwrap();
+
+        // Without the confirmation token nothing is touched
+        assert!(matches!(
+            service.clear_all_data("yes".to_string()).await,
+            Err(WidgetIntelligenceError::ValidationError(_))
+        ));
+        let stats = service.get_intelligence_stats().await.unwrap();
+        assert_eq!(stats.total_presets, 1);
+
+        let stats = service
+            .clear_all_data(CLEAR_ALL_DATA_CONFIRMATION.to_string())
+            .await
+            .unwrap();
+        println!(
+            "{} {}",
+            "→".green(),
+            format!(
+                "After reset: {} widgets, {} presets, {} descriptions",
+                stats.total_widgets, stats.total_presets, stats.cache_size
+            )
+            .cyan()
+        );
+        assert_eq!(stats.total_widgets, 0);
+        assert_eq!(stats.total_presets, 0);
+        assert_eq!(stats.cache_size, 0);
+    }
+
+    // The reset is on disk, including the persisted descriptions
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    let stats = service.get_intelligence_stats().await.unwrap();
+    assert_eq!(stats.total_widgets, 0);
+    assert_eq!(stats.total_presets, 0);
+    assert_eq!(stats.cache_size, 0);
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}
+
+#[tokio::test]
+async fn test_widget_insight() {
+    control::set_override(true);
+
+    println!("\n{}", "WIDGET INSIGHT TEST".bold().underline());
+
+    let temp_dir = tempdir().unwrap();
+    let db_path_buf = temp_dir.path().join("test_widget_insight");
+    let db_path = db_path_buf.to_str().unwrap();
+
+    let service = StandaloneIntelligenceService::new(db_path).unwrap();
+    service
+        .cache_widget_description(
+            24001,
+            r#"{"concreteEventID": 24001, "label": "Cutoff", "minimum": 0.0, "maximum": 1.0}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+    // Two presets share a value, which counts as two observations
+    for (name, value) in [
+        ("Bright", 0.8),
+        ("Dark", 0.2),
+        ("Open", 0.9),
+        ("Bright Too", 0.8),
+    ] {
+        let mut values = HashMap::new();
+        values.insert(24001, value);
+        service
+            .snapshot_preset(name.to_string(), values)
+            .await
+            .unwrap();
+    }
+
+    // The label comes from the cached description
+    let insight = service.get_widget_insight(24001, None).await.unwrap();
+    let text = insight.insights.clone().unwrap_or_default();
+    println!("{} {}", "→".green(), text.cyan());
+    assert!(text.contains("4 values learned"));
+    assert!(text.contains("'Bright' preset"));
+    assert!(!insight.suggested_values.is_empty());
+    assert_eq!(
+        insight.suggested_values.len(),
+        insight.confidence_scores.len()
+    );
+
+    // Nothing learned about an unknown widget
+    let insight = service.get_widget_insight(24999, None).await.unwrap();
+    assert!(insight.insights.is_none());
+
+    println!("\n{}", "TEST PASSED".bold().green());
+}